@@ -3,13 +3,18 @@ use std::fs::File;
 use std::io::{BufRead, BufReader};
 use std::path::Path;
 
-use super::parse::{parse_important_log_line, DebugParseContext};
-use super::{ParseCombatLogDebugResult, ParsedCombatEvent, MAX_DEBUG_EVENTS};
+use super::parse::{parse_important_log_line, DebugParseContext, SUPPORTED_COMBAT_EVENTS};
+use super::{
+    ParseCombatLogDebugResult, ParseCombatLogRangeResult, ParsedCombatEvent, SupportedCombatEvent,
+    MAX_COMBAT_LOG_RANGE_LINES, MAX_DEBUG_EVENTS,
+};
 
 #[tauri::command]
-pub(crate) fn parse_combat_log_file(
-    file_path: String,
-) -> Result<ParseCombatLogDebugResult, String> {
+pub(crate) fn supported_combat_events() -> Vec<SupportedCombatEvent> {
+    SUPPORTED_COMBAT_EVENTS.to_vec()
+}
+
+fn validate_debug_log_path(file_path: &str) -> Result<&Path, String> {
     if !cfg!(debug_assertions) {
         return Err("Combat log debug parsing is only available in debug builds".to_string());
     }
@@ -18,11 +23,20 @@ pub(crate) fn parse_combat_log_file(
         return Err("Combat log file path is required".to_string());
     }
 
-    let path = Path::new(&file_path);
+    let path = Path::new(file_path);
     if !path.is_file() {
         return Err(format!("Combat log file not found: {file_path}"));
     }
 
+    Ok(path)
+}
+
+#[tauri::command]
+pub(crate) fn parse_combat_log_file(
+    file_path: String,
+) -> Result<ParseCombatLogDebugResult, String> {
+    let path = validate_debug_log_path(&file_path)?;
+
     let file_size_bytes = std::fs::metadata(path)
         .map_err(|error| error.to_string())?
         .len();
@@ -60,3 +74,73 @@ pub(crate) fn parse_combat_log_file(
         truncated,
     })
 }
+
+/// Parses `count` lines starting at the 1-based `start_line`, so a large log can be paged
+/// through without holding all of it in memory. Lines before the window are skipped
+/// unparsed, so zone and encounter context only comes from lines inside it.
+#[tauri::command]
+pub(crate) fn parse_combat_log_range(
+    file_path: String,
+    start_line: u64,
+    count: u64,
+) -> Result<ParseCombatLogRangeResult, String> {
+    let path = validate_debug_log_path(&file_path)?;
+
+    let file_size_bytes = std::fs::metadata(path)
+        .map_err(|error| error.to_string())?
+        .len();
+    let reader = BufReader::new(File::open(path).map_err(|error| error.to_string())?);
+    let start_line = start_line.max(1);
+    let (lines_read, parsed_events, reached_end_of_file) =
+        parse_log_line_range(reader, start_line, count.min(MAX_COMBAT_LOG_RANGE_LINES))?;
+
+    Ok(ParseCombatLogRangeResult {
+        file_path,
+        file_size_bytes,
+        start_line,
+        lines_read,
+        parsed_events,
+        reached_end_of_file,
+    })
+}
+
+pub(crate) fn parse_log_line_range(
+    mut reader: impl BufRead,
+    start_line: u64,
+    count: u64,
+) -> Result<(u64, Vec<ParsedCombatEvent>, bool), String> {
+    let mut line = String::new();
+    let mut line_number = 0_u64;
+    let mut lines_read = 0_u64;
+    let mut parsed_events: Vec<ParsedCombatEvent> = Vec::new();
+    let mut debug_context = DebugParseContext::default();
+
+    while lines_read < count {
+        line.clear();
+        if reader
+            .read_line(&mut line)
+            .map_err(|error| error.to_string())?
+            == 0
+        {
+            return Ok((lines_read, parsed_events, true));
+        }
+        line_number += 1;
+        if line_number < start_line {
+            continue;
+        }
+
+        lines_read += 1;
+        let trimmed_line = line.trim_end_matches(['\r', '\n']);
+        if let Some(parsed_event) =
+            parse_important_log_line(trimmed_line, line_number, &mut debug_context)
+        {
+            parsed_events.push(parsed_event);
+        }
+    }
+
+    let reached_end_of_file = reader
+        .fill_buf()
+        .map_err(|error| error.to_string())?
+        .is_empty();
+    Ok((lines_read, parsed_events, reached_end_of_file))
+}
@@ -3,8 +3,8 @@ use std::path::Path;
 use std::sync::{Arc, Mutex};
 
 use crate::recording::metadata::{
-    RecordingEncounterSnapshot, RecordingImportantEventMetadata, RecordingMetadata,
-    RecordingMetadataSnapshot, RecordingPlayerMetadata,
+    RecordingEncounterSnapshot, RecordingImportantEventMetadata, RecordingMetadataSnapshot,
+    RecordingPlayerMetadata,
 };
 
 use super::parse::{
@@ -36,6 +36,9 @@ pub(crate) struct RecordingMetadataAccumulator {
     recording_active: bool,
     recording_elapsed_origin_seconds: f64,
     session_log_origin_seconds: Option<f64>,
+    /// Set when recording data changed since the sidecar was last written, so the periodic
+    /// flush skips quiet stretches.
+    has_unflushed_changes: bool,
 }
 
 impl RecordingMetadataAccumulator {
@@ -61,9 +64,19 @@ impl RecordingMetadataAccumulator {
         Some(parsed_event)
     }
 
+    /// Feeds a backlog line for zone, encounter and roster context only. Nothing is recorded,
+    /// even while a recording session is active.
+    pub(crate) fn consume_context_line(&mut self, line: &str) -> Option<ImportantCombatEvent> {
+        let recording_active = std::mem::replace(&mut self.recording_active, false);
+        let parsed_event = self.consume_combat_log_line(line, 0.0);
+        self.recording_active = recording_active;
+        parsed_event
+    }
+
     pub(crate) fn begin_recording_session(&mut self, elapsed_seconds: f64) {
         self.reset_recording_data();
         self.recording_active = true;
+        self.has_unflushed_changes = true;
         self.recording_elapsed_origin_seconds = elapsed_seconds;
         self.recording_players = self.context_players.clone();
         self.zone_name = self.context.current_zone.clone();
@@ -117,6 +130,7 @@ impl RecordingMetadataAccumulator {
                 encounter_name: self.latest_encounter_name.clone(),
                 encounter_category: self.latest_encounter_category.clone(),
                 key_level: self.key_level,
+                note: None,
             });
         }
     }
@@ -129,6 +143,10 @@ impl RecordingMetadataAccumulator {
         self.recording_active
     }
 
+    pub(crate) fn has_unflushed_changes(&self) -> bool {
+        self.has_unflushed_changes
+    }
+
     pub(crate) fn current_context_zone_name(&self) -> Option<String> {
         self.context.current_zone.clone()
     }
@@ -191,7 +209,16 @@ impl RecordingMetadataAccumulator {
         self.session_log_origin_seconds = None;
     }
 
-    pub(crate) fn record_manual_marker(&mut self, elapsed_seconds: f64) {
+    /// Moves a manual marker back by `lead_seconds`, but never before the recording began.
+    pub(crate) fn manual_marker_elapsed_seconds(
+        &self,
+        elapsed_seconds: f64,
+        lead_seconds: f64,
+    ) -> f64 {
+        (elapsed_seconds - lead_seconds).max(self.recording_elapsed_origin_seconds)
+    }
+
+    pub(crate) fn record_manual_marker(&mut self, elapsed_seconds: f64, note: Option<String>) {
         if !self.recording_active {
             return;
         }
@@ -202,11 +229,14 @@ impl RecordingMetadataAccumulator {
             event_type: EVENT_MANUAL_MARKER.to_string(),
             source: None,
             target: None,
+            source_guid: None,
+            target_guid: None,
             target_kind: None,
             zone_name: self.zone_name.clone(),
             encounter_name: self.latest_encounter_name.clone(),
             encounter_category: self.latest_encounter_category.clone(),
             key_level: self.key_level,
+            note,
         };
         self.record_important_event(&manual_event, elapsed_seconds);
     }
@@ -227,6 +257,7 @@ impl RecordingMetadataAccumulator {
         else {
             return;
         };
+        self.has_unflushed_changes = true;
 
         *self
             .important_event_counts
@@ -263,6 +294,7 @@ impl RecordingMetadataAccumulator {
             encounter_name: event.encounter_name.clone(),
             encounter_category: event.encounter_category.clone(),
             key_level: event.key_level,
+            note: event.note.clone(),
         });
     }
 
@@ -270,6 +302,7 @@ impl RecordingMetadataAccumulator {
         self.context_players.clear();
         if self.recording_active {
             self.recording_players.clear();
+            self.has_unflushed_changes = true;
         }
     }
 
@@ -315,7 +348,10 @@ impl RecordingMetadataAccumulator {
 
         if self.recording_active {
             if let Some(player) = self.recording_players.get_mut(player_guid) {
-                player.name = Some(normalized_name);
+                if player.name.as_deref() != Some(name) {
+                    player.name = Some(normalized_name);
+                    self.has_unflushed_changes = true;
+                }
             }
         }
     }
@@ -325,6 +361,7 @@ impl RecordingMetadataAccumulator {
 
         if self.recording_active {
             merge_player_update(&mut self.recording_players, &update);
+            self.has_unflushed_changes = true;
         }
     }
 
@@ -445,9 +482,10 @@ pub(crate) fn persist_recording_metadata_snapshot(
     metadata_accumulator: &Arc<Mutex<RecordingMetadataAccumulator>>,
 ) -> Result<(), String> {
     let snapshot = {
-        let accumulator = metadata_accumulator
+        let mut accumulator = metadata_accumulator
             .lock()
             .map_err(|error| error.to_string())?;
+        accumulator.has_unflushed_changes = false;
         accumulator.snapshot()
     };
 
@@ -455,11 +493,18 @@ pub(crate) fn persist_recording_metadata_snapshot(
         return Ok(());
     }
 
-    let mut metadata = crate::recording::metadata::read_recording_metadata(recording_output_path)?
-        .unwrap_or_else(|| RecordingMetadata::new(recording_output_path));
-    metadata.apply_combat_log_snapshot(snapshot.clone());
-
-    crate::recording::metadata::write_recording_metadata(recording_output_path, &metadata)?;
+    if let Err(error) =
+        crate::recording::metadata::update_recording_metadata(recording_output_path, |metadata| {
+            metadata.apply_combat_log_snapshot(snapshot);
+            Ok(())
+        })
+    {
+        // Keep the changes pending so the next flush retries them.
+        if let Ok(mut accumulator) = metadata_accumulator.lock() {
+            accumulator.has_unflushed_changes = true;
+        }
+        return Err(error);
+    }
     Ok(())
 }
 
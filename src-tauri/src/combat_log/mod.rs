@@ -1,14 +1,36 @@
 pub(crate) mod debug;
 mod metadata;
 pub(crate) mod parse;
+mod stream;
+mod throttle;
 pub(crate) mod watch;
 
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use std::collections::BTreeMap;
+use std::time::Duration;
 
 const MAX_DEBUG_EVENTS: usize = 2_000;
+/// Upper bound on the lines one `parse_combat_log_range` call reads.
+const MAX_COMBAT_LOG_RANGE_LINES: u64 = 100_000;
 const MAX_PERSISTED_HIGH_VOLUME_EVENTS: usize = 20_000;
+const MAX_LIVE_COMBAT_EVENTS_PER_WINDOW: usize = 20;
+const LIVE_COMBAT_EVENT_WINDOW: Duration = Duration::from_secs(1);
+const MAX_MANUAL_MARKER_LEAD_SECS: f64 = 30.0;
+const MAX_MANUAL_MARKER_NOTE_CHARS: usize = 200;
+const EVENT_STREAM_PORT: u16 = 47_810;
+const EVENT_STREAM_ACCEPT_POLL_INTERVAL: Duration = Duration::from_millis(200);
+const EVENT_STREAM_WRITE_TIMEOUT: Duration = Duration::from_millis(250);
+/// Lines queued per stream client; a client that falls this far behind is disconnected.
+const EVENT_STREAM_CLIENT_QUEUE_LEN: usize = 256;
+const COMBAT_WATCH_HEARTBEAT_INTERVAL: Duration = Duration::from_secs(10);
+const COMBAT_LOG_POLL_INTERVAL: Duration = Duration::from_secs(1);
+/// Bytes of backlog replayed per cursor lock, so a stop never waits on a whole log.
+const COMBAT_LOG_REPLAY_CHUNK_BYTES: u64 = 1024 * 1024;
+/// How often pending markers are written to the recording's sidecar while the watch runs,
+/// bounding what a crash can lose without rewriting the file on every event.
+const METADATA_SIDECAR_FLUSH_INTERVAL: Duration = Duration::from_secs(5);
 const EVENT_MANUAL_MARKER: &str = "MANUAL_MARKER";
+const ADVANCED_COMBAT_LOGGING_DISABLED_MESSAGE: &str = "Advanced Combat Logging is off, so markers miss spell names and unit details. Enable it in WoW under Options > Network.";
 const EVENT_ENCOUNTER_START: &str = "ENCOUNTER_START";
 const EVENT_ENCOUNTER_END: &str = "ENCOUNTER_END";
 
@@ -19,6 +41,25 @@ pub struct CombatEvent {
     pub event_type: String,
     pub source: Option<String>,
     pub target: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source_guid: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target_guid: Option<String>,
+    /// Recording session the event was emitted during, if one is attached to the watch.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub session_id: Option<String>,
+    /// Free-text annotation of a manual marker.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+}
+
+/// Summarizes live combat events of one type that were dropped by the rate limit.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CombatEventOverflow {
+    pub timestamp: f64,
+    pub event_type: String,
+    pub suppressed_count: u64,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -39,6 +80,35 @@ pub struct CombatWatchStatusEvent {
     pub watched_log_path: Option<String>,
 }
 
+/// Periodic proof of life from the watcher, so the UI can flag a watch that went quiet.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CombatWatchHeartbeatEvent {
+    pub watched_log_path: String,
+    pub file_offset: u64,
+    pub bytes_processed: u64,
+}
+
+/// Settings for one combat watch. They are kept in the watch state, so a restarted watch
+/// never inherits the previous watch's options.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct CombatWatchOptions {
+    /// Replay the existing log before live tailing.
+    pub from_beginning: bool,
+    /// Whether live combat events carry source and target GUIDs for log cross-referencing.
+    pub include_guids: bool,
+    /// How far manual markers are moved back to cover the delay between the moment and
+    /// the hotkey press. Combat log markers are timed by the log and never shifted.
+    pub marker_lead_secs: f64,
+    pub event_stream: bool,
+    pub watch_mode: Option<String>,
+    /// Session id of the recording the watch is attached to; stamped onto live combat
+    /// events. Set by the backend, never by the caller.
+    #[serde(skip)]
+    pub recording_session_id: Option<String>,
+}
+
 #[derive(Debug, Clone, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ParsedCombatEvent {
@@ -54,6 +124,21 @@ pub struct ParsedCombatEvent {
     pub key_level: Option<u32>,
 }
 
+/// Describes one normalized event type the parser understands and the payload fields it
+/// can populate, so the UI does not have to hardcode the parser's capabilities.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SupportedCombatEvent {
+    pub event_type: &'static str,
+    pub raw_event_types: &'static [&'static str],
+    pub description: &'static str,
+    pub fields: &'static [&'static str],
+    /// Context events only update zone, key level and match state and are never stored.
+    pub context_only: bool,
+    /// Whether the event is emitted live as `combat-event` while recording.
+    pub live: bool,
+}
+
 #[derive(Debug, Clone, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ParseCombatLogDebugResult {
@@ -65,5 +150,18 @@ pub struct ParseCombatLogDebugResult {
     pub truncated: bool,
 }
 
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ParseCombatLogRangeResult {
+    pub file_path: String,
+    pub file_size_bytes: u64,
+    /// 1-based line number of the first line in the window.
+    pub start_line: u64,
+    /// Lines actually read, fewer than requested when the log ends inside the window.
+    pub lines_read: u64,
+    pub parsed_events: Vec<ParsedCombatEvent>,
+    pub reached_end_of_file: bool,
+}
+
 #[cfg(test)]
 mod tests;
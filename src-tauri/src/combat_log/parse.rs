@@ -1,4 +1,99 @@
-use super::{CombatTriggerEvent, ParsedCombatEvent, EVENT_ENCOUNTER_END, EVENT_ENCOUNTER_START};
+use super::{
+    CombatTriggerEvent, ParsedCombatEvent, SupportedCombatEvent, EVENT_ENCOUNTER_END,
+    EVENT_ENCOUNTER_START,
+};
+
+const UNIT_EVENT_FIELDS: &[&str] = &["source", "target", "sourceGuid", "targetGuid", "targetKind"];
+
+/// Must stay in step with `normalize_important_event_type` and `into_live_event`.
+pub(crate) const SUPPORTED_COMBAT_EVENTS: &[SupportedCombatEvent] = &[
+    SupportedCombatEvent {
+        event_type: "PARTY_KILL",
+        raw_event_types: &["PARTY_KILL"],
+        description: "A party member landed a killing blow.",
+        fields: UNIT_EVENT_FIELDS,
+        context_only: false,
+        live: true,
+    },
+    SupportedCombatEvent {
+        event_type: "UNIT_DIED",
+        raw_event_types: &["UNIT_DIED", "UNIT_DESTROYED"],
+        description: "A unit died or was destroyed. Feign deaths and guardians are skipped.",
+        fields: &["target", "targetGuid", "targetKind"],
+        context_only: false,
+        live: true,
+    },
+    SupportedCombatEvent {
+        event_type: "SPELL_INTERRUPT",
+        raw_event_types: &["SPELL_INTERRUPT"],
+        description: "A spell cast was interrupted.",
+        fields: UNIT_EVENT_FIELDS,
+        context_only: false,
+        live: false,
+    },
+    SupportedCombatEvent {
+        event_type: "SPELL_DISPEL",
+        raw_event_types: &["SPELL_DISPEL"],
+        description: "An aura was dispelled.",
+        fields: UNIT_EVENT_FIELDS,
+        context_only: false,
+        live: false,
+    },
+    SupportedCombatEvent {
+        event_type: "ENCOUNTER_START",
+        raw_event_types: &["ENCOUNTER_START"],
+        description: "A boss encounter was pulled.",
+        fields: &["encounterName", "encounterCategory"],
+        context_only: false,
+        live: false,
+    },
+    SupportedCombatEvent {
+        event_type: "ENCOUNTER_END",
+        raw_event_types: &["ENCOUNTER_END"],
+        description: "A boss encounter ended in a kill or a wipe.",
+        fields: &["encounterName", "encounterCategory"],
+        context_only: false,
+        live: false,
+    },
+    SupportedCombatEvent {
+        event_type: "ZONE_CONTEXT",
+        raw_event_types: &[
+            "ZONE_CHANGE",
+            "ZONE_CHANGE_NEW_AREA",
+            "ZONE_CHANGED",
+            "ZONE_CHANGED_INDOORS",
+            "PLAYER_ENTERING_WORLD",
+            "MAP_CHANGE",
+        ],
+        description: "The player changed zone or map.",
+        fields: &["zoneName"],
+        context_only: true,
+        live: false,
+    },
+    SupportedCombatEvent {
+        event_type: "CHALLENGE_CONTEXT",
+        raw_event_types: &["CHALLENGE_MODE_START", "CHALLENGE_MODE_END"],
+        description: "A Mythic+ key started or ended.",
+        fields: &["keyLevel"],
+        context_only: true,
+        live: false,
+    },
+    SupportedCombatEvent {
+        event_type: "PVP_CONTEXT",
+        raw_event_types: &[
+            "ARENA_MATCH_START",
+            "ARENA_MATCH_END",
+            "PVP_MATCH_START",
+            "PVP_MATCH_COMPLETE",
+            "BATTLEGROUND_START",
+            "BATTLEGROUND_END",
+        ],
+        description: "An arena, battleground or rated PvP match started or ended.",
+        fields: &[],
+        context_only: true,
+        live: false,
+    },
+];
 
 #[derive(Debug, Clone)]
 pub(crate) struct ImportantCombatEvent {
@@ -7,11 +102,14 @@ pub(crate) struct ImportantCombatEvent {
     pub(crate) event_type: String,
     pub(crate) source: Option<String>,
     pub(crate) target: Option<String>,
+    pub(crate) source_guid: Option<String>,
+    pub(crate) target_guid: Option<String>,
     pub(crate) target_kind: Option<String>,
     pub(crate) zone_name: Option<String>,
     pub(crate) encounter_name: Option<String>,
     pub(crate) encounter_category: Option<String>,
     pub(crate) key_level: Option<u32>,
+    pub(crate) note: Option<String>,
 }
 
 #[derive(Debug, Clone)]
@@ -29,9 +127,11 @@ pub(crate) struct PlayerIdentity {
 }
 
 impl ImportantCombatEvent {
+    /// GUIDs are only kept when requested so the default live payload stays small.
     pub(crate) fn into_live_event(
         self,
         recording_elapsed_seconds: Option<f64>,
+        include_guids: bool,
     ) -> Option<super::CombatEvent> {
         let timestamp = recording_elapsed_seconds?;
         match self.event_type.as_str() {
@@ -40,6 +140,10 @@ impl ImportantCombatEvent {
                 event_type: self.event_type,
                 source: self.source,
                 target: self.target,
+                source_guid: self.source_guid.filter(|_| include_guids),
+                target_guid: self.target_guid.filter(|_| include_guids),
+                session_id: None,
+                note: None,
             }),
             _ => None,
         }
@@ -105,11 +209,14 @@ pub(crate) fn parse_important_combat_event(
         event_type: parsed_line.normalized_event_type,
         source: parsed_line.source,
         target: parsed_line.target,
+        source_guid: parsed_line.source_guid,
+        target_guid: parsed_line.target_guid,
         target_kind: parsed_line.target_kind,
         zone_name: context.current_zone.clone(),
         encounter_name,
         encounter_category,
         key_level: context.current_key_level,
+        note: None,
     })
 }
 
@@ -195,6 +302,8 @@ struct ParsedLogLine {
     log_timestamp: String,
     source: Option<String>,
     target: Option<String>,
+    source_guid: Option<String>,
+    target_guid: Option<String>,
     target_kind: Option<String>,
     fields: Vec<String>,
 }
@@ -228,12 +337,14 @@ fn parse_log_line_fields(line: &str) -> Option<ParsedLogLine> {
         log_timestamp: extract_log_timestamp(header),
         source: normalize_entity_name(source_name, source_kind.as_deref()),
         target: normalize_entity_name(dest_name, target_kind.as_deref()),
+        source_guid: normalize_guid(source_guid),
+        target_guid: normalize_guid(dest_guid),
         target_kind,
         fields: remaining_fields,
     })
 }
 
-fn normalize_important_event_type(event_type: &str) -> Option<&'static str> {
+pub(super) fn normalize_important_event_type(event_type: &str) -> Option<&'static str> {
     match event_type {
         "PARTY_KILL" => Some("PARTY_KILL"),
         "UNIT_DIED" | "UNIT_DESTROYED" => Some("UNIT_DIED"),
@@ -490,6 +601,45 @@ pub(crate) fn extract_raw_event_type_from_line(line: &str) -> Option<&str> {
     extract_event_type(header)
 }
 
+/// Whether the line shows Advanced Combat Logging on, when it can tell. The
+/// `COMBAT_LOG_VERSION` header states it directly. Damage and heal events carry the
+/// advanced block right after their spell fields, starting with the unit's GUID; without
+/// advanced logging that position holds the amount instead.
+pub(crate) fn detect_advanced_combat_logging(line: &str) -> Option<bool> {
+    let mut fields = line.trim().split(',').map(str::trim);
+    let raw_event_type = extract_event_type(fields.next()?)?;
+    let fields = fields.collect::<Vec<&str>>();
+
+    if raw_event_type == "COMBAT_LOG_VERSION" {
+        let flag_index = fields
+            .iter()
+            .position(|field| *field == "ADVANCED_LOG_ENABLED")?;
+        return match *fields.get(flag_index + 1)? {
+            "1" => Some(true),
+            "0" => Some(false),
+            _ => None,
+        };
+    }
+
+    let advanced_block_index = match raw_event_type {
+        "SWING_DAMAGE" => 8,
+        "SPELL_DAMAGE"
+        | "SPELL_PERIODIC_DAMAGE"
+        | "RANGE_DAMAGE"
+        | "SPELL_HEAL"
+        | "SPELL_PERIODIC_HEAL" => 11,
+        _ => return None,
+    };
+    let field = *fields.get(advanced_block_index)?;
+    if field.contains('-') || field == "0000000000000000" {
+        Some(true)
+    } else if field.parse::<u64>().is_ok() {
+        Some(false)
+    } else {
+        None
+    }
+}
+
 pub(crate) fn should_reset_player_roster_for_event(raw_event_type: &str) -> bool {
     matches!(
         raw_event_type,
@@ -630,6 +780,11 @@ pub(crate) fn normalize_name(name: Option<&str>) -> Option<String> {
     Some(normalized.to_string())
 }
 
+/// Environmental and unitless events use `nil` or an all-zero GUID for the missing side.
+fn normalize_guid(guid: Option<&str>) -> Option<String> {
+    normalize_name(guid).filter(|value| value.bytes().any(|byte| byte != b'0'))
+}
+
 pub(crate) fn parse_player_identities_from_log_line(
     line: &str,
 ) -> Option<(Option<PlayerIdentity>, Option<PlayerIdentity>)> {
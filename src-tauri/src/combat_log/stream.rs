@@ -0,0 +1,178 @@
+use std::io::{ErrorKind, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{Receiver, SyncSender, TrySendError};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use super::{
+    CombatEvent, EVENT_STREAM_ACCEPT_POLL_INTERVAL, EVENT_STREAM_CLIENT_QUEUE_LEN,
+    EVENT_STREAM_PORT, EVENT_STREAM_WRITE_TIMEOUT,
+};
+
+/// Localhost server that mirrors live combat events as JSON lines for overlay tools.
+struct EventStreamServer {
+    shutdown: Arc<AtomicBool>,
+    clients: Arc<Mutex<Vec<EventStreamClient>>>,
+    accept_thread: thread::JoinHandle<()>,
+}
+
+/// Each client is written to from its own thread, so a slow reader only ever fills its
+/// own queue and never blocks the watcher.
+struct EventStreamClient {
+    peer: SocketAddr,
+    lines: SyncSender<Arc<str>>,
+}
+
+static EVENT_STREAM: Mutex<Option<EventStreamServer>> = Mutex::new(None);
+
+/// Starts the server if it is not already running. Binding only ever happens on the
+/// loopback interface so the stream is never reachable from other machines.
+pub(super) fn start_event_stream() -> Result<(), String> {
+    let mut server = EVENT_STREAM.lock().map_err(|error| error.to_string())?;
+    if server.is_some() {
+        return Ok(());
+    }
+
+    let listener = TcpListener::bind(("127.0.0.1", EVENT_STREAM_PORT)).map_err(|error| {
+        format!(
+            "Failed to listen for combat event stream clients on port {EVENT_STREAM_PORT}: {error}"
+        )
+    })?;
+    listener
+        .set_nonblocking(true)
+        .map_err(|error| format!("Failed to configure combat event stream listener: {error}"))?;
+
+    let shutdown = Arc::new(AtomicBool::new(false));
+    let clients = Arc::new(Mutex::new(Vec::new()));
+    let accept_shutdown = Arc::clone(&shutdown);
+    let accept_clients = Arc::clone(&clients);
+    let accept_thread = thread::Builder::new()
+        .name("combat-event-stream".to_string())
+        .spawn(move || accept_event_stream_clients(listener, &accept_shutdown, &accept_clients))
+        .map_err(|error| format!("Failed to start combat event stream thread: {error}"))?;
+
+    tracing::info!(port = EVENT_STREAM_PORT, "Combat event stream listening");
+    *server = Some(EventStreamServer {
+        shutdown,
+        clients,
+        accept_thread,
+    });
+    Ok(())
+}
+
+/// Returns once the accept thread has exited, so the port is free for the next
+/// `start_event_stream` and no client can connect after the clients are dropped.
+pub(super) fn stop_event_stream() {
+    let server = match EVENT_STREAM.lock() {
+        Ok(mut server) => server.take(),
+        Err(error) => {
+            tracing::warn!("Failed to lock combat event stream: {error}");
+            return;
+        }
+    };
+
+    if let Some(server) = server {
+        server.shutdown.store(true, Ordering::Relaxed);
+        if let Err(error) = server.accept_thread.join() {
+            tracing::warn!("Failed to join combat event stream accept thread: {error:?}");
+        }
+        if let Ok(mut clients) = server.clients.lock() {
+            clients.clear();
+        }
+        tracing::info!("Combat event stream stopped");
+    }
+}
+
+/// Queues the event for every connected client and drops clients whose queue is full or
+/// whose writer thread has exited.
+pub(super) fn broadcast_combat_event(event: &CombatEvent) {
+    let clients = match EVENT_STREAM.lock() {
+        Ok(server) => match server.as_ref() {
+            Some(server) => Arc::clone(&server.clients),
+            None => return,
+        },
+        Err(_) => return,
+    };
+
+    let line: Arc<str> = match event_stream_line(event) {
+        Ok(line) => line.into(),
+        Err(error) => {
+            tracing::warn!(event_type = %event.event_type, "{error}");
+            return;
+        }
+    };
+
+    let Ok(mut connected_clients) = clients.lock() else {
+        return;
+    };
+    connected_clients.retain(|client| match client.lines.try_send(Arc::clone(&line)) {
+        Ok(()) => true,
+        Err(TrySendError::Full(_)) => {
+            tracing::warn!(peer = %client.peer, "Combat event stream client fell behind; disconnecting");
+            false
+        }
+        Err(TrySendError::Disconnected(_)) => false,
+    });
+}
+
+pub(super) fn event_stream_line(event: &CombatEvent) -> Result<String, String> {
+    let mut line = serde_json::to_string(event)
+        .map_err(|error| format!("Failed to serialize combat event for stream: {error}"))?;
+    line.push('\n');
+    Ok(line)
+}
+
+fn accept_event_stream_clients(
+    listener: TcpListener,
+    shutdown: &AtomicBool,
+    clients: &Mutex<Vec<EventStreamClient>>,
+) {
+    while !shutdown.load(Ordering::Relaxed) {
+        match listener.accept() {
+            Ok((stream, peer)) => {
+                // Accepted sockets inherit non-blocking mode; writes happen on the client's own
+                // thread, so they block but time out rather than stall on a reader that stopped reading.
+                if let Err(error) = stream
+                    .set_nonblocking(false)
+                    .and_then(|()| stream.set_write_timeout(Some(EVENT_STREAM_WRITE_TIMEOUT)))
+                    .and_then(|()| stream.set_nodelay(true))
+                {
+                    tracing::warn!(peer = %peer, "Failed to configure combat event stream client: {error}");
+                    continue;
+                }
+                let (lines, queued_lines) =
+                    std::sync::mpsc::sync_channel(EVENT_STREAM_CLIENT_QUEUE_LEN);
+                if let Err(error) = thread::Builder::new()
+                    .name("combat-event-stream-client".to_string())
+                    .spawn(move || write_event_stream_client(stream, peer, queued_lines))
+                {
+                    tracing::warn!(peer = %peer, "Failed to start combat event stream client thread: {error}");
+                    continue;
+                }
+                tracing::info!(peer = %peer, "Combat event stream client connected");
+                if let Ok(mut clients) = clients.lock() {
+                    clients.push(EventStreamClient { peer, lines });
+                }
+            }
+            Err(error) if error.kind() == ErrorKind::WouldBlock => {
+                thread::sleep(EVENT_STREAM_ACCEPT_POLL_INTERVAL);
+            }
+            Err(error) => {
+                tracing::warn!("Failed to accept combat event stream client: {error}");
+                thread::sleep(EVENT_STREAM_ACCEPT_POLL_INTERVAL);
+            }
+        }
+    }
+}
+
+/// Writes queued lines until the client disconnects, a write times out, or the server
+/// drops the sending side on stop.
+fn write_event_stream_client(mut stream: TcpStream, peer: SocketAddr, lines: Receiver<Arc<str>>) {
+    for line in lines {
+        if let Err(error) = stream.write_all(line.as_bytes()) {
+            tracing::info!(peer = %peer, "Combat event stream client disconnected: {error}");
+            return;
+        }
+    }
+}
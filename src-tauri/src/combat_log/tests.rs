@@ -1,12 +1,142 @@
+use std::path::Path;
+
+use super::debug::parse_log_line_range;
 use super::metadata::RecordingMetadataAccumulator;
-use super::parse::LogTimestamp;
+use super::parse::{
+    detect_advanced_combat_logging, is_context_only_event, normalize_important_event_type,
+    LogTimestamp, SUPPORTED_COMBAT_EVENTS,
+};
+use super::stream::event_stream_line;
+use super::throttle::CombatEventRateLimiter;
+use super::watch::{has_unc_server_and_share, is_network_path, CombatWatchMode};
 use super::MAX_PERSISTED_HIGH_VOLUME_EVENTS;
+use super::{CombatEvent, LIVE_COMBAT_EVENT_WINDOW, MAX_LIVE_COMBAT_EVENTS_PER_WINDOW};
+
+#[test]
+fn network_log_folders_default_to_polling() {
+    let unc_logs = Path::new(r"\\nas\games\World of Warcraft\_retail_\Logs");
+    assert!(is_network_path(unc_logs));
+    assert!(is_network_path(Path::new("//nas/games/Logs")));
+    assert!(is_network_path(Path::new(r"\\?\UNC\nas\games\Logs")));
+    assert!(!is_network_path(Path::new(r"\\?\C:\Games\Logs")));
+    assert!(!is_network_path(Path::new(
+        r"C:\Games\World of Warcraft\_retail_\Logs"
+    )));
+
+    assert_eq!(
+        CombatWatchMode::resolve(None, unc_logs),
+        CombatWatchMode::Poll
+    );
+    assert_eq!(
+        CombatWatchMode::resolve(Some("notify"), unc_logs),
+        CombatWatchMode::Notify
+    );
+    assert_eq!(
+        CombatWatchMode::resolve(Some("auto"), Path::new(r"C:\Games\Logs")),
+        CombatWatchMode::Notify
+    );
+    assert_eq!(
+        CombatWatchMode::resolve(Some("poll"), Path::new(r"Z:\Games\Logs")),
+        CombatWatchMode::Poll
+    );
+}
+
+#[test]
+fn unc_wow_folders_need_a_server_and_share() {
+    assert!(has_unc_server_and_share(r"\\nas\games\World of Warcraft"));
+    assert!(has_unc_server_and_share(r"\\nas\games\"));
+    assert!(has_unc_server_and_share(r"\\?\UNC\nas\games"));
+    assert!(has_unc_server_and_share(r"C:\Games\World of Warcraft"));
+    assert!(!has_unc_server_and_share(r"\\nas"));
+    assert!(!has_unc_server_and_share(r"\\nas\"));
+    assert!(!has_unc_server_and_share(r"\\"));
+}
+
+#[test]
+fn detects_advanced_combat_logging_from_header_and_damage_events() {
+    assert_eq!(
+        detect_advanced_combat_logging(
+            "4/22/2024 19:32:10.123  COMBAT_LOG_VERSION,20,ADVANCED_LOG_ENABLED,0,BUILD_VERSION,10.2.6,PROJECT_ID,1"
+        ),
+        Some(false)
+    );
+    assert_eq!(
+        detect_advanced_combat_logging(
+            "4/22/2024 19:32:10.123  COMBAT_LOG_VERSION,20,ADVANCED_LOG_ENABLED,1,BUILD_VERSION,10.2.6,PROJECT_ID,1"
+        ),
+        Some(true)
+    );
+    assert_eq!(
+        detect_advanced_combat_logging(
+            "4/22/2024 19:32:11.000  SPELL_DAMAGE,Player-1-0A,\"Aly\",0x511,0x0,Creature-0-1-2-3-4-5,\"Boss\",0x10a48,0x0,133,\"Fireball\",0x4,Player-1-0A,0000000000000000,100,100,0,0,0,0,0,0,0,0,1,0,0,0,0,0,0,80,5000,-1,4,0,0,0,nil,nil,nil"
+        ),
+        Some(true)
+    );
+    assert_eq!(
+        detect_advanced_combat_logging(
+            "4/22/2024 19:32:11.000  SPELL_DAMAGE,Player-1-0A,\"Aly\",0x511,0x0,Creature-0-1-2-3-4-5,\"Boss\",0x10a48,0x0,133,\"Fireball\",0x4,5000,-1,4,0,0,0,nil,nil,nil"
+        ),
+        Some(false)
+    );
+    assert_eq!(
+        detect_advanced_combat_logging(
+            "4/22/2024 19:32:11.000  SPELL_AURA_APPLIED,Player-1-0A,\"Aly\",0x511,0x0,Player-1-0A,\"Aly\",0x511,0x0,1459,\"Arcane Intellect\",0x40,BUFF"
+        ),
+        None
+    );
+}
+
+#[test]
+fn manual_marker_lead_is_clamped_to_recording_start() {
+    let mut accumulator = RecordingMetadataAccumulator::default();
+    accumulator.begin_recording_session(10.0);
+
+    let marker_elapsed = accumulator.manual_marker_elapsed_seconds(15.0, 2.0);
+    assert_eq!(marker_elapsed, 13.0);
+    assert_eq!(
+        accumulator.recording_elapsed_seconds(marker_elapsed, None),
+        Some(3.0)
+    );
+
+    let early_marker_elapsed = accumulator.manual_marker_elapsed_seconds(11.0, 5.0);
+    assert_eq!(early_marker_elapsed, 10.0);
+    accumulator.record_manual_marker(early_marker_elapsed, None);
+    let snapshot = accumulator.snapshot();
+    assert_eq!(snapshot.important_events.len(), 1);
+    assert_eq!(snapshot.important_events[0].timestamp_seconds, 0.0);
+}
+
+#[test]
+fn manual_marker_note_is_kept_in_the_snapshot() {
+    let mut accumulator = RecordingMetadataAccumulator::default();
+    accumulator.begin_recording_session(0.0);
+    accumulator.record_manual_marker(4.0, Some("boss phase 2 transition".to_string()));
+
+    let snapshot = accumulator.snapshot();
+    assert_eq!(snapshot.important_events.len(), 1);
+    assert_eq!(
+        snapshot.important_events[0].note.as_deref(),
+        Some("boss phase 2 transition")
+    );
+}
+
+#[test]
+fn tracks_unflushed_recording_changes() {
+    let mut accumulator = RecordingMetadataAccumulator::default();
+    assert!(!accumulator.has_unflushed_changes());
+
+    accumulator.record_manual_marker(1.0, None);
+    assert!(!accumulator.has_unflushed_changes());
+
+    accumulator.begin_recording_session(0.0);
+    assert!(accumulator.has_unflushed_changes());
+}
 
 #[test]
 fn caps_high_volume_events_but_keeps_structural_events() {
     let mut accumulator = RecordingMetadataAccumulator::default();
     accumulator.begin_recording_session(0.0);
-    accumulator.record_manual_marker(0.25);
+    accumulator.record_manual_marker(0.25, None);
 
     let encounter_start_line = build_line("ENCOUNTER_START", &["1", "\"Training Boss\"", "16"]);
     accumulator.consume_combat_log_line(&encounter_start_line, 0.5);
@@ -64,6 +194,50 @@ fn updates_zone_context_without_persisting_context_only_events() {
     assert_eq!(snapshot.important_events[0].event_type, "PARTY_KILL");
 }
 
+#[test]
+fn context_lines_update_context_without_recording_events() {
+    let mut accumulator = RecordingMetadataAccumulator::default();
+    accumulator.begin_recording_session(0.0);
+
+    let zone_line = build_line("ZONE_CHANGED", &["\"Nerub-ar Palace\""]);
+    accumulator.consume_context_line(&zone_line);
+    let party_kill_line = build_party_kill_line(1);
+    assert!(accumulator.consume_context_line(&party_kill_line).is_some());
+
+    assert_eq!(
+        accumulator.current_context_zone_name().as_deref(),
+        Some("Nerub-ar Palace")
+    );
+    assert!(accumulator.is_recording_session_active());
+    assert!(accumulator.snapshot().important_events.is_empty());
+}
+
+#[test]
+fn parses_only_the_requested_line_range() {
+    let log = (0..5)
+        .map(build_party_kill_line)
+        .collect::<Vec<_>>()
+        .join("\r\n");
+
+    let (lines_read, events, reached_end_of_file) =
+        parse_log_line_range(log.as_bytes(), 2, 2).expect("range should parse");
+    assert_eq!(lines_read, 2);
+    assert!(!reached_end_of_file);
+    assert_eq!(
+        events
+            .iter()
+            .map(|event| event.line_number)
+            .collect::<Vec<_>>(),
+        vec![2, 3]
+    );
+
+    let (lines_read, events, reached_end_of_file) =
+        parse_log_line_range(log.as_bytes(), 4, 10).expect("range should parse");
+    assert_eq!(lines_read, 2);
+    assert!(reached_end_of_file);
+    assert_eq!(events.len(), 2);
+}
+
 #[test]
 fn captures_mythic_plus_key_level_from_challenge_start() {
     let mut accumulator = RecordingMetadataAccumulator::default();
@@ -1029,3 +1203,134 @@ fn ignores_unconscious_death_events() {
         "Unconscious deaths should be ignored"
     );
 }
+
+#[test]
+fn live_events_include_guids_only_when_requested() {
+    let mut accumulator = RecordingMetadataAccumulator::default();
+    accumulator.begin_recording_session(0.0);
+    let party_kill = accumulator
+        .consume_combat_log_line(&build_party_kill_line(1), 1.0)
+        .expect("party kill should be parsed");
+
+    let with_guids = party_kill
+        .clone()
+        .into_live_event(Some(1.0), true)
+        .expect("party kill should be a live event");
+    assert_eq!(
+        with_guids.source_guid.as_deref(),
+        Some("Player-1111-00000001")
+    );
+    assert_eq!(
+        with_guids.target_guid.as_deref(),
+        Some("Creature-0-0-0-0-1001-0000000000")
+    );
+
+    let without_guids = party_kill
+        .into_live_event(Some(1.0), false)
+        .expect("party kill should be a live event");
+    assert_eq!(without_guids.source_guid, None);
+    assert_eq!(without_guids.target_guid, None);
+}
+
+#[test]
+fn treats_zeroed_guids_as_missing() {
+    let mut accumulator = RecordingMetadataAccumulator::default();
+    accumulator.begin_recording_session(0.0);
+    let death_line = build_line(
+        "UNIT_DIED",
+        &[
+            "0000000000000000",
+            "nil",
+            "0x80000000",
+            "0x80000000",
+            "Player-3682-0B561573",
+            "\"Aliandria-Ragnaros-EU\"",
+            "0x512",
+            "0x80000000",
+        ],
+    );
+    let death = accumulator
+        .consume_combat_log_line(&death_line, 1.0)
+        .and_then(|event| event.into_live_event(Some(1.0), true))
+        .expect("unit death should be a live event");
+
+    assert_eq!(death.source_guid, None);
+    assert_eq!(death.target_guid.as_deref(), Some("Player-3682-0B561573"));
+}
+
+#[test]
+fn rate_limiter_coalesces_overflow_per_event_type() {
+    let live_event = |timestamp: f64, event_type: &str| CombatEvent {
+        timestamp,
+        event_type: event_type.to_string(),
+        source: None,
+        target: None,
+        source_guid: None,
+        target_guid: None,
+        session_id: None,
+        note: None,
+    };
+    let window_start = std::time::Instant::now();
+    let mut limiter = CombatEventRateLimiter::new();
+    assert!(limiter.roll_window(window_start).is_empty());
+
+    for index in 0..MAX_LIVE_COMBAT_EVENTS_PER_WINDOW {
+        assert!(limiter.try_admit(&live_event(index as f64, "PARTY_KILL")));
+    }
+    for _ in 0..3 {
+        assert!(!limiter.try_admit(&live_event(30.0, "UNIT_DIED")));
+    }
+    assert!(!limiter.try_admit(&live_event(31.0, "UNIT_DIED")));
+    assert!(!limiter.try_admit(&live_event(32.0, "PARTY_KILL")));
+    assert!(limiter.roll_window(window_start).is_empty());
+
+    let overflow = limiter.roll_window(window_start + LIVE_COMBAT_EVENT_WINDOW);
+
+    assert_eq!(overflow.len(), 2);
+    assert_eq!(overflow[0].event_type, "PARTY_KILL");
+    assert_eq!(overflow[0].suppressed_count, 1);
+    assert_eq!(overflow[1].event_type, "UNIT_DIED");
+    assert_eq!(overflow[1].suppressed_count, 4);
+    assert_eq!(overflow[1].timestamp, 31.0);
+    assert!(limiter.try_admit(&live_event(33.0, "UNIT_DIED")));
+}
+
+#[test]
+fn supported_combat_events_match_parser_normalization() {
+    for supported_event in SUPPORTED_COMBAT_EVENTS {
+        for raw_event_type in supported_event.raw_event_types {
+            assert_eq!(
+                normalize_important_event_type(raw_event_type),
+                Some(supported_event.event_type),
+                "{raw_event_type} should normalize to {}",
+                supported_event.event_type
+            );
+            assert_eq!(
+                is_context_only_event(raw_event_type),
+                supported_event.context_only,
+                "{raw_event_type} context-only flag is out of date"
+            );
+        }
+    }
+}
+
+#[test]
+fn event_stream_line_is_one_json_object_per_line() {
+    let event = CombatEvent {
+        timestamp: 12.5,
+        event_type: "UNIT_DIED".to_string(),
+        source: None,
+        target: Some("Boss".to_string()),
+        source_guid: None,
+        target_guid: None,
+        session_id: None,
+        note: None,
+    };
+    let line = event_stream_line(&event).expect("event serializes");
+
+    assert!(line.ends_with('\n'));
+    assert_eq!(line.matches('\n').count(), 1);
+    let parsed: serde_json::Value = serde_json::from_str(line.trim_end()).expect("valid JSON");
+    assert_eq!(parsed["eventType"], "UNIT_DIED");
+    assert_eq!(parsed["timestamp"], 12.5);
+}
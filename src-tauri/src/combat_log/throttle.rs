@@ -0,0 +1,65 @@
+use std::collections::BTreeMap;
+use std::time::Instant;
+
+use super::{
+    CombatEvent, CombatEventOverflow, LIVE_COMBAT_EVENT_WINDOW, MAX_LIVE_COMBAT_EVENTS_PER_WINDOW,
+};
+
+/// Caps live `combat-event` emissions per window. Events over the cap are counted per
+/// event type and reported as overflow summaries once the window rolls over.
+#[derive(Debug, Default)]
+pub(crate) struct CombatEventRateLimiter {
+    window_started_at: Option<Instant>,
+    emitted_in_window: usize,
+    suppressed: BTreeMap<String, CombatEventOverflow>,
+}
+
+impl CombatEventRateLimiter {
+    pub(crate) const fn new() -> Self {
+        Self {
+            window_started_at: None,
+            emitted_in_window: 0,
+            suppressed: BTreeMap::new(),
+        }
+    }
+
+    /// Starts a new window if the current one has elapsed and returns the overflow
+    /// summaries collected during the previous window.
+    pub(crate) fn roll_window(&mut self, now: Instant) -> Vec<CombatEventOverflow> {
+        let window_elapsed = self.window_started_at.is_none_or(|window_started_at| {
+            now.saturating_duration_since(window_started_at) >= LIVE_COMBAT_EVENT_WINDOW
+        });
+        if !window_elapsed {
+            return Vec::new();
+        }
+
+        self.window_started_at = Some(now);
+        self.emitted_in_window = 0;
+        self.take_overflow()
+    }
+
+    /// Returns the overflow summaries collected so far without waiting for the window
+    /// to elapse, e.g. when the watch stops.
+    pub(crate) fn take_overflow(&mut self) -> Vec<CombatEventOverflow> {
+        std::mem::take(&mut self.suppressed).into_values().collect()
+    }
+
+    pub(crate) fn try_admit(&mut self, event: &CombatEvent) -> bool {
+        if self.emitted_in_window < MAX_LIVE_COMBAT_EVENTS_PER_WINDOW {
+            self.emitted_in_window += 1;
+            return true;
+        }
+
+        let overflow = self
+            .suppressed
+            .entry(event.event_type.clone())
+            .or_insert_with(|| CombatEventOverflow {
+                timestamp: event.timestamp,
+                event_type: event.event_type.clone(),
+                suppressed_count: 0,
+            });
+        overflow.timestamp = event.timestamp;
+        overflow.suppressed_count += 1;
+        false
+    }
+}
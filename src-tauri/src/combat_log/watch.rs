@@ -2,23 +2,79 @@ use notify::{Event, EventKind, RecursiveMode, Watcher};
 use std::fs::File;
 use std::io::{BufRead, BufReader, Read, Seek, SeekFrom};
 use std::path::{Path, PathBuf};
+use std::sync::atomic::Ordering;
 use std::sync::{Arc, Mutex};
 use std::time::{Instant, SystemTime};
-use tauri::{AppHandle, Emitter};
+use tauri::{AppHandle, Emitter, Manager};
 use tokio::sync::mpsc;
 use tokio::task::JoinHandle;
 
+use crate::recording::SharedRecordingState;
+
 use super::metadata::{persist_recording_metadata_snapshot, RecordingMetadataAccumulator};
-use super::parse::{extract_combat_trigger_event, extract_log_timestamp, LogTimestamp};
-use super::{CombatEvent, CombatTriggerEvent, CombatWatchStatusEvent, EVENT_MANUAL_MARKER};
+use super::parse::{
+    detect_advanced_combat_logging, extract_combat_trigger_event, extract_log_timestamp,
+    LogTimestamp,
+};
+use super::stream::{broadcast_combat_event, start_event_stream, stop_event_stream};
+use super::throttle::CombatEventRateLimiter;
+use super::{
+    CombatEvent, CombatEventOverflow, CombatTriggerEvent, CombatWatchHeartbeatEvent,
+    CombatWatchOptions, CombatWatchStatusEvent, ADVANCED_COMBAT_LOGGING_DISABLED_MESSAGE,
+    COMBAT_LOG_POLL_INTERVAL, COMBAT_LOG_REPLAY_CHUNK_BYTES, COMBAT_WATCH_HEARTBEAT_INTERVAL,
+    EVENT_MANUAL_MARKER, MAX_MANUAL_MARKER_LEAD_SECS, MAX_MANUAL_MARKER_NOTE_CHARS,
+    METADATA_SIDECAR_FLUSH_INTERVAL,
+};
 
 struct WatchState {
     handle: Option<JoinHandle<()>>,
     start_time: Instant,
     recording_output_path: Option<PathBuf>,
     metadata_accumulator: Arc<Mutex<RecordingMetadataAccumulator>>,
+    log_cursor: Arc<Mutex<CombatLogCursor>>,
+    /// Shared with the watch task so a recording attached later reaches live events.
+    options: Arc<Mutex<CombatWatchOptions>>,
+}
+
+/// Where the watcher has read up to. Shared with the stop path so it can read the lines
+/// written after the last file notification before the watch goes away.
+struct CombatLogCursor {
+    log_path: PathBuf,
+    file_offset: u64,
+    /// Bytes read since the watch started, across log file switches.
+    bytes_processed: u64,
+    /// Set once a line showed whether Advanced Combat Logging is on, so the check and the
+    /// warning happen once per watch.
+    advanced_logging_checked: bool,
+    /// Set while the backlog is being replayed. A stop clears it to end the replay, and the
+    /// unread backlog is not flushed as live events.
+    replaying: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum CombatWatchMode {
+    /// File change notifications from `notify`.
+    Notify,
+    /// Checks the log's length and modification time on a timer.
+    Poll,
+}
+
+impl CombatWatchMode {
+    /// `auto` (or no value) polls network paths, where change notifications are often
+    /// never delivered.
+    pub(super) fn resolve(setting: Option<&str>, logs_directory: &Path) -> Self {
+        match setting.map(str::trim) {
+            Some("poll") => Self::Poll,
+            Some("notify") => Self::Notify,
+            _ if is_network_path(logs_directory) => Self::Poll,
+            _ => Self::Notify,
+        }
+    }
 }
 
+static LIVE_EVENT_RATE_LIMITER: Mutex<CombatEventRateLimiter> =
+    Mutex::new(CombatEventRateLimiter::new());
+
 lazy_static::lazy_static! {
     static ref WATCH_STATE: Arc<Mutex<Option<WatchState>>> = Arc::new(Mutex::new(None));
 }
@@ -26,21 +82,47 @@ lazy_static::lazy_static! {
 #[tauri::command]
 pub async fn start_combat_watch(
     app_handle: AppHandle,
+    recording_state: tauri::State<'_, SharedRecordingState>,
     wow_folder: String,
     recording_output_path: Option<String>,
+    options: Option<CombatWatchOptions>,
 ) -> Result<(), String> {
+    let mut options = options.unwrap_or_default();
+    options.marker_lead_secs = if options.marker_lead_secs.is_finite() {
+        options
+            .marker_lead_secs
+            .clamp(0.0, MAX_MANUAL_MARKER_LEAD_SECS)
+    } else {
+        0.0
+    };
+    if options.event_stream {
+        if let Err(error) = start_event_stream() {
+            emit_combat_watch_status(&app_handle, "warn", &error, None);
+        }
+    } else {
+        stop_event_stream();
+    }
+    let recording_started_at = active_recording_started_at(&recording_state).await;
     let mut state = WATCH_STATE.lock().map_err(|error| error.to_string())?;
 
     if let Some(watch_state) = state.as_mut() {
+        match watch_state.options.lock() {
+            Ok(mut current_options) => {
+                options.recording_session_id = current_options.recording_session_id.take();
+                *current_options = options;
+            }
+            Err(error) => tracing::warn!("Failed to lock combat watch options: {error}"),
+        }
         if let Some(output_path) =
             normalized_output_recording_path(recording_output_path.as_deref())
         {
-            begin_watch_recording_session(watch_state, output_path);
+            begin_watch_recording_session(watch_state, output_path, recording_started_at);
         }
         emit_combat_watch_status(&app_handle, "info", "Combatlog watcher active!", None);
         return Ok(());
     }
 
+    reset_combat_event_rate_limiter();
     let logs_directory = build_combat_log_directory_path(&wow_folder);
     let log_path = find_latest_combat_log_path(&wow_folder)?.ok_or_else(|| {
         format!(
@@ -50,16 +132,45 @@ pub async fn start_combat_watch(
         )
     })?;
 
-    let initial_offset = std::fs::metadata(&log_path)
-        .map_err(|error| error.to_string())?
-        .len();
+    // Replaying from the start rebuilds the zone/encounter context on its own, so the
+    // tail seed below is only needed when tailing from the current end of the log.
+    let from_beginning = options.from_beginning;
+    let initial_offset = if from_beginning {
+        0
+    } else {
+        std::fs::metadata(&log_path)
+            .map_err(|error| error.to_string())?
+            .len()
+    };
+
+    let watch_mode = CombatWatchMode::resolve(options.watch_mode.as_deref(), &logs_directory);
+    tracing::info!(
+        logs_directory = %logs_directory.display(),
+        watch_mode = ?watch_mode,
+        "Starting combat log watcher"
+    );
 
     let app_handle_clone = app_handle.clone();
     let logs_directory_clone = logs_directory.clone();
-    let log_path_clone = log_path.clone();
+    let log_cursor = Arc::new(Mutex::new(CombatLogCursor {
+        log_path: log_path.clone(),
+        file_offset: initial_offset,
+        bytes_processed: 0,
+        advanced_logging_checked: false,
+        replaying: from_beginning,
+    }));
+    let log_cursor_clone = Arc::clone(&log_cursor);
     let start_time = Instant::now();
     let metadata_accumulator = Arc::new(Mutex::new(RecordingMetadataAccumulator::default()));
-    if let Err(error) = seed_metadata_context_from_log_tail(&log_path, &metadata_accumulator) {
+    if from_beginning {
+        emit_combat_watch_status(
+            &app_handle,
+            "info",
+            "Replaying existing combat log before live tailing",
+            Some(&log_path),
+        );
+    } else if let Err(error) = seed_metadata_context_from_log_tail(&log_path, &metadata_accumulator)
+    {
         emit_combat_watch_status(
             &app_handle,
             "warn",
@@ -81,15 +192,18 @@ pub async fn start_combat_watch(
         }
     }
     let metadata_accumulator_clone = Arc::clone(&metadata_accumulator);
+    let options = Arc::new(Mutex::new(options));
+    let options_clone = Arc::clone(&options);
 
     let handle = tokio::spawn(async move {
         if let Err(error) = watch_combat_log(
             app_handle_clone,
             logs_directory_clone,
-            log_path_clone,
-            initial_offset,
+            log_cursor_clone,
+            options_clone,
             start_time,
             metadata_accumulator_clone,
+            watch_mode,
         )
         .await
         {
@@ -102,11 +216,13 @@ pub async fn start_combat_watch(
         start_time,
         recording_output_path: normalized_output_recording_path(recording_output_path.as_deref()),
         metadata_accumulator,
+        log_cursor,
+        options,
     });
 
     if let Some(watch_state) = state.as_mut() {
         if let Some(output_path) = watch_state.recording_output_path.clone() {
-            begin_watch_recording_session(watch_state, output_path);
+            begin_watch_recording_session(watch_state, output_path, recording_started_at);
         }
     }
 
@@ -127,6 +243,10 @@ fn normalized_output_recording_path(recording_output_path: Option<&str>) -> Opti
         .map(PathBuf::from)
 }
 
+pub(crate) fn is_combat_watch_running() -> bool {
+    WATCH_STATE.lock().is_ok_and(|state| state.is_some())
+}
+
 #[tauri::command]
 pub async fn stop_combat_watch(app_handle: AppHandle) -> Result<(), String> {
     let mut state = WATCH_STATE.lock().map_err(|error| error.to_string())?;
@@ -136,8 +256,11 @@ pub async fn stop_combat_watch(app_handle: AppHandle) -> Result<(), String> {
             handle.abort();
         }
 
+        flush_remaining_events(&app_handle, &watch_state);
         persist_watch_metadata_if_configured(&watch_state);
+        drain_combat_event_overflow(&app_handle);
     }
+    stop_event_stream();
 
     emit_combat_watch_status(&app_handle, "info", "Combatlog watcher stopped", None);
 
@@ -145,19 +268,24 @@ pub async fn stop_combat_watch(app_handle: AppHandle) -> Result<(), String> {
 }
 
 #[tauri::command]
-pub fn set_combat_watch_recording_output(
+pub async fn set_combat_watch_recording_output(
+    recording_state: tauri::State<'_, SharedRecordingState>,
     recording_output_path: Option<String>,
+    recording_session_id: Option<String>,
 ) -> Result<(), String> {
+    let recording_started_at = active_recording_started_at(&recording_state).await;
     let mut state = WATCH_STATE.lock().map_err(|error| error.to_string())?;
     let Some(watch_state) = state.as_mut() else {
         return Err("Combat watch not running".to_string());
     };
 
     if let Some(output_path) = normalized_output_recording_path(recording_output_path.as_deref()) {
-        begin_watch_recording_session(watch_state, output_path);
+        set_recording_session_id(watch_state, recording_session_id);
+        begin_watch_recording_session(watch_state, output_path, recording_started_at);
         return Ok(());
     }
 
+    set_recording_session_id(watch_state, None);
     persist_watch_metadata_if_configured(watch_state);
     watch_state.recording_output_path = None;
     match watch_state.metadata_accumulator.lock() {
@@ -173,9 +301,28 @@ pub fn set_combat_watch_recording_output(
     Ok(())
 }
 
-fn begin_watch_recording_session(watch_state: &mut WatchState, output_path: PathBuf) {
+async fn active_recording_started_at(recording_state: &SharedRecordingState) -> Option<Instant> {
+    let recording_state = recording_state.read().await;
+    recording_state
+        .is_recording
+        .then_some(recording_state.started_at)
+        .flatten()
+}
+
+/// Wall-clock marker times count from the recording's own start when it is known, not
+/// from whenever the output was attached to the watch, so they land on the right frame.
+/// The origin is negative when the recording started before the watch.
+fn begin_watch_recording_session(
+    watch_state: &mut WatchState,
+    output_path: PathBuf,
+    recording_started_at: Option<Instant>,
+) {
     watch_state.recording_output_path = Some(output_path);
-    let elapsed_seconds = watch_state.start_time.elapsed().as_secs_f64();
+    let watch_elapsed_seconds = watch_state.start_time.elapsed().as_secs_f64();
+    let elapsed_seconds = match recording_started_at {
+        Some(started_at) => watch_elapsed_seconds - started_at.elapsed().as_secs_f64(),
+        None => watch_elapsed_seconds,
+    };
 
     match watch_state.metadata_accumulator.lock() {
         Ok(mut metadata_accumulator) => {
@@ -223,6 +370,31 @@ fn seed_metadata_context_from_log_tail(
     Ok(())
 }
 
+/// Reads whatever was appended since the last notification so events written just before
+/// the stop still reach the UI and the recording metadata.
+fn flush_remaining_events(app_handle: &AppHandle, watch_state: &WatchState) {
+    let mut log_cursor = match watch_state.log_cursor.lock() {
+        Ok(log_cursor) => log_cursor,
+        Err(error) => {
+            tracing::warn!("Failed to lock combat log cursor for final flush: {error}");
+            return;
+        }
+    };
+    if log_cursor.replaying {
+        log_cursor.replaying = false;
+        return;
+    }
+    if let Err(error) = read_and_emit_new_events(
+        app_handle,
+        &mut log_cursor,
+        watch_state.start_time,
+        &watch_state.metadata_accumulator,
+        &watch_state.options,
+    ) {
+        tracing::warn!("Failed to flush remaining combat log lines: {error}");
+    }
+}
+
 fn persist_watch_metadata_if_configured(watch_state: &WatchState) {
     let Some(recording_output_path) = watch_state.recording_output_path.as_deref() else {
         return;
@@ -240,9 +412,36 @@ fn persist_watch_metadata_if_configured(watch_state: &WatchState) {
     }
 }
 
+/// Writes the sidecar if recording data changed since the last write. Called from the watch
+/// loops on `METADATA_SIDECAR_FLUSH_INTERVAL` so a burst of events costs one write.
+fn flush_pending_watch_metadata() {
+    let state = match WATCH_STATE.lock() {
+        Ok(state) => state,
+        Err(error) => {
+            tracing::warn!("Failed to lock combat watch state for metadata flush: {error}");
+            return;
+        }
+    };
+    let Some(watch_state) = state.as_ref() else {
+        return;
+    };
+    if watch_state.recording_output_path.is_none() {
+        return;
+    }
+
+    let has_unflushed_changes = watch_state
+        .metadata_accumulator
+        .lock()
+        .map(|metadata_accumulator| metadata_accumulator.has_unflushed_changes())
+        .unwrap_or(false);
+    if has_unflushed_changes {
+        persist_watch_metadata_if_configured(watch_state);
+    }
+}
+
 #[tauri::command]
 pub fn validate_wow_folder(path: String) -> bool {
-    if path.trim().is_empty() {
+    if path.trim().is_empty() || !has_unc_server_and_share(&path) {
         return false;
     }
 
@@ -254,19 +453,57 @@ pub fn validate_wow_folder(path: String) -> bool {
 
 #[tauri::command]
 pub async fn emit_manual_marker(app_handle: AppHandle) -> Result<(), String> {
+    record_manual_marker(app_handle, None).await
+}
+
+/// Drops a manual marker annotated with `note`, e.g. "boss phase 2 transition".
+#[tauri::command]
+pub async fn emit_manual_marker_note(app_handle: AppHandle, note: String) -> Result<(), String> {
+    let note = note.trim();
+    if note.is_empty() {
+        return Err("Marker note must not be empty".to_string());
+    }
+    if note.chars().count() > MAX_MANUAL_MARKER_NOTE_CHARS {
+        return Err(format!(
+            "Marker note must be at most {MAX_MANUAL_MARKER_NOTE_CHARS} characters"
+        ));
+    }
+
+    record_manual_marker(app_handle, Some(note.to_string())).await
+}
+
+async fn record_manual_marker(app_handle: AppHandle, note: Option<String>) -> Result<(), String> {
+    let marker_tone_pending = app_handle
+        .state::<SharedRecordingState>()
+        .read()
+        .await
+        .marker_tone_pending
+        .clone();
     let state = WATCH_STATE.lock().map_err(|error| error.to_string())?;
 
     if let Some(watch_state) = state.as_ref() {
         let elapsed = watch_state.start_time.elapsed().as_secs_f64();
+        let (lead_seconds, session_id) = watch_state
+            .options
+            .lock()
+            .map(|options| {
+                (
+                    options.marker_lead_secs,
+                    options.recording_session_id.clone(),
+                )
+            })
+            .unwrap_or_default();
         let mut should_emit_event = false;
         let mut event_timestamp = elapsed;
 
         match watch_state.metadata_accumulator.lock() {
             Ok(mut metadata_accumulator) => {
                 if metadata_accumulator.is_recording_session_active() {
-                    metadata_accumulator.record_manual_marker(elapsed);
+                    let marker_elapsed =
+                        metadata_accumulator.manual_marker_elapsed_seconds(elapsed, lead_seconds);
+                    metadata_accumulator.record_manual_marker(marker_elapsed, note.clone());
                     if let Some(recording_elapsed_seconds) =
-                        metadata_accumulator.recording_elapsed_seconds(elapsed, None)
+                        metadata_accumulator.recording_elapsed_seconds(marker_elapsed, None)
                     {
                         event_timestamp = recording_elapsed_seconds;
                     }
@@ -282,11 +519,18 @@ pub async fn emit_manual_marker(app_handle: AppHandle) -> Result<(), String> {
         }
 
         if should_emit_event {
+            if let Some(marker_tone_pending) = &marker_tone_pending {
+                marker_tone_pending.store(true, Ordering::Relaxed);
+            }
             let event = CombatEvent {
                 timestamp: event_timestamp,
                 event_type: EVENT_MANUAL_MARKER.to_string(),
                 source: None,
                 target: None,
+                source_guid: None,
+                target_guid: None,
+                session_id,
+                note,
             };
             emit_combat_event(&app_handle, &event);
         }
@@ -297,7 +541,17 @@ pub async fn emit_manual_marker(app_handle: AppHandle) -> Result<(), String> {
     Err("Combat watch not running".to_string())
 }
 
+fn set_recording_session_id(watch_state: &WatchState, session_id: Option<String>) {
+    match watch_state.options.lock() {
+        Ok(mut options) => {
+            options.recording_session_id = session_id.filter(|value| !value.trim().is_empty())
+        }
+        Err(error) => tracing::warn!("Failed to lock combat watch options: {error}"),
+    }
+}
+
 fn emit_combat_event(app_handle: &AppHandle, event: &CombatEvent) {
+    broadcast_combat_event(event);
     if let Err(error) = app_handle.emit("combat-event", event) {
         tracing::warn!(
             event_type = %event.event_type,
@@ -307,6 +561,72 @@ fn emit_combat_event(app_handle: &AppHandle, event: &CombatEvent) {
     }
 }
 
+/// Manual markers bypass this and are always emitted; only parsed log events are capped.
+fn emit_rate_limited_combat_event(app_handle: &AppHandle, event: &CombatEvent) {
+    let (overflow, admitted) = match LIVE_EVENT_RATE_LIMITER.lock() {
+        Ok(mut limiter) => {
+            let overflow = limiter.roll_window(Instant::now());
+            (overflow, limiter.try_admit(event))
+        }
+        Err(error) => {
+            tracing::warn!("Failed to lock combat event rate limiter: {error}");
+            (Vec::new(), true)
+        }
+    };
+
+    emit_combat_event_overflow(app_handle, &overflow);
+    if admitted {
+        emit_combat_event(app_handle, event);
+    }
+}
+
+fn flush_combat_event_overflow(app_handle: &AppHandle) {
+    let overflow = match LIVE_EVENT_RATE_LIMITER.lock() {
+        Ok(mut limiter) => limiter.roll_window(Instant::now()),
+        Err(error) => {
+            tracing::warn!("Failed to lock combat event rate limiter: {error}");
+            return;
+        }
+    };
+    emit_combat_event_overflow(app_handle, &overflow);
+}
+
+/// Emits whatever is still suppressed and starts the next watch with an empty window.
+fn drain_combat_event_overflow(app_handle: &AppHandle) {
+    let overflow = match LIVE_EVENT_RATE_LIMITER.lock() {
+        Ok(mut limiter) => {
+            let overflow = limiter.take_overflow();
+            *limiter = CombatEventRateLimiter::new();
+            overflow
+        }
+        Err(error) => {
+            tracing::warn!("Failed to lock combat event rate limiter: {error}");
+            return;
+        }
+    };
+    emit_combat_event_overflow(app_handle, &overflow);
+}
+
+fn reset_combat_event_rate_limiter() {
+    match LIVE_EVENT_RATE_LIMITER.lock() {
+        Ok(mut limiter) => *limiter = CombatEventRateLimiter::new(),
+        Err(error) => tracing::warn!("Failed to lock combat event rate limiter: {error}"),
+    }
+}
+
+fn emit_combat_event_overflow(app_handle: &AppHandle, overflow: &[CombatEventOverflow]) {
+    for summary in overflow {
+        if let Err(error) = app_handle.emit("combat-event-overflow", summary) {
+            tracing::warn!(
+                event_type = %summary.event_type,
+                suppressed_count = summary.suppressed_count,
+                emit_error = %error,
+                "Failed to emit combat event overflow"
+            );
+        }
+    }
+}
+
 fn emit_combat_trigger_event(app_handle: &AppHandle, event: &CombatTriggerEvent) {
     if let Err(error) = app_handle.emit("combat-trigger", event) {
         tracing::warn!(
@@ -334,6 +654,71 @@ fn emit_combat_watch_status(
     }
 }
 
+fn emit_combat_log_advanced_disabled(app_handle: &AppHandle, log_path: &Path) {
+    tracing::warn!(
+        log_path = %log_path.display(),
+        "Combat log was written without Advanced Combat Logging"
+    );
+    let status_event = CombatWatchStatusEvent {
+        level: "warn".to_string(),
+        message: ADVANCED_COMBAT_LOGGING_DISABLED_MESSAGE.to_string(),
+        watched_log_path: Some(log_path.to_string_lossy().to_string()),
+    };
+
+    if let Err(error) = app_handle.emit("combat-log-advanced-disabled", status_event) {
+        tracing::warn!(emit_error = %error, "Failed to emit advanced combat logging warning");
+    }
+}
+
+fn emit_combat_watch_heartbeat(app_handle: &AppHandle, log_cursor: &Mutex<CombatLogCursor>) {
+    let heartbeat_event = match log_cursor.lock() {
+        Ok(log_cursor) => CombatWatchHeartbeatEvent {
+            watched_log_path: log_cursor.log_path.to_string_lossy().to_string(),
+            file_offset: log_cursor.file_offset,
+            bytes_processed: log_cursor.bytes_processed,
+        },
+        Err(error) => {
+            tracing::warn!("Failed to lock combat log cursor for heartbeat: {error}");
+            return;
+        }
+    };
+
+    if let Err(error) = app_handle.emit("combat-watch-heartbeat", heartbeat_event) {
+        tracing::warn!(emit_error = %error, "Failed to emit combat watch heartbeat event");
+    }
+}
+
+/// UNC paths in either separator style, including the verbatim `\\?\UNC\` form. Mapped
+/// network drives look local and need the poll mode selected explicitly.
+pub(super) fn is_network_path(path: &Path) -> bool {
+    let normalized = path.to_string_lossy().replace('/', "\\");
+    if let Some(verbatim) = normalized.strip_prefix(r"\\?\") {
+        return verbatim
+            .get(..4)
+            .is_some_and(|prefix| prefix.eq_ignore_ascii_case(r"UNC\"));
+    }
+    normalized.starts_with(r"\\") && !normalized.starts_with(r"\\.\")
+}
+
+/// A UNC path has to name at least a server and a share; `\\server` alone cannot be read.
+pub(super) fn has_unc_server_and_share(path: &str) -> bool {
+    let normalized = path.trim().replace('/', "\\");
+    if !is_network_path(Path::new(&normalized)) {
+        return true;
+    }
+
+    let unc_path = normalized
+        .strip_prefix(r"\\?\UNC\")
+        .or_else(|| normalized.strip_prefix(r"\\?\unc\"))
+        .or_else(|| normalized.strip_prefix(r"\\"))
+        .unwrap_or(&normalized);
+    unc_path
+        .split('\\')
+        .filter(|component| !component.is_empty())
+        .count()
+        >= 2
+}
+
 fn build_combat_log_directory_path(wow_folder: &str) -> PathBuf {
     let candidate_path = Path::new(wow_folder);
     let is_logs_directory = candidate_path
@@ -406,11 +791,32 @@ fn find_latest_combat_log_in_directory(logs_directory: &Path) -> Result<Option<P
 async fn watch_combat_log(
     app_handle: AppHandle,
     logs_directory: PathBuf,
-    initial_log_path: PathBuf,
-    initial_offset: u64,
+    log_cursor: Arc<Mutex<CombatLogCursor>>,
+    options: Arc<Mutex<CombatWatchOptions>>,
     start_time: Instant,
     metadata_accumulator: Arc<Mutex<RecordingMetadataAccumulator>>,
+    watch_mode: CombatWatchMode,
 ) -> Result<(), String> {
+    let replay_existing = options
+        .lock()
+        .map(|options| options.from_beginning)
+        .map_err(|error| error.to_string())?;
+    if watch_mode == CombatWatchMode::Poll {
+        if replay_existing {
+            replay_existing_events(&app_handle, &log_cursor, &metadata_accumulator, &options).await;
+        }
+        poll_combat_log(
+            &app_handle,
+            &logs_directory,
+            &log_cursor,
+            start_time,
+            &metadata_accumulator,
+            &options,
+        )
+        .await;
+        return Ok(());
+    }
+
     let (notify_sender, mut notify_receiver) =
         mpsc::unbounded_channel::<Result<Event, notify::Error>>();
 
@@ -425,38 +831,48 @@ async fn watch_combat_log(
         .watch(&logs_directory, RecursiveMode::NonRecursive)
         .map_err(|error| error.to_string())?;
 
-    let mut current_log_path = initial_log_path;
-    let mut file_offset = initial_offset;
-    while let Some(notification_result) = notify_receiver.recv().await {
+    if replay_existing {
+        replay_existing_events(&app_handle, &log_cursor, &metadata_accumulator, &options).await;
+    }
+
+    // Heartbeats are sent from this loop, so they stop if the notification channel closes
+    // or the task dies; the UI treats a missing heartbeat as a stalled watch.
+    let mut next_heartbeat_at = tokio::time::Instant::now() + COMBAT_WATCH_HEARTBEAT_INTERVAL;
+    let mut next_metadata_flush_at = tokio::time::Instant::now() + METADATA_SIDECAR_FLUSH_INTERVAL;
+    loop {
+        if tokio::time::Instant::now() >= next_heartbeat_at {
+            emit_combat_watch_heartbeat(&app_handle, &log_cursor);
+            flush_combat_event_overflow(&app_handle);
+            next_heartbeat_at = tokio::time::Instant::now() + COMBAT_WATCH_HEARTBEAT_INTERVAL;
+        }
+        if tokio::time::Instant::now() >= next_metadata_flush_at {
+            flush_pending_watch_metadata();
+            next_metadata_flush_at = tokio::time::Instant::now() + METADATA_SIDECAR_FLUSH_INTERVAL;
+        }
+
+        let wake_at = next_heartbeat_at.min(next_metadata_flush_at);
+        let notification_result =
+            match tokio::time::timeout_at(wake_at, notify_receiver.recv()).await {
+                Ok(Some(notification_result)) => notification_result,
+                Ok(None) => break,
+                Err(_) => continue,
+            };
+
         match notification_result {
             Ok(event) => {
                 if !is_relevant_notification(&event) {
                     continue;
                 }
 
-                if let Some(latest_log_path) = find_latest_combat_log_in_directory(&logs_directory)?
-                {
-                    if latest_log_path != current_log_path {
-                        current_log_path = latest_log_path.clone();
-                        file_offset = 0;
-                        // emit_combat_watch_status(
-                        //     &app_handle,
-                        //     "info",
-                        //     "Switched watched combat log file",
-                        //     Some(&latest_log_path),
-                        // );
-                    }
-                }
-
-                if let Err(error) = read_and_emit_new_events(
+                let latest_log_path = find_latest_combat_log_in_directory(&logs_directory)?;
+                read_latest_combat_log(
                     &app_handle,
-                    &current_log_path,
-                    &mut file_offset,
+                    &log_cursor,
+                    latest_log_path,
                     start_time,
                     &metadata_accumulator,
-                ) {
-                    tracing::warn!("Failed to parse combat log update: {error}");
-                }
+                    &options,
+                )?;
             }
             Err(error) => {
                 tracing::warn!("Combat log watcher error: {error}");
@@ -467,6 +883,268 @@ async fn watch_combat_log(
     Ok(())
 }
 
+/// Network filesystems often never deliver change notifications, so this checks the newest
+/// log on a timer and reads only when its length or modification time changed. Lookup
+/// errors are logged and retried, since a share can drop out briefly.
+async fn poll_combat_log(
+    app_handle: &AppHandle,
+    logs_directory: &Path,
+    log_cursor: &Mutex<CombatLogCursor>,
+    start_time: Instant,
+    metadata_accumulator: &Arc<Mutex<RecordingMetadataAccumulator>>,
+    options: &Mutex<CombatWatchOptions>,
+) {
+    let mut last_seen_log_state: Option<(PathBuf, u64, Option<SystemTime>)> = None;
+    let mut poll_interval = tokio::time::interval(COMBAT_LOG_POLL_INTERVAL);
+    poll_interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+    let mut next_heartbeat_at = tokio::time::Instant::now() + COMBAT_WATCH_HEARTBEAT_INTERVAL;
+    let mut next_metadata_flush_at = tokio::time::Instant::now() + METADATA_SIDECAR_FLUSH_INTERVAL;
+    loop {
+        poll_interval.tick().await;
+        if tokio::time::Instant::now() >= next_heartbeat_at {
+            emit_combat_watch_heartbeat(app_handle, log_cursor);
+            flush_combat_event_overflow(app_handle);
+            next_heartbeat_at = tokio::time::Instant::now() + COMBAT_WATCH_HEARTBEAT_INTERVAL;
+        }
+        if tokio::time::Instant::now() >= next_metadata_flush_at {
+            flush_pending_watch_metadata();
+            next_metadata_flush_at = tokio::time::Instant::now() + METADATA_SIDECAR_FLUSH_INTERVAL;
+        }
+
+        let latest_log_path = match find_latest_combat_log_in_directory(logs_directory) {
+            Ok(Some(latest_log_path)) => latest_log_path,
+            Ok(None) => continue,
+            Err(error) => {
+                tracing::warn!("Failed to poll combat log directory: {error}");
+                continue;
+            }
+        };
+
+        // Metadata from an open handle, because SMB clients cache path-based lookups
+        // for several seconds.
+        let log_metadata = match File::open(&latest_log_path).and_then(|file| file.metadata()) {
+            Ok(log_metadata) => log_metadata,
+            Err(error) => {
+                tracing::warn!("Failed to poll combat log file: {error}");
+                continue;
+            }
+        };
+        let log_state = (
+            latest_log_path,
+            log_metadata.len(),
+            log_metadata.modified().ok(),
+        );
+        if last_seen_log_state.as_ref() == Some(&log_state) {
+            continue;
+        }
+
+        let latest_log_path = log_state.0.clone();
+        last_seen_log_state = Some(log_state);
+        if let Err(error) = read_latest_combat_log(
+            app_handle,
+            log_cursor,
+            Some(latest_log_path),
+            start_time,
+            metadata_accumulator,
+            options,
+        ) {
+            tracing::warn!("Failed to read polled combat log: {error}");
+        }
+    }
+}
+
+/// Moves the cursor to `latest_log_path` when WoW started a new log file, then reads the
+/// lines appended since the cursor.
+fn read_latest_combat_log(
+    app_handle: &AppHandle,
+    log_cursor: &Mutex<CombatLogCursor>,
+    latest_log_path: Option<PathBuf>,
+    start_time: Instant,
+    metadata_accumulator: &Arc<Mutex<RecordingMetadataAccumulator>>,
+    options: &Mutex<CombatWatchOptions>,
+) -> Result<(), String> {
+    let mut log_cursor = log_cursor.lock().map_err(|error| error.to_string())?;
+    if let Some(latest_log_path) = latest_log_path {
+        if latest_log_path != log_cursor.log_path {
+            log_cursor.log_path = latest_log_path.clone();
+            log_cursor.file_offset = 0;
+            // emit_combat_watch_status(
+            //     &app_handle,
+            //     "info",
+            //     "Switched watched combat log file",
+            //     Some(&latest_log_path),
+            // );
+        }
+    }
+
+    if let Err(error) = read_and_emit_new_events(
+        app_handle,
+        &mut log_cursor,
+        start_time,
+        metadata_accumulator,
+        options,
+    ) {
+        tracing::warn!("Failed to parse combat log update: {error}");
+    }
+    Ok(())
+}
+
+/// Catches up on lines already in the log before live tailing starts. Kills and deaths in
+/// the backlog are emitted through the live event rate limiter, timed from the first
+/// backlog line, so a finished session can be reviewed. The backlog also rebuilds zone,
+/// encounter and roster context, but nothing is written to the recording metadata, and
+/// its trigger events are dropped: they describe encounters that are already over and
+/// must not start or stop a recording.
+async fn replay_existing_events(
+    app_handle: &AppHandle,
+    log_cursor: &Arc<Mutex<CombatLogCursor>>,
+    metadata_accumulator: &Arc<Mutex<RecordingMetadataAccumulator>>,
+    options: &Arc<Mutex<CombatWatchOptions>>,
+) {
+    let replay_app_handle = app_handle.clone();
+    let replay_cursor = Arc::clone(log_cursor);
+    let replay_accumulator = Arc::clone(metadata_accumulator);
+    let replay_options = Arc::clone(options);
+    let replay_result = tokio::task::spawn_blocking(move || {
+        let result = replay_log_backlog(
+            &replay_app_handle,
+            &replay_cursor,
+            &replay_accumulator,
+            &replay_options,
+        );
+        if let Ok(mut log_cursor) = replay_cursor.lock() {
+            log_cursor.replaying = false;
+        }
+        result
+    })
+    .await;
+
+    match replay_result {
+        Ok(Ok(())) => {}
+        Ok(Err(error)) => tracing::warn!("Failed to replay existing combat log: {error}"),
+        Err(error) => tracing::warn!("Combat log replay task failed: {error}"),
+    }
+}
+
+/// Replays the log in chunks, releasing the cursor between them so a stop that arrives
+/// meanwhile does not wait for the whole backlog. The replay ends at the first chunk after
+/// the stop cleared `replaying`.
+fn replay_log_backlog(
+    app_handle: &AppHandle,
+    log_cursor: &Mutex<CombatLogCursor>,
+    metadata_accumulator: &Mutex<RecordingMetadataAccumulator>,
+    options: &Mutex<CombatWatchOptions>,
+) -> Result<(), String> {
+    let include_guids = options
+        .lock()
+        .map(|options| options.include_guids)
+        .map_err(|error| error.to_string())?;
+    let mut replay_origin_seconds = None;
+    loop {
+        let lines = {
+            let mut log_cursor = log_cursor.lock().map_err(|error| error.to_string())?;
+            if !log_cursor.replaying {
+                return Ok(());
+            }
+            read_log_chunk(app_handle, &mut log_cursor)?
+        };
+        if lines.is_empty() {
+            return Ok(());
+        }
+
+        let mut replayed_events = Vec::new();
+        {
+            let mut accumulator = metadata_accumulator
+                .lock()
+                .map_err(|error| error.to_string())?;
+            for line in &lines {
+                let Some(parsed_event) = accumulator.consume_context_line(line) else {
+                    continue;
+                };
+                let Some(log_timestamp_seconds) = line_log_timestamp_seconds(line) else {
+                    continue;
+                };
+                let origin_seconds = *replay_origin_seconds.get_or_insert(log_timestamp_seconds);
+                let mut replay_seconds = log_timestamp_seconds - origin_seconds;
+                if replay_seconds < 0.0 {
+                    // The backlog crossed midnight.
+                    replay_seconds += 86400.0;
+                }
+                replayed_events
+                    .extend(parsed_event.into_live_event(Some(replay_seconds), include_guids));
+            }
+        }
+
+        for event in &replayed_events {
+            emit_rate_limited_combat_event(app_handle, event);
+        }
+        flush_combat_event_overflow(app_handle);
+    }
+}
+
+/// Log clock time of a combat log line, in seconds since midnight.
+fn line_log_timestamp_seconds(line: &str) -> Option<f64> {
+    let header = line.trim().split(',').next()?;
+    LogTimestamp::parse(&extract_log_timestamp(header)).map(|t| t.to_seconds_since_midnight())
+}
+
+/// Reads whole lines from the cursor until about `COMBAT_LOG_REPLAY_CHUNK_BYTES` were read
+/// or the end of the log is reached.
+fn read_log_chunk(
+    app_handle: &AppHandle,
+    log_cursor: &mut CombatLogCursor,
+) -> Result<Vec<String>, String> {
+    let mut reader = open_log_at_cursor(log_cursor)?;
+    let mut lines = Vec::new();
+    let mut chunk_bytes = 0_u64;
+    while chunk_bytes < COMBAT_LOG_REPLAY_CHUNK_BYTES {
+        let mut line = String::new();
+        let bytes_read = reader
+            .read_line(&mut line)
+            .map_err(|error| error.to_string())?;
+        if bytes_read == 0 {
+            break;
+        }
+
+        chunk_bytes = chunk_bytes.saturating_add(bytes_read as u64);
+        advance_log_cursor(app_handle, log_cursor, &line, bytes_read);
+        lines.push(line);
+    }
+    Ok(lines)
+}
+
+/// Opens the watched log at the cursor, starting over when the file was truncated.
+fn open_log_at_cursor(log_cursor: &mut CombatLogCursor) -> Result<BufReader<File>, String> {
+    let mut file = File::open(&log_cursor.log_path).map_err(|error| error.to_string())?;
+    let file_length = file.metadata().map_err(|error| error.to_string())?.len();
+
+    if file_length < log_cursor.file_offset {
+        log_cursor.file_offset = 0;
+    }
+
+    file.seek(SeekFrom::Start(log_cursor.file_offset))
+        .map_err(|error| error.to_string())?;
+    Ok(BufReader::new(file))
+}
+
+fn advance_log_cursor(
+    app_handle: &AppHandle,
+    log_cursor: &mut CombatLogCursor,
+    line: &str,
+    bytes_read: usize,
+) {
+    log_cursor.file_offset = log_cursor.file_offset.saturating_add(bytes_read as u64);
+    log_cursor.bytes_processed = log_cursor.bytes_processed.saturating_add(bytes_read as u64);
+    if !log_cursor.advanced_logging_checked {
+        if let Some(advanced_logging_enabled) = detect_advanced_combat_logging(line) {
+            log_cursor.advanced_logging_checked = true;
+            if !advanced_logging_enabled {
+                emit_combat_log_advanced_disabled(app_handle, &log_cursor.log_path);
+            }
+        }
+    }
+}
+
 fn is_relevant_notification(event: &Event) -> bool {
     let relevant_kind = matches!(event.kind, EventKind::Modify(_) | EventKind::Create(_));
     if !relevant_kind {
@@ -483,22 +1161,16 @@ fn is_relevant_notification(event: &Event) -> bool {
 
 fn read_and_emit_new_events(
     app_handle: &AppHandle,
-    log_path: &Path,
-    file_offset: &mut u64,
+    log_cursor: &mut CombatLogCursor,
     start_time: Instant,
     metadata_accumulator: &Arc<Mutex<RecordingMetadataAccumulator>>,
+    options: &Mutex<CombatWatchOptions>,
 ) -> Result<(), String> {
-    let mut file = File::open(log_path).map_err(|error| error.to_string())?;
-    let file_length = file.metadata().map_err(|error| error.to_string())?.len();
-
-    if file_length < *file_offset {
-        *file_offset = 0;
-    }
-
-    file.seek(SeekFrom::Start(*file_offset))
+    let (include_guids, session_id) = options
+        .lock()
+        .map(|options| (options.include_guids, options.recording_session_id.clone()))
         .map_err(|error| error.to_string())?;
-
-    let mut reader = BufReader::new(file);
+    let mut reader = open_log_at_cursor(log_cursor)?;
     let mut line = String::new();
 
     loop {
@@ -510,12 +1182,10 @@ fn read_and_emit_new_events(
             break;
         }
 
-        *file_offset = file_offset.saturating_add(bytes_read as u64);
+        advance_log_cursor(app_handle, log_cursor, &line, bytes_read);
+
         let elapsed_seconds = start_time.elapsed().as_secs_f64();
-        let log_timestamp_seconds = line.trim().split(',').next().and_then(|header| {
-            let ts = extract_log_timestamp(header);
-            LogTimestamp::parse(&ts).map(|t| t.to_seconds_since_midnight())
-        });
+        let log_timestamp_seconds = line_log_timestamp_seconds(&line);
         let (parsed_event, recording_active, recording_elapsed_seconds) = {
             let mut accumulator = metadata_accumulator
                 .lock()
@@ -532,13 +1202,16 @@ fn read_and_emit_new_events(
         }
 
         if recording_active {
-            if let Some(event) =
-                parsed_event.and_then(|value| value.into_live_event(recording_elapsed_seconds))
+            if let Some(mut event) = parsed_event
+                .and_then(|value| value.into_live_event(recording_elapsed_seconds, include_guids))
             {
-                emit_combat_event(app_handle, &event);
+                event.session_id = session_id.clone();
+                emit_rate_limited_combat_event(app_handle, &event);
             }
         }
     }
 
+    flush_combat_event_overflow(app_handle);
+
     Ok(())
 }
@@ -1,6 +1,6 @@
 use std::sync::Mutex;
 use tauri::AppHandle;
-use tauri_plugin_global_shortcut::{GlobalShortcutExt, ShortcutState};
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut, ShortcutState};
 
 use std::sync::LazyLock;
 
@@ -48,6 +48,36 @@ pub async fn register_marker_hotkey(app_handle: AppHandle, hotkey: String) -> Re
     Ok(())
 }
 
+/// Reports whether `shortcut` could be registered, by registering and immediately
+/// unregistering it. A shortcut FloorPoV already holds counts as available.
+#[tauri::command]
+pub async fn test_hotkey(app_handle: AppHandle, shortcut: String) -> Result<bool, String> {
+    if shortcut == "none" {
+        return Ok(true);
+    }
+
+    let parsed_shortcut = shortcut
+        .parse::<Shortcut>()
+        .map_err(|e| format!("Invalid hotkey '{}': {}", shortcut, e))?;
+    let global_shortcut = app_handle.global_shortcut();
+    if global_shortcut.is_registered(parsed_shortcut) {
+        return Ok(true);
+    }
+
+    match global_shortcut.register(parsed_shortcut) {
+        Ok(()) => {
+            if let Err(e) = global_shortcut.unregister(parsed_shortcut) {
+                tracing::warn!("Failed to unregister tested hotkey '{}': {}", shortcut, e);
+            }
+            Ok(true)
+        }
+        Err(e) => {
+            tracing::info!("Hotkey '{}' is not available: {}", shortcut, e);
+            Ok(false)
+        }
+    }
+}
+
 #[tauri::command]
 pub async fn unregister_marker_hotkey(app_handle: AppHandle) -> Result<(), String> {
     let mut current = CURRENT_HOTKEY.lock().map_err(|e| e.to_string())?;
@@ -1,5 +1,6 @@
 mod combat_log;
 mod hotkey;
+mod logging;
 mod recording;
 mod settings;
 mod wcl_upload;
@@ -22,11 +23,8 @@ pub fn run() {
         "--disable-features=HardwareMediaKeyHandling",
     );
 
-    let _ = tracing_subscriber::fmt()
-        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
-        .try_init();
-
     let recording_state = Arc::new(RwLock::new(recording::RecordingState::new()));
+    let audio_monitor_state = Arc::new(RwLock::new(recording::AudioMonitorState::default()));
 
     tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
@@ -36,8 +34,11 @@ pub fn run() {
         .plugin(tauri_plugin_global_shortcut::Builder::new().build())
         .plugin(tauri_plugin_updater::Builder::new().build())
         .manage(recording_state)
+        .manage(audio_monitor_state)
         .manage(wcl_upload::WclAuthService::new())
         .setup(|app| {
+            logging::init(app.handle());
+
             let main_window = app
                 .get_webview_window("main")
                 .ok_or_else(|| "Main application window was not created".to_string())?;
@@ -85,17 +86,54 @@ pub fn run() {
                 tracing::info!("Registered asset scope for output folder '{output_folder}'");
             }
 
+            recording::offer_crashed_recording_recovery(app.handle(), &output_folder);
+
             Ok(())
         })
         .invoke_handler(tauri::generate_handler![
             is_debug_build,
+            logging::get_log_directory,
+            logging::open_logs,
+            recording::estimate_recording_size,
             recording::start_recording,
             recording::stop_recording,
+            recording::stop_recording_and_wait,
+            recording::start_audio_monitor,
+            recording::stop_audio_monitor,
+            recording::get_recording_status,
             recording::list_capture_windows,
+            recording::get_window_thumbnail,
+            recording::list_capture_monitors,
+            recording::list_audio_output_devices,
+            recording::list_audio_sessions,
+            recording::get_current_audio_device,
+            recording::switch_audio_device,
             recording::get_available_video_encoders,
+            recording::get_capture_diagnostics,
+            recording::list_orphaned_recordings,
+            recording::recover_crashed_recording,
+            recording::get_frame_at,
+            recording::reconcile_markers,
+            recording::import_markers,
+            recording::compare_recordings,
+            recording::repair_recording,
+            recording::annotate_recording,
+            recording::verify_recording,
+            recording::concat_recordings,
+            recording::cancel_job,
+            recording::stop_all,
+            settings::list_recording_presets,
+            settings::list_profiles,
+            settings::save_profile,
+            settings::load_profile,
+            settings::delete_profile,
             settings::get_default_output_folder,
+            settings::set_output_folder,
             settings::get_folder_size,
             settings::get_recordings_list,
+            settings::get_recordings_page,
+            settings::refresh_recordings_cache,
+            settings::prune_orphaned_sidecars,
             settings::get_recording_metadata,
             settings::delete_recording,
             settings::cleanup_old_recordings,
@@ -104,7 +142,10 @@ pub fn run() {
             combat_log::watch::set_combat_watch_recording_output,
             combat_log::watch::validate_wow_folder,
             combat_log::watch::emit_manual_marker,
+            combat_log::watch::emit_manual_marker_note,
             combat_log::debug::parse_combat_log_file,
+            combat_log::debug::parse_combat_log_range,
+            combat_log::debug::supported_combat_events,
             wcl_upload::start_wcl_upload,
             wcl_upload::cancel_wcl_upload,
             wcl_upload::get_latest_combat_log_path,
@@ -119,7 +160,15 @@ pub fn run() {
             wcl_upload::get_wcl_live_upload_state,
             hotkey::register_marker_hotkey,
             hotkey::unregister_marker_hotkey,
+            hotkey::test_hotkey,
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app_handle, event| {
+            if let tauri::RunEvent::ExitRequested { api, .. } = event {
+                if recording::defer_exit_for_active_recording(app_handle) {
+                    api.prevent_exit();
+                }
+            }
+        });
 }
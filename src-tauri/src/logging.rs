@@ -0,0 +1,150 @@
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager};
+use tauri_plugin_opener::OpenerExt;
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{fmt, EnvFilter};
+
+const LOG_FILE_PREFIX: &str = "floorpov";
+const LOG_FILE_SUFFIX: &str = "log";
+const MAX_LOG_FILES: usize = 14;
+const FFMPEG_LOG_FILE_PREFIX: &str = "ffmpeg-";
+const MAX_FFMPEG_SESSION_LOG_FILES: usize = 20;
+const DEFAULT_LOG_FILTER: &str = "info";
+
+fn resolve_log_directory(app_handle: &AppHandle) -> Result<PathBuf, String> {
+    app_handle
+        .path()
+        .app_log_dir()
+        .map_err(|error| format!("Failed to resolve log directory: {error}"))
+}
+
+fn build_file_appender(log_directory: &Path) -> Result<RollingFileAppender, String> {
+    std::fs::create_dir_all(log_directory).map_err(|error| {
+        format!(
+            "Failed to create log directory '{}': {error}",
+            log_directory.display()
+        )
+    })?;
+
+    RollingFileAppender::builder()
+        .rotation(Rotation::DAILY)
+        .filename_prefix(LOG_FILE_PREFIX)
+        .filename_suffix(LOG_FILE_SUFFIX)
+        .max_log_files(MAX_LOG_FILES)
+        .build(log_directory)
+        .map_err(|error| format!("Failed to create log file appender: {error}"))
+}
+
+/// Deletes all but the newest `MAX_FFMPEG_SESSION_LOG_FILES` FFmpeg session logs, never
+/// touching `current_log_path`. Failures only cost disk space, so they are logged.
+fn prune_ffmpeg_session_logs(log_directory: &Path, current_log_path: &Path) {
+    let entries = match std::fs::read_dir(log_directory) {
+        Ok(entries) => entries,
+        Err(error) => {
+            tracing::warn!("Failed to list FFmpeg session logs: {error}");
+            return;
+        }
+    };
+    let mut session_logs: Vec<(std::time::SystemTime, PathBuf)> = entries
+        .filter_map(Result::ok)
+        .filter(|entry| {
+            let file_name = entry.file_name();
+            let file_name = file_name.to_string_lossy();
+            file_name.starts_with(FFMPEG_LOG_FILE_PREFIX)
+                && file_name.ends_with(&format!(".{LOG_FILE_SUFFIX}"))
+        })
+        .map(|entry| entry.path())
+        .filter(|path| path != current_log_path)
+        .map(|path| {
+            let modified_at = std::fs::metadata(&path)
+                .and_then(|metadata| metadata.modified())
+                .unwrap_or(std::time::UNIX_EPOCH);
+            (modified_at, path)
+        })
+        .collect();
+    // The current session's log counts toward the limit.
+    let keep_count = MAX_FFMPEG_SESSION_LOG_FILES.saturating_sub(1);
+    if session_logs.len() <= keep_count {
+        return;
+    }
+
+    session_logs.sort_by_key(|(modified_at, _)| std::cmp::Reverse(*modified_at));
+    for (_, path) in session_logs.into_iter().skip(keep_count) {
+        if let Err(error) = std::fs::remove_file(&path) {
+            tracing::warn!(
+                log_path = %path.display(),
+                "Failed to delete old FFmpeg session log: {error}"
+            );
+        }
+    }
+}
+
+/// Log file for the FFmpeg output of one recording session, in the app log directory.
+/// Opening one prunes the logs of older sessions.
+pub(crate) fn ffmpeg_session_log_path(
+    app_handle: &AppHandle,
+    session_id: &str,
+) -> Result<PathBuf, String> {
+    let log_directory = resolve_log_directory(app_handle)?;
+    std::fs::create_dir_all(&log_directory).map_err(|error| {
+        format!(
+            "Failed to create log directory '{}': {error}",
+            log_directory.display()
+        )
+    })?;
+    let log_path = log_directory.join(format!(
+        "{FFMPEG_LOG_FILE_PREFIX}{session_id}.{LOG_FILE_SUFFIX}"
+    ));
+    prune_ffmpeg_session_logs(&log_directory, &log_path);
+    Ok(log_path)
+}
+
+/// Release builds have no console, so logs also go to a daily rotating file in the app
+/// log directory. `RUST_LOG` still overrides the default filter for both outputs.
+pub(crate) fn init(app_handle: &AppHandle) {
+    let env_filter =
+        EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(DEFAULT_LOG_FILTER));
+    let (file_layer, file_error) =
+        match resolve_log_directory(app_handle).and_then(|dir| build_file_appender(&dir)) {
+            Ok(file_appender) => (
+                Some(fmt::layer().with_ansi(false).with_writer(file_appender)),
+                None,
+            ),
+            Err(error) => (None, Some(error)),
+        };
+
+    let _ = tracing_subscriber::registry()
+        .with(env_filter)
+        .with(fmt::layer())
+        .with(file_layer)
+        .try_init();
+
+    if let Some(error) = file_error {
+        tracing::warn!("File logging is unavailable: {error}");
+    }
+}
+
+#[tauri::command]
+pub fn get_log_directory(app_handle: AppHandle) -> Result<String, String> {
+    Ok(resolve_log_directory(&app_handle)?
+        .to_string_lossy()
+        .to_string())
+}
+
+#[tauri::command]
+pub fn open_logs(app_handle: AppHandle) -> Result<(), String> {
+    let log_directory = resolve_log_directory(&app_handle)?;
+    std::fs::create_dir_all(&log_directory).map_err(|error| {
+        format!(
+            "Failed to create log directory '{}': {error}",
+            log_directory.display()
+        )
+    })?;
+
+    app_handle
+        .opener()
+        .open_path(log_directory.to_string_lossy(), None::<&str>)
+        .map_err(|error| format!("Failed to open log directory: {error}"))
+}
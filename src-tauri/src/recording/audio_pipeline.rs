@@ -1,32 +1,142 @@
-use std::collections::VecDeque;
+use std::collections::{BTreeSet, VecDeque};
 use std::io::Write;
-use std::sync::atomic::Ordering;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::mpsc as std_mpsc;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
-use wasapi::{initialize_mta, DeviceEnumerator, Direction, SampleType, StreamMode, WaveFormat};
+use wasapi::{
+    initialize_mta, DeviceEnumerator, Direction, SampleType, SessionState, StreamMode, WasapiError,
+    WaveFormat,
+};
 
 use super::model::{
-    AudioPipelineStats, SYSTEM_AUDIO_BITS_PER_SAMPLE, SYSTEM_AUDIO_CHANNEL_COUNT,
-    SYSTEM_AUDIO_CHUNK_FRAMES, SYSTEM_AUDIO_EVENT_TIMEOUT, SYSTEM_AUDIO_SAMPLE_RATE_HZ,
+    AudioLevelPayload, AudioOutputDeviceInfo, AudioPipelineStats, AudioSessionInfo,
+    CurrentAudioDeviceInfo, MARKER_TONE_AMPLITUDE, MARKER_TONE_FRAMES, MARKER_TONE_FREQUENCY_HZ,
+    SYSTEM_AUDIO_BITS_PER_SAMPLE, SYSTEM_AUDIO_CHANNEL_COUNT, SYSTEM_AUDIO_CHUNK_FRAMES,
+    SYSTEM_AUDIO_EVENT_TIMEOUT, SYSTEM_AUDIO_INIT_ATTEMPTS, SYSTEM_AUDIO_INIT_INITIAL_BACKOFF,
+    SYSTEM_AUDIO_REINIT_ERROR_THRESHOLD, SYSTEM_AUDIO_SAMPLE_RATE_HZ,
 };
+use super::window_capture::resolve_process_name;
+
+/// `AUDCLNT_E_DEVICE_IN_USE`: another application holds the endpoint in exclusive mode.
+const AUDCLNT_E_DEVICE_IN_USE: u32 = 0x8889_000A;
+
+/// Endpoint the recording's loopback capture was asked to switch to. `Some(None)` is the
+/// default output device. Cleared when a new recording starts.
+static LOOPBACK_DEVICE_OVERRIDE: Mutex<Option<Option<String>>> = Mutex::new(None);
+/// Bumped on every switch request so the capture thread notices it.
+static LOOPBACK_DEVICE_SWITCH_GENERATION: AtomicU64 = AtomicU64::new(0);
+static ACTIVE_LOOPBACK_DEVICE: Mutex<Option<CurrentAudioDeviceInfo>> = Mutex::new(None);
+
+type LoopbackCaptureContext = (
+    wasapi::Device,
+    wasapi::AudioClient,
+    wasapi::AudioCaptureClient,
+    WaveFormat,
+);
+
+/// Keeps exclusive-mode failures apart from other init errors: they do not clear within
+/// the init backoff, and a running recording reports them as a warning.
+enum LoopbackInitError {
+    DeviceInUse(String),
+    Failed(String),
+}
+
+impl From<String> for LoopbackInitError {
+    fn from(message: String) -> Self {
+        Self::Failed(message)
+    }
+}
+
+impl std::fmt::Display for LoopbackInitError {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::DeviceInUse(message) | Self::Failed(message) => formatter.write_str(message),
+        }
+    }
+}
+
+impl From<LoopbackInitError> for String {
+    fn from(error: LoopbackInitError) -> Self {
+        error.to_string()
+    }
+}
+
+fn is_device_in_use_error(error: &WasapiError) -> bool {
+    matches!(error, WasapiError::Windows(error) if error.code().0 as u32 == AUDCLNT_E_DEVICE_IN_USE)
+}
+
+/// Windows does not say which application holds the exclusive stream, so this names the
+/// one other process with an active session on the device, if there is exactly one.
+fn exclusive_mode_owner(device: &wasapi::Device) -> Option<String> {
+    let sessions = device
+        .get_iaudiosessionmanager()
+        .and_then(|manager| manager.get_audiosessionenumerator())
+        .ok()?;
+    let own_process_id = std::process::id();
+    let owners: BTreeSet<String> = (0..sessions.get_count().ok()?)
+        .filter_map(|index| sessions.get_session(index).ok())
+        .filter(|session| matches!(session.get_state(), Ok(SessionState::Active)))
+        .filter_map(|session| session.get_process_id().ok())
+        .filter(|process_id| *process_id != own_process_id)
+        .filter_map(resolve_process_name)
+        .collect();
+
+    if owners.len() == 1 {
+        owners.into_iter().next()
+    } else {
+        None
+    }
+}
+
+fn device_in_use_message(device: &wasapi::Device) -> String {
+    let device_name = device
+        .get_friendlyname()
+        .unwrap_or_else(|_| "the output audio device".to_string());
+    let owner = exclusive_mode_owner(device)
+        .map(|owner| format!("Another application ({owner})"))
+        .unwrap_or_else(|| "Another application".to_string());
+    format!(
+        "{owner} is using {device_name} in exclusive mode, so system audio cannot be recorded. Close it, or untick \"Allow applications to take exclusive control of this device\" in the device's Sound properties."
+    )
+}
 
+/// `session_process_id` captures only the audio sessions of that process (and its
+/// children) through process loopback instead of everything played on the device.
 fn build_loopback_capture_context(
-) -> Result<(wasapi::AudioClient, wasapi::AudioCaptureClient, WaveFormat), String> {
+    device_id: Option<&str>,
+    session_process_id: Option<u32>,
+) -> Result<LoopbackCaptureContext, LoopbackInitError> {
     initialize_mta()
         .ok()
         .map_err(|error| format!("Failed to initialize COM for system audio capture: {error}"))?;
 
     let enumerator = DeviceEnumerator::new()
         .map_err(|error| format!("Failed to enumerate audio devices: {error}"))?;
-    let device = enumerator
-        .get_default_device(&Direction::Render)
-        .map_err(|error| format!("Failed to access default output audio device: {error}"))?;
-    let mut audio_client = device
-        .get_iaudioclient()
-        .map_err(|error| format!("Failed to create WASAPI audio client: {error}"))?;
+    let device = match device_id {
+        Some(device_id) => enumerator
+            .get_device(device_id)
+            .map_err(|error| format!("Failed to access selected output audio device: {error}"))?,
+        None => enumerator
+            .get_default_device(&Direction::Render)
+            .map_err(|error| format!("Failed to access default output audio device: {error}"))?,
+    };
+    let mut audio_client = match session_process_id {
+        Some(process_id) => {
+            wasapi::AudioClient::new_application_loopback_client(process_id, true).map_err(
+                |error| {
+                    format!(
+                        "Failed to create loopback client for the audio session of process {process_id}: {error}"
+                    )
+                },
+            )?
+        }
+        None => device
+            .get_iaudioclient()
+            .map_err(|error| format!("Failed to create WASAPI audio client: {error}"))?,
+    };
 
     let wave_format = WaveFormat::new(
         SYSTEM_AUDIO_BITS_PER_SAMPLE,
@@ -44,37 +154,445 @@ fn build_loopback_capture_context(
     audio_client
         .initialize_client(&wave_format, &Direction::Capture, &mode)
         .map_err(|error| {
-            format!("Failed to initialize WASAPI loopback client for system audio: {error}")
+            if is_device_in_use_error(&error) {
+                LoopbackInitError::DeviceInUse(device_in_use_message(&device))
+            } else {
+                LoopbackInitError::Failed(format!(
+                    "Failed to initialize WASAPI loopback client for system audio: {error}"
+                ))
+            }
         })?;
 
     let capture_client = audio_client
         .get_audiocaptureclient()
         .map_err(|error| format!("Failed to create WASAPI capture client: {error}"))?;
 
-    Ok((audio_client, capture_client, wave_format))
+    Ok((device, audio_client, capture_client, wave_format))
+}
+
+/// Device switches briefly leave no default render endpoint, so initialization is
+/// retried with backoff before the failure is reported. An exclusive-mode hold is
+/// reported straight away.
+fn build_loopback_capture_context_with_retry(
+    device_id: Option<&str>,
+    session_process_id: Option<u32>,
+) -> Result<LoopbackCaptureContext, LoopbackInitError> {
+    let mut backoff = SYSTEM_AUDIO_INIT_INITIAL_BACKOFF;
+    let mut attempt = 1;
+    loop {
+        match build_loopback_capture_context(device_id, session_process_id) {
+            Ok(context) => return Ok(context),
+            Err(LoopbackInitError::Failed(error)) if attempt < SYSTEM_AUDIO_INIT_ATTEMPTS => {
+                tracing::warn!(
+                    attempt,
+                    max_attempts = SYSTEM_AUDIO_INIT_ATTEMPTS,
+                    retry_in_ms = backoff.as_millis() as u64,
+                    "System audio initialization failed; retrying: {error}"
+                );
+                thread::sleep(backoff);
+                backoff = backoff.saturating_mul(2);
+                attempt += 1;
+            }
+            Err(error) => return Err(error),
+        }
+    }
 }
 
-pub(crate) fn validate_system_audio_capture_available() -> Result<(), String> {
-    let _ = build_loopback_capture_context()?;
+pub(crate) fn validate_system_audio_capture_available(
+    device_id: Option<&str>,
+    session_process_id: Option<u32>,
+) -> Result<(), String> {
+    build_loopback_capture_context_with_retry(device_id, session_process_id)?;
     Ok(())
 }
 
+/// Headless hosts and RDP sessions can have no active render endpoint at all, leaving
+/// nothing to loop back. Enumeration errors count as "has a device" so the regular
+/// capture validation reports them.
+pub(crate) fn has_active_output_device() -> bool {
+    if initialize_mta().is_err() {
+        return true;
+    }
+
+    DeviceEnumerator::new()
+        .and_then(|enumerator| enumerator.get_device_collection(&Direction::Render))
+        .and_then(|collection| collection.get_nbr_devices())
+        .map(|device_count| device_count > 0)
+        .unwrap_or(true)
+}
+
+pub(crate) fn list_audio_output_devices() -> Result<Vec<AudioOutputDeviceInfo>, String> {
+    initialize_mta()
+        .ok()
+        .map_err(|error| format!("Failed to initialize COM for audio device listing: {error}"))?;
+
+    let enumerator = DeviceEnumerator::new()
+        .map_err(|error| format!("Failed to enumerate audio devices: {error}"))?;
+    let default_device_id = enumerator
+        .get_default_device(&Direction::Render)
+        .and_then(|device| device.get_id())
+        .ok();
+    let collection = enumerator
+        .get_device_collection(&Direction::Render)
+        .map_err(|error| format!("Failed to list output audio devices: {error}"))?;
+
+    let mut devices = Vec::new();
+    for device in &collection {
+        let device = match device {
+            Ok(device) => device,
+            Err(error) => {
+                tracing::warn!("Skipping unreadable output audio device: {error}");
+                continue;
+            }
+        };
+        let (Ok(id), Ok(name)) = (device.get_id(), device.get_friendlyname()) else {
+            continue;
+        };
+        devices.push(AudioOutputDeviceInfo {
+            is_default: default_device_id.as_deref() == Some(id.as_str()),
+            id,
+            name,
+        });
+    }
+
+    Ok(devices)
+}
+
+/// Audio sessions on every output device, one per process and device. The system sounds
+/// session (process 0) and this app's own sessions are left out.
+pub(crate) fn list_audio_sessions() -> Result<Vec<AudioSessionInfo>, String> {
+    initialize_mta()
+        .ok()
+        .map_err(|error| format!("Failed to initialize COM for audio session listing: {error}"))?;
+
+    let enumerator = DeviceEnumerator::new()
+        .map_err(|error| format!("Failed to enumerate audio devices: {error}"))?;
+    let collection = enumerator
+        .get_device_collection(&Direction::Render)
+        .map_err(|error| format!("Failed to list output audio devices: {error}"))?;
+    let own_process_id = std::process::id();
+
+    let mut sessions = Vec::new();
+    for device in &collection {
+        let Ok(device) = device else {
+            continue;
+        };
+        let (Ok(device_id), Ok(device_name)) = (device.get_id(), device.get_friendlyname()) else {
+            continue;
+        };
+        let session_enumerator = match device
+            .get_iaudiosessionmanager()
+            .and_then(|manager| manager.get_audiosessionenumerator())
+        {
+            Ok(session_enumerator) => session_enumerator,
+            Err(error) => {
+                tracing::warn!(
+                    audio_device = %device_name,
+                    "Skipping audio sessions of output device: {error}"
+                );
+                continue;
+            }
+        };
+
+        for index in 0..session_enumerator.get_count().unwrap_or(0) {
+            let Ok(session) = session_enumerator.get_session(index) else {
+                continue;
+            };
+            let Ok(process_id) = session.get_process_id() else {
+                continue;
+            };
+            if process_id == 0 || process_id == own_process_id {
+                continue;
+            }
+            let session_id = format!("{device_id}|{process_id}");
+            if sessions
+                .iter()
+                .any(|existing: &AudioSessionInfo| existing.session_id == session_id)
+            {
+                continue;
+            }
+
+            sessions.push(AudioSessionInfo {
+                session_id,
+                process_id,
+                app_name: resolve_process_name(process_id),
+                device_id: device_id.clone(),
+                device_name: device_name.clone(),
+                is_active: matches!(session.get_state(), Ok(SessionState::Active)),
+            });
+        }
+    }
+
+    Ok(sessions)
+}
+
+/// Picks the process whose audio the `system_audio_session` setting names, matching the
+/// executable name case-insensitively. A process that is playing audio wins over one
+/// whose session is idle.
+pub(crate) fn select_audio_session_process_id(
+    app_name: &str,
+    sessions: &[AudioSessionInfo],
+) -> Option<u32> {
+    let app_name = app_name.trim();
+    let mut matches = sessions.iter().filter(|session| {
+        session
+            .app_name
+            .as_deref()
+            .is_some_and(|name| name.eq_ignore_ascii_case(app_name))
+    });
+    let first_match = matches.next()?;
+    if first_match.is_active {
+        return Some(first_match.process_id);
+    }
+    Some(
+        matches
+            .find(|session| session.is_active)
+            .unwrap_or(first_match)
+            .process_id,
+    )
+}
+
+/// HDMI and DisplayPort audio endpoints are usually named after the attached display,
+/// e.g. "LG TV SSCR2 (NVIDIA High Definition Audio)" for the monitor "LG TV SSCR2".
+/// Generic monitor names and ambiguous matches are ignored.
+pub(crate) fn match_monitor_audio_device<'a>(
+    monitor_name: &str,
+    devices: &'a [AudioOutputDeviceInfo],
+) -> Option<&'a AudioOutputDeviceInfo> {
+    let monitor_name = monitor_name.trim().to_lowercase();
+    if monitor_name.is_empty() || monitor_name.starts_with("generic ") {
+        return None;
+    }
+
+    let mut matches = devices
+        .iter()
+        .filter(|device| device.name.to_lowercase().contains(&monitor_name));
+    let matched_device = matches.next()?;
+    matches.next().is_none().then_some(matched_device)
+}
+
+/// Name and mix format of an output device, or of the default output for `None`, without
+/// starting a capture stream.
+pub(crate) fn describe_output_device(
+    device_id: Option<&str>,
+) -> Result<CurrentAudioDeviceInfo, String> {
+    initialize_mta()
+        .ok()
+        .map_err(|error| format!("Failed to initialize COM for audio device lookup: {error}"))?;
+
+    let enumerator = DeviceEnumerator::new()
+        .map_err(|error| format!("Failed to enumerate audio devices: {error}"))?;
+    let default_device_id = enumerator
+        .get_default_device(&Direction::Render)
+        .and_then(|device| device.get_id())
+        .ok();
+    let device = match device_id {
+        Some(device_id) => enumerator
+            .get_device(device_id)
+            .map_err(|error| format!("Failed to access selected output audio device: {error}"))?,
+        None => enumerator
+            .get_default_device(&Direction::Render)
+            .map_err(|error| format!("Failed to access default output audio device: {error}"))?,
+    };
+    let mix_format = device
+        .get_iaudioclient()
+        .and_then(|audio_client| audio_client.get_mixformat())
+        .map_err(|error| format!("Failed to read output audio device format: {error}"))?;
+    let id = device.get_id().unwrap_or_default();
+
+    Ok(CurrentAudioDeviceInfo {
+        is_default: default_device_id.as_deref() == Some(id.as_str()),
+        id,
+        name: device.get_friendlyname().unwrap_or_default(),
+        sample_rate_hz: mix_format.get_samplespersec(),
+        channels: mix_format.get_nchannels(),
+        bits_per_sample: mix_format.get_bitspersample(),
+    })
+}
+
+/// Resolves the `system_audio_device` setting: `default` follows the default output,
+/// `auto` prefers the capture monitor's own audio endpoint, anything else is an
+/// endpoint id chosen by the user.
+pub(crate) fn resolve_system_audio_device_id(
+    setting: &str,
+    capture_monitor_name: Option<&str>,
+) -> Option<String> {
+    match setting {
+        "default" | "" => None,
+        "auto" => {
+            let monitor_name = capture_monitor_name?;
+            let devices = match list_audio_output_devices() {
+                Ok(devices) => devices,
+                Err(error) => {
+                    tracing::warn!("Failed to match capture monitor audio device: {error}");
+                    return None;
+                }
+            };
+            let matched_device = match_monitor_audio_device(monitor_name, &devices)?;
+            tracing::info!(
+                monitor_name,
+                audio_device = %matched_device.name,
+                "Using the capture monitor's audio output for system audio"
+            );
+            Some(matched_device.id.clone())
+        }
+        device_id => Some(device_id.to_string()),
+    }
+}
+
+/// Asks the running recording's loopback capture to move to another output device. The
+/// choice also applies to capture segments started later in the same recording.
+pub(crate) fn request_loopback_device_switch(device_id: Option<String>) -> Result<(), String> {
+    let mut device_override = LOOPBACK_DEVICE_OVERRIDE
+        .lock()
+        .map_err(|error| error.to_string())?;
+    *device_override = Some(device_id);
+    LOOPBACK_DEVICE_SWITCH_GENERATION.fetch_add(1, Ordering::Relaxed);
+    Ok(())
+}
+
+pub(crate) fn clear_loopback_device_override() {
+    match LOOPBACK_DEVICE_OVERRIDE.lock() {
+        Ok(mut device_override) => *device_override = None,
+        Err(error) => tracing::warn!("Failed to lock system audio device override: {error}"),
+    }
+}
+
+/// The device the recording is currently looping back, or `None` when no recording
+/// captures system audio (or a capture segment is restarting).
+pub(crate) fn current_loopback_device() -> Result<Option<CurrentAudioDeviceInfo>, String> {
+    ACTIVE_LOOPBACK_DEVICE
+        .lock()
+        .map(|active_device| active_device.clone())
+        .map_err(|error| error.to_string())
+}
+
+fn set_active_loopback_device(device: Option<CurrentAudioDeviceInfo>) {
+    match ACTIVE_LOOPBACK_DEVICE.lock() {
+        Ok(mut active_device) => *active_device = device,
+        Err(error) => tracing::warn!("Failed to lock active system audio device: {error}"),
+    }
+}
+
+fn set_device_in_use_warning(stats: &AudioPipelineStats, message: Option<String>) {
+    match stats.device_in_use_warning.lock() {
+        Ok(mut warning) => *warning = message,
+        Err(error) => tracing::warn!("Failed to lock system audio device warning: {error}"),
+    }
+}
+
+/// Returns the overridden device when a switch was requested, else the configured one.
+fn loopback_device_for_recording(configured_device_id: Option<&str>) -> Option<String> {
+    match LOOPBACK_DEVICE_OVERRIDE.lock() {
+        Ok(device_override) => device_override
+            .clone()
+            .unwrap_or_else(|| configured_device_id.map(ToString::to_string)),
+        Err(error) => {
+            tracing::warn!("Failed to lock system audio device override: {error}");
+            configured_device_id.map(ToString::to_string)
+        }
+    }
+}
+
+struct LoopbackStream {
+    audio_client: wasapi::AudioClient,
+    capture_client: wasapi::AudioCaptureClient,
+    event_handle: wasapi::Handle,
+    chunk_size_bytes: usize,
+    device_info: CurrentAudioDeviceInfo,
+}
+
+impl LoopbackStream {
+    /// Falls back to the default output device if the selected endpoint is unavailable.
+    fn open(
+        device_id: Option<&str>,
+        session_process_id: Option<u32>,
+    ) -> Result<Self, LoopbackInitError> {
+        match (Self::open_device(device_id, session_process_id), device_id) {
+            (Err(error), Some(device_id)) => {
+                tracing::warn!(
+                    device_id,
+                    "Selected system audio device is unavailable; using the default output: {error}"
+                );
+                Self::open_device(None, session_process_id)
+            }
+            (result, _) => result,
+        }
+    }
+
+    fn open_device(
+        device_id: Option<&str>,
+        session_process_id: Option<u32>,
+    ) -> Result<Self, LoopbackInitError> {
+        let (device, audio_client, capture_client, wave_format) =
+            build_loopback_capture_context_with_retry(device_id, session_process_id)?;
+        // Process loopback clients cannot report a mix format; they deliver the requested one.
+        let mix_format = match session_process_id {
+            Some(_) => wave_format.clone(),
+            None => audio_client
+                .get_mixformat()
+                .map_err(|error| format!("Failed to read output audio device format: {error}"))?,
+        };
+        let default_device_id = DeviceEnumerator::new()
+            .and_then(|enumerator| enumerator.get_default_device(&Direction::Render))
+            .and_then(|default_device| default_device.get_id())
+            .ok();
+        let id = device.get_id().unwrap_or_default();
+        let device_info = CurrentAudioDeviceInfo {
+            is_default: default_device_id.as_deref() == Some(id.as_str()),
+            id,
+            name: device.get_friendlyname().unwrap_or_default(),
+            sample_rate_hz: mix_format.get_samplespersec(),
+            channels: mix_format.get_nchannels(),
+            bits_per_sample: mix_format.get_bitspersample(),
+        };
+        let event_handle = audio_client
+            .set_get_eventhandle()
+            .map_err(|error| format!("Failed to configure WASAPI event handle: {error}"))?;
+
+        audio_client
+            .start_stream()
+            .map_err(|error| format!("Failed to start system audio stream: {error}"))?;
+
+        Ok(Self {
+            audio_client,
+            capture_client,
+            event_handle,
+            chunk_size_bytes: wave_format.get_blockalign() as usize * SYSTEM_AUDIO_CHUNK_FRAMES,
+            device_info,
+        })
+    }
+
+    fn stop(&self) {
+        if let Err(error) = self.audio_client.stop_stream() {
+            tracing::warn!("Failed to stop system audio stream cleanly: {error}");
+        }
+    }
+}
+
+/// `is_recording_capture` marks the recording's own capture: it is reported by
+/// `current_loopback_device` and follows `request_loopback_device_switch`, while the
+/// level meter's capture is left alone.
 pub(crate) fn run_system_audio_capture_to_queue(
     audio_tx: std_mpsc::SyncSender<Vec<u8>>,
     stop_rx: std_mpsc::Receiver<()>,
     stats: Arc<AudioPipelineStats>,
+    device_id: Option<&str>,
+    session_process_id: Option<u32>,
+    is_recording_capture: bool,
 ) -> Result<(), String> {
-    let (audio_client, capture_client, wave_format) = build_loopback_capture_context()?;
-    let event_handle = audio_client
-        .set_get_eventhandle()
-        .map_err(|error| format!("Failed to configure WASAPI event handle: {error}"))?;
-
-    audio_client
-        .start_stream()
-        .map_err(|error| format!("Failed to start system audio stream: {error}"))?;
-
+    let mut seen_switch_generation = LOOPBACK_DEVICE_SWITCH_GENERATION.load(Ordering::Relaxed);
+    let mut device_id = if is_recording_capture {
+        loopback_device_for_recording(device_id)
+    } else {
+        device_id.map(ToString::to_string)
+    };
+    let mut stream = LoopbackStream::open(device_id.as_deref(), session_process_id)?;
+    if is_recording_capture {
+        set_active_loopback_device(Some(stream.device_info.clone()));
+    }
     let mut sample_queue: VecDeque<u8> = VecDeque::new();
-    let chunk_size_bytes = wave_format.get_blockalign() as usize * SYSTEM_AUDIO_CHUNK_FRAMES;
+    let mut consecutive_device_errors = 0u32;
     let mut should_stop = false;
     loop {
         match stop_rx.try_recv() {
@@ -84,26 +602,86 @@ pub(crate) fn run_system_audio_capture_to_queue(
             Err(std_mpsc::TryRecvError::Empty) => {}
         }
 
-        let next_packet_frames = match capture_client.get_next_packet_size() {
+        let switch_generation = LOOPBACK_DEVICE_SWITCH_GENERATION.load(Ordering::Relaxed);
+        if is_recording_capture && switch_generation != seen_switch_generation && !should_stop {
+            seen_switch_generation = switch_generation;
+            let requested_device_id = loopback_device_for_recording(device_id.as_deref());
+            // The new stream is opened before the old one stops and the sample queue is
+            // kept, so the only audio lost is what plays while the new client starts.
+            match LoopbackStream::open_device(requested_device_id.as_deref(), session_process_id) {
+                Ok(switched_stream) => {
+                    tracing::info!(
+                        audio_device = %switched_stream.device_info.name,
+                        "Switched system audio capture to a new output device"
+                    );
+                    stream.stop();
+                    stream = switched_stream;
+                    device_id = requested_device_id;
+                    consecutive_device_errors = 0;
+                    set_active_loopback_device(Some(stream.device_info.clone()));
+                }
+                Err(error) => {
+                    tracing::warn!("Failed to switch system audio device: {error}");
+                }
+            }
+        }
+
+        if consecutive_device_errors >= SYSTEM_AUDIO_REINIT_ERROR_THRESHOLD && !should_stop {
+            // The endpoint was most likely invalidated by a device switch; keep the audio
+            // stream alive by reopening the new default device instead of giving up.
+            tracing::warn!(
+                consecutive_device_errors,
+                "System audio device appears lost; reinitializing loopback capture"
+            );
+            stream.stop();
+            match LoopbackStream::open(device_id.as_deref(), session_process_id) {
+                Ok(reopened_stream) => {
+                    tracing::info!("System audio loopback capture reinitialized");
+                    stream = reopened_stream;
+                    consecutive_device_errors = 0;
+                    set_device_in_use_warning(&stats, None);
+                    if is_recording_capture {
+                        set_active_loopback_device(Some(stream.device_info.clone()));
+                    }
+                }
+                Err(error) => {
+                    tracing::warn!("Failed to reinitialize system audio capture: {error}");
+                    // Keeps retrying until the exclusive stream lets go of the device.
+                    if let LoopbackInitError::DeviceInUse(message) = error {
+                        set_device_in_use_warning(&stats, Some(message));
+                    }
+                    thread::sleep(SYSTEM_AUDIO_EVENT_TIMEOUT);
+                    continue;
+                }
+            }
+        }
+
+        let next_packet_frames = match stream.capture_client.get_next_packet_size() {
             Ok(packet_size) => packet_size.unwrap_or(0),
             Err(error) => {
                 tracing::warn!("Failed to poll system audio packets: {error}");
+                consecutive_device_errors = consecutive_device_errors.saturating_add(1);
                 thread::sleep(Duration::from_millis(10));
                 continue;
             }
         };
 
         if next_packet_frames > 0 {
-            if let Err(error) = capture_client.read_from_device_to_deque(&mut sample_queue) {
+            if let Err(error) = stream
+                .capture_client
+                .read_from_device_to_deque(&mut sample_queue)
+            {
                 tracing::warn!("Failed to read system audio packet: {error}");
+                consecutive_device_errors = consecutive_device_errors.saturating_add(1);
                 thread::sleep(Duration::from_millis(10));
                 continue;
             }
         }
+        consecutive_device_errors = 0;
 
-        while sample_queue.len() >= chunk_size_bytes {
-            let mut chunk = Vec::with_capacity(chunk_size_bytes);
-            chunk.extend(sample_queue.drain(..chunk_size_bytes));
+        while sample_queue.len() >= stream.chunk_size_bytes {
+            let mut chunk = Vec::with_capacity(stream.chunk_size_bytes);
+            chunk.extend(sample_queue.drain(..stream.chunk_size_bytes));
 
             match audio_tx.try_send(chunk) {
                 Ok(()) => {
@@ -118,7 +696,12 @@ pub(crate) fn run_system_audio_capture_to_queue(
                         );
                     }
                 }
-                Err(std_mpsc::TrySendError::Disconnected(_)) => return Ok(()),
+                Err(std_mpsc::TrySendError::Disconnected(_)) => {
+                    if is_recording_capture {
+                        set_active_loopback_device(None);
+                    }
+                    return Ok(());
+                }
             }
         }
 
@@ -126,8 +709,9 @@ pub(crate) fn run_system_audio_capture_to_queue(
             break;
         }
 
-        if let Err(error) =
-            event_handle.wait_for_event(SYSTEM_AUDIO_EVENT_TIMEOUT.as_millis() as u32)
+        if let Err(error) = stream
+            .event_handle
+            .wait_for_event(SYSTEM_AUDIO_EVENT_TIMEOUT.as_millis() as u32)
         {
             tracing::debug!("System audio wait event timed/failed: {error}");
         }
@@ -141,19 +725,66 @@ pub(crate) fn run_system_audio_capture_to_queue(
         }
     }
 
-    if let Err(error) = audio_client.stop_stream() {
-        tracing::warn!("Failed to stop system audio stream cleanly: {error}");
+    stream.stop();
+    if is_recording_capture {
+        set_active_loopback_device(None);
     }
 
     Ok(())
 }
 
+/// Mixes the marker beep into interleaved s16le system audio. A request starts the beep
+/// at the next chunk written, which is at most one chunk after the marker was dropped.
+pub(crate) struct MarkerToneMixer {
+    pending: Arc<AtomicBool>,
+    /// Frames of the current beep already mixed; `MARKER_TONE_FRAMES` when idle.
+    position: usize,
+}
+
+impl MarkerToneMixer {
+    pub(crate) fn new(pending: Arc<AtomicBool>) -> Self {
+        Self {
+            pending,
+            position: MARKER_TONE_FRAMES,
+        }
+    }
+
+    pub(crate) fn mix_into_s16le(&mut self, bytes: &mut [u8]) {
+        if self.pending.swap(false, Ordering::Relaxed) {
+            self.position = 0;
+        }
+
+        let frame_bytes = SYSTEM_AUDIO_CHANNEL_COUNT * SYSTEM_AUDIO_BITS_PER_SAMPLE / 8;
+        for frame in bytes.chunks_exact_mut(frame_bytes) {
+            if self.position >= MARKER_TONE_FRAMES {
+                break;
+            }
+
+            let phase = std::f32::consts::TAU * MARKER_TONE_FREQUENCY_HZ * self.position as f32
+                / SYSTEM_AUDIO_SAMPLE_RATE_HZ as f32;
+            let tone = (phase.sin() * MARKER_TONE_AMPLITUDE * f32::from(i16::MAX)) as i32;
+            for sample_bytes in frame.chunks_exact_mut(2) {
+                let sample = i32::from(i16::from_le_bytes([sample_bytes[0], sample_bytes[1]]));
+                let mixed = (sample + tone).clamp(i32::from(i16::MIN), i32::from(i16::MAX)) as i16;
+                sample_bytes.copy_from_slice(&mixed.to_le_bytes());
+            }
+            self.position += 1;
+        }
+    }
+}
+
+/// With `silence_origin`, the first chunk is preceded by silence for the time that passed
+/// between that instant and the chunk's capture, so start-up delays of the capture client
+/// stay on the sample-count timeline FFmpeg derives from the input.
 pub(crate) fn run_audio_queue_to_writer<W: Write>(
     mut writer: W,
     audio_rx: std_mpsc::Receiver<Vec<u8>>,
     stop_rx: std_mpsc::Receiver<()>,
     stats: Arc<AudioPipelineStats>,
+    mut marker_tone: Option<MarkerToneMixer>,
+    mut silence_origin: Option<Instant>,
 ) -> Result<(), String> {
+    let frame_bytes = SYSTEM_AUDIO_CHANNEL_COUNT * SYSTEM_AUDIO_BITS_PER_SAMPLE / 8;
     loop {
         match stop_rx.try_recv() {
             Ok(()) | Err(std_mpsc::TryRecvError::Disconnected) => break,
@@ -161,8 +792,23 @@ pub(crate) fn run_audio_queue_to_writer<W: Write>(
         }
 
         match audio_rx.recv_timeout(Duration::from_millis(10)) {
-            Ok(chunk) => {
+            Ok(mut chunk) => {
                 stats.dequeued_chunks.fetch_add(1, Ordering::Relaxed);
+                if let Some(origin) = silence_origin.take() {
+                    let elapsed_frames = (origin.elapsed().as_secs_f64()
+                        * SYSTEM_AUDIO_SAMPLE_RATE_HZ as f64)
+                        as usize;
+                    let gap_frames = elapsed_frames.saturating_sub(chunk.len() / frame_bytes);
+                    if gap_frames > 0 {
+                        tracing::debug!(gap_frames, "Padding system audio start with silence");
+                        let mut padded_chunk = vec![0u8; gap_frames * frame_bytes];
+                        padded_chunk.append(&mut chunk);
+                        chunk = padded_chunk;
+                    }
+                }
+                if let Some(marker_tone) = marker_tone.as_mut() {
+                    marker_tone.mix_into_s16le(&mut chunk);
+                }
                 if let Err(error) = writer.write_all(&chunk) {
                     if matches!(
                         error.kind(),
@@ -185,8 +831,171 @@ pub(crate) fn run_audio_queue_to_writer<W: Write>(
     Ok(())
 }
 
+/// Accumulates peak and RMS levels over interleaved s16le samples, normalized to 0..=1.
+#[derive(Default)]
+pub(crate) struct AudioLevelMeter {
+    peak: f32,
+    sum_of_squares: f64,
+    sample_count: u64,
+}
+
+impl AudioLevelMeter {
+    pub(crate) fn push_s16le(&mut self, bytes: &[u8]) {
+        for sample_bytes in bytes.chunks_exact(2) {
+            let sample = i16::from_le_bytes([sample_bytes[0], sample_bytes[1]]);
+            let normalized = f32::from(sample) / 32768.0;
+            self.peak = self.peak.max(normalized.abs());
+            self.sum_of_squares += f64::from(normalized) * f64::from(normalized);
+            self.sample_count += 1;
+        }
+    }
+
+    pub(crate) fn take_levels(&mut self) -> AudioLevelPayload {
+        let rms = if self.sample_count == 0 {
+            0.0
+        } else {
+            (self.sum_of_squares / self.sample_count as f64).sqrt() as f32
+        };
+        let levels = AudioLevelPayload {
+            peak: self.peak,
+            rms,
+        };
+        *self = Self::default();
+        levels
+    }
+}
+
 pub(crate) fn is_expected_audio_disconnect_error(error: &str) -> bool {
     error.contains("os error 10053")
         || error.contains("Broken pipe")
         || error.contains("connection reset")
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+
+    use super::{
+        match_monitor_audio_device, select_audio_session_process_id, AudioLevelMeter,
+        MarkerToneMixer,
+    };
+    use crate::recording::model::{AudioOutputDeviceInfo, AudioSessionInfo, MARKER_TONE_FRAMES};
+
+    fn output_device(id: &str, name: &str) -> AudioOutputDeviceInfo {
+        AudioOutputDeviceInfo {
+            id: id.to_string(),
+            name: name.to_string(),
+            is_default: false,
+        }
+    }
+
+    fn audio_session(process_id: u32, app_name: &str, is_active: bool) -> AudioSessionInfo {
+        AudioSessionInfo {
+            session_id: format!("device|{process_id}"),
+            process_id,
+            app_name: Some(app_name.to_string()),
+            device_id: "device".to_string(),
+            device_name: "Speakers".to_string(),
+            is_active,
+        }
+    }
+
+    fn s16le_bytes(samples: &[i16]) -> Vec<u8> {
+        samples
+            .iter()
+            .flat_map(|sample| sample.to_le_bytes())
+            .collect()
+    }
+
+    #[test]
+    fn measures_peak_and_rms_levels() {
+        let mut meter = AudioLevelMeter::default();
+        meter.push_s16le(&s16le_bytes(&[16384, -16384, 16384, -16384]));
+
+        let levels = meter.take_levels();
+
+        assert_eq!(levels.peak, 0.5);
+        assert_eq!(levels.rms, 0.5);
+    }
+
+    #[test]
+    fn resets_levels_after_take() {
+        let mut meter = AudioLevelMeter::default();
+        meter.push_s16le(&s16le_bytes(&[i16::MIN, 0]));
+        assert_eq!(meter.take_levels().peak, 1.0);
+
+        let silent_levels = meter.take_levels();
+
+        assert_eq!(silent_levels.peak, 0.0);
+        assert_eq!(silent_levels.rms, 0.0);
+    }
+
+    #[test]
+    fn matches_monitor_to_its_hdmi_audio_device() {
+        let devices = vec![
+            output_device("speakers", "Speakers (Realtek(R) Audio)"),
+            output_device("tv", "LG TV SSCR2 (NVIDIA High Definition Audio)"),
+        ];
+
+        let matched_device = match_monitor_audio_device("LG TV SSCR2", &devices);
+
+        assert_eq!(matched_device.map(|device| device.id.as_str()), Some("tv"));
+    }
+
+    #[test]
+    fn ignores_generic_and_ambiguous_monitor_names() {
+        let devices = vec![
+            output_device("first", "DELL U2720Q (NVIDIA High Definition Audio)"),
+            output_device("second", "DELL U2720Q (Intel(R) Display Audio)"),
+            output_device("generic", "Generic PnP Monitor (AMD High Definition Audio)"),
+        ];
+
+        assert_eq!(
+            match_monitor_audio_device("Generic PnP Monitor", &devices),
+            None
+        );
+        assert_eq!(match_monitor_audio_device("DELL U2720Q", &devices), None);
+        assert_eq!(match_monitor_audio_device("ASUS VG27A", &devices), None);
+    }
+
+    #[test]
+    fn marker_tone_is_mixed_only_after_a_request() {
+        let pending = Arc::new(AtomicBool::new(false));
+        let mut mixer = MarkerToneMixer::new(Arc::clone(&pending));
+        let mut silence = vec![0u8; 960 * 4];
+
+        mixer.mix_into_s16le(&mut silence);
+        assert!(silence.iter().all(|byte| *byte == 0));
+
+        pending.store(true, Ordering::Relaxed);
+        let mut beep = vec![0u8; (MARKER_TONE_FRAMES + 10) * 4];
+        mixer.mix_into_s16le(&mut beep);
+
+        assert!(!pending.load(Ordering::Relaxed));
+        assert!(beep[..MARKER_TONE_FRAMES * 4].iter().any(|byte| *byte != 0));
+        assert!(beep[MARKER_TONE_FRAMES * 4..].iter().all(|byte| *byte == 0));
+    }
+
+    #[test]
+    fn selects_the_named_app_preferring_an_active_session() {
+        let sessions = [
+            audio_session(10, "Discord.exe", true),
+            audio_session(20, "Wow.exe", false),
+            audio_session(30, "Wow.exe", true),
+        ];
+
+        assert_eq!(
+            select_audio_session_process_id("wow.exe", &sessions),
+            Some(30)
+        );
+        assert_eq!(
+            select_audio_session_process_id("Discord.exe", &sessions),
+            Some(10)
+        );
+        assert_eq!(
+            select_audio_session_process_id("Spotify.exe", &sessions),
+            None
+        );
+    }
+}
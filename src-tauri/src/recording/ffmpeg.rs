@@ -6,12 +6,19 @@ use std::process::{Command, Stdio};
 use tauri::path::BaseDirectory;
 use tauri::{AppHandle, Manager};
 
-use super::model::{CaptureInput, RuntimeCaptureMode, CREATE_NO_WINDOW, FFMPEG_RESOURCE_PATH};
+use super::model::{
+    AudioResampleSync, CaptureInput, FfmpegFrameStats, FrameSync, GpuVendor, RateControl,
+    RecordingAnnotationMarker, RecordingStreamInfo, RuntimeCaptureMode, SegmentConfig,
+    VirtualDesktopLayout, WindowCaptureRegion, ANNOTATION_MARKER_DISPLAY_SECONDS,
+    BLACK_FRAME_MAX_LUMA, BLACK_FRAME_SAMPLE_HEIGHT, BLACK_FRAME_SAMPLE_WIDTH, CREATE_NO_WINDOW,
+    FFMPEG_RESOURCE_PATH, MAX_AUDIO_OFFSET_MS, SYSTEM_AUDIO_CHANNEL_COUNT, VBR_MAX_BITRATE_PERCENT,
+};
 use super::window_capture::{
-    resolve_window_capture_handle, resolve_window_capture_region, sanitize_capture_dimensions,
+    resolve_monitor_rect, resolve_virtual_desktop_layout, resolve_window_capture_handle,
+    resolve_window_capture_region, sanitize_capture_dimensions,
 };
 
-pub(crate) fn resolve_ffmpeg_binary_path(app_handle: &AppHandle) -> Result<PathBuf, String> {
+fn resolve_ffmpeg_binary_path(app_handle: &AppHandle) -> Result<PathBuf, String> {
     let mut candidates: Vec<PathBuf> = Vec::new();
 
     if let Ok(resource_path) = app_handle
@@ -48,6 +55,63 @@ pub(crate) fn resolve_ffmpeg_binary_path(app_handle: &AppHandle) -> Result<PathB
     ))
 }
 
+/// A user-supplied FFmpeg build takes precedence over the bundled search as long as it
+/// answers `-version`; otherwise the search below is used after a warning.
+pub(crate) fn resolve_preferred_ffmpeg_binary_path(
+    app_handle: &AppHandle,
+    custom_ffmpeg_path: Option<&str>,
+) -> Result<PathBuf, String> {
+    if let Some(custom_path) = custom_ffmpeg_path
+        .map(str::trim)
+        .filter(|custom_path| !custom_path.is_empty())
+    {
+        let custom_path = PathBuf::from(custom_path);
+        match validate_ffmpeg_binary(&custom_path) {
+            Ok(()) => return Ok(custom_path),
+            Err(error) => tracing::warn!(
+                custom_ffmpeg_path = %custom_path.display(),
+                "Ignoring custom FFmpeg binary and falling back to the bundled search: {error}"
+            ),
+        }
+    }
+
+    resolve_ffmpeg_binary_path(app_handle)
+}
+
+/// FFmpeg for commands that are not given the recording settings: the custom binary saved
+/// on the settings page, or the bundled one.
+pub(crate) fn resolve_configured_ffmpeg_binary_path(
+    app_handle: &AppHandle,
+) -> Result<PathBuf, String> {
+    resolve_preferred_ffmpeg_binary_path(
+        app_handle,
+        crate::settings::stored_custom_ffmpeg_path(app_handle).as_deref(),
+    )
+}
+
+fn validate_ffmpeg_binary(ffmpeg_binary_path: &Path) -> Result<(), String> {
+    if !ffmpeg_binary_path.is_file() {
+        return Err("the file does not exist".to_string());
+    }
+
+    let mut command = Command::new(ffmpeg_binary_path);
+    #[cfg(target_os = "windows")]
+    command.creation_flags(CREATE_NO_WINDOW);
+    let output = command
+        .arg("-hide_banner")
+        .arg("-version")
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .output()
+        .map_err(|error| format!("failed to run it: {error}"))?;
+
+    if !output.status.success() || !output.stdout.starts_with(b"ffmpeg version") {
+        return Err("it did not report an FFmpeg version".to_string());
+    }
+
+    Ok(())
+}
+
 fn load_ffmpeg_encoders_output(ffmpeg_binary_path: &Path) -> String {
     let mut command = Command::new(ffmpeg_binary_path);
     #[cfg(target_os = "windows")]
@@ -67,6 +131,97 @@ fn load_ffmpeg_encoders_output(ffmpeg_binary_path: &Path) -> String {
     }
 }
 
+/// First line of `ffmpeg -version`, e.g. `ffmpeg version 7.1-full_build-www.gyan.dev`.
+pub(crate) fn ffmpeg_version(ffmpeg_binary_path: &Path) -> Option<String> {
+    let mut command = Command::new(ffmpeg_binary_path);
+    #[cfg(target_os = "windows")]
+    command.creation_flags(CREATE_NO_WINDOW);
+    let output = command
+        .arg("-hide_banner")
+        .arg("-version")
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .next()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(ToString::to_string)
+}
+
+pub(crate) fn ffmpeg_has_filter(ffmpeg_binary_path: &Path, filter_name: &str) -> bool {
+    let mut command = Command::new(ffmpeg_binary_path);
+    #[cfg(target_os = "windows")]
+    command.creation_flags(CREATE_NO_WINDOW);
+    let Ok(output) = command
+        .arg("-hide_banner")
+        .arg("-filters")
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .output()
+    else {
+        return false;
+    };
+
+    // Each line reads `<flags> <name> <pads> <description>`.
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .any(|line| line.split_whitespace().nth(1) == Some(filter_name))
+}
+
+/// Encodes a single synthetic frame to check that `video_encoder` actually starts on this
+/// machine. `-encoders` lists every encoder FFmpeg was built with, whether or not the
+/// matching GPU and driver are present.
+pub(crate) fn video_encoder_initializes(
+    ffmpeg_binary_path: &Path,
+    video_encoder: &str,
+    pixel_format: &str,
+) -> bool {
+    let mut command = Command::new(ffmpeg_binary_path);
+    #[cfg(target_os = "windows")]
+    command.creation_flags(CREATE_NO_WINDOW);
+    command
+        .arg("-hide_banner")
+        .arg("-loglevel")
+        .arg("error")
+        .arg("-f")
+        .arg("lavfi")
+        .arg("-i")
+        .arg("color=c=black:s=256x256:r=30")
+        .arg("-frames:v")
+        .arg("1")
+        .arg("-c:v")
+        .arg(video_encoder)
+        .arg("-pix_fmt")
+        .arg(pixel_format)
+        .arg("-f")
+        .arg("null")
+        .arg("-")
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .is_ok_and(|status| status.success())
+}
+
+/// Picks a decode accelerator for post-processing from the GPU encoders FFmpeg detected.
+/// Without a hardware encoder there is most likely no usable GPU decoder either.
+pub(crate) fn select_decode_hwaccel(available_encoders: &[String]) -> Option<&'static str> {
+    let has_encoder = |name: &str| available_encoders.iter().any(|encoder| encoder == name);
+    if has_encoder("h264_nvenc") {
+        Some("cuda")
+    } else if has_encoder("h264_qsv") {
+        Some("qsv")
+    } else if has_encoder("h264_amf") {
+        Some("d3d11va")
+    } else {
+        None
+    }
+}
+
 pub(crate) fn list_available_video_encoders(ffmpeg_binary_path: &Path) -> Vec<String> {
     let encoders_output = load_ffmpeg_encoders_output(ffmpeg_binary_path);
     let mut available_encoders: Vec<String> = Vec::new();
@@ -90,6 +245,22 @@ pub(crate) fn list_available_video_encoders(ffmpeg_binary_path: &Path) -> Vec<St
     available_encoders
 }
 
+/// MP4 holds Opus without `-strict experimental` since FFmpeg 4.3, and the segments keep
+/// it through the `-c copy` concat. Only `libopus` is used; FFmpeg's native Opus encoder
+/// is still experimental.
+pub(crate) fn select_audio_encoder(ffmpeg_binary_path: &Path, audio_codec: &str) -> &'static str {
+    if audio_codec != "opus" {
+        return "aac";
+    }
+
+    if load_ffmpeg_encoders_output(ffmpeg_binary_path).contains(" libopus") {
+        "libopus"
+    } else {
+        tracing::warn!("FFmpeg has no libopus encoder. Recording system audio as AAC");
+        "aac"
+    }
+}
+
 pub(crate) fn video_encoder_label(encoder: &str) -> &'static str {
     match encoder {
         "h264_nvenc" => "NVIDIA NVENC",
@@ -100,6 +271,25 @@ pub(crate) fn video_encoder_label(encoder: &str) -> &'static str {
     }
 }
 
+/// Turns an `auto` encoder preference into the hardware encoder of the chosen GPU. With
+/// `encoder_gpu` set to `auto`, that is the GPU driving the captured display on hybrid
+/// systems. An explicitly chosen encoder always wins.
+pub(crate) fn resolve_video_encoder_preference<'a>(
+    video_encoder_preference: &'a str,
+    encoder_gpu: &str,
+    hybrid_capture_gpu_vendor: Option<GpuVendor>,
+) -> &'a str {
+    if video_encoder_preference != "auto" {
+        return video_encoder_preference;
+    }
+
+    let bound_vendor = match encoder_gpu {
+        "auto" => hybrid_capture_gpu_vendor,
+        other => GpuVendor::from_setting(other),
+    };
+    bound_vendor.map_or("auto", GpuVendor::hardware_encoder)
+}
+
 pub(crate) fn select_video_encoder(
     ffmpeg_binary_path: &Path,
     video_quality: &str,
@@ -132,26 +322,167 @@ pub(crate) fn select_video_encoder(
         "libx264".to_string()
     };
 
-    if resolved_encoder == "h264_nvenc" {
-        let preset = if video_quality == "ultra" { "p5" } else { "p3" };
-        return ("h264_nvenc".to_string(), Some(preset.to_string()));
-    }
+    let preset = video_encoder_preset(&resolved_encoder, video_quality);
+    (resolved_encoder, preset)
+}
 
-    if resolved_encoder == "h264_qsv" {
-        return ("h264_qsv".to_string(), None);
+pub(crate) fn video_encoder_preset(video_encoder: &str, video_quality: &str) -> Option<String> {
+    let preset = match video_encoder {
+        "h264_nvenc" if video_quality == "ultra" => "p5",
+        "h264_nvenc" => "p3",
+        "h264_qsv" | "h264_amf" => return None,
+        _ if video_quality == "ultra" => "faster",
+        _ => "superfast",
+    };
+    Some(preset.to_string())
+}
+
+/// Bitrate arguments for the video encoder. CBR pins `-maxrate` to the target. VBR maps
+/// to each hardware encoder's quality-targeted mode, capped at `VBR_MAX_BITRATE_PERCENT`
+/// of the target: NVENC `-rc vbr -cq`, Quick Sync QVBR through `-global_quality`, and AMF
+/// peak-constrained VBR, which has no quality target. libx264 always uses CBR.
+pub(crate) fn video_rate_control_args(
+    video_encoder: &str,
+    rate_control: RateControl,
+    bitrate: u32,
+    vbr_quality: u32,
+) -> Vec<String> {
+    let is_hardware_encoder = matches!(video_encoder, "h264_nvenc" | "h264_qsv" | "h264_amf");
+    if rate_control == RateControl::Cbr || !is_hardware_encoder {
+        return [
+            "-b:v".to_string(),
+            bitrate.to_string(),
+            "-maxrate".to_string(),
+            bitrate.to_string(),
+            "-bufsize".to_string(),
+            bitrate.saturating_mul(2).to_string(),
+        ]
+        .to_vec();
     }
 
-    if resolved_encoder == "h264_amf" {
-        return ("h264_amf".to_string(), None);
+    let max_bitrate = (u64::from(bitrate) * u64::from(VBR_MAX_BITRATE_PERCENT) / 100)
+        .min(u64::from(u32::MAX)) as u32;
+    let quality = vbr_quality.to_string();
+    let mut args: Vec<String> = match video_encoder {
+        "h264_nvenc" => ["-rc", "vbr", "-cq", quality.as_str()]
+            .map(str::to_string)
+            .to_vec(),
+        "h264_qsv" => ["-global_quality", quality.as_str()]
+            .map(str::to_string)
+            .to_vec(),
+        _ => ["-rc", "vbr_peak"].map(str::to_string).to_vec(),
+    };
+    args.extend([
+        "-b:v".to_string(),
+        bitrate.to_string(),
+        "-maxrate".to_string(),
+        max_bitrate.to_string(),
+        "-bufsize".to_string(),
+        max_bitrate.saturating_mul(2).to_string(),
+    ]);
+    args
+}
+
+/// Encoders tried in order when the current one fails to initialize at runtime.
+const VIDEO_ENCODER_FALLBACK_CHAIN: [&str; 4] = ["h264_nvenc", "h264_qsv", "h264_amf", "libx264"];
+
+/// The next encoder after `failed_encoder` in the fallback chain that this FFmpeg build
+/// provides, or `None` once the chain is exhausted.
+pub(crate) fn next_fallback_video_encoder(
+    failed_encoder: &str,
+    available_encoders: &[String],
+) -> Option<&'static str> {
+    let failed_position = VIDEO_ENCODER_FALLBACK_CHAIN
+        .iter()
+        .position(|encoder| *encoder == failed_encoder)?;
+    VIDEO_ENCODER_FALLBACK_CHAIN[failed_position + 1..]
+        .iter()
+        .copied()
+        .find(|encoder| {
+            available_encoders
+                .iter()
+                .any(|available| available == encoder)
+        })
+}
+
+/// Only libx264 can write 10-bit H.264; the NVENC, Quick Sync and AMF H.264 encoders are
+/// 8-bit only, so anything they cannot produce falls back to `yuv420p`.
+pub(crate) fn resolve_pixel_format(
+    video_encoder: &str,
+    requested_pixel_format: &str,
+) -> &'static str {
+    match requested_pixel_format {
+        "yuv420p" => "yuv420p",
+        "yuv420p10le" if video_encoder == "libx264" => {
+            tracing::warn!(
+                "Recording 10-bit H.264 (High 10); WebView2 cannot decode it, so the recording \
+                 only plays in an external player"
+            );
+            "yuv420p10le"
+        }
+        "yuv420p10le" => {
+            tracing::warn!(
+                video_encoder,
+                "Selected encoder cannot produce 10-bit output; recording 8-bit yuv420p instead"
+            );
+            "yuv420p"
+        }
+        other => {
+            tracing::warn!(
+                pixel_format = %other,
+                "Unknown pixel format value. Falling back to yuv420p"
+            );
+            "yuv420p"
+        }
     }
+}
+
+/// H.264 levels as (max macroblocks per second, max High-profile bitrate in kbit/s).
+const H264_LEVEL_LIMITS: [(u64, u32); 9] = [
+    (108_000, 17_500),
+    (216_000, 25_000),
+    (245_760, 62_500),
+    (522_240, 62_500),
+    (589_824, 168_750),
+    (983_040, 300_000),
+    (2_073_600, 300_000),
+    (4_177_920, 300_000),
+    (8_355_840, 600_000),
+];
 
-    let preset = if video_quality == "ultra" {
-        "faster"
+/// Every encoder `select_video_encoder` can pick produces H.264, so the ceiling is the
+/// High-profile bitrate limit of the lowest level that fits the capture's macroblock rate.
+pub(crate) fn h264_max_bitrate_bps(width: u32, height: u32, frame_rate: u32) -> u32 {
+    let macroblocks_per_second = u64::from(width.div_ceil(16))
+        * u64::from(height.div_ceil(16))
+        * u64::from(frame_rate.max(1));
+    let max_bitrate_kbps = H264_LEVEL_LIMITS
+        .iter()
+        .find(|(max_macroblocks_per_second, _)| {
+            macroblocks_per_second <= *max_macroblocks_per_second
+        })
+        .map(|(_, max_bitrate_kbps)| *max_bitrate_kbps)
+        .unwrap_or(800_000);
+
+    max_bitrate_kbps.saturating_mul(1000)
+}
+
+/// Recording segments log at `warning`. Diagnostics raise it to `verbose` and add the
+/// `level` flag, which tags every line with its severity, e.g. `[h264_nvenc @ 0x..] [info]`.
+pub(crate) fn recording_ffmpeg_loglevel(enable_diagnostics: bool) -> &'static str {
+    if enable_diagnostics {
+        "level+verbose"
     } else {
-        "superfast"
-    };
+        "warning"
+    }
+}
 
-    ("libx264".to_string(), Some(preset.to_string()))
+/// Whether a severity-tagged stderr line is below `warning`. Such lines only appear with
+/// diagnostics on and are logged, but kept out of the failure hints.
+pub(crate) fn is_ffmpeg_informational_line(line: &str) -> bool {
+    ["[info]", "[verbose]", "[debug]", "[trace]"]
+        .iter()
+        .any(|level| line.contains(level))
 }
 
 pub(crate) fn parse_ffmpeg_speed(line: &str) -> Option<f64> {
@@ -162,33 +493,249 @@ pub(crate) fn parse_ffmpeg_speed(line: &str) -> Option<f64> {
     numeric.parse::<f64>().ok()
 }
 
-fn append_monitor_capture_input_args(command: &mut Command, requested_frame_rate: u32) {
+fn parse_stats_token<'a>(report: &'a str, key: &str) -> Option<&'a str> {
+    let value_index = report.find(key)? + key.len();
+    report[value_index..].split_whitespace().next()
+}
+
+/// Parses the most recent `-stats` report on a line. Reports are separated by `\r`, so
+/// one stderr line can hold several of them.
+pub(crate) fn parse_ffmpeg_frame_stats(line: &str) -> Option<FfmpegFrameStats> {
+    let report = &line[line.rfind("frame=")?..];
+    let frames = parse_stats_token(report, "frame=")?.parse().ok()?;
+    let encoded_seconds = parse_stats_token(report, "time=")
+        .and_then(parse_duration_seconds)
+        .unwrap_or(0.0);
+
+    Some(FfmpegFrameStats {
+        frames,
+        duplicated: parse_stats_token(report, "dup=")
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(0),
+        dropped: parse_stats_token(report, "drop=")
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(0),
+        encoded_seconds,
+        speed: parse_stats_token(report, "speed=")
+            .and_then(|value| value.trim_end_matches('x').parse().ok()),
+        output_bytes: parse_stats_token(report, "size=").and_then(parse_stats_size_bytes),
+    })
+}
+
+/// `-stats` sizes are in KiB, printed as `KiB` by current FFmpeg and `kB` by older builds.
+fn parse_stats_size_bytes(value: &str) -> Option<u64> {
+    let kibibytes = value
+        .strip_suffix("KiB")
+        .or_else(|| value.strip_suffix("kB"))?;
+    kibibytes
+        .parse::<u64>()
+        .ok()
+        .map(|kibibytes| kibibytes.saturating_mul(1024))
+}
+
+/// Reads the summaries the `ssim` and `psnr` filters log when they finish, such as
+/// `SSIM Y:0.99 (20.1) U:0.99 (21.0) V:0.99 (21.4) All:0.99 (20.5)` and
+/// `PSNR y:41.2 u:44.0 v:44.3 average:42.0 min:38.1 max:46.7`. Identical inputs report a
+/// PSNR of `inf`, which parses to infinity.
+pub(crate) fn parse_compare_scores(stderr: &str) -> Option<(f64, f64)> {
+    let mut ssim = None;
+    let mut psnr = None;
+    for line in stderr.lines() {
+        if let Some((_, summary)) = line.split_once("] SSIM ") {
+            ssim = parse_stats_token(summary, "All:").and_then(|value| value.parse().ok());
+        } else if let Some((_, summary)) = line.split_once("] PSNR ") {
+            psnr = parse_stats_token(summary, "average:").and_then(|value| value.parse().ok());
+        }
+    }
+    Some((ssim?, psnr?))
+}
+
+/// Parses the `out_time_us` key emitted by `-progress pipe:1`.
+pub(crate) fn parse_progress_out_time_seconds(line: &str) -> Option<f64> {
+    let micros = line.trim().strip_prefix("out_time_us=")?;
+    micros
+        .parse::<u64>()
+        .ok()
+        .map(|value| value as f64 / 1_000_000.0)
+}
+
+fn parse_duration_seconds(value: &str) -> Option<f64> {
+    let mut parts = value.trim().split(':');
+    let hours = parts.next()?.parse::<f64>().ok()?;
+    let minutes = parts.next()?.parse::<f64>().ok()?;
+    let seconds = parts.next()?.parse::<f64>().ok()?;
+    Some(hours * 3600.0 + minutes * 60.0 + seconds)
+}
+
+fn parse_stream_codec(description: &str) -> Option<String> {
+    description
+        .split([' ', ','])
+        .next()
+        .filter(|codec| !codec.is_empty())
+        .map(str::to_string)
+}
+
+fn parse_video_dimensions(description: &str) -> Option<(u32, u32)> {
+    description.split(", ").find_map(|part| {
+        let token = part.split_whitespace().next()?;
+        let (width, height) = token.split_once('x')?;
+        Some((width.parse().ok()?, height.parse().ok()?))
+    })
+}
+
+fn parse_video_frame_rate(description: &str) -> Option<f64> {
+    description
+        .split(", ")
+        .find_map(|part| part.trim().strip_suffix(" fps")?.parse().ok())
+}
+
+/// Reads the input section of FFmpeg's stderr banner (`ffmpeg -i <file>`); output
+/// streams listed after `Output #` are ignored.
+pub(crate) fn parse_input_stream_info(stderr: &str) -> RecordingStreamInfo {
+    let mut info = RecordingStreamInfo::default();
+
+    for line in stderr.lines() {
+        let line = line.trim();
+        if line.starts_with("Output #") || line.starts_with("Stream mapping:") {
+            break;
+        }
+
+        if let Some(rest) = line.strip_prefix("Duration:") {
+            let value = rest.split(',').next().unwrap_or_default();
+            info.duration_seconds = info.duration_seconds.or(parse_duration_seconds(value));
+            continue;
+        }
+
+        if !line.starts_with("Stream #") {
+            continue;
+        }
+
+        if let Some((_, description)) = line.split_once("Video: ") {
+            if info.video_codec.is_none() {
+                info.video_codec = parse_stream_codec(description);
+                if let Some((width, height)) = parse_video_dimensions(description) {
+                    info.width = Some(width);
+                    info.height = Some(height);
+                }
+                info.frame_rate = parse_video_frame_rate(description);
+            }
+        } else if let Some((_, description)) = line.split_once("Audio: ") {
+            info.audio_codec = info.audio_codec.or(parse_stream_codec(description));
+        }
+    }
+
+    info
+}
+
+fn append_monitor_capture_input_args(
+    command: &mut Command,
+    output_idx: u32,
+    requested_frame_rate: u32,
+    draw_mouse: u8,
+) {
     command.arg("-f").arg("lavfi").arg("-i").arg(format!(
-        "ddagrab=output_idx=0:framerate={requested_frame_rate}:draw_mouse=1,hwdownload,format=bgra"
+        "ddagrab=output_idx={output_idx}:framerate={requested_frame_rate}:draw_mouse={draw_mouse},hwdownload,format=bgra"
     ));
 }
 
+/// GDI capture works without desktop duplication, for GPUs and drivers `ddagrab` cannot
+/// use. Frames are copied through system memory, so it costs more CPU at high frame rates.
+fn append_gdigrab_monitor_capture_input_args(
+    command: &mut Command,
+    monitor_rect: [i32; 4],
+    requested_frame_rate: u32,
+    draw_mouse: u8,
+) {
+    let [left, top, right, bottom] = monitor_rect;
+    command
+        .arg("-f")
+        .arg("gdigrab")
+        .arg("-framerate")
+        .arg(requested_frame_rate.to_string())
+        .arg("-draw_mouse")
+        .arg(draw_mouse.to_string())
+        .arg("-offset_x")
+        .arg(left.to_string())
+        .arg("-offset_y")
+        .arg(top.to_string())
+        .arg("-video_size")
+        .arg(format!("{}x{}", right.abs_diff(left), bottom.abs_diff(top)))
+        .arg("-i")
+        .arg("desktop");
+}
+
+/// `ddagrab` duplicates a single output, so every monitor gets its own source in one lavfi
+/// graph and `xstack` places them at their desktop offsets.
+fn build_virtual_desktop_capture_graph(
+    layout: &VirtualDesktopLayout,
+    requested_frame_rate: u32,
+    draw_mouse: u8,
+) -> String {
+    let source = |output_idx: u32| {
+        format!(
+            "ddagrab=output_idx={output_idx}:framerate={requested_frame_rate}:draw_mouse={draw_mouse},hwdownload,format=bgra"
+        )
+    };
+    let scale = if (layout.output_width, layout.output_height) == (layout.width, layout.height) {
+        String::new()
+    } else {
+        format!(
+            ",scale={}:{}:flags=bicubic",
+            layout.output_width, layout.output_height
+        )
+    };
+
+    if let [monitor] = layout.monitors.as_slice() {
+        return format!("{}{scale}", source(monitor.output_idx));
+    }
+
+    let mut graph = String::new();
+    for (index, monitor) in layout.monitors.iter().enumerate() {
+        graph.push_str(&format!("{}[m{index}];", source(monitor.output_idx)));
+    }
+    for index in 0..layout.monitors.len() {
+        graph.push_str(&format!("[m{index}]"));
+    }
+    let positions = layout
+        .monitors
+        .iter()
+        .map(|monitor| format!("{}_{}", monitor.x, monitor.y))
+        .collect::<Vec<_>>()
+        .join("|");
+    graph.push_str(&format!(
+        "xstack=inputs={}:layout={positions}:fill=black{scale}[out0]",
+        layout.monitors.len()
+    ));
+    graph
+}
+
+/// WGC captures the target window's own surface, so overlay windows stacked above it
+/// never reach the recording. There is nothing to exclude in this mode.
 fn append_window_capture_input_args(
     command: &mut Command,
     requested_frame_rate: u32,
     window_hwnd: usize,
     capture_width: u32,
     capture_height: u32,
+    draw_mouse: u8,
 ) {
     let (safe_width, safe_height) = sanitize_capture_dimensions(capture_width, capture_height);
 
     command.arg("-f").arg("lavfi").arg("-i").arg(format!(
-        "gfxcapture=hwnd={window_hwnd}:max_framerate={requested_frame_rate}:capture_cursor=1:capture_border=0:output_fmt=bgra:width={safe_width}:height={safe_height}:resize_mode=scale_aspect,hwdownload,format=bgra",
+        "gfxcapture=hwnd={window_hwnd}:max_framerate={requested_frame_rate}:capture_cursor={draw_mouse}:capture_border=0:output_fmt=bgra:width={safe_width}:height={safe_height}:resize_mode=scale_aspect,hwdownload,format=bgra",
     ));
 }
 
+/// Region fallback duplicates the monitor output under the window rectangle, so any
+/// overlay drawn on top of the game is recorded too and cannot be filtered out here.
 fn append_window_region_capture_input_args(
     command: &mut Command,
     requested_frame_rate: u32,
-    region: super::model::WindowCaptureRegion,
+    region: WindowCaptureRegion,
+    draw_mouse: u8,
 ) {
     command.arg("-f").arg("lavfi").arg("-i").arg(format!(
-        "ddagrab=output_idx={}:framerate={requested_frame_rate}:draw_mouse=1:offset_x={}:offset_y={}:video_size={}x{},hwdownload,format=bgra",
+        "ddagrab=output_idx={}:framerate={requested_frame_rate}:draw_mouse={draw_mouse}:offset_x={}:offset_y={}:video_size={}x{},hwdownload,format=bgra",
         region.output_idx, region.offset_x, region.offset_y, region.width, region.height
     ));
 }
@@ -196,21 +743,67 @@ fn append_window_region_capture_input_args(
 pub(crate) struct RuntimeCaptureInputInfo {
     pub(crate) width: u32,
     pub(crate) height: u32,
+    /// The region-capture area fixed for this segment, if region capture is used.
+    pub(crate) region: Option<WindowCaptureRegion>,
 }
 
 pub(crate) fn append_runtime_capture_input_args(
     command: &mut Command,
-    runtime_capture_mode: RuntimeCaptureMode,
+    config: &SegmentConfig,
     capture_input: &CaptureInput,
-    requested_frame_rate: u32,
-    capture_width: u32,
-    capture_height: u32,
 ) -> Result<RuntimeCaptureInputInfo, String> {
-    match runtime_capture_mode {
+    let requested_frame_rate = config.requested_frame_rate;
+    let capture_width = config.capture_width;
+    let capture_height = config.capture_height;
+    let draw_mouse = u8::from(config.capture_cursor);
+
+    match config.runtime_capture_mode {
         RuntimeCaptureMode::Monitor => {
-            append_monitor_capture_input_args(command, requested_frame_rate);
+            if let CaptureInput::VirtualDesktop = capture_input {
+                let layout = resolve_virtual_desktop_layout()?;
+                command
+                    .arg("-f")
+                    .arg("lavfi")
+                    .arg("-i")
+                    .arg(build_virtual_desktop_capture_graph(
+                        &layout,
+                        requested_frame_rate,
+                        draw_mouse,
+                    ));
+                return Ok(RuntimeCaptureInputInfo {
+                    width: layout.output_width,
+                    height: layout.output_height,
+                    region: None,
+                });
+            }
+
+            let output_idx = match capture_input {
+                CaptureInput::Monitor { output_idx, .. } => *output_idx,
+                CaptureInput::Window { .. }
+                | CaptureInput::VirtualDesktop
+                | CaptureInput::TestPattern => 0,
+            };
+            if capture_input.uses_gdigrab_monitor_capture() {
+                append_gdigrab_monitor_capture_input_args(
+                    command,
+                    resolve_monitor_rect(output_idx)?,
+                    requested_frame_rate,
+                    draw_mouse,
+                );
+            } else {
+                append_monitor_capture_input_args(
+                    command,
+                    output_idx,
+                    requested_frame_rate,
+                    draw_mouse,
+                );
+            }
             let (width, height) = sanitize_capture_dimensions(capture_width, capture_height);
-            Ok(RuntimeCaptureInputInfo { width, height })
+            Ok(RuntimeCaptureInputInfo {
+                width,
+                height,
+                region: None,
+            })
         }
         RuntimeCaptureMode::Window => {
             if capture_input.uses_wgc_window_capture() {
@@ -221,15 +814,26 @@ pub(crate) fn append_runtime_capture_input_args(
                     window_hwnd,
                     capture_width,
                     capture_height,
+                    draw_mouse,
                 );
                 let (width, height) = sanitize_capture_dimensions(capture_width, capture_height);
-                Ok(RuntimeCaptureInputInfo { width, height })
+                Ok(RuntimeCaptureInputInfo {
+                    width,
+                    height,
+                    region: None,
+                })
             } else {
                 let region = resolve_window_capture_region(capture_input)?;
-                append_window_region_capture_input_args(command, requested_frame_rate, region);
+                append_window_region_capture_input_args(
+                    command,
+                    requested_frame_rate,
+                    region,
+                    draw_mouse,
+                );
                 Ok(RuntimeCaptureInputInfo {
                     width: region.width,
                     height: region.height,
+                    region: Some(region),
                 })
             }
         }
@@ -246,25 +850,869 @@ pub(crate) fn append_runtime_capture_input_args(
             Ok(RuntimeCaptureInputInfo {
                 width: safe_width,
                 height: safe_height,
+                region: None,
             })
         }
+        RuntimeCaptureMode::TestPattern => {
+            let (safe_width, safe_height) =
+                sanitize_capture_dimensions(capture_width, capture_height);
+            // Throttled with `realtime` for the same reason as the black placeholder.
+            command.arg("-f").arg("lavfi").arg("-i").arg(format!(
+                "testsrc2=s={safe_width}x{safe_height}:r={requested_frame_rate},realtime"
+            ));
+            Ok(RuntimeCaptureInputInfo {
+                width: safe_width,
+                height: safe_height,
+                region: None,
+            })
+        }
+    }
+}
+
+/// Color names FFmpeg accepts (`color_table` in libavutil's `parseutils.c`), lowercased
+/// and sorted for `binary_search`. FFmpeg compares them case-insensitively.
+const FFMPEG_COLOR_NAMES: [&str; 140] = [
+    "aliceblue",
+    "antiquewhite",
+    "aqua",
+    "aquamarine",
+    "azure",
+    "beige",
+    "bisque",
+    "black",
+    "blanchedalmond",
+    "blue",
+    "blueviolet",
+    "brown",
+    "burlywood",
+    "cadetblue",
+    "chartreuse",
+    "chocolate",
+    "coral",
+    "cornflowerblue",
+    "cornsilk",
+    "crimson",
+    "cyan",
+    "darkblue",
+    "darkcyan",
+    "darkgoldenrod",
+    "darkgray",
+    "darkgreen",
+    "darkkhaki",
+    "darkmagenta",
+    "darkolivegreen",
+    "darkorange",
+    "darkorchid",
+    "darkred",
+    "darksalmon",
+    "darkseagreen",
+    "darkslateblue",
+    "darkslategray",
+    "darkturquoise",
+    "darkviolet",
+    "deeppink",
+    "deepskyblue",
+    "dimgray",
+    "dodgerblue",
+    "firebrick",
+    "floralwhite",
+    "forestgreen",
+    "fuchsia",
+    "gainsboro",
+    "ghostwhite",
+    "gold",
+    "goldenrod",
+    "gray",
+    "green",
+    "greenyellow",
+    "honeydew",
+    "hotpink",
+    "indianred",
+    "indigo",
+    "ivory",
+    "khaki",
+    "lavender",
+    "lavenderblush",
+    "lawngreen",
+    "lemonchiffon",
+    "lightblue",
+    "lightcoral",
+    "lightcyan",
+    "lightgoldenrodyellow",
+    "lightgreen",
+    "lightgrey",
+    "lightpink",
+    "lightsalmon",
+    "lightseagreen",
+    "lightskyblue",
+    "lightslategray",
+    "lightsteelblue",
+    "lightyellow",
+    "lime",
+    "limegreen",
+    "linen",
+    "magenta",
+    "maroon",
+    "mediumaquamarine",
+    "mediumblue",
+    "mediumorchid",
+    "mediumpurple",
+    "mediumseagreen",
+    "mediumslateblue",
+    "mediumspringgreen",
+    "mediumturquoise",
+    "mediumvioletred",
+    "midnightblue",
+    "mintcream",
+    "mistyrose",
+    "moccasin",
+    "navajowhite",
+    "navy",
+    "oldlace",
+    "olive",
+    "olivedrab",
+    "orange",
+    "orangered",
+    "orchid",
+    "palegoldenrod",
+    "palegreen",
+    "paleturquoise",
+    "palevioletred",
+    "papayawhip",
+    "peachpuff",
+    "peru",
+    "pink",
+    "plum",
+    "powderblue",
+    "purple",
+    "red",
+    "rosybrown",
+    "royalblue",
+    "saddlebrown",
+    "salmon",
+    "sandybrown",
+    "seagreen",
+    "seashell",
+    "sienna",
+    "silver",
+    "skyblue",
+    "slateblue",
+    "slategray",
+    "snow",
+    "springgreen",
+    "steelblue",
+    "tan",
+    "teal",
+    "thistle",
+    "tomato",
+    "turquoise",
+    "violet",
+    "wheat",
+    "white",
+    "whitesmoke",
+    "yellow",
+    "yellowgreen",
+];
+
+/// Normalizes the letterbox color into FFmpeg color syntax: one of FFmpeg's color names
+/// such as `black`, or `#RRGGBB`/`0xRRGGBB` hex, which is passed on as `0xRRGGBB`.
+pub(crate) fn resolve_letterbox_color(color: &str) -> Result<String, String> {
+    let color = color.trim();
+    let hex_digits = color
+        .strip_prefix('#')
+        .or_else(|| color.strip_prefix("0x"))
+        .or_else(|| color.strip_prefix("0X"));
+    match hex_digits {
+        Some(digits) if digits.len() == 6 && digits.chars().all(|c| c.is_ascii_hexdigit()) => {
+            Ok(format!("0x{}", digits.to_ascii_uppercase()))
+        }
+        None if FFMPEG_COLOR_NAMES
+            .binary_search(&color.to_ascii_lowercase().as_str())
+            .is_ok() =>
+        {
+            Ok(color.to_ascii_lowercase())
+        }
+        _ => Err(format!(
+            "Invalid letterbox color '{color}'. Use a color name such as black or a #RRGGBB hex value"
+        )),
     }
 }
 
+/// Window and black placeholder segments are scaled to the output size. With a
+/// `letterbox_color` the window keeps its aspect ratio and the rest is padded in that
+/// color instead of stretching the picture.
 pub(crate) fn resolve_video_filter(
     runtime_capture_mode: RuntimeCaptureMode,
+    frame_sync: FrameSync,
     output_frame_rate: u32,
     capture_width: u32,
     capture_height: u32,
+    pixel_format: &str,
+    letterbox_color: Option<&str>,
 ) -> String {
+    // Synthetic sources (black placeholders, the test pattern) always run at the output rate.
+    let fps_filter = if frame_sync == FrameSync::Cfr
+        || matches!(
+            runtime_capture_mode,
+            RuntimeCaptureMode::Black | RuntimeCaptureMode::TestPattern
+        ) {
+        format!("fps={output_frame_rate},")
+    } else {
+        String::new()
+    };
+
     if matches!(
         runtime_capture_mode,
         RuntimeCaptureMode::Window | RuntimeCaptureMode::Black
     ) {
-        return format!(
-            "fps={output_frame_rate},scale={capture_width}:{capture_height}:flags=bicubic,format=yuv420p"
+        return match letterbox_color {
+            Some(color) => format!(
+                "{fps_filter}scale={capture_width}:{capture_height}:flags=bicubic:force_original_aspect_ratio=decrease:force_divisible_by=2,\
+pad={capture_width}:{capture_height}:(ow-iw)/2:(oh-ih)/2:color={color},format={pixel_format}"
+            ),
+            None => format!(
+                "{fps_filter}scale={capture_width}:{capture_height}:flags=bicubic,format={pixel_format}"
+            ),
+        };
+    }
+
+    format!("{fps_filter}format={pixel_format}")
+}
+
+/// Adds a second output that writes one tiny grayscale frame per second to stdout. WGC
+/// hands back black frames instead of an error for windows that block capture, so the
+/// samples are the only way to notice.
+pub(crate) fn append_black_frame_sampler_output_args(command: &mut Command, video_input_index: u8) {
+    command
+        .arg("-map")
+        .arg(format!("{video_input_index}:v:0"))
+        .arg("-vf")
+        .arg(format!(
+            "fps=1,scale={BLACK_FRAME_SAMPLE_WIDTH}:{BLACK_FRAME_SAMPLE_HEIGHT}:flags=area,format=gray"
+        ))
+        .arg("-an")
+        .arg("-c:v")
+        .arg("rawvideo")
+        .arg("-f")
+        .arg("rawvideo")
+        .arg("pipe:1");
+}
+
+pub(crate) fn is_black_frame_sample(sample: &[u8]) -> bool {
+    sample.iter().all(|luma| *luma <= BLACK_FRAME_MAX_LUMA)
+}
+
+/// Quotes a filter option value and then escapes it for the filtergraph parser, so labels
+/// and paths may contain quotes, colons, commas and brackets.
+fn escape_filter_option_value(value: &str) -> String {
+    let quoted_value = format!("'{}'", value.replace('\'', r"'\''"));
+    let mut escaped_value = String::with_capacity(quoted_value.len());
+    for character in quoted_value.chars() {
+        if matches!(character, '\\' | '\'' | '[' | ']' | ',' | ';') {
+            escaped_value.push('\\');
+        }
+        escaped_value.push(character);
+    }
+    escaped_value
+}
+
+/// Windows FFmpeg builds cannot always resolve fonts by family name, so drawtext gets the
+/// system Arial file when it exists.
+pub(crate) fn default_annotation_font_file() -> Option<String> {
+    let system_root = std::env::var("SystemRoot").ok()?;
+    let font_path = Path::new(&system_root).join("Fonts").join("arial.ttf");
+    font_path
+        .is_file()
+        .then(|| font_path.to_string_lossy().replace('\\', "/"))
+}
+
+/// Builds one `drawtext` per marker that shows its label for a few seconds from the
+/// marker timestamp. `%` expansion is disabled so labels are drawn verbatim.
+pub(crate) fn build_annotation_filter(
+    markers: &[RecordingAnnotationMarker],
+    font_file: Option<&str>,
+) -> String {
+    let font_option = font_file
+        .map(|font_file| format!("fontfile={}:", escape_filter_option_value(font_file)))
+        .unwrap_or_default();
+
+    markers
+        .iter()
+        .map(|marker| {
+            let label = marker
+                .label
+                .chars()
+                .filter(|character| !character.is_control())
+                .collect::<String>();
+            let start_seconds = marker.timestamp_seconds.max(0.0);
+            let end_seconds = start_seconds + ANNOTATION_MARKER_DISPLAY_SECONDS;
+            format!(
+                "drawtext={font_option}text={}:expansion=none:fontcolor=white:fontsize=h/18:box=1:boxcolor=black@0.6:boxborderw=12:x=(w-text_w)/2:y=h/10:enable={}",
+                escape_filter_option_value(&label),
+                escape_filter_option_value(&format!(
+                    "between(t,{start_seconds:.3},{end_seconds:.3})"
+                ))
+            )
+        })
+        .collect::<Vec<String>>()
+        .join(",")
+}
+
+/// The default chain boosts quiet game audio and relies on the limiter to catch the
+/// resulting peaks. Bypassing the limiter also drops the boost, since it would clip.
+pub(crate) fn resolve_system_audio_filter(
+    bypass_limiter: bool,
+    resample_sync: AudioResampleSync,
+) -> String {
+    let resample_filter = format!(
+        "aresample=async={}:min_hard_comp={:.3}:first_pts=0",
+        resample_sync.async_samples, resample_sync.min_hard_comp_seconds
+    );
+    if bypass_limiter {
+        return resample_filter;
+    }
+
+    format!("{resample_filter},volume=2.2,alimiter=limit=0.98")
+}
+
+fn parse_channel_index(channel: &str) -> Option<usize> {
+    channel.trim().strip_prefix('c')?.parse().ok()
+}
+
+/// Checks a manual `pan` map such as `c0=c1|c1=0.5*c0+0.5*c1` against the captured
+/// channel count and returns it without whitespace. Each output channel of the stereo
+/// result may be assigned once, from input channels the capture actually has.
+fn validate_audio_channel_map(map: &str, captured_channels: usize) -> Result<String, String> {
+    let mut assigned_outputs = Vec::new();
+    let mut assignments = Vec::new();
+    for assignment in map
+        .split('|')
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+    {
+        let (output, expression) = assignment
+            .split_once('=')
+            .ok_or_else(|| format!("Channel map entry '{assignment}' is missing '='"))?;
+        let output_index = parse_channel_index(output)
+            .filter(|index| *index < SYSTEM_AUDIO_CHANNEL_COUNT)
+            .ok_or_else(|| format!("Channel map output '{}' must be c0 or c1", output.trim()))?;
+        if assigned_outputs.contains(&output_index) {
+            return Err(format!(
+                "Channel map assigns c{output_index} more than once"
+            ));
+        }
+        assigned_outputs.push(output_index);
+
+        let expression = expression.replace(char::is_whitespace, "");
+        let terms = expression.replace('-', "+-");
+        let mut has_term = false;
+        for term in terms.split('+').filter(|term| !term.is_empty()) {
+            let channel = match term.rsplit_once('*') {
+                Some((gain, channel)) => {
+                    gain.parse::<f64>()
+                        .ok()
+                        .filter(|gain| gain.is_finite())
+                        .ok_or_else(|| format!("Channel map gain '{gain}' is not a number"))?;
+                    channel
+                }
+                None => term.trim_start_matches('-'),
+            };
+            parse_channel_index(channel)
+                .filter(|index| *index < captured_channels)
+                .ok_or_else(|| {
+                    format!(
+                        "Channel map input '{channel}' does not exist; the capture has {captured_channels} channels (c0 to c{})",
+                        captured_channels.saturating_sub(1)
+                    )
+                })?;
+            has_term = true;
+        }
+        if !has_term {
+            return Err(format!(
+                "Channel map entry '{assignment}' has no input channels"
+            ));
+        }
+        assignments.push(format!("c{output_index}={expression}"));
+    }
+
+    if assignments.is_empty() {
+        return Err("Channel map is empty".to_string());
+    }
+    Ok(assignments.join("|"))
+}
+
+/// Builds the `pan` filter that remaps captured loopback channels for virtual audio
+/// routing that leaves one side silent. `stereo` keeps the capture as it is; `swap`,
+/// `left` and `right` swap the sides or copy one side to both; `custom` uses `custom_map`.
+/// Windows folds surround mixes into the stereo capture format, so maps can only address
+/// the captured channels.
+pub(crate) fn resolve_audio_channel_map_filter(
+    preset: &str,
+    custom_map: Option<&str>,
+    captured_channels: usize,
+) -> Result<Option<String>, String> {
+    let map = match preset {
+        "stereo" => return Ok(None),
+        "swap" => "c0=c1|c1=c0".to_string(),
+        "left" => "c0=c0|c1=c0".to_string(),
+        "right" => "c0=c1|c1=c1".to_string(),
+        "custom" => validate_audio_channel_map(custom_map.unwrap_or_default(), captured_channels)?,
+        other => {
+            tracing::warn!(
+                audio_channel_map = %other,
+                "Unknown audio channel map value. Keeping the captured channels as they are"
+            );
+            return Ok(None);
+        }
+    };
+    Ok(Some(format!("pan=stereo|{map}")))
+}
+
+/// Latency correction that runs ahead of the resampler. A delay pads silence in front of
+/// the audio; an advance trims the start and restarts the timestamps at zero.
+pub(crate) fn resolve_audio_offset_filter(offset_ms: i32) -> Option<String> {
+    let offset_ms = offset_ms.clamp(-MAX_AUDIO_OFFSET_MS, MAX_AUDIO_OFFSET_MS);
+    match offset_ms {
+        0 => None,
+        delay_ms if delay_ms > 0 => Some(format!("adelay=delays={delay_ms}:all=1")),
+        advance_ms => Some(format!(
+            "atrim=start={:.3},asetpts=PTS-STARTPTS",
+            f64::from(advance_ms.unsigned_abs()) / 1000.0
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        build_annotation_filter, build_virtual_desktop_capture_graph, h264_max_bitrate_bps,
+        is_black_frame_sample, is_ffmpeg_informational_line, next_fallback_video_encoder,
+        parse_compare_scores, parse_ffmpeg_frame_stats, parse_input_stream_info,
+        parse_progress_out_time_seconds, resolve_audio_channel_map_filter,
+        resolve_audio_offset_filter, resolve_letterbox_color, resolve_pixel_format,
+        resolve_system_audio_filter, resolve_video_encoder_preference, resolve_video_filter,
+        select_decode_hwaccel, video_rate_control_args,
+    };
+    use crate::recording::model::{
+        AudioResampleSync, FrameSync, GpuVendor, RateControl, RecordingAnnotationMarker,
+        RuntimeCaptureMode, VirtualDesktopLayout, VirtualDesktopMonitor,
+    };
+
+    #[test]
+    fn stacks_virtual_desktop_monitors_at_their_offsets() {
+        let layout = VirtualDesktopLayout {
+            monitors: vec![
+                VirtualDesktopMonitor {
+                    output_idx: 0,
+                    x: 1280,
+                    y: 300,
+                },
+                VirtualDesktopMonitor {
+                    output_idx: 1,
+                    x: 0,
+                    y: 0,
+                },
+            ],
+            width: 3200,
+            height: 1380,
+            output_width: 3200,
+            output_height: 1380,
+        };
+
+        assert_eq!(
+            build_virtual_desktop_capture_graph(&layout, 60, 1),
+            "ddagrab=output_idx=0:framerate=60:draw_mouse=1,hwdownload,format=bgra[m0];\
+ddagrab=output_idx=1:framerate=60:draw_mouse=1,hwdownload,format=bgra[m1];\
+[m0][m1]xstack=inputs=2:layout=1280_300|0_0:fill=black[out0]"
         );
     }
 
-    format!("fps={output_frame_rate},format=yuv420p")
+    #[test]
+    fn parses_input_stream_info_from_ffmpeg_banner() {
+        let stderr = "\
+Input #0, mov,mp4,m4a,3gp,3g2,mj2, from 'recording.mp4':
+  Duration: 00:01:02.50, start: 0.000000, bitrate: 6123 kb/s
+  Stream #0:0[0x1](und): Video: h264 (High) (avc1 / 0x31637661), yuv420p(tv, bt709, progressive), 1920x1080 [SAR 1:1 DAR 16:9], 5981 kb/s, 60 fps, 60 tbr, 15360 tbn (default)
+  Stream #0:1[0x2](und): Audio: aac (LC) (mp4a / 0x6134706D), 48000 Hz, stereo, fltp, 128 kb/s (default)
+Stream mapping:
+  Stream #0:0 -> #0:0 (h264 (native) -> wrapped_avframe (native))
+Output #0, null, to 'pipe:':
+  Stream #0:0(und): Video: wrapped_avframe, yuv420p, 1280x720, q=2-31, 200 kb/s, 30 fps
+";
+
+        let info = parse_input_stream_info(stderr);
+
+        assert_eq!(info.duration_seconds, Some(62.5));
+        assert_eq!(info.video_codec.as_deref(), Some("h264"));
+        assert_eq!(info.width, Some(1920));
+        assert_eq!(info.height, Some(1080));
+        assert_eq!(info.frame_rate, Some(60.0));
+        assert_eq!(info.audio_codec.as_deref(), Some("aac"));
+    }
+
+    #[test]
+    fn leaves_stream_info_empty_for_unreadable_input() {
+        let stderr = "recording.mp4: Invalid data found when processing input\n";
+
+        let info = parse_input_stream_info(stderr);
+
+        assert_eq!(info, Default::default());
+    }
+
+    #[test]
+    fn parses_ssim_and_psnr_summaries() {
+        let stderr = "[Parsed_ssim_3 @ 000001] SSIM Y:0.981234 (17.26) U:0.990000 (20.00) V:0.990000 (20.00) All:0.984321 (18.05)\n[Parsed_psnr_4 @ 000002] PSNR y:40.12 u:43.50 v:43.80 average:41.07 min:36.90 max:45.31";
+        assert_eq!(parse_compare_scores(stderr), Some((0.984321, 41.07)));
+
+        let identical = "[Parsed_ssim_3 @ 01] SSIM Y:1.000000 (inf) U:1.000000 (inf) V:1.000000 (inf) All:1.000000 (inf)\n[Parsed_psnr_4 @ 02] PSNR y:inf u:inf v:inf average:inf min:inf max:inf";
+        assert_eq!(parse_compare_scores(identical), Some((1.0, f64::INFINITY)));
+        assert_eq!(parse_compare_scores("frame=120"), None);
+    }
+
+    #[test]
+    fn parses_progress_out_time() {
+        assert_eq!(
+            parse_progress_out_time_seconds("out_time_us=2500000"),
+            Some(2.5)
+        );
+        assert_eq!(parse_progress_out_time_seconds("out_time_us=N/A"), None);
+        assert_eq!(parse_progress_out_time_seconds("frame=120"), None);
+    }
+
+    #[test]
+    fn maps_vbr_to_each_hardware_encoder() {
+        assert_eq!(
+            video_rate_control_args("h264_nvenc", RateControl::Vbr, 10_000_000, 23),
+            [
+                "-rc", "vbr", "-cq", "23", "-b:v", "10000000", "-maxrate", "15000000", "-bufsize",
+                "30000000"
+            ]
+        );
+        assert_eq!(
+            video_rate_control_args("h264_qsv", RateControl::Vbr, 10_000_000, 25)[..2],
+            ["-global_quality", "25"]
+        );
+        assert_eq!(
+            video_rate_control_args("h264_amf", RateControl::Vbr, 10_000_000, 23)[..2],
+            ["-rc", "vbr_peak"]
+        );
+    }
+
+    #[test]
+    fn keeps_cbr_for_software_encoding() {
+        let cbr_args = [
+            "-b:v", "8000000", "-maxrate", "8000000", "-bufsize", "16000000",
+        ];
+        assert_eq!(
+            video_rate_control_args("libx264", RateControl::Vbr, 8_000_000, 23),
+            cbr_args
+        );
+        assert_eq!(
+            video_rate_control_args("h264_nvenc", RateControl::Cbr, 8_000_000, 23),
+            cbr_args
+        );
+    }
+
+    #[test]
+    fn separates_informational_lines_from_warnings() {
+        assert!(is_ffmpeg_informational_line(
+            "[h264_nvenc @ 000001] [verbose] Nvenc initialized successfully"
+        ));
+        assert!(is_ffmpeg_informational_line(
+            "[info] Stream mapping: 0:0 -> 0:0"
+        ));
+        assert!(!is_ffmpeg_informational_line(
+            "[h264_nvenc @ 000001] [warning] Bitrate too high"
+        ));
+        assert!(!is_ffmpeg_informational_line(
+            "[h264_nvenc @ 000001] OpenEncodeSessionEx failed: out of memory (10)"
+        ));
+    }
+
+    #[test]
+    fn parses_latest_frame_stats_report() {
+        let line = "frame=   60 fps= 60 q=23.0 size=     512KiB time=00:00:01.00 bitrate=4194.3kbits/s speed=1.0x\rframe=  600 fps= 59 q=23.0 size=    5120KiB time=00:00:10.00 bitrate=4194.3kbits/s dup=150 drop=3 speed=1.0x";
+
+        let stats = parse_ffmpeg_frame_stats(line).expect("Expected frame stats");
+
+        assert_eq!(stats.frames, 600);
+        assert_eq!(stats.duplicated, 150);
+        assert_eq!(stats.dropped, 3);
+        assert_eq!(stats.encoded_seconds, 10.0);
+        assert_eq!(stats.speed, Some(1.0));
+        assert_eq!(stats.output_bytes, Some(5120 * 1024));
+        assert_eq!(stats.captured_frame_rate(), Some(45.3));
+    }
+
+    #[test]
+    fn scales_captured_frame_rate_by_encode_speed() {
+        let line = "frame=  600 fps= 48 q=23.0 size=    5120KiB time=00:00:10.00 bitrate=4194.3kbits/s speed=0.8x";
+
+        let stats = parse_ffmpeg_frame_stats(line).expect("Expected frame stats");
+
+        assert_eq!(stats.captured_frame_rate(), Some(48.0));
+    }
+
+    #[test]
+    fn derives_h264_bitrate_ceiling_from_macroblock_rate() {
+        assert_eq!(h264_max_bitrate_bps(1280, 720, 30), 17_500_000);
+        assert_eq!(h264_max_bitrate_bps(1920, 1080, 60), 62_500_000);
+        assert_eq!(h264_max_bitrate_bps(3840, 2160, 60), 300_000_000);
+    }
+
+    #[test]
+    fn system_audio_filter_drops_boost_when_limiter_is_bypassed() {
+        assert_eq!(
+            resolve_system_audio_filter(false, AudioResampleSync::BALANCED),
+            "aresample=async=1:min_hard_comp=0.100:first_pts=0,volume=2.2,alimiter=limit=0.98"
+        );
+        assert_eq!(
+            resolve_system_audio_filter(true, AudioResampleSync::BALANCED),
+            "aresample=async=1:min_hard_comp=0.100:first_pts=0"
+        );
+    }
+
+    #[test]
+    fn system_audio_filter_applies_sync_presets() {
+        assert_eq!(
+            resolve_system_audio_filter(
+                true,
+                AudioResampleSync::from_setting("strict", None, None)
+            ),
+            "aresample=async=1000:min_hard_comp=0.050:first_pts=0"
+        );
+        assert_eq!(
+            resolve_system_audio_filter(
+                true,
+                AudioResampleSync::from_setting("smooth", None, None)
+            ),
+            "aresample=async=1:min_hard_comp=0.500:first_pts=0"
+        );
+        assert_eq!(
+            resolve_system_audio_filter(
+                true,
+                AudioResampleSync::from_setting("custom", Some(0), Some(f64::NAN))
+            ),
+            "aresample=async=1:min_hard_comp=0.100:first_pts=0"
+        );
+        assert_eq!(
+            resolve_system_audio_filter(
+                true,
+                AudioResampleSync::from_setting("custom", Some(500), Some(0.25))
+            ),
+            "aresample=async=500:min_hard_comp=0.250:first_pts=0"
+        );
+    }
+
+    #[test]
+    fn letterboxes_window_capture_when_preserving_aspect_ratio() {
+        assert_eq!(
+            resolve_video_filter(
+                RuntimeCaptureMode::Window,
+                FrameSync::Cfr,
+                60,
+                1920,
+                1080,
+                "yuv420p",
+                None
+            ),
+            "fps=60,scale=1920:1080:flags=bicubic,format=yuv420p"
+        );
+        assert_eq!(
+            resolve_video_filter(
+                RuntimeCaptureMode::Window,
+                FrameSync::Cfr,
+                60,
+                1920,
+                1080,
+                "yuv420p",
+                Some("0x101010")
+            ),
+            "fps=60,scale=1920:1080:flags=bicubic:force_original_aspect_ratio=decrease:force_divisible_by=2,\
+pad=1920:1080:(ow-iw)/2:(oh-ih)/2:color=0x101010,format=yuv420p"
+        );
+        assert_eq!(
+            resolve_video_filter(
+                RuntimeCaptureMode::Monitor,
+                FrameSync::Cfr,
+                60,
+                1920,
+                1080,
+                "yuv420p",
+                Some("black")
+            ),
+            "fps=60,format=yuv420p"
+        );
+
+        assert_eq!(resolve_letterbox_color(" Black ").as_deref(), Ok("black"));
+        assert_eq!(
+            resolve_letterbox_color("#1a2B3c").as_deref(),
+            Ok("0x1A2B3C")
+        );
+        assert!(resolve_letterbox_color("#12345").is_err());
+        assert!(resolve_letterbox_color("black:t=1").is_err());
+        assert_eq!(
+            resolve_letterbox_color("DarkSlateGray").as_deref(),
+            Ok("darkslategray")
+        );
+        assert!(resolve_letterbox_color("blak").is_err());
+    }
+
+    #[test]
+    fn resolves_audio_channel_map_presets_and_custom_maps() {
+        assert_eq!(
+            resolve_audio_channel_map_filter("stereo", None, 2),
+            Ok(None)
+        );
+        assert_eq!(
+            resolve_audio_channel_map_filter("swap", None, 2),
+            Ok(Some("pan=stereo|c0=c1|c1=c0".to_string()))
+        );
+        assert_eq!(
+            resolve_audio_channel_map_filter("custom", Some("c0 = c1 | c1=0.5*c0 - 0.5*c1"), 2),
+            Ok(Some("pan=stereo|c0=c1|c1=0.5*c0-0.5*c1".to_string()))
+        );
+        assert!(resolve_audio_channel_map_filter("custom", Some("c0=c4"), 2).is_err());
+        assert!(resolve_audio_channel_map_filter("custom", Some("c2=c0"), 2).is_err());
+        assert!(resolve_audio_channel_map_filter("custom", Some("c0=c0|c0=c1"), 2).is_err());
+        assert!(resolve_audio_channel_map_filter("custom", None, 2).is_err());
+    }
+
+    #[test]
+    fn resolves_audio_offset_filter() {
+        assert_eq!(resolve_audio_offset_filter(0), None);
+        assert_eq!(
+            resolve_audio_offset_filter(120).as_deref(),
+            Some("adelay=delays=120:all=1")
+        );
+        assert_eq!(
+            resolve_audio_offset_filter(-85).as_deref(),
+            Some("atrim=start=0.085,asetpts=PTS-STARTPTS")
+        );
+        assert_eq!(
+            resolve_audio_offset_filter(i32::MIN).as_deref(),
+            Some("atrim=start=5.000,asetpts=PTS-STARTPTS")
+        );
+    }
+
+    #[test]
+    fn falls_back_through_the_available_encoders() {
+        let available =
+            |names: &[&str]| -> Vec<String> { names.iter().map(|name| name.to_string()).collect() };
+
+        assert_eq!(
+            next_fallback_video_encoder(
+                "h264_nvenc",
+                &available(&["h264_nvenc", "h264_amf", "libx264"])
+            ),
+            Some("h264_amf")
+        );
+        assert_eq!(
+            next_fallback_video_encoder("h264_qsv", &available(&["h264_qsv", "libx264"])),
+            Some("libx264")
+        );
+        assert_eq!(
+            next_fallback_video_encoder("libx264", &available(&["h264_nvenc", "libx264"])),
+            None
+        );
+        assert_eq!(
+            next_fallback_video_encoder("h264_amf", &available(&["h264_amf"])),
+            None
+        );
+    }
+
+    #[test]
+    fn binds_auto_encoder_to_the_chosen_gpu() {
+        assert_eq!(
+            resolve_video_encoder_preference("auto", "auto", Some(GpuVendor::Intel)),
+            "h264_qsv"
+        );
+        assert_eq!(
+            resolve_video_encoder_preference("auto", "auto", None),
+            "auto"
+        );
+        assert_eq!(
+            resolve_video_encoder_preference("auto", "amd", Some(GpuVendor::Nvidia)),
+            "h264_amf"
+        );
+        assert_eq!(
+            resolve_video_encoder_preference("libx264", "nvidia", Some(GpuVendor::Intel)),
+            "libx264"
+        );
+        assert_eq!(
+            GpuVendor::from_pci_vendor_id(0x10DE),
+            Some(GpuVendor::Nvidia)
+        );
+        // Microsoft Basic Render Driver.
+        assert_eq!(GpuVendor::from_pci_vendor_id(0x1414), None);
+    }
+
+    #[test]
+    fn selects_decode_hwaccel_from_detected_encoders() {
+        let encoders =
+            |names: &[&str]| -> Vec<String> { names.iter().map(|name| name.to_string()).collect() };
+
+        assert_eq!(
+            select_decode_hwaccel(&encoders(&["h264_qsv", "h264_nvenc", "libx264"])),
+            Some("cuda")
+        );
+        assert_eq!(
+            select_decode_hwaccel(&encoders(&["h264_qsv", "libx264"])),
+            Some("qsv")
+        );
+        assert_eq!(
+            select_decode_hwaccel(&encoders(&["h264_amf", "libx264"])),
+            Some("d3d11va")
+        );
+        assert_eq!(select_decode_hwaccel(&encoders(&["libx264"])), None);
+    }
+
+    #[test]
+    fn builds_escaped_annotation_filter() {
+        let markers = vec![
+            RecordingAnnotationMarker {
+                timestamp_seconds: 12.5,
+                label: "Death: Jaina's Pet, 50%".to_string(),
+            },
+            RecordingAnnotationMarker {
+                timestamp_seconds: -1.0,
+                label: "Pull\n[1]".to_string(),
+            },
+        ];
+
+        let filter = build_annotation_filter(&markers, Some("C:/Windows/Fonts/arial.ttf"));
+
+        assert_eq!(
+            filter,
+            concat!(
+                r"drawtext=fontfile=\'C:/Windows/Fonts/arial.ttf\':text=\'Death: Jaina\'\\\'\'s Pet\, 50%\':",
+                r"expansion=none:fontcolor=white:fontsize=h/18:box=1:boxcolor=black@0.6:boxborderw=12:x=(w-text_w)/2:y=h/10:",
+                r"enable=\'between(t\,12.500\,15.500)\',",
+                r"drawtext=fontfile=\'C:/Windows/Fonts/arial.ttf\':text=\'Pull\[1\]\':",
+                r"expansion=none:fontcolor=white:fontsize=h/18:box=1:boxcolor=black@0.6:boxborderw=12:x=(w-text_w)/2:y=h/10:",
+                r"enable=\'between(t\,0.000\,3.000)\'"
+            )
+        );
+    }
+
+    #[test]
+    fn resolves_pixel_format_for_encoder() {
+        assert_eq!(
+            resolve_pixel_format("libx264", "yuv420p10le"),
+            "yuv420p10le"
+        );
+        assert_eq!(resolve_pixel_format("h264_nvenc", "yuv420p10le"), "yuv420p");
+        assert_eq!(resolve_pixel_format("h264_qsv", "yuv420p"), "yuv420p");
+        assert_eq!(resolve_pixel_format("libx264", "rgb24"), "yuv420p");
+    }
+
+    #[test]
+    fn detects_black_frame_samples() {
+        assert!(is_black_frame_sample(&[16; 144]));
+        assert!(is_black_frame_sample(&[0, 16, 24]));
+
+        let mut dark_scene = [18u8; 144];
+        dark_scene[70] = 90;
+        assert!(!is_black_frame_sample(&dark_scene));
+    }
 }
@@ -0,0 +1,178 @@
+use std::fs::OpenOptions;
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+#[cfg(target_os = "windows")]
+use windows_sys::Win32::Foundation::{CloseHandle, FILETIME, STILL_ACTIVE};
+#[cfg(target_os = "windows")]
+use windows_sys::Win32::System::Threading::{
+    GetExitCodeProcess, GetProcessTimes, OpenProcess, PROCESS_QUERY_LIMITED_INFORMATION,
+};
+
+use super::model::RECORDING_FOLDER_LOCK_FILE_NAME;
+
+/// Seconds between the FILETIME epoch (1601-01-01) and the Unix epoch.
+#[cfg(target_os = "windows")]
+const FILETIME_UNIX_EPOCH_OFFSET_SECS: u64 = 11_644_473_600;
+
+/// Contents of the lock file, used to tell a live holder from one left behind by a crash.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RecordingFolderLockHolder {
+    process_id: u32,
+    acquired_at_unix: u64,
+}
+
+/// Advisory lock on an output folder, held for the whole recording session so a second
+/// app instance cannot write or clean up recordings in the same folder. The lock file is
+/// removed when this is dropped.
+#[derive(Debug)]
+pub(crate) struct RecordingFolderLock {
+    lock_path: PathBuf,
+}
+
+impl RecordingFolderLock {
+    pub(crate) fn acquire(output_folder: &Path) -> Result<Self, String> {
+        let lock_path = output_folder.join(RECORDING_FOLDER_LOCK_FILE_NAME);
+        let holder = RecordingFolderLockHolder {
+            process_id: std::process::id(),
+            acquired_at_unix: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|duration| duration.as_secs())
+                .unwrap_or(0),
+        };
+        let contents = serde_json::to_vec(&holder)
+            .map_err(|error| format!("Failed to serialize recording folder lock: {error}"))?;
+
+        // One retry: a stale lock is removed and the create is attempted again.
+        for _ in 0..2 {
+            match OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&lock_path)
+            {
+                Ok(mut file) => {
+                    let lock = Self { lock_path };
+                    file.write_all(&contents).map_err(|error| {
+                        format!("Failed to write recording folder lock: {error}")
+                    })?;
+                    return Ok(lock);
+                }
+                Err(error) if error.kind() == ErrorKind::AlreadyExists => {
+                    if let Some(process_id) = live_lock_holder(&lock_path) {
+                        return Err(format!(
+                            "Another FloorPoV instance (process {process_id}) is already recording to this folder. Stop that recording or choose a different output folder."
+                        ));
+                    }
+
+                    tracing::warn!(
+                        lock_path = %lock_path.display(),
+                        "Removing stale recording folder lock"
+                    );
+                    match std::fs::remove_file(&lock_path) {
+                        Ok(()) => {}
+                        Err(error) if error.kind() == ErrorKind::NotFound => {}
+                        Err(error) => {
+                            return Err(format!(
+                                "Failed to remove stale recording folder lock '{}': {error}",
+                                lock_path.display()
+                            ));
+                        }
+                    }
+                }
+                Err(error) => {
+                    return Err(format!(
+                        "Failed to create recording folder lock '{}': {error}",
+                        lock_path.display()
+                    ));
+                }
+            }
+        }
+
+        Err(format!(
+            "Failed to acquire recording folder lock '{}'",
+            lock_path.display()
+        ))
+    }
+}
+
+impl Drop for RecordingFolderLock {
+    fn drop(&mut self) {
+        if let Err(error) = std::fs::remove_file(&self.lock_path) {
+            if error.kind() != ErrorKind::NotFound {
+                tracing::warn!(
+                    lock_path = %self.lock_path.display(),
+                    "Failed to release recording folder lock: {error}"
+                );
+            }
+        }
+    }
+}
+
+/// Returns the PID holding the lock, or `None` when the lock is unreadable, its process
+/// has exited, or the PID now belongs to a process started after the lock was taken.
+fn live_lock_holder(lock_path: &Path) -> Option<u32> {
+    let contents = std::fs::read(lock_path).ok()?;
+    let holder: RecordingFolderLockHolder = serde_json::from_slice(&contents).ok()?;
+    let started_at_unix = process_started_at_unix(holder.process_id)?;
+    (started_at_unix <= holder.acquired_at_unix).then_some(holder.process_id)
+}
+
+/// Creation time of a running process, or `None` if no such process is running.
+#[cfg(target_os = "windows")]
+fn process_started_at_unix(process_id: u32) -> Option<u64> {
+    if process_id == 0 {
+        return None;
+    }
+
+    let process_handle = unsafe { OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, 0, process_id) };
+    if process_handle.is_null() {
+        return None;
+    }
+
+    let mut exit_code = 0u32;
+    let empty_time = FILETIME {
+        dwLowDateTime: 0,
+        dwHighDateTime: 0,
+    };
+    let (mut created_at, mut exited_at, mut kernel_time, mut user_time) =
+        (empty_time, empty_time, empty_time, empty_time);
+    let (exit_code_result, times_result) = unsafe {
+        (
+            GetExitCodeProcess(process_handle, &mut exit_code),
+            GetProcessTimes(
+                process_handle,
+                &mut created_at,
+                &mut exited_at,
+                &mut kernel_time,
+                &mut user_time,
+            ),
+        )
+    };
+
+    unsafe {
+        CloseHandle(process_handle);
+    }
+
+    if exit_code_result == 0 || exit_code != STILL_ACTIVE as u32 || times_result == 0 {
+        return None;
+    }
+
+    let created_at_100ns =
+        (u64::from(created_at.dwHighDateTime) << 32) | u64::from(created_at.dwLowDateTime);
+    Some((created_at_100ns / 10_000_000).saturating_sub(FILETIME_UNIX_EPOCH_OFFSET_SECS))
+}
+
+/// Start times are not read here, so a live PID is reported as predating every lock.
+/// Without procfs liveness cannot be checked and the lock is treated as held.
+#[cfg(not(target_os = "windows"))]
+fn process_started_at_unix(process_id: u32) -> Option<u64> {
+    let proc_root = Path::new("/proc");
+    if !proc_root.is_dir() {
+        return Some(0);
+    }
+    proc_root.join(process_id.to_string()).exists().then_some(0)
+}
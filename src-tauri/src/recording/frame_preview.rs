@@ -0,0 +1,105 @@
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+use tauri::{AppHandle, Manager};
+
+use super::model::{FRAME_PREVIEW_CACHE_CAPACITY, FRAME_PREVIEW_TIMESTAMP_STEP_MS};
+
+/// Extracted frames, least recently used first. Evicted frames are deleted from disk.
+static FRAME_PREVIEW_CACHE: Mutex<VecDeque<(FramePreviewKey, PathBuf)>> =
+    Mutex::new(VecDeque::new());
+/// Frames left over from an earlier run are removed the first time the folder is used.
+static PREVIEW_DIRECTORY_RESET: AtomicBool = AtomicBool::new(false);
+
+/// Identifies one extracted frame. The modification time keeps a rewritten recording
+/// from serving frames of its previous contents.
+#[derive(Clone, PartialEq, Eq)]
+pub(crate) struct FramePreviewKey {
+    input_path: PathBuf,
+    modified_at: Option<SystemTime>,
+    timestamp_millis: u64,
+}
+
+impl FramePreviewKey {
+    /// Rounds the timestamp to `FRAME_PREVIEW_TIMESTAMP_STEP_MS` so hovering along the
+    /// timeline reuses frames instead of extracting one per mouse move.
+    pub(crate) fn new(input_path: &Path, timestamp_secs: f64) -> Self {
+        Self {
+            input_path: input_path.to_path_buf(),
+            modified_at: input_path
+                .metadata()
+                .and_then(|metadata| metadata.modified())
+                .ok(),
+            timestamp_millis: quantize_timestamp_millis(timestamp_secs),
+        }
+    }
+
+    pub(crate) fn timestamp_secs(&self) -> f64 {
+        self.timestamp_millis as f64 / 1000.0
+    }
+}
+
+fn quantize_timestamp_millis(timestamp_secs: f64) -> u64 {
+    let steps = (timestamp_secs.max(0.0) * 1000.0 / FRAME_PREVIEW_TIMESTAMP_STEP_MS as f64).round();
+    steps as u64 * FRAME_PREVIEW_TIMESTAMP_STEP_MS
+}
+
+pub(crate) fn cached_frame(key: &FramePreviewKey) -> Option<PathBuf> {
+    let mut cache = FRAME_PREVIEW_CACHE.lock().ok()?;
+    let index = cache.iter().position(|(cached_key, _)| cached_key == key)?;
+    let entry = cache.remove(index)?;
+    if !entry.1.is_file() {
+        return None;
+    }
+
+    let frame_path = entry.1.clone();
+    cache.push_back(entry);
+    Some(frame_path)
+}
+
+pub(crate) fn store_frame(key: FramePreviewKey, frame_path: PathBuf) {
+    let mut cache = match FRAME_PREVIEW_CACHE.lock() {
+        Ok(cache) => cache,
+        Err(error) => {
+            tracing::warn!("Failed to lock frame preview cache: {error}");
+            return;
+        }
+    };
+    cache.push_back((key, frame_path));
+    while cache.len() > FRAME_PREVIEW_CACHE_CAPACITY {
+        if let Some((_, evicted_path)) = cache.pop_front() {
+            if let Err(error) = std::fs::remove_file(&evicted_path) {
+                tracing::debug!(
+                    frame_path = %evicted_path.display(),
+                    "Failed to remove evicted frame preview: {error}"
+                );
+            }
+        }
+    }
+}
+
+/// Folder for extracted frames, inside the app cache and readable through the asset
+/// protocol so the frontend can load frames with `convertFileSrc`.
+pub(crate) fn frame_preview_directory(app_handle: &AppHandle) -> Result<PathBuf, String> {
+    let directory = app_handle
+        .path()
+        .app_cache_dir()
+        .map_err(|error| format!("Failed to resolve the app cache folder: {error}"))?
+        .join("frame_previews");
+    if !PREVIEW_DIRECTORY_RESET.swap(true, Ordering::SeqCst) && directory.exists() {
+        if let Err(error) = std::fs::remove_dir_all(&directory) {
+            tracing::debug!("Failed to clear old frame previews: {error}");
+        }
+    }
+
+    std::fs::create_dir_all(&directory)
+        .map_err(|error| format!("Failed to create the frame preview folder: {error}"))?;
+    app_handle
+        .asset_protocol_scope()
+        .allow_directory(&directory, false)
+        .map_err(|error| format!("Failed to allow frame previews in asset scope: {error}"))?;
+    Ok(directory)
+}
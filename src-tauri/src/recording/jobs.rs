@@ -0,0 +1,147 @@
+use std::collections::BTreeMap;
+use std::process::{Child, ExitStatus};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use tauri::{AppHandle, Emitter};
+
+use super::model::{JobCancelledPayload, POST_PROCESS_JOB_POLL_INTERVAL};
+
+static RUNNING_JOBS: Mutex<BTreeMap<String, Arc<PostProcessJob>>> = Mutex::new(BTreeMap::new());
+
+/// A long-running FFmpeg post-process (concat, repair, annotate, compare) that `cancel_job` can stop.
+pub(crate) struct PostProcessJob {
+    pub(crate) id: String,
+    cancelled: AtomicBool,
+    child: Mutex<Option<Child>>,
+}
+
+impl PostProcessJob {
+    pub(crate) fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// Keeps the FFmpeg child where a cancel can reach it. A job cancelled before its
+    /// process started kills the process straight away.
+    pub(crate) fn attach_child(&self, mut child: Child) -> Result<(), String> {
+        let mut slot = self
+            .child
+            .lock()
+            .map_err(|error| format!("Failed to lock job process: {error}"))?;
+        if self.is_cancelled() {
+            if let Err(error) = child.kill() {
+                tracing::warn!(job_id = %self.id, "Failed to kill cancelled job process: {error}");
+            }
+            if let Err(error) = child.wait() {
+                tracing::warn!(job_id = %self.id, "Failed to reap cancelled job process: {error}");
+            }
+        }
+        *slot = Some(child);
+        Ok(())
+    }
+
+    /// Polls instead of blocking in `wait` so the lock stays free for `cancel_job`.
+    pub(crate) fn wait_child(&self) -> Result<ExitStatus, String> {
+        loop {
+            {
+                let mut slot = self
+                    .child
+                    .lock()
+                    .map_err(|error| format!("Failed to lock job process: {error}"))?;
+                let child = slot
+                    .as_mut()
+                    .ok_or_else(|| "Job has no running process".to_string())?;
+                if let Some(status) = child.try_wait().map_err(|error| error.to_string())? {
+                    *slot = None;
+                    return Ok(status);
+                }
+            }
+            thread::sleep(POST_PROCESS_JOB_POLL_INTERVAL);
+        }
+    }
+
+    fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+        if let Ok(mut slot) = self.child.lock() {
+            if let Some(child) = slot.as_mut() {
+                if let Err(error) = child.kill() {
+                    tracing::debug!(job_id = %self.id, "Killing job process returned: {error}");
+                }
+            }
+        };
+    }
+}
+
+/// Registers a job under `job_id`, or a fresh id when the caller does not need to cancel
+/// it by a known id.
+pub(crate) fn begin_job(job_id: Option<String>) -> Result<Arc<PostProcessJob>, String> {
+    let id = job_id.unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+    let mut jobs = RUNNING_JOBS
+        .lock()
+        .map_err(|error| format!("Failed to lock running jobs: {error}"))?;
+    if jobs.contains_key(&id) {
+        return Err(format!("A job with id {id} is already running"));
+    }
+
+    let job = Arc::new(PostProcessJob {
+        id: id.clone(),
+        cancelled: AtomicBool::new(false),
+        child: Mutex::new(None),
+    });
+    jobs.insert(id, Arc::clone(&job));
+    Ok(job)
+}
+
+/// Unregisters the job. A job that failed after being cancelled reports the cancellation
+/// instead of the FFmpeg error and emits `job-cancelled`; one that finished before the
+/// cancel took effect keeps its result.
+pub(crate) fn finish_job<T>(
+    app_handle: &AppHandle,
+    job: &PostProcessJob,
+    result: Result<T, String>,
+) -> Result<T, String> {
+    if let Ok(mut jobs) = RUNNING_JOBS.lock() {
+        jobs.remove(&job.id);
+    };
+
+    if result.is_ok() || !job.is_cancelled() {
+        return result;
+    }
+
+    tracing::info!(job_id = %job.id, "Post-process job cancelled");
+    let payload = JobCancelledPayload {
+        job_id: job.id.clone(),
+    };
+    if let Err(error) = app_handle.emit("job-cancelled", payload) {
+        tracing::warn!("Failed to emit job-cancelled event: {error}");
+    }
+    Err("Job was cancelled".to_string())
+}
+
+/// Cancels every running job and returns how many there were.
+pub(crate) fn cancel_all_jobs() -> Result<usize, String> {
+    let jobs: Vec<Arc<PostProcessJob>> = RUNNING_JOBS
+        .lock()
+        .map_err(|error| format!("Failed to lock running jobs: {error}"))?
+        .values()
+        .cloned()
+        .collect();
+
+    for job in &jobs {
+        job.cancel();
+    }
+    Ok(jobs.len())
+}
+
+pub(crate) fn cancel_job(job_id: &str) -> Result<(), String> {
+    let job = RUNNING_JOBS
+        .lock()
+        .map_err(|error| format!("Failed to lock running jobs: {error}"))?
+        .get(job_id)
+        .cloned()
+        .ok_or_else(|| format!("No running job with id {job_id}"))?;
+
+    job.cancel();
+    Ok(())
+}
@@ -2,9 +2,14 @@ use serde::{Deserialize, Serialize};
 use std::collections::BTreeMap;
 use std::io::ErrorKind;
 use std::path::{Path, PathBuf};
+use std::sync::Mutex;
 use std::time::{SystemTime, UNIX_EPOCH};
 
 pub(crate) const RECORDING_METADATA_SCHEMA_VERSION: u32 = 2;
+const IMPORTED_MARKER_DEFAULT_EVENT_TYPE: &str = "MANUAL_MARKER";
+/// Imported markers this close to an existing marker of the same type and note are
+/// treated as duplicates, so re-importing the same file is a no-op.
+const IMPORTED_MARKER_DUPLICATE_TOLERANCE_SECS: f64 = 0.001;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -38,6 +43,9 @@ pub struct RecordingImportantEventMetadata {
     pub encounter_category: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub key_level: Option<u32>,
+    /// Free-text annotation of a manual marker.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -54,6 +62,47 @@ pub struct RecordingPlayerMetadata {
     pub spec_id: Option<u32>,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecordingSegmentFrameRateMetadata {
+    pub segment_index: usize,
+    pub achieved_frame_rate: f64,
+    pub duration_seconds: f64,
+}
+
+/// One FFmpeg segment of the recording, including ones that were discarded. Start times
+/// are relative to the start of the recording file, i.e. of the part for split sessions.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecordingSegmentTimelineMetadata {
+    pub segment_index: usize,
+    pub capture_mode: String,
+    pub started_at_seconds: f64,
+    pub duration_seconds: f64,
+    pub force_killed: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecordingCaptureQualityMetadata {
+    pub requested_frame_rate: u32,
+    pub achieved_frame_rate: f64,
+    pub below_requested_frame_rate: bool,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub segments: Vec<RecordingSegmentFrameRateMetadata>,
+}
+
+/// Stream lengths probed from the finalized file; a mismatch means the recording is
+/// likely to drift out of sync during playback.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecordingAudioSyncMetadata {
+    pub video_duration_seconds: f64,
+    pub audio_duration_seconds: f64,
+    pub mismatch_seconds: f64,
+    pub mismatch_detected: bool,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct RecordingMetadata {
@@ -77,9 +126,50 @@ pub struct RecordingMetadata {
     pub important_events_dropped_count: u64,
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub players: Vec<RecordingPlayerMetadata>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub capture_quality: Option<RecordingCaptureQualityMetadata>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub audio_sync: Option<RecordingAudioSyncMetadata>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub segment_timeline: Vec<RecordingSegmentTimelineMetadata>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session_id: Option<String>,
     pub captured_at_unix: u64,
 }
 
+/// Outcome of `reconcile_markers`, reported back to the UI.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecordingMarkerReconciliation {
+    pub duration_seconds: f64,
+    pub dropped_event_count: usize,
+    pub dropped_encounter_count: usize,
+    pub clamped_encounter_count: usize,
+}
+
+/// Marker supplied by an external tool, e.g. pull timings exported from a log site.
+/// Timestamps are seconds relative to the start of the recording.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportedRecordingMarker {
+    pub timestamp_seconds: f64,
+    #[serde(default)]
+    pub event_type: Option<String>,
+    #[serde(default)]
+    pub note: Option<String>,
+    #[serde(default)]
+    pub encounter_name: Option<String>,
+}
+
+/// Outcome of `import_markers`, reported back to the UI.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecordingMarkerImport {
+    pub imported_count: usize,
+    pub duplicate_count: usize,
+    pub total_event_count: usize,
+}
+
 #[derive(Debug, Clone)]
 pub(crate) struct RecordingEncounterSnapshot {
     pub(crate) name: String,
@@ -129,6 +219,10 @@ impl RecordingMetadata {
             important_event_counts: BTreeMap::new(),
             important_events_dropped_count: 0,
             players: Vec::new(),
+            capture_quality: None,
+            audio_sync: None,
+            segment_timeline: Vec::new(),
+            session_id: None,
             captured_at_unix,
         }
     }
@@ -153,6 +247,217 @@ impl RecordingMetadata {
         self.important_events_dropped_count = snapshot.important_events_dropped_count;
         self.players = snapshot.players;
     }
+
+    /// Shifts all marker timestamps by `offset_seconds` (negative after trimming the
+    /// start), then drops events outside `0..=duration_seconds` and clamps or drops
+    /// encounters so they fit the video. Per-type counts shrink with dropped events.
+    pub(crate) fn reconcile_with_duration(
+        &mut self,
+        duration_seconds: f64,
+        offset_seconds: f64,
+    ) -> RecordingMarkerReconciliation {
+        let in_range = |seconds: f64| (0.0..=duration_seconds).contains(&seconds);
+
+        let mut dropped_event_count = 0;
+        let important_event_counts = &mut self.important_event_counts;
+        self.important_events.retain_mut(|event| {
+            event.timestamp_seconds += offset_seconds;
+            if in_range(event.timestamp_seconds) {
+                return true;
+            }
+
+            dropped_event_count += 1;
+            if let Some(count) = important_event_counts.get_mut(&event.event_type) {
+                *count = count.saturating_sub(1);
+                if *count == 0 {
+                    important_event_counts.remove(&event.event_type);
+                }
+            }
+            false
+        });
+
+        let mut dropped_encounter_count = 0;
+        let mut clamped_encounter_count = 0;
+        self.encounters.retain_mut(|encounter| {
+            let started_at = encounter
+                .started_at_seconds
+                .map(|value| value + offset_seconds);
+            let ended_at = encounter
+                .ended_at_seconds
+                .map(|value| value + offset_seconds);
+            let starts_after_video = started_at.is_some_and(|value| value > duration_seconds);
+            let ends_before_video = ended_at.is_some_and(|value| value < 0.0);
+            if starts_after_video || ends_before_video {
+                dropped_encounter_count += 1;
+                return false;
+            }
+
+            let clamped_started_at = started_at.map(|value| value.clamp(0.0, duration_seconds));
+            let clamped_ended_at = ended_at.map(|value| value.clamp(0.0, duration_seconds));
+            if clamped_started_at != started_at || clamped_ended_at != ended_at {
+                clamped_encounter_count += 1;
+            }
+            encounter.started_at_seconds = clamped_started_at;
+            encounter.ended_at_seconds = clamped_ended_at;
+            true
+        });
+
+        RecordingMarkerReconciliation {
+            duration_seconds,
+            dropped_event_count,
+            dropped_encounter_count,
+            clamped_encounter_count,
+        }
+    }
+
+    /// Takes the combat log markers of one part of a split recording from the session's
+    /// sidecar, keeping only what falls inside the part and timing it from the part's start.
+    pub(crate) fn apply_part_markers(
+        &mut self,
+        session_metadata: &RecordingMetadata,
+        part_started_at_seconds: f64,
+        part_duration_seconds: f64,
+    ) {
+        let mut part_metadata = session_metadata.clone();
+        part_metadata.reconcile_with_duration(part_duration_seconds, -part_started_at_seconds);
+        self.zone_name = part_metadata.zone_name;
+        self.encounter_name = part_metadata.encounter_name;
+        self.encounter_category = part_metadata.encounter_category;
+        self.key_level = part_metadata.key_level;
+        self.encounters = part_metadata.encounters;
+        self.important_events = part_metadata.important_events;
+        self.important_event_counts = part_metadata.important_event_counts;
+        self.important_events_dropped_count = part_metadata.important_events_dropped_count;
+        self.players = part_metadata.players;
+    }
+
+    /// Adds imported events next to the existing ones instead of replacing them,
+    /// skipping duplicates, and keeps the event list ordered by timestamp.
+    pub(crate) fn merge_imported_events(
+        &mut self,
+        events: Vec<RecordingImportantEventMetadata>,
+    ) -> RecordingMarkerImport {
+        let mut imported_count = 0;
+        let mut duplicate_count = 0;
+        for event in events {
+            let is_duplicate = self.important_events.iter().any(|existing| {
+                existing.event_type == event.event_type
+                    && existing.note == event.note
+                    && (existing.timestamp_seconds - event.timestamp_seconds).abs()
+                        <= IMPORTED_MARKER_DUPLICATE_TOLERANCE_SECS
+            });
+            if is_duplicate {
+                duplicate_count += 1;
+                continue;
+            }
+
+            *self
+                .important_event_counts
+                .entry(event.event_type.clone())
+                .or_insert(0) += 1;
+            self.important_events.push(event);
+            imported_count += 1;
+        }
+
+        self.important_events
+            .sort_by(|left, right| left.timestamp_seconds.total_cmp(&right.timestamp_seconds));
+
+        RecordingMarkerImport {
+            imported_count,
+            duplicate_count,
+            total_event_count: self.important_events.len(),
+        }
+    }
+}
+
+impl ImportedRecordingMarker {
+    /// Checks the timestamp against the recording and converts the marker into a
+    /// sidecar event; blank event types fall back to a manual marker.
+    pub(crate) fn into_important_event(
+        self,
+        duration_seconds: f64,
+    ) -> Result<RecordingImportantEventMetadata, String> {
+        if !self.timestamp_seconds.is_finite()
+            || !(0.0..=duration_seconds).contains(&self.timestamp_seconds)
+        {
+            return Err(format!(
+                "Marker at {} seconds is outside the recording (0 to {duration_seconds:.3} seconds)",
+                self.timestamp_seconds
+            ));
+        }
+
+        let event_type = self
+            .event_type
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty())
+            .unwrap_or_else(|| IMPORTED_MARKER_DEFAULT_EVENT_TYPE.to_string());
+        let note = self
+            .note
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty());
+        Ok(RecordingImportantEventMetadata {
+            timestamp_seconds: self.timestamp_seconds,
+            log_timestamp: None,
+            event_type,
+            source: None,
+            target: None,
+            target_kind: None,
+            zone_name: None,
+            encounter_name: self.encounter_name,
+            encounter_category: None,
+            key_level: None,
+            note,
+        })
+    }
+}
+
+impl RecordingCaptureQualityMetadata {
+    /// Averages segment frame rates weighted by duration and flags recordings that fell
+    /// below `low_frame_rate_ratio` of the requested rate.
+    pub(crate) fn from_segments(
+        requested_frame_rate: u32,
+        segments: Vec<RecordingSegmentFrameRateMetadata>,
+        low_frame_rate_ratio: f64,
+    ) -> Option<Self> {
+        let total_seconds: f64 = segments
+            .iter()
+            .map(|segment| segment.duration_seconds)
+            .sum();
+        if total_seconds <= 0.0 {
+            return None;
+        }
+
+        let achieved_frame_rate = segments
+            .iter()
+            .map(|segment| segment.achieved_frame_rate * segment.duration_seconds)
+            .sum::<f64>()
+            / total_seconds;
+
+        Some(Self {
+            requested_frame_rate,
+            achieved_frame_rate,
+            below_requested_frame_rate: achieved_frame_rate
+                < f64::from(requested_frame_rate) * low_frame_rate_ratio,
+            segments,
+        })
+    }
+}
+
+impl RecordingAudioSyncMetadata {
+    /// `mismatch_seconds` is positive when the audio stream is longer than the video.
+    pub(crate) fn from_stream_durations(
+        video_duration_seconds: f64,
+        audio_duration_seconds: f64,
+        threshold_seconds: f64,
+    ) -> Self {
+        let mismatch_seconds = audio_duration_seconds - video_duration_seconds;
+        Self {
+            video_duration_seconds,
+            audio_duration_seconds,
+            mismatch_seconds,
+            mismatch_detected: mismatch_seconds.abs() > threshold_seconds,
+        }
+    }
 }
 
 impl RecordingMetadataSnapshot {
@@ -173,6 +478,23 @@ pub(crate) fn metadata_sidecar_path(recording_path: &Path) -> PathBuf {
     recording_path.with_extension("meta.json")
 }
 
+/// The recording a sidecar (or a temporary sidecar left by an interrupted write) belongs
+/// to, or `None` when `sidecar_path` is not a metadata sidecar.
+pub(crate) fn sidecar_recording_path(sidecar_path: &Path) -> Option<PathBuf> {
+    let file_name = sidecar_path.file_name()?.to_str()?;
+    let stem = file_name
+        .strip_suffix(".meta.json.tmp")
+        .or_else(|| file_name.strip_suffix(".meta.json"))
+        .filter(|stem| !stem.is_empty())?;
+    Some(sidecar_path.with_file_name(format!("{stem}.mp4")))
+}
+
+lazy_static::lazy_static! {
+    /// Serializes sidecar read-modify-write cycles so the combat log flush and the
+    /// recording finalize steps cannot overwrite each other's fields.
+    static ref SIDECAR_UPDATE_LOCK: Mutex<()> = Mutex::new(());
+}
+
 pub(crate) fn read_recording_metadata(
     recording_path: &Path,
 ) -> Result<Option<RecordingMetadata>, String> {
@@ -251,6 +573,22 @@ pub(crate) fn write_recording_metadata(
     Ok(sidecar_path)
 }
 
+/// Loads the sidecar (or a fresh one), applies `update` and writes it back while holding
+/// the sidecar lock. Nothing is written when `update` returns an error.
+pub(crate) fn update_recording_metadata<T>(
+    recording_path: &Path,
+    update: impl FnOnce(&mut RecordingMetadata) -> Result<T, String>,
+) -> Result<T, String> {
+    let _guard = SIDECAR_UPDATE_LOCK
+        .lock()
+        .map_err(|error| format!("Failed to lock recording metadata: {error}"))?;
+    let mut metadata = read_recording_metadata(recording_path)?
+        .unwrap_or_else(|| RecordingMetadata::new(recording_path));
+    let result = update(&mut metadata)?;
+    write_recording_metadata(recording_path, &metadata)?;
+    Ok(result)
+}
+
 pub(crate) fn delete_recording_metadata(recording_path: &Path) -> Result<(), String> {
     let sidecar_path = metadata_sidecar_path(recording_path);
     match std::fs::remove_file(&sidecar_path) {
@@ -275,7 +613,9 @@ fn temporary_sidecar_path(sidecar_path: &Path) -> PathBuf {
 mod tests {
     use super::{
         delete_recording_metadata, metadata_sidecar_path, read_recording_metadata,
-        write_recording_metadata, RecordingImportantEventMetadata, RecordingMetadata,
+        sidecar_recording_path, write_recording_metadata, ImportedRecordingMarker,
+        RecordingAudioSyncMetadata, RecordingCaptureQualityMetadata, RecordingEncounterMetadata,
+        RecordingImportantEventMetadata, RecordingMetadata, RecordingSegmentFrameRateMetadata,
     };
     use std::path::Path;
     use std::time::{SystemTime, UNIX_EPOCH};
@@ -302,6 +642,20 @@ mod tests {
         );
     }
 
+    #[test]
+    fn derives_recording_path_from_sidecar_path() {
+        assert_eq!(
+            sidecar_recording_path(Path::new("capture.meta.json")),
+            Some(Path::new("capture.mp4").to_path_buf())
+        );
+        assert_eq!(
+            sidecar_recording_path(Path::new("capture.meta.json.tmp")),
+            Some(Path::new("capture.mp4").to_path_buf())
+        );
+        assert_eq!(sidecar_recording_path(Path::new("capture.mp4")), None);
+        assert_eq!(sidecar_recording_path(Path::new(".meta.json")), None);
+    }
+
     #[test]
     fn writes_reads_and_deletes_recording_metadata() {
         let temp_directory = unique_temp_directory();
@@ -370,6 +724,7 @@ mod tests {
                 encounter_name: Some("Test Encounter".to_string()),
                 encounter_category: Some("raid".to_string()),
                 key_level: None,
+                note: None,
             });
         metadata
             .important_event_counts
@@ -401,4 +756,174 @@ mod tests {
         std::fs::remove_dir_all(&temp_directory)
             .expect("Failed to remove temporary metadata test directory");
     }
+
+    #[test]
+    fn weights_capture_quality_by_segment_duration() {
+        let segments = vec![
+            RecordingSegmentFrameRateMetadata {
+                segment_index: 0,
+                achieved_frame_rate: 60.0,
+                duration_seconds: 30.0,
+            },
+            RecordingSegmentFrameRateMetadata {
+                segment_index: 1,
+                achieved_frame_rate: 30.0,
+                duration_seconds: 10.0,
+            },
+        ];
+
+        let quality = RecordingCaptureQualityMetadata::from_segments(60, segments, 0.9)
+            .expect("Expected capture quality summary");
+
+        assert_eq!(quality.achieved_frame_rate, 52.5);
+        assert!(quality.below_requested_frame_rate);
+        assert_eq!(quality.segments.len(), 2);
+        assert!(RecordingCaptureQualityMetadata::from_segments(60, Vec::new(), 0.9).is_none());
+    }
+
+    fn important_event_at(timestamp_seconds: f64) -> RecordingImportantEventMetadata {
+        RecordingImportantEventMetadata {
+            timestamp_seconds,
+            log_timestamp: None,
+            event_type: "UNIT_DIED".to_string(),
+            source: None,
+            target: None,
+            target_kind: None,
+            zone_name: None,
+            encounter_name: None,
+            encounter_category: None,
+            key_level: None,
+            note: None,
+        }
+    }
+
+    fn encounter_between(started_at: f64, ended_at: f64) -> RecordingEncounterMetadata {
+        RecordingEncounterMetadata {
+            name: "Test Encounter".to_string(),
+            category: "raid".to_string(),
+            started_at_seconds: Some(started_at),
+            ended_at_seconds: Some(ended_at),
+        }
+    }
+
+    #[test]
+    fn reconciles_markers_with_trimmed_duration() {
+        let mut metadata = RecordingMetadata::new(Path::new("recording.mp4"));
+        metadata.important_events = vec![
+            important_event_at(5.0),
+            important_event_at(20.0),
+            important_event_at(75.0),
+        ];
+        metadata
+            .important_event_counts
+            .insert("UNIT_DIED".to_string(), 3);
+        metadata.encounters = vec![
+            encounter_between(2.0, 8.0),
+            encounter_between(15.0, 40.0),
+            encounter_between(50.0, 90.0),
+            encounter_between(80.0, 95.0),
+        ];
+
+        let reconciliation = metadata.reconcile_with_duration(60.0, -10.0);
+
+        assert_eq!(reconciliation.dropped_event_count, 2);
+        assert_eq!(reconciliation.dropped_encounter_count, 2);
+        assert_eq!(reconciliation.clamped_encounter_count, 1);
+        assert_eq!(metadata.important_events.len(), 1);
+        assert_eq!(metadata.important_events[0].timestamp_seconds, 10.0);
+        assert_eq!(
+            metadata.important_event_counts.get("UNIT_DIED").copied(),
+            Some(1)
+        );
+        assert_eq!(metadata.encounters[0].started_at_seconds, Some(5.0));
+        assert_eq!(metadata.encounters[1].started_at_seconds, Some(40.0));
+        assert_eq!(metadata.encounters[1].ended_at_seconds, Some(60.0));
+    }
+
+    #[test]
+    fn takes_part_markers_relative_to_the_part_start() {
+        let mut session_metadata = RecordingMetadata::new(Path::new("recording.mp4"));
+        session_metadata.important_events = vec![
+            important_event_at(5.0),
+            important_event_at(130.0),
+            important_event_at(250.0),
+        ];
+        session_metadata
+            .important_event_counts
+            .insert("UNIT_DIED".to_string(), 3);
+        session_metadata.encounters = vec![encounter_between(90.0, 150.0)];
+
+        let mut part_metadata = RecordingMetadata::new(Path::new("recording_part2.mp4"));
+        part_metadata.apply_part_markers(&session_metadata, 120.0, 120.0);
+
+        assert_eq!(part_metadata.recording_file, "recording_part2.mp4");
+        assert_eq!(part_metadata.important_events.len(), 1);
+        assert_eq!(part_metadata.important_events[0].timestamp_seconds, 10.0);
+        assert_eq!(
+            part_metadata
+                .important_event_counts
+                .get("UNIT_DIED")
+                .copied(),
+            Some(1)
+        );
+        assert_eq!(part_metadata.encounters[0].started_at_seconds, Some(0.0));
+        assert_eq!(part_metadata.encounters[0].ended_at_seconds, Some(30.0));
+        assert_eq!(session_metadata.important_events.len(), 3);
+    }
+
+    #[test]
+    fn merges_imported_markers_without_overwriting_existing_ones() {
+        let mut metadata = RecordingMetadata::new(Path::new("recording.mp4"));
+        metadata.important_events = vec![important_event_at(5.0), important_event_at(30.0)];
+        metadata
+            .important_event_counts
+            .insert("UNIT_DIED".to_string(), 2);
+
+        let imported = [12.5, 30.0, 12.5]
+            .into_iter()
+            .map(|timestamp_seconds| ImportedRecordingMarker {
+                timestamp_seconds,
+                event_type: Some(if timestamp_seconds == 30.0 {
+                    "UNIT_DIED".to_string()
+                } else {
+                    String::new()
+                }),
+                note: None,
+                encounter_name: None,
+            })
+            .map(|marker| marker.into_important_event(60.0).unwrap())
+            .collect();
+        let import = metadata.merge_imported_events(imported);
+
+        assert_eq!(import.imported_count, 1);
+        assert_eq!(import.duplicate_count, 2);
+        assert_eq!(import.total_event_count, 3);
+        assert_eq!(metadata.important_events[1].timestamp_seconds, 12.5);
+        assert_eq!(metadata.important_events[1].event_type, "MANUAL_MARKER");
+        assert_eq!(
+            metadata
+                .important_event_counts
+                .get("MANUAL_MARKER")
+                .copied(),
+            Some(1)
+        );
+
+        let out_of_range = ImportedRecordingMarker {
+            timestamp_seconds: 61.0,
+            event_type: None,
+            note: None,
+            encounter_name: None,
+        };
+        assert!(out_of_range.into_important_event(60.0).is_err());
+    }
+
+    #[test]
+    fn flags_audio_video_duration_mismatch_beyond_threshold() {
+        let in_sync = RecordingAudioSyncMetadata::from_stream_durations(120.0, 120.2, 0.5);
+        assert!(!in_sync.mismatch_detected);
+
+        let audio_short = RecordingAudioSyncMetadata::from_stream_durations(120.0, 118.5, 0.5);
+        assert!(audio_short.mismatch_detected);
+        assert_eq!(audio_short.mismatch_seconds, -1.5);
+    }
 }
@@ -1,17 +1,24 @@
 mod audio_pipeline;
 mod ffmpeg;
+mod folder_lock;
+mod frame_preview;
+mod jobs;
 pub(crate) mod metadata;
 mod model;
 mod segments;
 mod session;
 mod window_capture;
+mod window_thumbnail;
 
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
 
-use tauri::{AppHandle, Emitter};
-use tokio::sync::mpsc;
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_dialog::{DialogExt, MessageDialogButtons, MessageDialogKind};
+use tokio::sync::{mpsc, oneshot};
 
-pub use model::RecordingState;
+pub use model::{AudioMonitorState, RecordingState, SharedRecordingState};
 use model::{CaptureInput, RecordingSessionConfig};
 
 fn sanitize_for_filename(input: &str) -> String {
@@ -33,16 +40,122 @@ fn sanitize_for_filename(input: &str) -> String {
     result.chars().take(30).collect()
 }
 
+/// With `include_thumbnails`, the first `MAX_WINDOW_THUMBNAILS_PER_LIST` windows that can
+/// be grabbed come with a JPEG preview; use `get_window_thumbnail` for the others.
 #[tauri::command]
-pub fn list_capture_windows() -> Result<Vec<model::CaptureWindowInfo>, String> {
-    window_capture::list_capture_windows_internal()
+pub async fn list_capture_windows(
+    app_handle: AppHandle,
+    include_thumbnails: Option<bool>,
+) -> Result<Vec<model::CaptureWindowInfo>, String> {
+    let mut windows = window_capture::list_capture_windows_internal()?;
+    if !include_thumbnails.unwrap_or(false) {
+        return Ok(windows);
+    }
+
+    let ffmpeg_binary_path = ffmpeg::resolve_configured_ffmpeg_binary_path(&app_handle)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        for window in windows
+            .iter_mut()
+            .take(model::MAX_WINDOW_THUMBNAILS_PER_LIST)
+        {
+            let thumbnail = window_capture::resolve_thumbnail_window_handle(&window.hwnd).and_then(
+                |window_hwnd| {
+                    window_thumbnail::window_thumbnail_base64(&ffmpeg_binary_path, window_hwnd)
+                },
+            );
+            match thumbnail {
+                Ok(thumbnail) => window.thumbnail_jpeg_base64 = Some(thumbnail),
+                Err(error) => tracing::debug!(
+                    hwnd = %window.hwnd,
+                    "Skipping window thumbnail: {error}"
+                ),
+            }
+        }
+        windows
+    })
+    .await
+    .map_err(|error| format!("Window thumbnail task failed: {error}"))
+}
+
+/// Base64 JPEG preview of a single window from the picker.
+#[tauri::command]
+pub async fn get_window_thumbnail(app_handle: AppHandle, hwnd: String) -> Result<String, String> {
+    let window_hwnd = window_capture::resolve_thumbnail_window_handle(&hwnd)?;
+    let ffmpeg_binary_path = ffmpeg::resolve_configured_ffmpeg_binary_path(&app_handle)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        window_thumbnail::window_thumbnail_base64(&ffmpeg_binary_path, window_hwnd)
+    })
+    .await
+    .map_err(|error| format!("Window thumbnail task failed: {error}"))?
+}
+
+#[tauri::command]
+pub fn list_capture_monitors() -> Result<Vec<model::CaptureMonitorInfo>, String> {
+    window_capture::list_capture_monitors_internal()
+}
+
+/// Runs on a blocking worker: WASAPI enumeration needs an MTA thread, and Tauri runs sync
+/// commands on the STA main thread.
+#[tauri::command]
+pub async fn list_audio_output_devices() -> Result<Vec<model::AudioOutputDeviceInfo>, String> {
+    tauri::async_runtime::spawn_blocking(audio_pipeline::list_audio_output_devices)
+        .await
+        .map_err(|error| format!("Audio device listing task failed: {error}"))?
+}
+
+/// Output audio sessions the `system_audio_session` setting can pick from. Listed on a
+/// blocking worker for the same MTA reason as `list_audio_output_devices`.
+#[tauri::command]
+pub async fn list_audio_sessions() -> Result<Vec<model::AudioSessionInfo>, String> {
+    tauri::async_runtime::spawn_blocking(audio_pipeline::list_audio_sessions)
+        .await
+        .map_err(|error| format!("Audio session listing task failed: {error}"))?
+}
+
+#[tauri::command]
+pub fn get_current_audio_device() -> Result<Option<model::CurrentAudioDeviceInfo>, String> {
+    audio_pipeline::current_loopback_device()
+}
+
+/// Moves the running recording's system audio to another output device without
+/// restarting the capture. `default` follows the default output device.
+#[tauri::command]
+pub async fn switch_audio_device(
+    state: tauri::State<'_, model::SharedRecordingState>,
+    device_id: String,
+) -> Result<(), String> {
+    if !state.read().await.is_recording {
+        return Err("No recording in progress".to_string());
+    }
+    if audio_pipeline::current_loopback_device()?.is_none() {
+        return Err("The current recording is not capturing system audio".to_string());
+    }
+
+    let device_id = match device_id.trim() {
+        "" | "default" => None,
+        device_id => {
+            let devices = audio_pipeline::list_audio_output_devices()?;
+            if !devices.iter().any(|device| device.id == device_id) {
+                return Err(format!("Output audio device '{device_id}' was not found"));
+            }
+            Some(device_id.to_string())
+        }
+    };
+
+    tracing::info!(
+        device_id = device_id.as_deref().unwrap_or("default"),
+        "Requested system audio device switch"
+    );
+    audio_pipeline::request_loopback_device_switch(device_id)
 }
 
 #[tauri::command]
 pub fn get_available_video_encoders(
     app_handle: AppHandle,
+    custom_ffmpeg_path: Option<String>,
 ) -> Result<Vec<model::AvailableVideoEncoder>, String> {
-    let ffmpeg_binary_path = ffmpeg::resolve_ffmpeg_binary_path(&app_handle)?;
+    let ffmpeg_binary_path =
+        ffmpeg::resolve_preferred_ffmpeg_binary_path(&app_handle, custom_ffmpeg_path.as_deref())?;
     let mut options = vec![model::AvailableVideoEncoder {
         value: "auto".to_string(),
         label: "Auto (Recommended)".to_string(),
@@ -61,6 +174,127 @@ pub fn get_available_video_encoders(
     Ok(options)
 }
 
+/// Collects the capture environment for `settings` into one payload users can paste into
+/// a bug report: monitors, the resolved window, WGC support, encoders, the system audio
+/// device and the FFmpeg build.
+#[tauri::command]
+pub async fn get_capture_diagnostics(
+    app_handle: AppHandle,
+    settings: crate::settings::RecordingSettings,
+) -> Result<model::CaptureDiagnostics, String> {
+    // The WASAPI lookup needs an MTA thread, which the STA main thread can never become.
+    tauri::async_runtime::spawn_blocking(move || collect_capture_diagnostics(&app_handle, settings))
+        .await
+        .map_err(|error| format!("Capture diagnostics task failed: {error}"))?
+}
+
+fn collect_capture_diagnostics(
+    app_handle: &AppHandle,
+    settings: crate::settings::RecordingSettings,
+) -> Result<model::CaptureDiagnostics, String> {
+    let (monitors, monitors_error) = match window_capture::list_capture_monitors_internal() {
+        Ok(monitors) => (monitors, None),
+        Err(error) => (Vec::new(), Some(error)),
+    };
+    let (capture_input, capture_error) = match window_capture::resolve_capture_input(&settings) {
+        Ok(capture_input) => (Some(capture_input), None),
+        Err(error) => (None, Some(error)),
+    };
+
+    let (ffmpeg_binary_path, ffmpeg_error) = match ffmpeg::resolve_preferred_ffmpeg_binary_path(
+        app_handle,
+        settings.custom_ffmpeg_path.as_deref(),
+    ) {
+        Ok(path) => (Some(path), None),
+        Err(error) => (None, Some(error)),
+    };
+    let hybrid_capture_gpu_vendor = capture_input
+        .as_ref()
+        .and_then(window_capture::resolve_hybrid_capture_gpu_vendor);
+    let selected_video_encoder = ffmpeg_binary_path.as_deref().map(|path| {
+        ffmpeg::select_video_encoder(
+            path,
+            &settings.video_quality,
+            ffmpeg::resolve_video_encoder_preference(
+                &settings.video_encoder_preference,
+                &settings.encoder_gpu,
+                hybrid_capture_gpu_vendor,
+            ),
+        )
+        .0
+    });
+
+    let system_audio_device_id = audio_pipeline::resolve_system_audio_device_id(
+        &settings.system_audio_device,
+        capture_input
+            .as_ref()
+            .and_then(window_capture::resolve_capture_monitor_name)
+            .as_deref(),
+    );
+    let (system_audio_device, system_audio_error) =
+        match audio_pipeline::describe_output_device(system_audio_device_id.as_deref()) {
+            Ok(device) => (Some(device), None),
+            Err(error) => (None, Some(error)),
+        };
+
+    Ok(model::CaptureDiagnostics {
+        capture_source: settings.capture_source.clone(),
+        resolved_capture_target: capture_input.as_ref().map(CaptureInput::target_label),
+        capture_error,
+        monitors,
+        monitors_error,
+        capture_window: capture_input
+            .as_ref()
+            .and_then(window_capture::describe_capture_window_diagnostics),
+        wgc_supported: ffmpeg_binary_path
+            .as_deref()
+            .is_some_and(|path| ffmpeg::ffmpeg_has_filter(path, "gfxcapture")),
+        hybrid_capture_gpu_vendor: hybrid_capture_gpu_vendor
+            .map(|vendor| vendor.label().to_string()),
+        ffmpeg_version: ffmpeg_binary_path
+            .as_deref()
+            .and_then(ffmpeg::ffmpeg_version),
+        available_video_encoders: ffmpeg_binary_path
+            .as_deref()
+            .map(ffmpeg::list_available_video_encoders)
+            .unwrap_or_default(),
+        ffmpeg_path: ffmpeg_binary_path.map(|path| path.to_string_lossy().to_string()),
+        ffmpeg_error,
+        selected_video_encoder,
+        system_audio_device,
+        system_audio_error,
+    })
+}
+
+/// Estimates the size of a recording of `duration_secs` with the given settings, using the
+/// same capture dimensions and effective bitrate `start_recording` would use.
+#[tauri::command]
+pub fn estimate_recording_size(
+    settings: crate::settings::RecordingSettings,
+    duration_secs: u64,
+) -> Result<model::RecordingSizeEstimate, String> {
+    let capture_input = window_capture::resolve_capture_input(&settings)?;
+    let (width, height) = window_capture::resolve_capture_dimensions(&capture_input);
+
+    Ok(model::RecordingSizeEstimate {
+        estimated_bytes: settings.estimate_size_bytes(width, height, duration_secs),
+        width,
+        height,
+        bitrate: capped_effective_bitrate(&settings, width, height),
+    })
+}
+
+/// The settings' effective bitrate, capped at the H.264 level limit for the capture. Every
+/// encoder the session can pick writes H.264, so the cap is known before one is chosen.
+fn capped_effective_bitrate(
+    settings: &crate::settings::RecordingSettings,
+    width: u32,
+    height: u32,
+) -> u32 {
+    let max_bitrate = ffmpeg::h264_max_bitrate_bps(width, height, settings.frame_rate.max(1));
+    settings.effective_bitrate(width, height).min(max_bitrate)
+}
+
 #[tauri::command]
 pub async fn start_recording(
     app_handle: AppHandle,
@@ -68,6 +302,7 @@ pub async fn start_recording(
     settings: crate::settings::RecordingSettings,
     output_folder: String,
     max_storage_bytes: u64,
+    output_path_override: Option<String>,
 ) -> Result<model::RecordingStartedPayload, String> {
     {
         let recording_state = state.read().await;
@@ -78,14 +313,48 @@ pub async fn start_recording(
 
     std::fs::create_dir_all(&output_folder)
         .map_err(|error| format!("Failed to create output directory: {error}"))?;
+    // Taken before storage cleanup so two instances never delete each other's files.
+    let folder_lock = folder_lock::RecordingFolderLock::acquire(Path::new(&output_folder))?;
+
+    // Scripted recordings can pick a deterministic file name; it still has to be a path the
+    // player is allowed to load through the asset protocol.
+    let output_path_override = match output_path_override
+        .as_deref()
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+    {
+        Some(requested_path) => {
+            let output_path =
+                segments::resolve_output_path_override(Path::new(&output_folder), requested_path)?;
+            if !app_handle.asset_protocol_scope().is_allowed(&output_path) {
+                return Err(format!(
+                    "Output path override '{}' is not in an allowed recordings folder",
+                    output_path.display()
+                ));
+            }
+            Some(output_path)
+        }
+        None => None,
+    };
 
     let mut recording_settings = settings;
     let capture_input = window_capture::resolve_capture_input(&recording_settings)?;
     let (width, height) = window_capture::resolve_capture_dimensions(&capture_input);
-    let effective_bitrate = recording_settings.effective_bitrate(width, height);
+    let requested_bitrate = recording_settings.effective_bitrate(width, height);
+    let effective_bitrate = capped_effective_bitrate(&recording_settings, width, height);
+    if effective_bitrate < requested_bitrate {
+        tracing::warn!(
+            requested_bitrate,
+            max_bitrate = effective_bitrate,
+            "Requested bitrate exceeds the H.264 limit for this resolution; clamping"
+        );
+    }
     let estimated_size = recording_settings.estimate_size_bytes_for_capture(width, height);
+    let preallocate_bytes = recording_settings.preallocate_output.then(|| {
+        recording_settings.estimate_size_bytes(width, height, model::PREALLOCATED_RECORDING_SECS)
+    });
 
-    let current_size = crate::settings::get_folder_size(output_folder.clone())?;
+    let current_size = crate::settings::get_folder_size(output_folder.clone(), None)?;
     if current_size + estimated_size > max_storage_bytes {
         let cleanup_result = crate::settings::cleanup_old_recordings(
             output_folder.clone(),
@@ -100,9 +369,12 @@ pub async fn start_recording(
         }
     }
 
+    let session_id = uuid::Uuid::new_v4().to_string();
     let timestamp = chrono::Local::now().format("%Y%m%d_%H%M%S");
     let prefix = match &capture_input {
-        CaptureInput::Monitor => "screen".to_string(),
+        CaptureInput::Monitor { .. } => "screen".to_string(),
+        CaptureInput::VirtualDesktop => "desktop".to_string(),
+        CaptureInput::TestPattern => "test_pattern".to_string(),
         CaptureInput::Window { window_title, .. } => {
             if let Some(title) = window_title {
                 let clean_title = sanitize_for_filename(title);
@@ -116,34 +388,129 @@ pub async fn start_recording(
             }
         }
     };
-    let filename = format!("{prefix}_recording_{timestamp}.mp4");
-    let output_path = Path::new(&output_folder).join(filename);
+    let filename = if recording_settings.session_id_in_filename {
+        format!("{prefix}_recording_{timestamp}_{session_id}.mp4")
+    } else {
+        format!("{prefix}_recording_{timestamp}.mp4")
+    };
+    let output_path =
+        output_path_override.unwrap_or_else(|| Path::new(&output_folder).join(filename));
     let output_path_str = output_path.to_string_lossy().to_string();
 
     recording_settings.bitrate = effective_bitrate;
     let output_frame_rate = recording_settings.frame_rate.max(1);
-    let ffmpeg_binary_path = ffmpeg::resolve_ffmpeg_binary_path(&app_handle)?;
+    let ffmpeg_binary_path = ffmpeg::resolve_preferred_ffmpeg_binary_path(
+        &app_handle,
+        recording_settings.custom_ffmpeg_path.as_deref(),
+    )?;
     let resolved_capture_target = capture_input.target_label();
+    let occlusion_independent_capture = capture_input.guarantees_occlusion_independent_capture();
+    let capture_fallback_warning =
+        window_capture::capture_fallback_warning(&recording_settings, &capture_input);
 
-    if recording_settings.enable_system_audio {
-        audio_pipeline::validate_system_audio_capture_available()?;
+    let skip_missing_system_audio = recording_settings.enable_system_audio
+        && recording_settings.audio_optional
+        && !audio_pipeline::has_active_output_device();
+    if skip_missing_system_audio {
+        tracing::warn!("No active audio output device found; recording without system audio");
+        recording_settings.enable_system_audio = false;
     }
 
+    let audio_channel_map_filter = if recording_settings.enable_system_audio {
+        ffmpeg::resolve_audio_channel_map_filter(
+            &recording_settings.audio_channel_map,
+            recording_settings.audio_channel_map_custom.as_deref(),
+            model::SYSTEM_AUDIO_CHANNEL_COUNT,
+        )?
+    } else {
+        None
+    };
+
+    let letterbox_color = if recording_settings.preserve_aspect_ratio {
+        Some(ffmpeg::resolve_letterbox_color(
+            &recording_settings.letterbox_color,
+        )?)
+    } else {
+        None
+    };
+
+    let system_audio_session = recording_settings.system_audio_session.trim();
+    let system_audio_session_process_id = if recording_settings.enable_system_audio
+        && !system_audio_session.is_empty()
+    {
+        let sessions = audio_pipeline::list_audio_sessions()?;
+        let process_id =
+                audio_pipeline::select_audio_session_process_id(system_audio_session, &sessions)
+                    .ok_or_else(|| {
+                        format!(
+                            "No audio session from '{system_audio_session}' is open. Start the application first, or record all system audio."
+                        )
+                    })?;
+        Some(process_id)
+    } else {
+        None
+    };
+
+    let system_audio_device_id = if recording_settings.enable_system_audio {
+        let capture_monitor_name = window_capture::resolve_capture_monitor_name(&capture_input);
+        let system_audio_device_id = audio_pipeline::resolve_system_audio_device_id(
+            &recording_settings.system_audio_device,
+            capture_monitor_name.as_deref(),
+        );
+        audio_pipeline::validate_system_audio_capture_available(
+            system_audio_device_id.as_deref(),
+            system_audio_session_process_id,
+        )?;
+        system_audio_device_id
+    } else {
+        None
+    };
+
     tracing::info!(
         backend = "ffmpeg",
+        session_id = %session_id,
         video_quality = %recording_settings.video_quality,
         video_encoder_preference = %recording_settings.video_encoder_preference,
+        encoder_gpu = %recording_settings.encoder_gpu,
+        pixel_format = %recording_settings.pixel_format,
         requested_frame_rate = recording_settings.frame_rate,
         output_frame_rate,
         capture_source = %recording_settings.capture_source,
         resolved_capture_target = %resolved_capture_target,
+        occlusion_independent_capture,
         include_system_audio = recording_settings.enable_system_audio,
         enable_diagnostics = recording_settings.enable_recording_diagnostics,
+        auto_bitrate = recording_settings.auto_bitrate,
+        cursor_mode = %recording_settings.cursor_mode,
+        follow_foreground = recording_settings.follow_foreground,
+        preserve_aspect_ratio = recording_settings.preserve_aspect_ratio,
+        fragmented_mp4 = recording_settings.fragmented_mp4,
+        trim_trailing_black = recording_settings.trim_trailing_black,
+        preallocate_output = recording_settings.preallocate_output,
+        performance_stats = recording_settings.enable_performance_stats,
+        max_file_size_mb = recording_settings.max_file_size_mb,
+        frame_sync = %recording_settings.frame_sync,
+        rate_control = %recording_settings.rate_control,
+        vbr_quality = recording_settings.vbr_quality,
+        bypass_audio_limiter = recording_settings.bypass_audio_limiter,
+        audio_offset_ms = recording_settings.audio_offset_ms,
+        downmix_audio_mono = recording_settings.downmix_audio_mono,
+        wait_for_audio_connection = recording_settings.wait_for_audio_connection,
+        audio_channel_map = ?audio_channel_map_filter,
+        audio_codec = recording_settings.audio_codec(),
+        audio_bitrate_bps = recording_settings.audio_bitrate_bps(),
+        audio_sync_mode = %recording_settings.audio_sync_mode,
+        system_audio_device = %recording_settings.system_audio_device,
+        system_audio_session = %recording_settings.system_audio_session,
+        ffmpeg_binary_path = %ffmpeg_binary_path.display(),
         effective_bitrate_bps = recording_settings.bitrate,
         "Using recording settings"
     );
 
     let (stop_tx, stop_rx) = mpsc::channel(1);
+    let marker_tone_pending = (recording_settings.marker_tone
+        && recording_settings.enable_system_audio)
+        .then(|| std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false)));
 
     {
         let mut recording_state = state.write().await;
@@ -151,40 +518,209 @@ pub async fn start_recording(
             return Err("Recording already in progress".to_string());
         }
 
+        audio_pipeline::clear_loopback_device_override();
         recording_state.is_recording = true;
         recording_state.is_stopping = false;
         recording_state.current_output_path = Some(output_path_str.clone());
+        recording_state.started_at = Some(std::time::Instant::now());
         recording_state.stop_tx = Some(stop_tx);
+        recording_state.marker_tone_pending = marker_tone_pending.clone();
+        recording_state.folder_lock = Some(folder_lock);
     }
 
     session::spawn_ffmpeg_recording_task(
         app_handle.clone(),
         state.inner().clone(),
         RecordingSessionConfig {
+            session_id: session_id.clone(),
             output_path: output_path_str.clone(),
             ffmpeg_binary_path,
             video_quality: recording_settings.video_quality.clone(),
             video_encoder_preference: recording_settings.video_encoder_preference.clone(),
+            encoder_gpu: recording_settings.encoder_gpu.clone(),
+            pixel_format: recording_settings.pixel_format.clone(),
             requested_frame_rate: recording_settings.frame_rate,
             output_frame_rate,
             bitrate: recording_settings.bitrate,
             capture_input,
             include_system_audio: recording_settings.enable_system_audio,
             enable_diagnostics: recording_settings.enable_recording_diagnostics,
+            capture_cursor: recording_settings.capture_cursor(),
+            max_part_bytes: recording_settings.max_part_bytes(),
+            preallocate_bytes,
+            frame_sync: model::FrameSync::from_setting(&recording_settings.frame_sync),
+            rate_control: model::RateControl::from_setting(&recording_settings.rate_control),
+            vbr_quality: recording_settings
+                .vbr_quality
+                .clamp(model::MIN_VBR_QUALITY, model::MAX_VBR_QUALITY),
+            bypass_audio_limiter: recording_settings.bypass_audio_limiter,
+            audio_offset_ms: recording_settings.audio_offset_ms,
+            downmix_audio_mono: recording_settings.downmix_audio_mono,
+            wait_for_audio_connection: recording_settings.wait_for_audio_connection,
+            audio_channel_map_filter,
+            audio_resample_sync: model::AudioResampleSync::from_setting(
+                &recording_settings.audio_sync_mode,
+                recording_settings.audio_resample_async,
+                recording_settings.audio_resample_min_hard_comp,
+            ),
+            audio_codec: recording_settings.audio_codec(),
+            audio_bitrate: recording_settings.audio_bitrate_bps(),
+            follow_foreground: recording_settings.follow_foreground,
+            window_status_poll_interval: recording_settings.window_status_poll_interval(),
+            letterbox_color,
+            fragmented_mp4: recording_settings.fragmented_mp4,
+            trim_trailing_black: recording_settings.trim_trailing_black,
+            emit_performance_stats: recording_settings.enable_performance_stats,
+            system_audio_device_id,
+            system_audio_session_process_id,
+            finalize_hook: model::FinalizeHook::from_setting(
+                &recording_settings.finalize_hook,
+                recording_settings.finalize_hook_target.as_deref(),
+            ),
+            marker_tone_pending,
         },
         stop_rx,
     );
 
-    Ok(model::RecordingStartedPayload {
+    if let Some(warning_message) = capture_fallback_warning {
+        let payload = model::RecordingWarningPayload {
+            session_id: session_id.clone(),
+            message: warning_message.to_string(),
+        };
+        if let Err(error) = app_handle.emit("recording-warning", payload) {
+            tracing::warn!("Failed to emit recording-warning event: {error}");
+        }
+    }
+
+    if skip_missing_system_audio {
+        let payload = model::RecordingWarningPayload {
+            session_id: session_id.clone(),
+            message: model::NO_AUDIO_OUTPUT_DEVICE_WARNING.to_string(),
+        };
+        if let Err(error) = app_handle.emit("recording-warning", payload) {
+            tracing::warn!("Failed to emit recording-warning event: {error}");
+        }
+    }
+
+    let started_payload = model::RecordingStartedPayload {
+        session_id,
         output_path: output_path_str,
         width,
         height,
-    })
+        bitrate: effective_bitrate,
+        occlusion_independent_capture,
+    };
+    // The caller gets the payload back directly; the event keeps every other listener in
+    // sync, the counterpart of `recording-stopped`.
+    if let Err(error) = app_handle.emit("recording-started", started_payload.clone()) {
+        tracing::error!("Failed to emit recording-started event: {error}");
+    }
+    Ok(started_payload)
+}
+
+/// Captures default-device loopback audio outside of a recording and emits
+/// `audio-level` readings until `stop_rx` fires or the capture thread exits.
+fn run_audio_level_monitor(
+    app_handle: &AppHandle,
+    stop_rx: std::sync::mpsc::Receiver<()>,
+) -> Result<(), String> {
+    let (audio_tx, audio_rx) =
+        std::sync::mpsc::sync_channel::<Vec<u8>>(model::SYSTEM_AUDIO_QUEUE_CAPACITY);
+    let capture_thread = thread::spawn(move || {
+        audio_pipeline::run_system_audio_capture_to_queue(
+            audio_tx,
+            stop_rx,
+            std::sync::Arc::new(model::AudioPipelineStats::default()),
+            None,
+            None,
+            false,
+        )
+    });
+
+    let mut meter = audio_pipeline::AudioLevelMeter::default();
+    let mut emitted_at = std::time::Instant::now();
+    loop {
+        match audio_rx.recv_timeout(model::AUDIO_LEVEL_EMIT_INTERVAL) {
+            Ok(chunk) => meter.push_s16le(&chunk),
+            Err(std::sync::mpsc::RecvTimeoutError::Timeout) => {}
+            Err(std::sync::mpsc::RecvTimeoutError::Disconnected) => break,
+        }
+
+        if emitted_at.elapsed() >= model::AUDIO_LEVEL_EMIT_INTERVAL {
+            emitted_at = std::time::Instant::now();
+            if let Err(error) = app_handle.emit("audio-level", meter.take_levels()) {
+                tracing::warn!("Failed to emit audio-level event: {error}");
+            }
+        }
+    }
+
+    capture_thread
+        .join()
+        .map_err(|error| format!("System audio monitor thread panicked: {error:?}"))?
 }
 
+/// Runs loopback capture without writing a file so the settings UI can show a live
+/// level meter for the default output device.
 #[tauri::command]
-pub async fn stop_recording(
+pub async fn start_audio_monitor(
+    app_handle: AppHandle,
+    state: tauri::State<'_, model::SharedAudioMonitorState>,
+) -> Result<(), String> {
+    let mut monitor_state = state.write().await;
+    if monitor_state.stop_tx.is_some() {
+        return Ok(());
+    }
+
+    audio_pipeline::validate_system_audio_capture_available(None, None)?;
+
+    let (stop_tx, stop_rx) = std::sync::mpsc::channel::<()>();
+    monitor_state.stop_tx = Some(stop_tx);
+    monitor_state.monitor_id = monitor_state.monitor_id.wrapping_add(1);
+    let monitor_id = monitor_state.monitor_id;
+
+    let monitor_state_handle = state.inner().clone();
+    thread::spawn(move || {
+        if let Err(error) = run_audio_level_monitor(&app_handle, stop_rx) {
+            tracing::warn!("System audio monitor stopped: {error}");
+        }
+
+        // A newer monitor may already own the state if this one was stopped and restarted.
+        let mut monitor_state = monitor_state_handle.blocking_write();
+        if monitor_state.monitor_id == monitor_id {
+            monitor_state.stop_tx = None;
+        }
+    });
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn stop_audio_monitor(
+    state: tauri::State<'_, model::SharedAudioMonitorState>,
+) -> Result<(), String> {
+    if let Some(stop_tx) = state.write().await.stop_tx.take() {
+        let _ = stop_tx.send(());
+    }
+    Ok(())
+}
+
+/// Reports the backend recording state. Capture runs through a single FFmpeg
+/// pipeline per session, so there is no separate preview state to reconcile.
+#[tauri::command]
+pub async fn get_recording_status(
     state: tauri::State<'_, model::SharedRecordingState>,
+) -> Result<model::RecordingStatus, String> {
+    let recording_state = state.read().await;
+    Ok(model::RecordingStatus {
+        is_recording: recording_state.is_recording,
+        is_stopping: recording_state.is_stopping,
+        output_path: recording_state.current_output_path.clone(),
+    })
+}
+
+async fn request_recording_stop(
+    state: &model::SharedRecordingState,
+    finalize_waiter: Option<oneshot::Sender<model::RecordingStopResult>>,
 ) -> Result<String, String> {
     let (output_path, stop_tx) = {
         let mut recording_state = state.write().await;
@@ -198,6 +734,10 @@ pub async fn stop_recording(
             .clone()
             .ok_or_else(|| "No output path found".to_string())?;
 
+        if let Some(finalize_waiter) = finalize_waiter {
+            recording_state.finalize_waiters.push(finalize_waiter);
+        }
+
         if recording_state.is_stopping {
             return Ok(output_path);
         }
@@ -215,3 +755,904 @@ pub async fn stop_recording(
 
     Ok(output_path)
 }
+
+#[tauri::command]
+pub async fn stop_recording(
+    state: tauri::State<'_, model::SharedRecordingState>,
+) -> Result<String, String> {
+    request_recording_stop(state.inner(), None).await
+}
+
+static EXIT_STOP_STARTED: AtomicBool = AtomicBool::new(false);
+
+/// Called when the app is about to exit. Returns `true` when an active recording is being
+/// stopped and the exit must wait; the app exits on its own once finalization completes
+/// or `APP_EXIT_FINALIZE_TIMEOUT` runs out.
+pub(crate) fn defer_exit_for_active_recording(app_handle: &AppHandle) -> bool {
+    let state = app_handle
+        .state::<model::SharedRecordingState>()
+        .inner()
+        .clone();
+    if !state.blocking_read().is_recording || EXIT_STOP_STARTED.swap(true, Ordering::SeqCst) {
+        return false;
+    }
+
+    tracing::info!("Stopping active recording before exit");
+    let app_handle = app_handle.clone();
+    tauri::async_runtime::spawn(async move {
+        let (finalize_tx, finalize_rx) = oneshot::channel();
+        match request_recording_stop(&state, Some(finalize_tx)).await {
+            Ok(_) => {
+                match tokio::time::timeout(model::APP_EXIT_FINALIZE_TIMEOUT, finalize_rx).await {
+                    Ok(Ok(result)) => tracing::info!(
+                        output_path = %result.output_path,
+                        finalized = result.finalized,
+                        "Recording stopped before exit"
+                    ),
+                    Ok(Err(_)) => {
+                        tracing::warn!("Recording task exited without reporting finalization")
+                    }
+                    Err(_) => tracing::warn!("Timed out waiting for recording to finalize on exit"),
+                }
+            }
+            Err(error) => tracing::warn!("Failed to stop recording before exit: {error}"),
+        }
+        app_handle.exit(0);
+    });
+
+    true
+}
+
+/// Like `stop_recording`, but resolves only after the session has finalized the output
+/// and emitted `recording-finalized`/`recording-stopped`.
+#[tauri::command]
+pub async fn stop_recording_and_wait(
+    state: tauri::State<'_, model::SharedRecordingState>,
+) -> Result<model::RecordingStopResult, String> {
+    let (finalize_tx, finalize_rx) = oneshot::channel();
+    request_recording_stop(state.inner(), Some(finalize_tx)).await?;
+
+    finalize_rx
+        .await
+        .map_err(|_| "Recording task exited without reporting finalization".to_string())
+}
+
+fn describe_orphaned_workspace(
+    segment_workspace: &Path,
+) -> Option<model::OrphanedRecordingWorkspace> {
+    let recovered_output_path = segments::recovered_output_path(segment_workspace)?;
+    let segment_paths = match segments::list_workspace_segments(segment_workspace) {
+        Ok(paths) => paths,
+        Err(error) => {
+            tracing::warn!(
+                segment_workspace = %segment_workspace.display(),
+                "Failed to inspect orphaned recording workspace: {error}"
+            );
+            return None;
+        }
+    };
+
+    let size_bytes = segment_paths
+        .iter()
+        .filter_map(|path| path.metadata().ok())
+        .map(|metadata| metadata.len())
+        .sum();
+
+    Some(model::OrphanedRecordingWorkspace {
+        workspace_path: segment_workspace.to_string_lossy().to_string(),
+        recovered_output_path: recovered_output_path.to_string_lossy().to_string(),
+        segment_count: segment_paths.len(),
+        size_bytes,
+    })
+}
+
+fn recover_workspace_blocking(
+    app_handle: &AppHandle,
+    ffmpeg_binary_path: &Path,
+    segment_workspace: &Path,
+    output_path: &str,
+) -> Result<(), String> {
+    segments::recover_segment_workspace(ffmpeg_binary_path, segment_workspace, output_path)?;
+    segments::cleanup_segment_workspace(segment_workspace);
+    crate::settings::record_recording_written(Path::new(output_path));
+
+    tracing::info!(
+        segment_workspace = %segment_workspace.display(),
+        output_path,
+        "Recovered recording from interrupted session"
+    );
+    if let Err(error) = app_handle.emit("recording-recovered", output_path) {
+        tracing::warn!("Failed to emit recording-recovered event: {error}");
+    }
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn list_orphaned_recordings(
+    state: tauri::State<'_, model::SharedRecordingState>,
+    output_folder: String,
+) -> Result<Vec<model::OrphanedRecordingWorkspace>, String> {
+    let active_output_path = state.read().await.current_output_path.clone();
+    let workspaces = segments::find_orphaned_segment_workspaces(Path::new(&output_folder))?;
+
+    Ok(workspaces
+        .iter()
+        .filter(|workspace| {
+            active_output_path.as_deref().is_none_or(|output_path| {
+                !segments::workspace_belongs_to_output(workspace, Path::new(output_path))
+            })
+        })
+        .filter_map(|workspace| describe_orphaned_workspace(workspace))
+        .collect())
+}
+
+#[tauri::command]
+pub async fn recover_crashed_recording(
+    app_handle: AppHandle,
+    state: tauri::State<'_, model::SharedRecordingState>,
+    workspace_path: String,
+    output_path: String,
+) -> Result<String, String> {
+    let segment_workspace = PathBuf::from(&workspace_path);
+    if !segments::is_segment_workspace(&segment_workspace) {
+        return Err("Selected path is not a recording segment workspace".to_string());
+    }
+
+    if let Some(active_output_path) = state.read().await.current_output_path.as_deref() {
+        if segments::workspace_belongs_to_output(&segment_workspace, Path::new(active_output_path))
+        {
+            return Err("This segment workspace belongs to the active recording".to_string());
+        }
+    }
+
+    let recovered_output = Path::new(&output_path);
+    if recovered_output
+        .extension()
+        .and_then(|value| value.to_str())
+        != Some("mp4")
+    {
+        return Err("Recovered recordings must use the .mp4 extension".to_string());
+    }
+    if recovered_output.exists() {
+        return Err("A file already exists at the recovery output path".to_string());
+    }
+
+    let ffmpeg_binary_path = ffmpeg::resolve_configured_ffmpeg_binary_path(&app_handle)?;
+    let recovery_output_path = output_path.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        recover_workspace_blocking(
+            &app_handle,
+            &ffmpeg_binary_path,
+            &segment_workspace,
+            &recovery_output_path,
+        )
+    })
+    .await
+    .map_err(|error| format!("Recording recovery task failed: {error}"))??;
+
+    Ok(output_path)
+}
+
+fn emit_concat_progress(
+    app_handle: &AppHandle,
+    output_path: &str,
+    progress: f64,
+    reencoding: bool,
+) {
+    let payload = model::RecordingConcatProgressPayload {
+        output_path: output_path.to_string(),
+        progress,
+        reencoding,
+    };
+    if let Err(error) = app_handle.emit("recording-concat-progress", payload) {
+        tracing::warn!("Failed to emit recording-concat-progress event: {error}");
+    }
+}
+
+fn concat_recordings_blocking(
+    app_handle: &AppHandle,
+    ffmpeg_binary_path: &Path,
+    input_paths: &[PathBuf],
+    output_path: &str,
+    force_software_decode: bool,
+    job: &jobs::PostProcessJob,
+) -> Result<(), String> {
+    let mut stream_infos = Vec::with_capacity(input_paths.len());
+    for input_path in input_paths {
+        let verification = segments::verify_recording_file(ffmpeg_binary_path, input_path)?;
+        if !verification.decodable {
+            return Err(format!(
+                "Recording could not be decoded: {}",
+                input_path.display()
+            ));
+        }
+        stream_infos.push(verification.stream_info);
+    }
+
+    let reencode = !segments::recordings_share_stream_layout(&stream_infos);
+    if reencode {
+        tracing::warn!(
+            output_path,
+            "Selected recordings use different codecs or resolutions; re-encoding concat output"
+        );
+    }
+
+    let decode_hwaccel = if reencode && !force_software_decode {
+        ffmpeg::select_decode_hwaccel(&ffmpeg::list_available_video_encoders(ffmpeg_binary_path))
+    } else {
+        None
+    };
+
+    let run_concat = |decode_hwaccel: Option<&str>| {
+        emit_concat_progress(app_handle, output_path, 0.0, reencode);
+        segments::concat_recordings(
+            ffmpeg_binary_path,
+            input_paths,
+            &stream_infos,
+            Path::new(output_path),
+            reencode,
+            decode_hwaccel,
+            job,
+            |progress| emit_concat_progress(app_handle, output_path, progress, reencode),
+        )
+    };
+    let used_hwaccel = match run_concat(decode_hwaccel) {
+        Ok(()) => decode_hwaccel,
+        Err(error) if decode_hwaccel.is_some() && !job.is_cancelled() => {
+            tracing::warn!(
+                output_path,
+                hwaccel = decode_hwaccel,
+                "Hardware-accelerated concat failed; retrying with software decode: {error}"
+            );
+            run_concat(None)?;
+            None
+        }
+        Err(error) => return Err(error),
+    };
+    crate::settings::record_recording_written(Path::new(output_path));
+
+    tracing::info!(
+        input_count = input_paths.len(),
+        output_path,
+        reencode,
+        hwaccel = used_hwaccel,
+        "Concatenated recordings"
+    );
+    Ok(())
+}
+
+/// `job_id` lets the caller stop the concat with `cancel_job`. Without
+/// `force_software_decode` the saved "Force Software Decoding" setting applies.
+#[tauri::command]
+pub async fn concat_recordings(
+    app_handle: AppHandle,
+    state: tauri::State<'_, model::SharedRecordingState>,
+    input_paths: Vec<String>,
+    output_path: String,
+    force_software_decode: Option<bool>,
+    job_id: Option<String>,
+) -> Result<String, String> {
+    if input_paths.len() < 2 {
+        return Err("Select at least two recordings to concatenate".to_string());
+    }
+
+    let output = Path::new(&output_path);
+    if output.extension().and_then(|value| value.to_str()) != Some("mp4") {
+        return Err("Concatenated recordings must use the .mp4 extension".to_string());
+    }
+    if output.exists() {
+        return Err("A file already exists at the concat output path".to_string());
+    }
+
+    let active_output_path = state.read().await.current_output_path.clone();
+    let mut inputs = Vec::with_capacity(input_paths.len());
+    for input_path in &input_paths {
+        let input = PathBuf::from(input_path);
+        if !input.is_file() {
+            return Err(format!("Selected recording does not exist: {input_path}"));
+        }
+        if active_output_path.as_deref() == Some(input_path.as_str()) {
+            return Err(
+                "Cannot concatenate the recording that is currently being written".to_string(),
+            );
+        }
+        inputs.push(input);
+    }
+
+    let force_software_decode = force_software_decode
+        .unwrap_or_else(|| crate::settings::stored_force_software_decode(&app_handle));
+    let ffmpeg_binary_path = ffmpeg::resolve_configured_ffmpeg_binary_path(&app_handle)?;
+    let job = jobs::begin_job(job_id)?;
+    let concat_output_path = output_path.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let result = concat_recordings_blocking(
+            &app_handle,
+            &ffmpeg_binary_path,
+            &inputs,
+            &concat_output_path,
+            force_software_decode,
+            &job,
+        );
+        jobs::finish_job(&app_handle, &job, result)
+    })
+    .await
+    .map_err(|error| format!("Recording concat task failed: {error}"))??;
+
+    Ok(output_path)
+}
+
+#[tauri::command]
+pub async fn verify_recording(
+    app_handle: AppHandle,
+    input_path: String,
+) -> Result<model::RecordingVerification, String> {
+    let input = PathBuf::from(&input_path);
+    if !input.is_file() {
+        return Err("Selected recording does not exist".to_string());
+    }
+
+    let ffmpeg_binary_path = ffmpeg::resolve_configured_ffmpeg_binary_path(&app_handle)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        segments::verify_recording_file(&ffmpeg_binary_path, &input)
+    })
+    .await
+    .map_err(|error| format!("Recording verification task failed: {error}"))?
+}
+
+/// Extracts one frame of a recording as a JPEG for timeline hover previews and returns
+/// its path, which the frontend loads through the asset protocol. Recently extracted
+/// frames are served from a cache.
+#[tauri::command]
+pub async fn get_frame_at(
+    app_handle: AppHandle,
+    input_path: String,
+    timestamp_secs: f64,
+) -> Result<String, String> {
+    let input = PathBuf::from(&input_path);
+    if !input.is_file() {
+        return Err("Selected recording does not exist".to_string());
+    }
+    if !timestamp_secs.is_finite() || timestamp_secs < 0.0 {
+        return Err("Frame timestamp must be zero or more seconds".to_string());
+    }
+
+    let key = frame_preview::FramePreviewKey::new(&input, timestamp_secs);
+    if let Some(frame_path) = frame_preview::cached_frame(&key) {
+        return Ok(frame_path.to_string_lossy().to_string());
+    }
+
+    let frame_path = frame_preview::frame_preview_directory(&app_handle)?
+        .join(format!("{}.jpg", uuid::Uuid::new_v4()));
+    let ffmpeg_binary_path = ffmpeg::resolve_configured_ffmpeg_binary_path(&app_handle)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        segments::extract_frame_jpeg(
+            &ffmpeg_binary_path,
+            &input,
+            key.timestamp_secs(),
+            &frame_path,
+        )?;
+        let frame_path_string = frame_path.to_string_lossy().to_string();
+        frame_preview::store_frame(key, frame_path);
+        Ok(frame_path_string)
+    })
+    .await
+    .map_err(|error| format!("Frame extraction task failed: {error}"))?
+}
+
+fn emit_compare_progress(
+    app_handle: &AppHandle,
+    input_path_a: &Path,
+    input_path_b: &Path,
+    progress: f64,
+) {
+    let payload = model::RecordingCompareProgressPayload {
+        input_path_a: input_path_a.to_string_lossy().to_string(),
+        input_path_b: input_path_b.to_string_lossy().to_string(),
+        progress,
+    };
+    if let Err(error) = app_handle.emit("recording-compare-progress", payload) {
+        tracing::warn!("Failed to emit recording-compare-progress event: {error}");
+    }
+}
+
+fn compare_recordings_blocking(
+    app_handle: &AppHandle,
+    ffmpeg_binary_path: &Path,
+    input_path_a: &Path,
+    input_path_b: &Path,
+    job: &jobs::PostProcessJob,
+) -> Result<model::RecordingComparison, String> {
+    let mut duration_seconds = f64::INFINITY;
+    for input_path in [input_path_a, input_path_b] {
+        let verification = segments::verify_recording_file(ffmpeg_binary_path, input_path)?;
+        if !verification.decodable {
+            return Err(format!(
+                "Recording could not be decoded: {}",
+                input_path.display()
+            ));
+        }
+        let input_duration = verification.stream_info.duration_seconds.ok_or_else(|| {
+            format!(
+                "Failed to determine the duration of {}",
+                input_path.display()
+            )
+        })?;
+        duration_seconds = duration_seconds.min(input_duration);
+    }
+
+    let windows = segments::compare_sample_windows(
+        duration_seconds,
+        model::RECORDING_COMPARE_SAMPLE_COUNT,
+        model::RECORDING_COMPARE_SAMPLE_SECS,
+    );
+    let window_count = windows.len() as f64;
+    emit_compare_progress(app_handle, input_path_a, input_path_b, 0.0);
+
+    let mut samples = Vec::with_capacity(windows.len());
+    for (index, window) in windows.into_iter().enumerate() {
+        let (ssim, psnr_db) = segments::compare_recording_window(
+            ffmpeg_binary_path,
+            input_path_a,
+            input_path_b,
+            window,
+            job,
+            &mut |progress| {
+                emit_compare_progress(
+                    app_handle,
+                    input_path_a,
+                    input_path_b,
+                    (index as f64 + progress) / window_count,
+                )
+            },
+        )?;
+        samples.push(model::RecordingComparisonSample {
+            start_seconds: window.0,
+            duration_seconds: window.1,
+            ssim,
+            psnr_db: psnr_db.is_finite().then_some(psnr_db),
+        });
+    }
+
+    let ssim = samples.iter().map(|sample| sample.ssim).sum::<f64>() / samples.len() as f64;
+    let finite_psnr = samples
+        .iter()
+        .filter_map(|sample| sample.psnr_db)
+        .collect::<Vec<f64>>();
+    let psnr_db = (!finite_psnr.is_empty())
+        .then(|| finite_psnr.iter().sum::<f64>() / finite_psnr.len() as f64);
+    emit_compare_progress(app_handle, input_path_a, input_path_b, 1.0);
+
+    tracing::info!(
+        input_path_a = %input_path_a.display(),
+        input_path_b = %input_path_b.display(),
+        sample_count = samples.len(),
+        ssim,
+        psnr_db,
+        "Compared recordings"
+    );
+    Ok(model::RecordingComparison {
+        ssim,
+        psnr_db,
+        samples,
+    })
+}
+
+/// Scores how closely recording `b` matches recording `a` with SSIM and PSNR, for A/B
+/// testing encoder settings. Only a few short stretches spread over the shorter of the
+/// two are compared, so the scores are quick estimates. `job_id` lets the caller stop the
+/// comparison with `cancel_job`.
+#[tauri::command]
+pub async fn compare_recordings(
+    app_handle: AppHandle,
+    input_path_a: String,
+    input_path_b: String,
+    job_id: Option<String>,
+) -> Result<model::RecordingComparison, String> {
+    let input_a = PathBuf::from(&input_path_a);
+    let input_b = PathBuf::from(&input_path_b);
+    if !input_a.is_file() || !input_b.is_file() {
+        return Err("Selected recording does not exist".to_string());
+    }
+
+    let ffmpeg_binary_path = ffmpeg::resolve_configured_ffmpeg_binary_path(&app_handle)?;
+    let job = jobs::begin_job(job_id)?;
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let result =
+            compare_recordings_blocking(&app_handle, &ffmpeg_binary_path, &input_a, &input_b, &job);
+        jobs::finish_job(&app_handle, &job, result)
+    })
+    .await
+    .map_err(|error| format!("Recording compare task failed: {error}"))?
+}
+
+/// Checks a recording's markers sidecar against the probed video duration and rewrites
+/// it so no marker points past the end of the file. `offset_seconds` shifts every marker
+/// first, e.g. by minus the trimmed length after cutting the start of a recording.
+#[tauri::command]
+pub async fn reconcile_markers(
+    app_handle: AppHandle,
+    state: tauri::State<'_, model::SharedRecordingState>,
+    input_path: String,
+    offset_seconds: Option<f64>,
+) -> Result<metadata::RecordingMarkerReconciliation, String> {
+    let input = PathBuf::from(&input_path);
+    if !input.is_file() {
+        return Err("Selected recording does not exist".to_string());
+    }
+    if state.read().await.current_output_path.as_deref() == Some(input_path.as_str()) {
+        return Err(
+            "Cannot reconcile markers of the recording that is currently being written".to_string(),
+        );
+    }
+    let offset_seconds = offset_seconds.unwrap_or(0.0);
+    if !offset_seconds.is_finite() {
+        return Err("Marker offset must be a finite number of seconds".to_string());
+    }
+
+    let ffmpeg_binary_path = ffmpeg::resolve_configured_ffmpeg_binary_path(&app_handle)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        if metadata::read_recording_metadata(&input)?.is_none() {
+            return Err("Recording has no markers sidecar to reconcile".to_string());
+        }
+        let duration_seconds = segments::verify_recording_file(&ffmpeg_binary_path, &input)?
+            .stream_info
+            .duration_seconds
+            .ok_or_else(|| "Failed to determine the recording duration".to_string())?;
+
+        let reconciliation = metadata::update_recording_metadata(&input, |recording_metadata| {
+            Ok(recording_metadata.reconcile_with_duration(duration_seconds, offset_seconds))
+        })?;
+        tracing::info!(
+            input_path = %input.display(),
+            duration_seconds,
+            offset_seconds,
+            dropped_event_count = reconciliation.dropped_event_count,
+            dropped_encounter_count = reconciliation.dropped_encounter_count,
+            clamped_encounter_count = reconciliation.clamped_encounter_count,
+            "Reconciled recording markers with video duration"
+        );
+        Ok(reconciliation)
+    })
+    .await
+    .map_err(|error| format!("Marker reconciliation task failed: {error}"))?
+}
+
+/// Merges markers from an external tool into the recording's sidecar. `markers_json`
+/// is an array of `{ timestampSeconds, eventType?, note?, encounterName? }` with
+/// timestamps relative to the start of the recording.
+#[tauri::command]
+pub async fn import_markers(
+    app_handle: AppHandle,
+    state: tauri::State<'_, model::SharedRecordingState>,
+    recording_path: String,
+    markers_json: String,
+) -> Result<metadata::RecordingMarkerImport, String> {
+    let input = PathBuf::from(&recording_path);
+    if !input.is_file() {
+        return Err("Selected recording does not exist".to_string());
+    }
+    if state.read().await.current_output_path.as_deref() == Some(recording_path.as_str()) {
+        return Err(
+            "Cannot import markers into the recording that is currently being written".to_string(),
+        );
+    }
+    let markers: Vec<metadata::ImportedRecordingMarker> = serde_json::from_str(&markers_json)
+        .map_err(|error| format!("Failed to parse imported markers: {error}"))?;
+    if markers.is_empty() {
+        return Err("No markers to import".to_string());
+    }
+
+    let ffmpeg_binary_path = ffmpeg::resolve_configured_ffmpeg_binary_path(&app_handle)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        let duration_seconds = segments::verify_recording_file(&ffmpeg_binary_path, &input)?
+            .stream_info
+            .duration_seconds
+            .ok_or_else(|| "Failed to determine the recording duration".to_string())?;
+        let events = markers
+            .into_iter()
+            .map(|marker| marker.into_important_event(duration_seconds))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let import = metadata::update_recording_metadata(&input, |recording_metadata| {
+            Ok(recording_metadata.merge_imported_events(events))
+        })?;
+        tracing::info!(
+            input_path = %input.display(),
+            duration_seconds,
+            imported_count = import.imported_count,
+            duplicate_count = import.duplicate_count,
+            total_event_count = import.total_event_count,
+            "Imported external recording markers"
+        );
+        Ok(import)
+    })
+    .await
+    .map_err(|error| format!("Marker import task failed: {error}"))?
+}
+
+#[tauri::command]
+pub async fn repair_recording(
+    app_handle: AppHandle,
+    state: tauri::State<'_, model::SharedRecordingState>,
+    input_path: String,
+    job_id: Option<String>,
+) -> Result<model::RecordingRepairResult, String> {
+    let input = PathBuf::from(&input_path);
+    if !input.is_file() {
+        return Err("Selected recording does not exist".to_string());
+    }
+    if input.extension().and_then(|value| value.to_str()) != Some("mp4") {
+        return Err("Only .mp4 recordings can be repaired".to_string());
+    }
+    if state.read().await.current_output_path.as_deref() == Some(input_path.as_str()) {
+        return Err("Cannot repair the recording that is currently being written".to_string());
+    }
+
+    let repaired_output = segments::repaired_output_path(&input)
+        .ok_or_else(|| "Failed to derive an output path for the repaired recording".to_string())?;
+    let ffmpeg_binary_path = ffmpeg::resolve_configured_ffmpeg_binary_path(&app_handle)?;
+    let job = jobs::begin_job(job_id)?;
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let result =
+            segments::remux_damaged_recording(&ffmpeg_binary_path, &input, &repaired_output, &job);
+        jobs::finish_job(&app_handle, &job, result)?;
+        crate::settings::record_recording_written(&repaired_output);
+        let playable = segments::recording_is_playable(&ffmpeg_binary_path, &repaired_output);
+        tracing::info!(
+            input_path = %input.display(),
+            output_path = %repaired_output.display(),
+            playable,
+            "Finished recording repair attempt"
+        );
+
+        Ok(model::RecordingRepairResult {
+            output_path: repaired_output.to_string_lossy().to_string(),
+            playable,
+        })
+    })
+    .await
+    .map_err(|error| format!("Recording repair task failed: {error}"))?
+}
+
+fn emit_annotate_progress(app_handle: &AppHandle, output_path: &str, progress: f64) {
+    let payload = model::RecordingAnnotateProgressPayload {
+        output_path: output_path.to_string(),
+        progress,
+    };
+    if let Err(error) = app_handle.emit("recording-annotate-progress", payload) {
+        tracing::warn!("Failed to emit recording-annotate-progress event: {error}");
+    }
+}
+
+fn annotate_recording_blocking(
+    app_handle: &AppHandle,
+    ffmpeg_binary_path: &Path,
+    input: &Path,
+    annotated_output: &Path,
+    markers: &[model::RecordingAnnotationMarker],
+    job: &jobs::PostProcessJob,
+) -> Result<String, String> {
+    let verification = segments::verify_recording_file(ffmpeg_binary_path, input)?;
+    if !verification.decodable {
+        return Err(format!(
+            "Recording could not be decoded: {}",
+            input.display()
+        ));
+    }
+
+    let output_path = annotated_output.to_string_lossy().to_string();
+    let font_file = ffmpeg::default_annotation_font_file();
+    let video_filter = ffmpeg::build_annotation_filter(markers, font_file.as_deref());
+    emit_annotate_progress(app_handle, &output_path, 0.0);
+    segments::annotate_recording(
+        ffmpeg_binary_path,
+        input,
+        annotated_output,
+        &video_filter,
+        verification.stream_info.duration_seconds.unwrap_or(0.0),
+        job,
+        |progress| emit_annotate_progress(app_handle, &output_path, progress),
+    )?;
+    crate::settings::record_recording_written(annotated_output);
+
+    tracing::info!(
+        input_path = %input.display(),
+        output_path,
+        marker_count = markers.len(),
+        "Exported annotated recording"
+    );
+    Ok(output_path)
+}
+
+/// Exports a copy of a recording with the given combat markers drawn onto the video. The
+/// original file is left untouched; the annotated copy is written next to it. `job_id`
+/// lets the caller stop the export with `cancel_job`.
+#[tauri::command]
+pub async fn annotate_recording(
+    app_handle: AppHandle,
+    state: tauri::State<'_, model::SharedRecordingState>,
+    input_path: String,
+    markers: Vec<model::RecordingAnnotationMarker>,
+    job_id: Option<String>,
+) -> Result<String, String> {
+    let input = PathBuf::from(&input_path);
+    if !input.is_file() {
+        return Err("Selected recording does not exist".to_string());
+    }
+    if input.extension().and_then(|value| value.to_str()) != Some("mp4") {
+        return Err("Only .mp4 recordings can be annotated".to_string());
+    }
+    if state.read().await.current_output_path.as_deref() == Some(input_path.as_str()) {
+        return Err("Cannot annotate the recording that is currently being written".to_string());
+    }
+    if markers.is_empty() {
+        return Err("Select at least one marker to annotate".to_string());
+    }
+    if markers
+        .iter()
+        .any(|marker| !marker.timestamp_seconds.is_finite() || marker.timestamp_seconds < 0.0)
+    {
+        return Err("Marker timestamps must be non-negative numbers".to_string());
+    }
+
+    let annotated_output = segments::annotated_output_path(&input)
+        .ok_or_else(|| "Failed to derive an output path for the annotated recording".to_string())?;
+    let ffmpeg_binary_path = ffmpeg::resolve_configured_ffmpeg_binary_path(&app_handle)?;
+    let job = jobs::begin_job(job_id)?;
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let result = annotate_recording_blocking(
+            &app_handle,
+            &ffmpeg_binary_path,
+            &input,
+            &annotated_output,
+            &markers,
+            &job,
+        );
+        jobs::finish_job(&app_handle, &job, result)
+    })
+    .await
+    .map_err(|error| format!("Recording annotate task failed: {error}"))?
+}
+
+/// Stops a running concat, repair, annotate or compare job by the id it was started
+/// with. The job's command then fails with a cancellation error once its partial output
+/// is removed.
+#[tauri::command]
+pub fn cancel_job(job_id: String) -> Result<(), String> {
+    jobs::cancel_job(&job_id)
+}
+
+/// Emergency reset: stops the active recording, the audio level monitor and the combat
+/// watch, and cancels running post-process jobs. Idle subsystems are skipped, so this is
+/// safe to call at any time.
+#[tauri::command]
+pub async fn stop_all(
+    app_handle: AppHandle,
+    state: tauri::State<'_, model::SharedRecordingState>,
+    audio_monitor_state: tauri::State<'_, model::SharedAudioMonitorState>,
+) -> Result<model::StopAllSummary, String> {
+    let mut summary = model::StopAllSummary::default();
+
+    if state.read().await.is_recording {
+        match request_recording_stop(state.inner(), None).await {
+            Ok(output_path) => summary.stopped_recording_path = Some(output_path),
+            Err(error) => summary
+                .errors
+                .push(format!("Failed to stop recording: {error}")),
+        }
+    }
+
+    if let Some(stop_tx) = audio_monitor_state.write().await.stop_tx.take() {
+        let _ = stop_tx.send(());
+        summary.audio_monitor_stopped = true;
+    }
+
+    if crate::combat_log::watch::is_combat_watch_running() {
+        match crate::combat_log::watch::stop_combat_watch(app_handle).await {
+            Ok(()) => summary.combat_watch_stopped = true,
+            Err(error) => summary
+                .errors
+                .push(format!("Failed to stop combat watch: {error}")),
+        }
+    }
+
+    match jobs::cancel_all_jobs() {
+        Ok(cancelled_job_count) => summary.cancelled_job_count = cancelled_job_count,
+        Err(error) => summary.errors.push(error),
+    }
+
+    tracing::info!(
+        stopped_recording = summary.stopped_recording_path.is_some(),
+        audio_monitor_stopped = summary.audio_monitor_stopped,
+        combat_watch_stopped = summary.combat_watch_stopped,
+        cancelled_job_count = summary.cancelled_job_count,
+        error_count = summary.errors.len(),
+        "Stopped all activity"
+    );
+    Ok(summary)
+}
+
+/// Whether `output_path` still has a segment workspace next to it: the recording is being
+/// written, or waits for crash recovery, and its final file does not exist yet.
+pub(crate) fn has_segment_workspace(output_path: &Path) -> bool {
+    let Some(output_folder) = output_path.parent() else {
+        return false;
+    };
+    segments::find_orphaned_segment_workspaces(output_folder).is_ok_and(|workspaces| {
+        workspaces
+            .iter()
+            .any(|workspace| segments::workspace_belongs_to_output(workspace, output_path))
+    })
+}
+
+pub(crate) fn offer_crashed_recording_recovery(app_handle: &AppHandle, output_folder: &str) {
+    let workspaces = match segments::find_orphaned_segment_workspaces(Path::new(output_folder)) {
+        Ok(workspaces) => workspaces,
+        Err(error) => {
+            tracing::warn!("Failed to scan for interrupted recordings: {error}");
+            return;
+        }
+    };
+
+    if workspaces.is_empty() {
+        return;
+    }
+
+    tracing::warn!(
+        workspace_count = workspaces.len(),
+        "Found recording segment workspaces left behind by an interrupted session"
+    );
+
+    let recovery_app_handle = app_handle.clone();
+    app_handle
+        .dialog()
+        .message(format!(
+            "FloorPoV found {} recording(s) that were interrupted before they could be saved. Recover them now?",
+            workspaces.len()
+        ))
+        .title("FloorPoV recovery")
+        .kind(MessageDialogKind::Info)
+        .buttons(MessageDialogButtons::OkCancelCustom(
+            "Recover".to_string(),
+            "Not now".to_string(),
+        ))
+        .show(move |should_recover| {
+            if !should_recover {
+                return;
+            }
+
+            thread::spawn(move || {
+                let ffmpeg_binary_path =
+                    match ffmpeg::resolve_configured_ffmpeg_binary_path(&recovery_app_handle) {
+                        Ok(path) => path,
+                        Err(error) => {
+                            tracing::error!("Cannot recover interrupted recordings: {error}");
+                            return;
+                        }
+                    };
+
+                for segment_workspace in workspaces {
+                    let Some(output_path) = segments::recovered_output_path(&segment_workspace)
+                    else {
+                        continue;
+                    };
+
+                    if let Err(error) = recover_workspace_blocking(
+                        &recovery_app_handle,
+                        &ffmpeg_binary_path,
+                        &segment_workspace,
+                        &output_path.to_string_lossy(),
+                    ) {
+                        tracing::error!(
+                            segment_workspace = %segment_workspace.display(),
+                            "Failed to recover interrupted recording: {error}"
+                        );
+                    }
+                }
+            });
+        });
+}
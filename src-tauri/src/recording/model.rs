@@ -1,18 +1,43 @@
 use std::path::PathBuf;
-use std::sync::atomic::AtomicU64;
-use std::sync::Arc;
-use std::time::Duration;
+use std::sync::atomic::{AtomicBool, AtomicU64};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
-use tokio::sync::{mpsc, RwLock};
+use tokio::sync::{mpsc, oneshot, RwLock};
 
 #[cfg(target_os = "windows")]
 use windows_sys::Win32::Graphics::Gdi::HMONITOR;
 
 #[derive(Clone, serde::Serialize)]
 pub struct RecordingStartedPayload {
+    pub(crate) session_id: String,
     pub(crate) output_path: String,
     pub(crate) width: u32,
     pub(crate) height: u32,
+    pub(crate) bitrate: u32,
+    /// The capture method records the target window even while other windows cover it.
+    /// A later `recording-warning` reports if the session loses this guarantee.
+    pub(crate) occlusion_independent_capture: bool,
+}
+
+/// The output device the recording's system audio loopback is reading from, with the
+/// device's own mix format (WASAPI converts it to the recorder's format).
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct CurrentAudioDeviceInfo {
+    pub(crate) id: String,
+    pub(crate) name: String,
+    pub(crate) is_default: bool,
+    pub(crate) sample_rate_hz: u32,
+    pub(crate) channels: u16,
+    pub(crate) bits_per_sample: u16,
+}
+
+#[derive(Clone, serde::Serialize)]
+pub struct RecordingSizeEstimate {
+    pub(crate) estimated_bytes: u64,
+    pub(crate) width: u32,
+    pub(crate) height: u32,
+    pub(crate) bitrate: u32,
 }
 
 #[derive(Clone, serde::Serialize)]
@@ -20,6 +45,75 @@ pub struct CaptureWindowInfo {
     pub(crate) hwnd: String,
     pub(crate) title: String,
     pub(crate) process_name: Option<String>,
+    /// Base64 JPEG preview, only filled in when thumbnails were requested.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) thumbnail_jpeg_base64: Option<String>,
+}
+
+/// A display that monitor capture can target. `device_name` (e.g. `\\.\DISPLAY2`) is
+/// what settings store; `output_idx` is only valid until the display layout changes.
+#[derive(Clone, Debug, PartialEq, serde::Serialize)]
+pub struct CaptureMonitorInfo {
+    pub(crate) device_name: String,
+    pub(crate) name: Option<String>,
+    pub(crate) output_idx: u32,
+    pub(crate) is_primary: bool,
+}
+
+/// The window a capture resolves to, as reported by `get_capture_diagnostics`.
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct CaptureWindowDiagnostics {
+    pub(crate) hwnd: String,
+    pub(crate) title: Option<String>,
+    pub(crate) class_name: Option<String>,
+    pub(crate) process_id: u32,
+    pub(crate) process_name: Option<String>,
+}
+
+/// Everything support needs about the capture environment in one payload. Each part is
+/// collected on its own, so one failing probe leaves an error next to it instead of
+/// failing the whole report.
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct CaptureDiagnostics {
+    pub(crate) capture_source: String,
+    pub(crate) resolved_capture_target: Option<String>,
+    pub(crate) capture_error: Option<String>,
+    /// Monitors in enumeration order; `output_idx` is the index passed to `ddagrab`.
+    pub(crate) monitors: Vec<CaptureMonitorInfo>,
+    pub(crate) monitors_error: Option<String>,
+    pub(crate) capture_window: Option<CaptureWindowDiagnostics>,
+    /// Whether the FFmpeg build ships the `gfxcapture` filter Windows Graphics Capture uses.
+    pub(crate) wgc_supported: bool,
+    /// Vendor of the GPU driving the captured display, only set on hybrid GPU systems.
+    pub(crate) hybrid_capture_gpu_vendor: Option<String>,
+    pub(crate) ffmpeg_path: Option<String>,
+    pub(crate) ffmpeg_version: Option<String>,
+    pub(crate) ffmpeg_error: Option<String>,
+    pub(crate) available_video_encoders: Vec<String>,
+    pub(crate) selected_video_encoder: Option<String>,
+    pub(crate) system_audio_device: Option<CurrentAudioDeviceInfo>,
+    pub(crate) system_audio_error: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, serde::Serialize)]
+pub struct AudioOutputDeviceInfo {
+    pub(crate) id: String,
+    pub(crate) name: String,
+    pub(crate) is_default: bool,
+}
+
+/// One process's audio session on an output device, as listed by `list_audio_sessions`.
+#[derive(Clone, Debug, PartialEq, serde::Serialize)]
+pub struct AudioSessionInfo {
+    /// `<device id>|<process id>`; stable while the process keeps its session open.
+    pub(crate) session_id: String,
+    pub(crate) process_id: u32,
+    /// Executable name, e.g. `Wow.exe`; `None` when the process cannot be queried.
+    pub(crate) app_name: Option<String>,
+    pub(crate) device_id: String,
+    pub(crate) device_name: String,
+    /// Whether the session is currently playing audio.
+    pub(crate) is_active: bool,
 }
 
 #[derive(Clone, serde::Serialize)]
@@ -28,21 +122,233 @@ pub struct AvailableVideoEncoder {
     pub(crate) label: String,
 }
 
+#[derive(Clone, serde::Serialize)]
+pub struct RecordingStatus {
+    pub(crate) is_recording: bool,
+    pub(crate) is_stopping: bool,
+    pub(crate) output_path: Option<String>,
+}
+
+#[derive(Clone, serde::Serialize)]
+pub struct RecordingPartFinalizedPayload {
+    pub(crate) session_id: String,
+    pub(crate) output_path: String,
+    pub(crate) part_number: usize,
+}
+
+#[derive(Clone, serde::Serialize)]
+pub struct RecordingFinalizedPayload {
+    pub(crate) session_id: String,
+    pub(crate) output_path: String,
+}
+
+/// Outcome of the finalize hook for one recording file.
+#[derive(Clone, serde::Serialize)]
+pub struct RecordingHookPayload {
+    pub(crate) session_id: String,
+    pub(crate) output_path: String,
+    pub(crate) action: String,
+    pub(crate) target: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) error: Option<String>,
+}
+
+#[derive(Clone, serde::Serialize)]
+pub struct RecordingWarningPayload {
+    pub(crate) session_id: String,
+    pub(crate) message: String,
+}
+
+/// Emitted as `recording-output-lost` when the output folder disappears mid-recording,
+/// e.g. because the external drive holding it was unplugged.
+#[derive(Clone, serde::Serialize)]
+pub struct RecordingOutputLostPayload {
+    pub(crate) session_id: String,
+    pub(crate) output_path: String,
+    pub(crate) message: String,
+}
+
+/// Emitted as `recording-encoder-fallback` each time the session moves on to the next
+/// encoder, so the last one received names the encoder the recording ends up using.
+#[derive(Clone, serde::Serialize)]
+pub struct RecordingEncoderFallbackPayload {
+    pub(crate) session_id: String,
+    pub(crate) failed_video_encoder: String,
+    pub(crate) video_encoder: String,
+    pub(crate) video_encoder_label: String,
+}
+
+/// Periodic summary of the recorder's own overhead. Frame counters cover the current
+/// segment and restart whenever a new segment starts.
+#[derive(Clone, serde::Serialize)]
+pub struct PerformanceStatsPayload {
+    pub(crate) session_id: String,
+    pub(crate) encode_speed: Option<f64>,
+    pub(crate) encode_fps: Option<f64>,
+    pub(crate) frames_duplicated: u64,
+    pub(crate) frames_dropped: u64,
+    pub(crate) audio_queue_depth: Option<u64>,
+}
+
+/// Payload for session events that carry nothing but the session they belong to.
+#[derive(Clone, serde::Serialize)]
+pub struct RecordingSessionEventPayload {
+    pub(crate) session_id: String,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, serde::Serialize)]
+pub struct AudioLevelPayload {
+    pub(crate) peak: f32,
+    pub(crate) rms: f32,
+}
+
+#[derive(Clone, serde::Serialize)]
+pub struct RecordingStopResult {
+    pub(crate) output_path: String,
+    pub(crate) finalized: bool,
+}
+
+/// What `stop_all` stopped. Subsystems that were idle are reported as not stopped;
+/// a subsystem that failed to stop adds an entry to `errors` without blocking the rest.
+#[derive(Clone, Default, serde::Serialize)]
+pub struct StopAllSummary {
+    /// Output path of the recording that was asked to stop. It finalizes in the background.
+    pub(crate) stopped_recording_path: Option<String>,
+    pub(crate) audio_monitor_stopped: bool,
+    pub(crate) combat_watch_stopped: bool,
+    pub(crate) cancelled_job_count: usize,
+    pub(crate) errors: Vec<String>,
+}
+
+#[derive(Clone, serde::Serialize)]
+pub struct OrphanedRecordingWorkspace {
+    pub(crate) workspace_path: String,
+    pub(crate) recovered_output_path: String,
+    pub(crate) segment_count: usize,
+    pub(crate) size_bytes: u64,
+}
+
+#[derive(Clone, serde::Serialize)]
+pub struct RecordingRepairResult {
+    pub(crate) output_path: String,
+    pub(crate) playable: bool,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, serde::Serialize)]
+pub struct RecordingStreamInfo {
+    pub(crate) duration_seconds: Option<f64>,
+    pub(crate) video_codec: Option<String>,
+    pub(crate) width: Option<u32>,
+    pub(crate) height: Option<u32>,
+    pub(crate) frame_rate: Option<f64>,
+    pub(crate) audio_codec: Option<String>,
+}
+
+#[derive(Clone, serde::Serialize)]
+pub struct RecordingVerification {
+    pub(crate) decodable: bool,
+    pub(crate) stream_info: RecordingStreamInfo,
+}
+
+#[derive(Clone, Debug, serde::Deserialize)]
+pub struct RecordingAnnotationMarker {
+    pub(crate) timestamp_seconds: f64,
+    pub(crate) label: String,
+}
+
+#[derive(Clone, serde::Serialize)]
+pub struct JobCancelledPayload {
+    pub(crate) job_id: String,
+}
+
+#[derive(Clone, serde::Serialize)]
+pub struct RecordingAnnotateProgressPayload {
+    pub(crate) output_path: String,
+    pub(crate) progress: f64,
+}
+
+#[derive(Clone, serde::Serialize)]
+pub struct RecordingCompareProgressPayload {
+    pub(crate) input_path_a: String,
+    pub(crate) input_path_b: String,
+    pub(crate) progress: f64,
+}
+
+/// Scores of one sampled stretch of two recordings. `psnr_db` is `None` when the frames
+/// were identical, which FFmpeg reports as an infinite PSNR.
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct RecordingComparisonSample {
+    pub(crate) start_seconds: f64,
+    pub(crate) duration_seconds: f64,
+    pub(crate) ssim: f64,
+    pub(crate) psnr_db: Option<f64>,
+}
+
+/// Averages over all samples; `psnr_db` skips samples with identical frames.
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct RecordingComparison {
+    pub(crate) ssim: f64,
+    pub(crate) psnr_db: Option<f64>,
+    pub(crate) samples: Vec<RecordingComparisonSample>,
+}
+
+#[derive(Clone, serde::Serialize)]
+pub struct RecordingConcatProgressPayload {
+    pub(crate) output_path: String,
+    pub(crate) progress: f64,
+    pub(crate) reencoding: bool,
+}
+
 #[derive(Clone)]
 pub(crate) enum CaptureInput {
-    Monitor,
+    /// `device_name` is set when a saved monitor was found; `None` records the primary one.
+    Monitor {
+        output_idx: u32,
+        device_name: Option<String>,
+        /// Set once `ddagrab` failed to start, so the monitor is recorded through GDI.
+        use_gdigrab: bool,
+    },
     Window {
         input_target: String,
         window_hwnd: Option<usize>,
         window_title: Option<String>,
         use_wgc: bool,
     },
+    /// Every monitor composed into one frame; the layout is read when each segment starts.
+    VirtualDesktop,
+    /// FFmpeg's `testsrc2` pattern, for machines without a display to duplicate.
+    TestPattern,
+}
+
+/// A monitor's offset from the top-left corner of the virtual desktop, in physical pixels.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct VirtualDesktopMonitor {
+    pub(crate) output_idx: u32,
+    pub(crate) x: u32,
+    pub(crate) y: u32,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct VirtualDesktopLayout {
+    pub(crate) monitors: Vec<VirtualDesktopMonitor>,
+    /// Bounding box of all monitors.
+    pub(crate) width: u32,
+    pub(crate) height: u32,
+    /// The bounding box scaled to fit `VIRTUAL_DESKTOP_MAX_DIMENSION` and made even.
+    pub(crate) output_width: u32,
+    pub(crate) output_height: u32,
 }
 
 impl CaptureInput {
     pub(crate) fn target_label(&self) -> String {
         match self {
-            CaptureInput::Monitor => "primary_monitor".to_string(),
+            CaptureInput::Monitor {
+                device_name: Some(device_name),
+                ..
+            } => format!("monitor={device_name}"),
+            CaptureInput::Monitor { .. } => "primary_monitor".to_string(),
+            CaptureInput::VirtualDesktop => "virtual_desktop".to_string(),
+            CaptureInput::TestPattern => "test_pattern".to_string(),
             CaptureInput::Window { input_target, .. } => input_target.clone(),
         }
     }
@@ -50,15 +356,54 @@ impl CaptureInput {
     pub(crate) fn uses_wgc_window_capture(&self) -> bool {
         match self {
             CaptureInput::Window { use_wgc, .. } => *use_wgc,
-            CaptureInput::Monitor => false,
+            CaptureInput::Monitor { .. }
+            | CaptureInput::VirtualDesktop
+            | CaptureInput::TestPattern => false,
         }
     }
 
+    /// Points window capture at a different window, keeping the capture method.
+    pub(crate) fn follow_window(&mut self, next_window_hwnd: usize, next_window_title: String) {
+        if let CaptureInput::Window {
+            input_target,
+            window_hwnd,
+            window_title,
+            ..
+        } = self
+        {
+            *input_target = format!("hwnd={next_window_hwnd}");
+            *window_hwnd = Some(next_window_hwnd);
+            *window_title = Some(next_window_title);
+        }
+    }
+
+    /// Only Windows Graphics Capture reads the window's own surface; region capture and
+    /// monitor capture record whatever is drawn on top of the target.
+    pub(crate) fn guarantees_occlusion_independent_capture(&self) -> bool {
+        self.uses_wgc_window_capture()
+    }
+
     pub(crate) fn disable_wgc_window_capture(&mut self) {
         if let CaptureInput::Window { use_wgc, .. } = self {
             *use_wgc = false;
         }
     }
+
+    pub(crate) fn uses_gdigrab_monitor_capture(&self) -> bool {
+        matches!(
+            self,
+            CaptureInput::Monitor {
+                use_gdigrab: true,
+                ..
+            }
+        )
+    }
+
+    pub(crate) fn enable_gdigrab_monitor_capture(&mut self) {
+        if let CaptureInput::Monitor { use_gdigrab, .. } = self {
+            *use_gdigrab = true;
+        }
+    }
 }
 
 #[derive(Clone, Copy, PartialEq, Eq)]
@@ -68,11 +413,228 @@ pub(crate) enum WindowCaptureAvailability {
     Closed,
 }
 
+/// GPU vendors with a hardware H.264 encoder FFmpeg can use.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum GpuVendor {
+    Nvidia,
+    Intel,
+    Amd,
+}
+
+impl GpuVendor {
+    /// Maps a PCI vendor id, as reported by DXGI, to the vendors with hardware encoders.
+    pub(crate) fn from_pci_vendor_id(vendor_id: u32) -> Option<Self> {
+        match vendor_id {
+            0x10DE => Some(Self::Nvidia),
+            0x8086 => Some(Self::Intel),
+            0x1002 | 0x1022 => Some(Self::Amd),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn from_setting(value: &str) -> Option<Self> {
+        match value {
+            "nvidia" => Some(Self::Nvidia),
+            "intel" => Some(Self::Intel),
+            "amd" => Some(Self::Amd),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn from_encoder(encoder: &str) -> Option<Self> {
+        match encoder {
+            "h264_nvenc" => Some(Self::Nvidia),
+            "h264_qsv" => Some(Self::Intel),
+            "h264_amf" => Some(Self::Amd),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn hardware_encoder(self) -> &'static str {
+        match self {
+            Self::Nvidia => "h264_nvenc",
+            Self::Intel => "h264_qsv",
+            Self::Amd => "h264_amf",
+        }
+    }
+
+    pub(crate) fn label(self) -> &'static str {
+        match self {
+            Self::Nvidia => "nvidia",
+            Self::Intel => "intel",
+            Self::Amd => "amd",
+        }
+    }
+}
+
 #[derive(Clone, Copy, PartialEq, Eq)]
 pub(crate) enum RuntimeCaptureMode {
     Monitor,
     Window,
     Black,
+    TestPattern,
+}
+
+/// Output timing passed to `-fps_mode`. CFR duplicates or drops frames to hit the exact
+/// output rate, which editors handle best; VFR and passthrough keep the capture's own
+/// timing for smaller files but can confuse editors that assume a fixed frame rate.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) enum FrameSync {
+    Cfr,
+    Vfr,
+    Passthrough,
+}
+
+/// Bitrate control for the video encoder. CBR holds the bitrate flat, which wastes bits
+/// on static scenes and starves fast motion; VBR lets the hardware encoders spend up to
+/// `VBR_MAX_BITRATE_PERCENT` of the target on motion while aiming for a quality level.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum RateControl {
+    Cbr,
+    Vbr,
+}
+
+/// Action run in the background on every finalized recording file.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum FinalizeHook {
+    /// Copies the recording and its metadata sidecar into a folder, such as a network share.
+    CopyToFolder(PathBuf),
+    /// Sends the recording and its metadata to a URL as a multipart POST.
+    HttpPost(String),
+}
+
+impl FinalizeHook {
+    pub(crate) fn from_setting(action: &str, target: Option<&str>) -> Option<Self> {
+        let target = target.map(str::trim).filter(|value| !value.is_empty());
+        match (action, target) {
+            ("off", _) => None,
+            ("copy", Some(folder)) if std::path::Path::new(folder).is_absolute() => {
+                Some(Self::CopyToFolder(PathBuf::from(folder)))
+            }
+            ("http", Some(url)) if url.starts_with("http://") || url.starts_with("https://") => {
+                Some(Self::HttpPost(url.to_string()))
+            }
+            (other, target) => {
+                tracing::warn!(
+                    finalize_hook = %other,
+                    target = ?target,
+                    "Invalid finalize hook setting. Recordings will not be copied or uploaded"
+                );
+                None
+            }
+        }
+    }
+
+    pub(crate) fn action_label(&self) -> &'static str {
+        match self {
+            Self::CopyToFolder(_) => "copy",
+            Self::HttpPost(_) => "http",
+        }
+    }
+
+    pub(crate) fn target_label(&self) -> String {
+        match self {
+            Self::CopyToFolder(folder) => folder.to_string_lossy().to_string(),
+            Self::HttpPost(url) => url.clone(),
+        }
+    }
+}
+
+impl FrameSync {
+    pub(crate) fn from_setting(value: &str) -> Self {
+        match value {
+            "cfr" => Self::Cfr,
+            "vfr" => Self::Vfr,
+            "passthrough" => Self::Passthrough,
+            other => {
+                tracing::warn!(
+                    frame_sync = %other,
+                    "Unknown frame sync value. Falling back to constant frame rate"
+                );
+                Self::Cfr
+            }
+        }
+    }
+
+    pub(crate) fn fps_mode(self) -> &'static str {
+        match self {
+            Self::Cfr => "cfr",
+            Self::Vfr => "vfr",
+            Self::Passthrough => "passthrough",
+        }
+    }
+}
+
+impl RateControl {
+    pub(crate) fn from_setting(value: &str) -> Self {
+        match value {
+            "cbr" => Self::Cbr,
+            "vbr" => Self::Vbr,
+            other => {
+                tracing::warn!(
+                    rate_control = %other,
+                    "Unknown rate control value. Falling back to constant bitrate"
+                );
+                Self::Cbr
+            }
+        }
+    }
+}
+
+/// Settings for the `aresample` stage that keeps system audio aligned with the video.
+/// `strict` stretches audio continuously to follow clock drift, which fixes gradual desync
+/// but can be audible; `smooth` only corrects large gaps, trading sync precision for fewer
+/// artifacts; `balanced` sits in between and is the long-standing default.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct AudioResampleSync {
+    /// Maximum samples per second that may be stretched or squeezed to follow timestamps;
+    /// 1 only fills gaps and trims overlaps.
+    pub(crate) async_samples: u32,
+    /// Timestamp error in seconds before samples are inserted or dropped outright.
+    pub(crate) min_hard_comp_seconds: f64,
+}
+
+impl AudioResampleSync {
+    pub(crate) const BALANCED: Self = Self {
+        async_samples: 1,
+        min_hard_comp_seconds: 0.100,
+    };
+    const STRICT: Self = Self {
+        async_samples: 1000,
+        min_hard_comp_seconds: 0.050,
+    };
+    const SMOOTH: Self = Self {
+        async_samples: 1,
+        min_hard_comp_seconds: 0.500,
+    };
+
+    pub(crate) fn from_setting(
+        value: &str,
+        custom_async_samples: Option<u32>,
+        custom_min_hard_comp_seconds: Option<f64>,
+    ) -> Self {
+        match value {
+            "balanced" => Self::BALANCED,
+            "strict" => Self::STRICT,
+            "smooth" => Self::SMOOTH,
+            "custom" => Self {
+                async_samples: custom_async_samples
+                    .unwrap_or(Self::BALANCED.async_samples)
+                    .clamp(1, MAX_AUDIO_RESAMPLE_ASYNC_SAMPLES),
+                min_hard_comp_seconds: custom_min_hard_comp_seconds
+                    .filter(|seconds| seconds.is_finite())
+                    .unwrap_or(Self::BALANCED.min_hard_comp_seconds)
+                    .clamp(0.0, MAX_AUDIO_RESAMPLE_MIN_HARD_COMP_SECONDS),
+            },
+            other => {
+                tracing::warn!(
+                    audio_sync_mode = %other,
+                    "Unknown audio sync mode value. Falling back to balanced"
+                );
+                Self::BALANCED
+            }
+        }
+    }
 }
 
 #[derive(Clone, Copy)]
@@ -88,6 +650,43 @@ pub(crate) struct SegmentRunResult {
     pub(crate) output_written: bool,
     pub(crate) force_killed: bool,
     pub(crate) wall_clock_duration: Duration,
+    pub(crate) frame_stats: Option<FfmpegFrameStats>,
+    pub(crate) part_limit_reached: bool,
+    /// The hardware video encoder could not start, e.g. because no NVENC session is free.
+    pub(crate) encoder_init_failed: bool,
+    /// The segment was restarted because the encoder stopped producing frames.
+    pub(crate) encoder_stalled: bool,
+    /// The folder the segment was written to disappeared, so the session has to stop.
+    pub(crate) output_folder_lost: bool,
+}
+
+/// Counters from the latest FFmpeg `-stats` report of a segment.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub(crate) struct FfmpegFrameStats {
+    pub(crate) frames: u64,
+    pub(crate) duplicated: u64,
+    pub(crate) dropped: u64,
+    pub(crate) encoded_seconds: f64,
+    /// Encode speed relative to realtime; below 1.0 the encoder is falling behind.
+    pub(crate) speed: Option<f64>,
+    /// Bytes muxed so far. Unlike the file length, this stays right for preallocated files.
+    pub(crate) output_bytes: Option<u64>,
+}
+
+impl FfmpegFrameStats {
+    /// Frames per second of real time. `time=` is the output timestamp, and the CFR `fps`
+    /// filter duplicates frames inside the filtergraph without counting them in `dup=`, so
+    /// frames per encoded second alone always reads as the output rate. Scaling it by the
+    /// encode speed shows when the pipeline could not keep up with realtime.
+    pub(crate) fn captured_frame_rate(&self) -> Option<f64> {
+        if self.encoded_seconds <= 0.0 {
+            return None;
+        }
+
+        let captured_frames = (self.frames + self.dropped).saturating_sub(self.duplicated);
+        let realtime_ratio = self.speed.map_or(1.0, |speed| speed.min(1.0));
+        Some(captured_frames as f64 / self.encoded_seconds * realtime_ratio)
+    }
 }
 
 #[derive(Clone, Copy, PartialEq, Eq)]
@@ -108,8 +707,33 @@ pub(crate) struct MonitorIndexSearchState {
 
 pub(crate) const FFMPEG_RESOURCE_PATH: &str = "bin/ffmpeg.exe";
 pub(crate) const FFMPEG_STOP_TIMEOUT: Duration = Duration::from_secs(30);
+pub(crate) const MAX_AUDIO_RESAMPLE_ASYNC_SAMPLES: u32 = 48_000;
+pub(crate) const MAX_AUDIO_RESAMPLE_MIN_HARD_COMP_SECONDS: f64 = 5.0;
+pub(crate) const MAX_AUDIO_OFFSET_MS: i32 = 5_000;
+/// Peak bitrate VBR may reach, as a percentage of the target bitrate.
+pub(crate) const VBR_MAX_BITRATE_PERCENT: u32 = 150;
+/// Quality targets use the H.264 quantizer scale: lower is better, 51 is the worst.
+pub(crate) const MIN_VBR_QUALITY: u32 = 1;
+pub(crate) const MAX_VBR_QUALITY: u32 = 51;
+pub(crate) const ANNOTATION_MARKER_DISPLAY_SECONDS: f64 = 3.0;
+pub(crate) const EXACT_CONCAT_ATTEMPTS: u32 = 3;
+pub(crate) const EXACT_CONCAT_RETRY_DELAY: Duration = Duration::from_millis(500);
+pub(crate) const APP_EXIT_FINALIZE_TIMEOUT: Duration = Duration::from_secs(45);
 pub(crate) const FFMPEG_MODE_SWITCH_TO_BLACK_TIMEOUT: Duration = Duration::from_secs(4);
 pub(crate) const FFMPEG_MODE_SWITCH_TO_WINDOW_TIMEOUT: Duration = Duration::from_secs(2);
+/// A segment whose frame count has not advanced for this long is treated as a stalled
+/// encoder, e.g. while the GPU driver recovers from a TDR.
+pub(crate) const ENCODER_STALL_TIMEOUT: Duration = Duration::from_secs(10);
+/// A stalled encoder rarely answers `q`, so it gets less time than a regular stop.
+pub(crate) const FFMPEG_ENCODER_STALL_STOP_TIMEOUT: Duration = Duration::from_secs(5);
+/// Stall recoveries per session; after this, stalls are only logged so a broken driver
+/// cannot keep the session restarting forever.
+pub(crate) const MAX_ENCODER_STALL_RESTARTS_PER_SESSION: u32 = 3;
+pub(crate) const ENCODER_STALL_RESTART_WARNING: &str = "The video encoder stopped responding, so recording restarted. A short gap may be missing from the video.";
+/// How often the poll loop checks that the folder being written to still exists.
+pub(crate) const OUTPUT_FOLDER_CHECK_INTERVAL: Duration = Duration::from_secs(2);
+/// FFmpeg cannot flush to a missing drive, so it is not given long to stop.
+pub(crate) const FFMPEG_OUTPUT_LOST_STOP_TIMEOUT: Duration = Duration::from_secs(5);
 pub(crate) const SYSTEM_AUDIO_SAMPLE_RATE_HZ: usize = 48_000;
 pub(crate) const SYSTEM_AUDIO_CHANNEL_COUNT: usize = 2;
 pub(crate) const SYSTEM_AUDIO_BITS_PER_SAMPLE: usize = 16;
@@ -117,15 +741,96 @@ pub(crate) const SYSTEM_AUDIO_CHUNK_FRAMES: usize = 960;
 pub(crate) const SYSTEM_AUDIO_EVENT_TIMEOUT: Duration = Duration::from_millis(500);
 pub(crate) const AUDIO_TCP_ACCEPT_WAIT: Duration = Duration::from_millis(25);
 pub(crate) const SYSTEM_AUDIO_QUEUE_CAPACITY: usize = 256;
+/// Silence written as soon as FFmpeg connects to the audio socket, so it finishes opening
+/// the audio input and starts the video input without waiting for the first packet.
+pub(crate) const AUDIO_CONNECTION_PRIMER_FRAMES: usize = 960;
+pub(crate) const SYSTEM_AUDIO_INIT_ATTEMPTS: u32 = 4;
+pub(crate) const SYSTEM_AUDIO_INIT_INITIAL_BACKOFF: Duration = Duration::from_millis(150);
+pub(crate) const SYSTEM_AUDIO_REINIT_ERROR_THRESHOLD: u32 = 20;
+pub(crate) const MARKER_TONE_FREQUENCY_HZ: f32 = 1_000.0;
+/// 120 ms at the system audio sample rate.
+pub(crate) const MARKER_TONE_FRAMES: usize = SYSTEM_AUDIO_SAMPLE_RATE_HZ * 120 / 1000;
+/// Peak level of the beep relative to full scale, added on top of the captured audio.
+pub(crate) const MARKER_TONE_AMPLITUDE: f32 = 0.3;
+pub(crate) const AUDIO_LEVEL_EMIT_INTERVAL: Duration = Duration::from_millis(100);
 #[cfg(target_os = "windows")]
 pub(crate) const CREATE_NO_WINDOW: u32 = 0x08000000;
-pub(crate) const WINDOW_CAPTURE_STATUS_POLL_INTERVAL: Duration = Duration::from_millis(150);
+pub(crate) const FOREGROUND_WINDOW_POLL_INTERVAL: Duration = Duration::from_millis(500);
+pub(crate) const CURSOR_CAPTURE_POLL_INTERVAL: Duration = Duration::from_millis(500);
+/// How long the cursor has to stay missing from the capture before a warning is shown.
+pub(crate) const CURSOR_MISSING_WARNING_DELAY: Duration = Duration::from_secs(2);
+pub(crate) const PART_SIZE_POLL_INTERVAL: Duration = Duration::from_secs(1);
+/// How much recording `preallocate_output` reserves space for up front.
+pub(crate) const PREALLOCATED_RECORDING_SECS: u64 = 30 * 60;
+pub(crate) const PERFORMANCE_STATS_INTERVAL: Duration = Duration::from_secs(1);
+pub(crate) const POST_PROCESS_JOB_POLL_INTERVAL: Duration = Duration::from_millis(100);
+/// Timeline hover previews are extracted at this spacing and cached.
+pub(crate) const FRAME_PREVIEW_TIMESTAMP_STEP_MS: u64 = 500;
+pub(crate) const FRAME_PREVIEW_CACHE_CAPACITY: usize = 64;
+pub(crate) const FRAME_PREVIEW_WIDTH: u32 = 320;
+pub(crate) const WINDOW_THUMBNAIL_WIDTH: u32 = 320;
+pub(crate) const WINDOW_THUMBNAIL_HEIGHT: u32 = 180;
+/// Each thumbnail is a one-shot FFmpeg WGC grab, so a listing only captures this many
+/// windows; the rest can be requested one at a time with `get_window_thumbnail`.
+pub(crate) const MAX_WINDOW_THUMBNAILS_PER_LIST: usize = 8;
+/// Thumbnails younger than this are served from cache instead of grabbed again.
+pub(crate) const WINDOW_THUMBNAIL_CACHE_TTL: Duration = Duration::from_secs(5);
+/// WGC may never deliver a frame for a window that does not present, so grabs are
+/// abandoned after this long.
+pub(crate) const WINDOW_THUMBNAIL_CAPTURE_TIMEOUT: Duration = Duration::from_secs(3);
+/// Held in the output folder while a recording runs so other app instances stay out.
+pub(crate) const RECORDING_FOLDER_LOCK_FILE_NAME: &str = ".floorpov-recording.lock";
+/// `compare_recordings` scores this many stretches of this length instead of whole files.
+pub(crate) const RECORDING_COMPARE_SAMPLE_COUNT: usize = 3;
+pub(crate) const RECORDING_COMPARE_SAMPLE_SECS: f64 = 5.0;
 pub(crate) const WINDOW_CAPTURE_MINIMIZED_WARNING: &str = "Selected window is minimized. Recording continues, but the video may be black until the window is restored.";
 pub(crate) const WINDOW_CAPTURE_CLOSED_WARNING: &str = "Selected window is unavailable or closed. Recording continues, but the video may be black until the window is available again.";
+pub(crate) const GPU_ADAPTER_MISMATCH_WARNING: &str = "The captured display is driven by a different GPU than the one encoding the video, so every frame is copied between GPUs and the recording may stutter. Choose the GPU driving this display under Encoder GPU, or use the CPU encoder.";
+pub(crate) const WINDOW_CAPTURE_REGION_FALLBACK_WARNING: &str = "Exclusive window capture stopped working, so recording continues with region-based capture. Windows that overlap the captured window will now appear in the video.";
 pub(crate) const WINDOW_CAPTURE_UNAVAILABLE_WARNING: &str = "Selected window is currently unavailable for capture. Recording continues, but the video may be black until the window is available.";
+pub(crate) const OUTPUT_DRIVE_DISCONNECTED_WARNING: &str = "The drive holding the recordings folder was disconnected, so recording stopped. Reconnect the drive to recover the segments recorded so far.";
+pub(crate) const NO_AUDIO_OUTPUT_DEVICE_WARNING: &str = "No audio output device is available, so system audio is not being recorded. Recording continues with video only.";
+pub(crate) const NO_DISPLAY_TEST_PATTERN_WARNING: &str =
+    "No display is available to capture. Recording a test pattern instead.";
+pub(crate) const CAPTURE_MONITOR_MISSING_WARNING: &str =
+    "The selected monitor is not connected. Recording the primary monitor instead.";
+pub(crate) const PROTECTED_WINDOW_CAPTURE_WARNING: &str = "The selected window blocks screen capture (protected or DRM content), so it records as black. Switch Capture Source to Monitor to record it.";
+pub(crate) const GDIGRAB_MONITOR_CAPTURE_WARNING: &str = "Desktop duplication is unavailable on this GPU or driver. Falling back to GDI monitor capture, which uses more CPU and may lower the frame rate.";
+/// Window capture is sampled once per second at this size to spot protected windows.
+pub(crate) const BLACK_FRAME_SAMPLE_WIDTH: usize = 16;
+pub(crate) const BLACK_FRAME_SAMPLE_HEIGHT: usize = 9;
+/// Brightest sampled luma still treated as black; limited-range black is 16.
+pub(crate) const BLACK_FRAME_MAX_LUMA: u8 = 24;
+/// Consecutive black samples (one per second) before the window is reported as protected.
+pub(crate) const PROTECTED_WINDOW_BLACK_SAMPLES: u32 = 10;
+pub(crate) const CURSOR_OUTSIDE_CAPTURE_WARNING: &str = "The cursor is outside the captured area, so it is missing from the recording. Monitor and region capture only draw the cursor while it is over the recorded monitor.";
+pub(crate) const CURSOR_SUPPRESSED_CAPTURE_WARNING: &str = "Windows is hiding the mouse cursor while pen or touch input is used, so no cursor is drawn into the recording. Move the mouse to bring the cursor back.";
 pub(crate) const DEFAULT_CAPTURE_WIDTH: u32 = 1920;
 pub(crate) const DEFAULT_CAPTURE_HEIGHT: u32 = 1080;
+/// Largest frame side H.264 hardware encoders accept. Wider multi-monitor desktops are
+/// scaled down to fit.
+pub(crate) const VIRTUAL_DESKTOP_MAX_DIMENSION: u32 = 4096;
 pub(crate) const MIN_CAPTURE_DIMENSION: u32 = 2;
+pub(crate) const WINDOW_REGION_RESIZE_TOLERANCE_PX: u32 = 8;
+/// Logical coordinates are expressed at this DPI (100% scaling).
+pub(crate) const DEFAULT_SCREEN_DPI: u32 = 96;
+pub(crate) const WINDOW_REGION_RESIZE_SETTLE_DURATION: Duration = Duration::from_millis(750);
+pub(crate) const FASTSTART_MP4_MOVFLAGS: &str = "+faststart";
+/// Writes an empty `moov` up front and a fragment per keyframe, so a file cut off by a
+/// crash still plays up to its last complete fragment.
+pub(crate) const FRAGMENTED_MP4_MOVFLAGS: &str = "+frag_keyframe+empty_moov";
+pub(crate) const LOW_ACHIEVED_FRAME_RATE_RATIO: f64 = 0.9;
+/// Audio and video stream lengths differing by more than this point at audio capture
+/// starting or ending out of step with the video.
+pub(crate) const AUDIO_VIDEO_DURATION_MISMATCH_THRESHOLD_SECS: f64 = 0.5;
+/// A part is finalized once it holds this many segments, so long sessions with frequent
+/// capture transitions never build an unbounded concat list.
+pub(crate) const MAX_SEGMENTS_PER_PART: usize = 1_000;
+/// After a part fails to finalize, its segments stay in the open part and no part limit
+/// applies for this long, so a failing concat is not retried after every segment.
+pub(crate) const PART_FINALIZE_RETRY_BACKOFF: Duration = Duration::from_secs(30);
+/// Uploads themselves are not time-limited since recordings can be several gigabytes.
+pub(crate) const FINALIZE_HOOK_CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
 
 #[derive(Default)]
 pub(crate) struct AudioPipelineStats {
@@ -133,6 +838,9 @@ pub(crate) struct AudioPipelineStats {
     pub(crate) dequeued_chunks: AtomicU64,
     pub(crate) dropped_chunks: AtomicU64,
     pub(crate) write_timeouts: AtomicU64,
+    /// Set while another application holds the output device in exclusive mode and the
+    /// loopback capture is waiting for it to return to shared mode.
+    pub(crate) device_in_use_warning: Mutex<Option<String>>,
 }
 
 #[derive(Default)]
@@ -140,7 +848,15 @@ pub struct RecordingState {
     pub(crate) is_recording: bool,
     pub(crate) is_stopping: bool,
     pub(crate) current_output_path: Option<String>,
+    /// Taken just before the first FFmpeg segment is spawned; markers are timed from here.
+    pub(crate) started_at: Option<Instant>,
     pub(crate) stop_tx: Option<mpsc::Sender<()>>,
+    pub(crate) finalize_waiters: Vec<oneshot::Sender<RecordingStopResult>>,
+    /// Set by a manual marker to have the audio writer mix in the marker beep. `None`
+    /// unless the recording has `marker_tone` on and records system audio.
+    pub(crate) marker_tone_pending: Option<Arc<AtomicBool>>,
+    /// Released when the session clears this state after finalizing or failing.
+    pub(crate) folder_lock: Option<super::folder_lock::RecordingFolderLock>,
 }
 
 impl RecordingState {
@@ -151,20 +867,70 @@ impl RecordingState {
 
 pub type SharedRecordingState = Arc<RwLock<RecordingState>>;
 
+#[derive(Default)]
+pub struct AudioMonitorState {
+    pub(crate) stop_tx: Option<std::sync::mpsc::Sender<()>>,
+    pub(crate) monitor_id: u64,
+}
+
+pub type SharedAudioMonitorState = Arc<RwLock<AudioMonitorState>>;
+
 pub(crate) struct RecordingSessionConfig {
+    /// Identifies the session in its metadata and every `recording-*` event it emits.
+    pub(crate) session_id: String,
     pub(crate) output_path: String,
     pub(crate) ffmpeg_binary_path: PathBuf,
     pub(crate) video_quality: String,
     pub(crate) video_encoder_preference: String,
+    /// `auto` binds the encoder to the GPU driving the captured display on hybrid
+    /// systems; `nvidia`, `intel` or `amd` pin it to that GPU's encoder.
+    pub(crate) encoder_gpu: String,
+    /// Requested output pixel format; validated against the encoder once it is selected.
+    pub(crate) pixel_format: String,
     pub(crate) requested_frame_rate: u32,
     pub(crate) output_frame_rate: u32,
     pub(crate) bitrate: u32,
     pub(crate) capture_input: CaptureInput,
     pub(crate) include_system_audio: bool,
     pub(crate) enable_diagnostics: bool,
+    pub(crate) capture_cursor: bool,
+    pub(crate) max_part_bytes: Option<u64>,
+    /// Space to reserve for each segment file, when `preallocate_output` is on.
+    pub(crate) preallocate_bytes: Option<u64>,
+    pub(crate) frame_sync: FrameSync,
+    pub(crate) rate_control: RateControl,
+    /// Quality target for VBR on hardware encoders, `MIN_VBR_QUALITY..=MAX_VBR_QUALITY`.
+    pub(crate) vbr_quality: u32,
+    pub(crate) bypass_audio_limiter: bool,
+    pub(crate) audio_offset_ms: i32,
+    pub(crate) downmix_audio_mono: bool,
+    /// Aligns system audio with the video start: audio captured before FFmpeg connects to
+    /// the audio socket is dropped and capture start-up time is filled with silence.
+    pub(crate) wait_for_audio_connection: bool,
+    /// `pan` filter that remaps the captured channels ahead of the rest of the chain.
+    pub(crate) audio_channel_map_filter: Option<String>,
+    pub(crate) audio_resample_sync: AudioResampleSync,
+    /// Requested system audio codec, `aac` or `opus`.
+    pub(crate) audio_codec: &'static str,
+    pub(crate) audio_bitrate: u32,
+    pub(crate) follow_foreground: bool,
+    pub(crate) window_status_poll_interval: Duration,
+    /// Pad color for window capture that keeps its aspect ratio; `None` stretches instead.
+    pub(crate) letterbox_color: Option<String>,
+    pub(crate) fragmented_mp4: bool,
+    /// Excludes black segments at the end of the session from the final concat.
+    pub(crate) trim_trailing_black: bool,
+    pub(crate) emit_performance_stats: bool,
+    /// Loopback endpoint for system audio; `None` follows the default output device.
+    pub(crate) system_audio_device_id: Option<String>,
+    /// Process whose audio sessions are recorded instead of the whole output device.
+    pub(crate) system_audio_session_process_id: Option<u32>,
+    pub(crate) finalize_hook: Option<FinalizeHook>,
+    pub(crate) marker_tone_pending: Option<Arc<AtomicBool>>,
 }
 
 pub(crate) struct SegmentConfig<'a> {
+    pub(crate) session_id: &'a str,
     pub(crate) ffmpeg_binary_path: &'a std::path::Path,
     pub(crate) runtime_capture_mode: RuntimeCaptureMode,
     pub(crate) output_path: &'a std::path::Path,
@@ -176,6 +942,37 @@ pub(crate) struct SegmentConfig<'a> {
     pub(crate) enable_diagnostics: bool,
     pub(crate) video_encoder: &'a str,
     pub(crate) encoder_preset: Option<&'a str>,
+    pub(crate) pixel_format: &'a str,
     pub(crate) capture_width: u32,
     pub(crate) capture_height: u32,
+    pub(crate) capture_cursor: bool,
+    pub(crate) frame_sync: FrameSync,
+    pub(crate) rate_control: RateControl,
+    pub(crate) vbr_quality: u32,
+    pub(crate) bypass_audio_limiter: bool,
+    pub(crate) audio_offset_ms: i32,
+    pub(crate) downmix_audio_mono: bool,
+    pub(crate) wait_for_audio_connection: bool,
+    pub(crate) audio_channel_map_filter: Option<&'a str>,
+    pub(crate) audio_resample_sync: AudioResampleSync,
+    /// FFmpeg encoder for system audio, `aac` or `libopus`.
+    pub(crate) audio_encoder: &'a str,
+    /// Stereo bitrate; halved when the audio is downmixed to mono.
+    pub(crate) audio_bitrate: u32,
+    pub(crate) system_audio_device_id: Option<&'a str>,
+    pub(crate) system_audio_session_process_id: Option<u32>,
+    /// Window capture retargets to whichever eligible window has focus.
+    pub(crate) follow_foreground: bool,
+    /// How often window capture re-checks whether the window is minimized or gone.
+    pub(crate) window_status_poll_interval: Duration,
+    pub(crate) letterbox_color: Option<&'a str>,
+    pub(crate) fragmented_mp4: bool,
+    pub(crate) emit_performance_stats: bool,
+    /// Bytes this segment may write before the current part reaches its size limit.
+    pub(crate) part_byte_budget: Option<u64>,
+    pub(crate) preallocate_bytes: Option<u64>,
+    pub(crate) marker_tone_pending: Option<&'a Arc<AtomicBool>>,
+    /// Whether a stalled encoder may restart this segment. Only segmented sessions can,
+    /// since a restart otherwise overwrites the output file.
+    pub(crate) restart_on_encoder_stall: bool,
 }
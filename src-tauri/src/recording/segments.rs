@@ -1,11 +1,23 @@
 use std::fs;
+use std::io::{self, BufRead, BufReader, Read, Seek, SeekFrom};
 #[cfg(target_os = "windows")]
 use std::os::windows::process::CommandExt;
-use std::path::{Path, PathBuf};
-use std::process::{Command, Stdio};
-use std::time::Duration;
+use std::path::{Component, Path, PathBuf};
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
 
-use super::model::CREATE_NO_WINDOW;
+use super::ffmpeg::{
+    parse_compare_scores, parse_input_stream_info, parse_progress_out_time_seconds,
+};
+use super::jobs::PostProcessJob;
+use super::model::{
+    RecordingStreamInfo, RecordingVerification, RuntimeCaptureMode, CREATE_NO_WINDOW,
+    EXACT_CONCAT_ATTEMPTS, EXACT_CONCAT_RETRY_DELAY, FRAME_PREVIEW_WIDTH,
+};
+
+const SEGMENT_WORKSPACE_MARKER: &str = "_segments_";
+const FFMPEG_ERROR_SUMMARY_LINES: usize = 5;
 
 pub(crate) fn create_segment_workspace(output_path: &str) -> Result<PathBuf, String> {
     let output = PathBuf::from(output_path);
@@ -20,14 +32,285 @@ pub(crate) fn create_segment_workspace(output_path: &str) -> Result<PathBuf, Str
         .duration_since(std::time::UNIX_EPOCH)
         .map(|duration| duration.as_millis())
         .unwrap_or(0);
-    let workspace = parent.join(format!(".{stem}_segments_{unique_suffix}"));
+    let workspace = parent.join(format!(".{stem}{SEGMENT_WORKSPACE_MARKER}{unique_suffix}"));
     fs::create_dir_all(&workspace)
         .map_err(|error| format!("Failed to create recording segment workspace: {error}"))?;
     Ok(workspace)
 }
 
 pub(crate) fn build_segment_output_path(segment_workspace: &Path, index: usize) -> PathBuf {
-    segment_workspace.join(format!("segment_{index:04}.mp4"))
+    segment_workspace.join(format!("segment_{index:06}.mp4"))
+}
+
+/// Reserves space for a segment before FFmpeg writes it so the file system can lay it out
+/// in one piece. FFmpeg has to open the file with `-truncate 0` to keep the reservation.
+pub(crate) fn preallocate_output_file(path: &Path, bytes: u64) -> Result<(), String> {
+    let file = fs::File::create(path)
+        .map_err(|error| format!("Failed to create recording output file: {error}"))?;
+    file.set_len(bytes)
+        .map_err(|error| format!("Failed to reserve {bytes} bytes for recording output: {error}"))
+}
+
+/// Where the last top-level MP4 box ends. The unused tail of a preallocated file is zeros,
+/// which reads as a box without a type. Anything that cannot be walked keeps its full
+/// length.
+fn mp4_boxes_end(reader: &mut (impl Read + Seek), file_length: u64) -> io::Result<u64> {
+    let mut offset = 0u64;
+    while offset.saturating_add(8) <= file_length {
+        reader.seek(SeekFrom::Start(offset))?;
+        let mut header = [0u8; 8];
+        reader.read_exact(&mut header)?;
+        if header[4..] == [0; 4] {
+            return Ok(offset);
+        }
+
+        let box_size = match u32::from_be_bytes([header[0], header[1], header[2], header[3]]) {
+            // A size of 0 runs to the end of the file, as in an unfinished `mdat`.
+            0 => return Ok(file_length),
+            1 => {
+                let mut large_size = [0u8; 8];
+                reader.read_exact(&mut large_size)?;
+                u64::from_be_bytes(large_size)
+            }
+            size => u64::from(size),
+        };
+        if box_size < 8 {
+            return Ok(file_length);
+        }
+        offset = offset.saturating_add(box_size);
+    }
+
+    Ok(offset.min(file_length))
+}
+
+/// Cuts a preallocated segment back to the bytes FFmpeg actually wrote.
+pub(crate) fn trim_preallocated_output(path: &Path) -> Result<(), String> {
+    let mut file = fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(path)
+        .map_err(|error| format!("Failed to open preallocated recording output: {error}"))?;
+    let file_length = file
+        .metadata()
+        .map_err(|error| format!("Failed to read preallocated recording output: {error}"))?
+        .len();
+    let written_length = mp4_boxes_end(&mut file, file_length)
+        .map_err(|error| format!("Failed to read preallocated recording output: {error}"))?;
+
+    if written_length < file_length {
+        file.set_len(written_length)
+            .map_err(|error| format!("Failed to trim preallocated recording output: {error}"))?;
+    }
+    Ok(())
+}
+
+/// Largest `moov` box read into memory when looking up stream durations.
+const MP4_MOOV_READ_LIMIT: u64 = 64 * 1024 * 1024;
+
+/// Video and audio stream lengths, in seconds, from the `mdhd` boxes of a finished MP4
+/// file's `moov` header. A stream is `None` when it is missing or its header holds no
+/// duration, as in fragmented MP4 where the samples live in `moof` fragments.
+pub(crate) fn read_mp4_stream_durations(
+    recording_path: &Path,
+) -> Result<(Option<f64>, Option<f64>), String> {
+    let mut file = fs::File::open(recording_path)
+        .map_err(|error| format!("Failed to open recording for header read: {error}"))?;
+    let file_length = file
+        .metadata()
+        .map_err(|error| format!("Failed to read recording for header read: {error}"))?
+        .len();
+    let moov = read_top_level_mp4_box(&mut file, file_length, b"moov")
+        .map_err(|error| format!("Failed to read recording MP4 header: {error}"))?
+        .ok_or_else(|| "Recording has no MP4 moov header".to_string())?;
+    Ok(parse_moov_stream_durations(&moov))
+}
+
+/// Payload of the first top-level box of `box_type`, walked the same way as
+/// `mp4_boxes_end`.
+fn read_top_level_mp4_box(
+    reader: &mut (impl Read + Seek),
+    file_length: u64,
+    box_type: &[u8; 4],
+) -> io::Result<Option<Vec<u8>>> {
+    let mut offset = 0u64;
+    while offset.saturating_add(8) <= file_length {
+        reader.seek(SeekFrom::Start(offset))?;
+        let mut header = [0u8; 8];
+        reader.read_exact(&mut header)?;
+        let (box_size, header_length) =
+            match u32::from_be_bytes([header[0], header[1], header[2], header[3]]) {
+                0 => (file_length - offset, 8),
+                1 => {
+                    let mut large_size = [0u8; 8];
+                    reader.read_exact(&mut large_size)?;
+                    (u64::from_be_bytes(large_size), 16)
+                }
+                size => (u64::from(size), 8),
+            };
+        if box_size < header_length || offset.saturating_add(box_size) > file_length {
+            return Ok(None);
+        }
+        if header[4..] == box_type[..] {
+            let payload_length = box_size - header_length;
+            if payload_length > MP4_MOOV_READ_LIMIT {
+                return Ok(None);
+            }
+            let mut payload = vec![0u8; payload_length as usize];
+            reader.read_exact(&mut payload)?;
+            return Ok(Some(payload));
+        }
+        offset = offset.saturating_add(box_size);
+    }
+
+    Ok(None)
+}
+
+/// Splits a box payload into its child boxes as `(type, payload)` pairs. Stops at the
+/// first child that does not fit.
+fn mp4_child_boxes(mut data: &[u8]) -> Vec<(&[u8], &[u8])> {
+    let mut children = Vec::new();
+    while data.len() >= 8 {
+        let Some(size) = read_be_u32(data, 0) else {
+            break;
+        };
+        let (box_size, header_length) = match size {
+            0 => (data.len(), 8),
+            1 => match read_be_u64(data, 8).and_then(|size| usize::try_from(size).ok()) {
+                Some(size) => (size, 16),
+                None => break,
+            },
+            size => (size as usize, 8),
+        };
+        if box_size < header_length || box_size > data.len() {
+            break;
+        }
+        children.push((&data[4..8], &data[header_length..box_size]));
+        data = &data[box_size..];
+    }
+    children
+}
+
+fn find_mp4_child_box<'a>(data: &'a [u8], box_type: &[u8; 4]) -> Option<&'a [u8]> {
+    mp4_child_boxes(data)
+        .into_iter()
+        .find(|(child_type, _)| *child_type == box_type)
+        .map(|(_, payload)| payload)
+}
+
+fn read_be_u32(data: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_be_bytes(
+        data.get(offset..offset + 4)?.try_into().ok()?,
+    ))
+}
+
+fn read_be_u64(data: &[u8], offset: usize) -> Option<u64> {
+    Some(u64::from_be_bytes(
+        data.get(offset..offset + 8)?.try_into().ok()?,
+    ))
+}
+
+/// Reads the first video (`vide`) and audio (`soun`) track durations from a `moov`
+/// payload.
+fn parse_moov_stream_durations(moov: &[u8]) -> (Option<f64>, Option<f64>) {
+    let mut video_duration_seconds = None;
+    let mut audio_duration_seconds = None;
+    for (box_type, trak) in mp4_child_boxes(moov) {
+        if box_type != b"trak" {
+            continue;
+        }
+        let Some(mdia) = find_mp4_child_box(trak, b"mdia") else {
+            continue;
+        };
+        // `hdlr` is version/flags, pre_defined, then the four-byte handler type.
+        let handler_type = find_mp4_child_box(mdia, b"hdlr").and_then(|hdlr| hdlr.get(8..12));
+        let duration_seconds =
+            find_mp4_child_box(mdia, b"mdhd").and_then(parse_mdhd_duration_seconds);
+        match handler_type {
+            Some(b"vide") if video_duration_seconds.is_none() => {
+                video_duration_seconds = duration_seconds;
+            }
+            Some(b"soun") if audio_duration_seconds.is_none() => {
+                audio_duration_seconds = duration_seconds;
+            }
+            _ => {}
+        }
+    }
+    (video_duration_seconds, audio_duration_seconds)
+}
+
+/// Media duration from an `mdhd` payload. Version 1 widens the timestamps and duration
+/// to 64 bits; a zero or all-ones duration means the header does not know it.
+fn parse_mdhd_duration_seconds(mdhd: &[u8]) -> Option<f64> {
+    let (timescale, duration) = match *mdhd.first()? {
+        1 => (read_be_u32(mdhd, 20)?, read_be_u64(mdhd, 24)?),
+        _ => {
+            let duration = read_be_u32(mdhd, 16)?;
+            let duration = if duration == u32::MAX {
+                u64::MAX
+            } else {
+                u64::from(duration)
+            };
+            (read_be_u32(mdhd, 12)?, duration)
+        }
+    };
+    (timescale > 0 && duration > 0 && duration != u64::MAX)
+        .then(|| duration as f64 / f64::from(timescale))
+}
+
+/// Validates a caller-chosen output path for scripted recordings. Relative paths are
+/// taken from the output folder; the result must be a file directly in it, end in `.mp4`
+/// and not overwrite an existing file. Subfolders are rejected because the gallery, the
+/// storage quota and crash recovery only look at the top level of the output folder.
+pub(crate) fn resolve_output_path_override(
+    output_folder: &Path,
+    requested_path: &str,
+) -> Result<PathBuf, String> {
+    let requested = Path::new(requested_path.trim());
+    if requested.as_os_str().is_empty() {
+        return Err("Output path override is empty".to_string());
+    }
+    if requested
+        .components()
+        .any(|component| matches!(component, Component::ParentDir))
+    {
+        return Err(format!(
+            "Output path override must not contain '..': '{}'",
+            requested.display()
+        ));
+    }
+
+    let output_path = if requested.is_absolute() {
+        requested.to_path_buf()
+    } else {
+        output_folder.join(requested)
+    };
+    if output_path.parent() != Some(output_folder) {
+        return Err(format!(
+            "Output path override '{}' must be a file directly in the output folder '{}'",
+            output_path.display(),
+            output_folder.display()
+        ));
+    }
+
+    let has_mp4_extension = output_path
+        .extension()
+        .and_then(|value| value.to_str())
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("mp4"));
+    if !has_mp4_extension {
+        return Err(format!(
+            "Output path override must end in .mp4: '{}'",
+            output_path.display()
+        ));
+    }
+
+    if output_path.exists() {
+        return Err(format!(
+            "Output path override already exists: '{}'",
+            output_path.display()
+        ));
+    }
+
+    Ok(output_path)
 }
 
 fn concat_file_path(segment_workspace: &Path) -> PathBuf {
@@ -109,10 +392,11 @@ fn finalize_with_exact_segments(
     let mut command = Command::new(ffmpeg_binary_path);
     #[cfg(target_os = "windows")]
     command.creation_flags(CREATE_NO_WINDOW);
-    let status = command
+    let output = command
         .arg("-hide_banner")
         .arg("-loglevel")
-        .arg("warning")
+        .arg("error")
+        .arg("-nostdin")
         .arg("-y")
         .arg("-f")
         .arg("concat")
@@ -125,18 +409,72 @@ fn finalize_with_exact_segments(
         .arg("-movflags")
         .arg("+faststart")
         .arg(output_path)
-        .status()
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .output()
         .map_err(|error| format!("Failed to start FFmpeg concat process: {error}"))?;
 
-    if !status.success() {
+    if !output.status.success() {
+        let stderr_summary = summarize_ffmpeg_stderr(&String::from_utf8_lossy(&output.stderr));
         return Err(format!(
-            "FFmpeg concat process failed with status: {status}"
+            "FFmpeg concat process failed with status: {}{}",
+            output.status,
+            if stderr_summary.is_empty() {
+                String::new()
+            } else {
+                format!(": {stderr_summary}")
+            }
         ));
     }
 
     Ok(())
 }
 
+/// Keeps the last few non-empty stderr lines, which is where FFmpeg reports the error
+/// that ended the process.
+fn summarize_ffmpeg_stderr(stderr: &str) -> String {
+    let lines = stderr
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<&str>>();
+    lines[lines.len().saturating_sub(FFMPEG_ERROR_SUMMARY_LINES)..].join(" | ")
+}
+
+/// Retries the exact concat a few times, since antivirus scanners and indexers can briefly
+/// lock freshly written segments.
+fn finalize_with_exact_segments_retrying(
+    ffmpeg_binary_path: &Path,
+    segment_workspace: &Path,
+    segment_paths: &[PathBuf],
+    segment_durations: &[Duration],
+    output_path: &str,
+) -> Result<(), String> {
+    let mut attempt = 1;
+    loop {
+        match finalize_with_exact_segments(
+            ffmpeg_binary_path,
+            segment_workspace,
+            segment_paths,
+            segment_durations,
+            output_path,
+        ) {
+            Ok(()) => return Ok(()),
+            Err(error) if attempt < EXACT_CONCAT_ATTEMPTS => {
+                tracing::warn!(
+                    attempt,
+                    max_attempts = EXACT_CONCAT_ATTEMPTS,
+                    "FFmpeg concat of recording segments failed; retrying: {error}"
+                );
+                std::thread::sleep(EXACT_CONCAT_RETRY_DELAY);
+                attempt += 1;
+            }
+            Err(error) => return Err(error),
+        }
+    }
+}
+
 fn collect_non_empty_segments(
     segment_paths: &[PathBuf],
     segment_durations: &[Duration],
@@ -158,17 +496,21 @@ fn collect_non_empty_segments(
     (paths, durations)
 }
 
-fn segment_is_decodable(ffmpeg_binary_path: &Path, segment_path: &Path) -> bool {
+fn decodes_single_frame(ffmpeg_binary_path: &Path, video_path: &Path, from_end: bool) -> bool {
     let mut command = Command::new(ffmpeg_binary_path);
     #[cfg(target_os = "windows")]
     command.creation_flags(CREATE_NO_WINDOW);
-    let status = command
+    command
         .arg("-hide_banner")
         .arg("-loglevel")
         .arg("error")
-        .arg("-nostdin")
+        .arg("-nostdin");
+    if from_end {
+        command.arg("-sseof").arg("-1");
+    }
+    let status = command
         .arg("-i")
-        .arg(segment_path)
+        .arg(video_path)
         .arg("-frames:v")
         .arg("1")
         .arg("-f")
@@ -183,14 +525,221 @@ fn segment_is_decodable(ffmpeg_binary_path: &Path, segment_path: &Path) -> bool
         Ok(status) => status.success(),
         Err(error) => {
             tracing::warn!(
-                segment_path = %segment_path.display(),
-                "Failed to validate recording segment readability: {error}"
+                video_path = %video_path.display(),
+                "Failed to validate recording readability: {error}"
             );
             false
         }
     }
 }
 
+fn segment_is_decodable(ffmpeg_binary_path: &Path, segment_path: &Path) -> bool {
+    decodes_single_frame(ffmpeg_binary_path, segment_path, false)
+}
+
+/// Decodes the first frame of a finished recording and reports the input streams
+/// FFmpeg found while opening it.
+pub(crate) fn verify_recording_file(
+    ffmpeg_binary_path: &Path,
+    recording_path: &Path,
+) -> Result<RecordingVerification, String> {
+    let mut command = Command::new(ffmpeg_binary_path);
+    #[cfg(target_os = "windows")]
+    command.creation_flags(CREATE_NO_WINDOW);
+    let output = command
+        .arg("-hide_banner")
+        .arg("-nostdin")
+        .arg("-i")
+        .arg(recording_path)
+        .arg("-frames:v")
+        .arg("1")
+        .arg("-f")
+        .arg("null")
+        .arg("-")
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .output()
+        .map_err(|error| format!("Failed to start FFmpeg verification process: {error}"))?;
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    Ok(RecordingVerification {
+        decodable: output.status.success(),
+        stream_info: parse_input_stream_info(&stderr),
+    })
+}
+
+/// Measures how long a single stream (`v:0`, `a:0`) runs by remuxing it into the null
+/// muxer, which reads packets without decoding. Returns `None` when the stream is absent.
+pub(crate) fn probe_stream_duration_seconds(
+    ffmpeg_binary_path: &Path,
+    recording_path: &Path,
+    stream_specifier: &str,
+) -> Result<Option<f64>, String> {
+    let mut command = Command::new(ffmpeg_binary_path);
+    #[cfg(target_os = "windows")]
+    command.creation_flags(CREATE_NO_WINDOW);
+    let output = command
+        .arg("-hide_banner")
+        .arg("-nostdin")
+        .arg("-nostats")
+        .arg("-i")
+        .arg(recording_path)
+        .arg("-map")
+        .arg(format!("0:{stream_specifier}"))
+        .arg("-c")
+        .arg("copy")
+        .arg("-progress")
+        .arg("pipe:1")
+        .arg("-f")
+        .arg("null")
+        .arg("-")
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .output()
+        .map_err(|error| format!("Failed to start FFmpeg stream probe process: {error}"))?;
+    if !output.status.success() {
+        return Ok(None);
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    Ok(stdout
+        .lines()
+        .rev()
+        .find_map(parse_progress_out_time_seconds)
+        .filter(|seconds| *seconds > 0.0))
+}
+
+/// Writes the frame at `timestamp_secs` as a small JPEG. Seeking before `-i` jumps to
+/// the nearest keyframe and decodes forward, which is fast enough for hover previews.
+pub(crate) fn extract_frame_jpeg(
+    ffmpeg_binary_path: &Path,
+    recording_path: &Path,
+    timestamp_secs: f64,
+    frame_path: &Path,
+) -> Result<(), String> {
+    let mut command = Command::new(ffmpeg_binary_path);
+    #[cfg(target_os = "windows")]
+    command.creation_flags(CREATE_NO_WINDOW);
+    let output = command
+        .arg("-hide_banner")
+        .arg("-loglevel")
+        .arg("error")
+        .arg("-nostdin")
+        .arg("-y")
+        .arg("-ss")
+        .arg(format!("{timestamp_secs:.3}"))
+        .arg("-i")
+        .arg(recording_path)
+        .arg("-frames:v")
+        .arg("1")
+        .arg("-vf")
+        .arg(format!("scale={FRAME_PREVIEW_WIDTH}:-2"))
+        .arg("-q:v")
+        .arg("5")
+        .arg(frame_path)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .output()
+        .map_err(|error| format!("Failed to start FFmpeg frame extraction: {error}"))?;
+
+    if !output.status.success() {
+        return Err(format!(
+            "FFmpeg frame extraction failed: {}",
+            summarize_ffmpeg_stderr(&String::from_utf8_lossy(&output.stderr))
+        ));
+    }
+    // Seeking past the end succeeds without writing a frame.
+    if !frame_path.is_file() {
+        return Err(format!("No frame at {timestamp_secs:.1}s in the recording"));
+    }
+    Ok(())
+}
+
+/// Grabs one frame of a window through Windows Graphics Capture and returns it as JPEG,
+/// scaled to fit `width`x`height`. The grab is killed after `timeout`.
+pub(crate) fn capture_window_thumbnail_jpeg(
+    ffmpeg_binary_path: &Path,
+    window_hwnd: usize,
+    width: u32,
+    height: u32,
+    timeout: Duration,
+) -> Result<Vec<u8>, String> {
+    let mut command = Command::new(ffmpeg_binary_path);
+    #[cfg(target_os = "windows")]
+    command.creation_flags(CREATE_NO_WINDOW);
+    let mut child = command
+        .arg("-hide_banner")
+        .arg("-loglevel")
+        .arg("error")
+        .arg("-nostdin")
+        .arg("-f")
+        .arg("lavfi")
+        .arg("-i")
+        .arg(format!(
+            "gfxcapture=hwnd={window_hwnd}:max_framerate=5:capture_cursor=0:capture_border=0:output_fmt=bgra:width={width}:height={height}:resize_mode=scale_aspect,hwdownload,format=bgra"
+        ))
+        .arg("-frames:v")
+        .arg("1")
+        .arg("-pix_fmt")
+        .arg("yuvj420p")
+        .arg("-q:v")
+        .arg("5")
+        .arg("-f")
+        .arg("mjpeg")
+        .arg("pipe:1")
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|error| format!("Failed to start FFmpeg window thumbnail capture: {error}"))?;
+
+    let mut stdout = child
+        .stdout
+        .take()
+        .ok_or_else(|| "FFmpeg window thumbnail output is unavailable".to_string())?;
+    let reader = thread::spawn(move || {
+        let mut jpeg = Vec::new();
+        stdout.read_to_end(&mut jpeg).map(|_| jpeg)
+    });
+
+    let deadline = Instant::now() + timeout;
+    let status = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => break status,
+            Ok(None) if Instant::now() >= deadline => {
+                let _ = child.kill();
+                let _ = child.wait();
+                let _ = reader.join();
+                return Err("Timed out capturing a window thumbnail".to_string());
+            }
+            Ok(None) => thread::sleep(Duration::from_millis(25)),
+            Err(error) => {
+                return Err(format!(
+                    "Failed to wait for window thumbnail capture: {error}"
+                ))
+            }
+        }
+    };
+
+    let jpeg = reader
+        .join()
+        .map_err(|_| "Window thumbnail reader thread panicked".to_string())?
+        .map_err(|error| format!("Failed to read window thumbnail: {error}"))?;
+    if !status.success() || jpeg.is_empty() {
+        return Err("FFmpeg could not capture a thumbnail of the window".to_string());
+    }
+    Ok(jpeg)
+}
+
+/// A recording counts as playable when both its first and its last frame decode.
+pub(crate) fn recording_is_playable(ffmpeg_binary_path: &Path, recording_path: &Path) -> bool {
+    decodes_single_frame(ffmpeg_binary_path, recording_path, false)
+        && decodes_single_frame(ffmpeg_binary_path, recording_path, true)
+}
+
 fn collect_decodable_segments(
     ffmpeg_binary_path: &Path,
     segment_paths: &[PathBuf],
@@ -231,20 +780,19 @@ pub(crate) fn finalize_segmented_recording(
 
     // Fast path: try concat with all non-empty segments first.
     // Only run decodability probing if this fails.
-    if finalize_with_exact_segments(
+    let full_set_error = match finalize_with_exact_segments_retrying(
         ffmpeg_binary_path,
         segment_workspace,
         &non_empty_paths,
         &non_empty_durations,
         output_path,
-    )
-    .is_ok()
-    {
-        return Ok(());
-    }
+    ) {
+        Ok(()) => return Ok(()),
+        Err(error) => error,
+    };
 
     tracing::warn!(
-        "FFmpeg concat failed for full segment set. Probing segment decodability and trying recovery strategies"
+        "FFmpeg concat failed for full segment set. Probing segment decodability and trying recovery strategies: {full_set_error}"
     );
 
     // Slow path: probe each segment for decodability, then run recovery
@@ -255,7 +803,29 @@ pub(crate) fn finalize_segmented_recording(
         return Err("No valid recording segments were produced".to_string());
     }
 
-    let mut last_error = String::new();
+    let mut last_error = full_set_error;
+
+    if valid_paths.len() < non_empty_paths.len() {
+        match finalize_with_exact_segments(
+            ffmpeg_binary_path,
+            segment_workspace,
+            &valid_paths,
+            &valid_durations,
+            output_path,
+        ) {
+            Ok(()) => {
+                tracing::warn!(
+                    skipped_segments = non_empty_paths.len() - valid_paths.len(),
+                    total_segments = non_empty_paths.len(),
+                    "Recovered recording by skipping undecodable segments"
+                );
+                return Ok(());
+            }
+            Err(error) => {
+                last_error = error;
+            }
+        }
+    }
 
     if valid_paths.len() > 2 {
         for remove_index in 1..(valid_paths.len() - 1) {
@@ -347,6 +917,40 @@ pub(crate) fn finalize_segmented_recording(
     ))
 }
 
+/// Number of leading segments to keep so the recording ends on the last segment with
+/// real content. A session that only ever recorded black keeps every segment.
+pub(crate) fn count_segments_before_trailing_black(segment_modes: &[RuntimeCaptureMode]) -> usize {
+    match segment_modes
+        .iter()
+        .rposition(|mode| *mode != RuntimeCaptureMode::Black)
+    {
+        Some(last_content_index) => last_content_index + 1,
+        None => segment_modes.len(),
+    }
+}
+
+pub(crate) fn build_part_output_path(output_path: &str, part_number: usize) -> PathBuf {
+    let output = Path::new(output_path);
+    let stem = output
+        .file_stem()
+        .and_then(|value| value.to_str())
+        .unwrap_or("recording");
+    output.with_file_name(format!("{stem}_part{part_number}.mp4"))
+}
+
+pub(crate) fn remove_segment_files(segment_paths: &[PathBuf]) {
+    for segment_path in segment_paths {
+        if let Err(error) = fs::remove_file(segment_path) {
+            if error.kind() != std::io::ErrorKind::NotFound {
+                tracing::warn!(
+                    segment_path = %segment_path.display(),
+                    "Failed to remove finalized recording segment: {error}"
+                );
+            }
+        }
+    }
+}
+
 pub(crate) fn cleanup_segment_workspace(segment_workspace: &Path) {
     if let Err(error) = fs::remove_dir_all(segment_workspace) {
         tracing::warn!(
@@ -355,3 +959,797 @@ pub(crate) fn cleanup_segment_workspace(segment_workspace: &Path) {
         );
     }
 }
+
+fn segment_workspace_stem(segment_workspace: &Path) -> Option<&str> {
+    let directory_name = segment_workspace.file_name()?.to_str()?;
+    let (stem, _) = directory_name
+        .strip_prefix('.')?
+        .rsplit_once(SEGMENT_WORKSPACE_MARKER)?;
+    Some(stem).filter(|value| !value.is_empty())
+}
+
+pub(crate) fn is_segment_workspace(path: &Path) -> bool {
+    path.is_dir() && segment_workspace_stem(path).is_some()
+}
+
+pub(crate) fn workspace_belongs_to_output(segment_workspace: &Path, output_path: &Path) -> bool {
+    let output_stem = output_path.file_stem().and_then(|value| value.to_str());
+    output_stem.is_some()
+        && segment_workspace_stem(segment_workspace) == output_stem
+        && segment_workspace.parent() == output_path.parent()
+}
+
+pub(crate) fn find_orphaned_segment_workspaces(
+    output_folder: &Path,
+) -> Result<Vec<PathBuf>, String> {
+    let entries = match fs::read_dir(output_folder) {
+        Ok(entries) => entries,
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(error) => return Err(format!("Failed to read output folder: {error}")),
+    };
+
+    let mut workspaces = Vec::new();
+    for entry in entries {
+        let path = entry.map_err(|error| error.to_string())?.path();
+        if is_segment_workspace(&path) {
+            workspaces.push(path);
+        }
+    }
+
+    workspaces.sort();
+    Ok(workspaces)
+}
+
+pub(crate) fn list_workspace_segments(segment_workspace: &Path) -> Result<Vec<PathBuf>, String> {
+    let entries = fs::read_dir(segment_workspace)
+        .map_err(|error| format!("Failed to read recording segment workspace: {error}"))?;
+
+    let mut segment_paths = Vec::new();
+    for entry in entries {
+        let path = entry.map_err(|error| error.to_string())?.path();
+        let segment_index = path
+            .file_name()
+            .and_then(|value| value.to_str())
+            .and_then(parse_segment_index);
+        if let Some(segment_index) = segment_index.filter(|_| path.is_file()) {
+            segment_paths.push((segment_index, path));
+        }
+    }
+
+    // Sort by the parsed index: padding widths differ between app versions, and an index
+    // that outgrows its padding would otherwise sort ahead of smaller ones.
+    segment_paths.sort();
+    Ok(segment_paths.into_iter().map(|(_, path)| path).collect())
+}
+
+fn parse_segment_index(file_name: &str) -> Option<usize> {
+    file_name
+        .strip_prefix("segment_")?
+        .strip_suffix(".mp4")?
+        .parse()
+        .ok()
+}
+
+pub(crate) fn recovered_output_path(segment_workspace: &Path) -> Option<PathBuf> {
+    let stem = segment_workspace_stem(segment_workspace)?;
+    let parent = segment_workspace.parent()?;
+    let original_output = parent.join(format!("{stem}.mp4"));
+    if !original_output.exists() {
+        return Some(original_output);
+    }
+
+    Some(parent.join(format!("{stem}_recovered.mp4")))
+}
+
+pub(crate) fn recover_segment_workspace(
+    ffmpeg_binary_path: &Path,
+    segment_workspace: &Path,
+    output_path: &str,
+) -> Result<(), String> {
+    let segment_paths = list_workspace_segments(segment_workspace)?;
+    if segment_paths.is_empty() {
+        return Err("The recording segment workspace does not contain any segments".to_string());
+    }
+
+    // Wall-clock durations are lost with the crashed session, so the concat demuxer
+    // falls back to each segment's own timestamps.
+    finalize_segmented_recording(
+        ffmpeg_binary_path,
+        segment_workspace,
+        &segment_paths,
+        &[],
+        output_path,
+    )
+}
+
+fn suffixed_output_path(input_path: &Path, label: &str) -> Option<PathBuf> {
+    let stem = input_path.file_stem()?.to_str()?;
+    let parent = input_path.parent()?;
+    let mut candidate = parent.join(format!("{stem}_{label}.mp4"));
+    let mut suffix = 2;
+    while candidate.exists() {
+        candidate = parent.join(format!("{stem}_{label}_{suffix}.mp4"));
+        suffix += 1;
+    }
+    Some(candidate)
+}
+
+pub(crate) fn repaired_output_path(input_path: &Path) -> Option<PathBuf> {
+    suffixed_output_path(input_path, "repaired")
+}
+
+pub(crate) fn annotated_output_path(input_path: &Path) -> Option<PathBuf> {
+    suffixed_output_path(input_path, "annotated")
+}
+
+/// Re-muxes a damaged recording into a fresh container, dropping corrupt packets and
+/// regenerating timestamps so FFmpeg can rebuild the index.
+pub(crate) fn remux_damaged_recording(
+    ffmpeg_binary_path: &Path,
+    input_path: &Path,
+    output_path: &Path,
+    job: &PostProcessJob,
+) -> Result<(), String> {
+    let mut command = Command::new(ffmpeg_binary_path);
+    #[cfg(target_os = "windows")]
+    command.creation_flags(CREATE_NO_WINDOW);
+    let child = command
+        .arg("-hide_banner")
+        .arg("-loglevel")
+        .arg("warning")
+        .arg("-nostdin")
+        .arg("-err_detect")
+        .arg("ignore_err")
+        .arg("-fflags")
+        .arg("+genpts+discardcorrupt")
+        .arg("-i")
+        .arg(input_path)
+        .arg("-map")
+        .arg("0")
+        .arg("-c")
+        .arg("copy")
+        .arg("-movflags")
+        .arg("+faststart")
+        .arg(output_path)
+        .stdin(Stdio::null())
+        .spawn()
+        .map_err(|error| format!("Failed to start FFmpeg repair process: {error}"))?;
+    let status = wait_with_progress(child, 0.0, &mut |_| {}, job)
+        .map_err(|error| format!("Failed to wait for FFmpeg repair process: {error}"))?;
+
+    if !status.success() {
+        if output_path.exists() {
+            let _ = fs::remove_file(output_path);
+        }
+        return Err(format!(
+            "FFmpeg could not re-mux the damaged recording (status: {status}). The file may be missing its index entirely"
+        ));
+    }
+
+    Ok(())
+}
+
+/// Stream copy only works when every input shares codecs and frame size; anything else
+/// has to go through the concat filter.
+pub(crate) fn recordings_share_stream_layout(stream_infos: &[RecordingStreamInfo]) -> bool {
+    let Some(first) = stream_infos.first() else {
+        return true;
+    };
+
+    stream_infos.iter().all(|info| {
+        info.video_codec == first.video_codec
+            && info.width == first.width
+            && info.height == first.height
+            && info.audio_codec == first.audio_codec
+    })
+}
+
+fn append_reencode_concat_args(command: &mut Command, stream_infos: &[RecordingStreamInfo]) {
+    let (width, height) = stream_infos
+        .first()
+        .and_then(|info| Some((info.width?, info.height?)))
+        .unwrap_or((1920, 1080));
+    let include_audio = stream_infos.iter().all(|info| info.audio_codec.is_some());
+    if !include_audio {
+        tracing::warn!("Some selected recordings have no audio track; dropping audio from concat");
+    }
+
+    let mut filter = String::new();
+    let mut concat_inputs = String::new();
+    for index in 0..stream_infos.len() {
+        filter.push_str(&format!(
+            "[{index}:v:0]scale={width}:{height}:force_original_aspect_ratio=decrease,pad={width}:{height}:(ow-iw)/2:(oh-ih)/2,setsar=1[v{index}];"
+        ));
+        concat_inputs.push_str(&format!("[v{index}]"));
+        if include_audio {
+            concat_inputs.push_str(&format!("[{index}:a:0]"));
+        }
+    }
+    filter.push_str(&format!(
+        "{concat_inputs}concat=n={}:v=1:a={}[outv]",
+        stream_infos.len(),
+        u8::from(include_audio)
+    ));
+    if include_audio {
+        filter.push_str("[outa]");
+    }
+
+    command
+        .arg("-filter_complex")
+        .arg(filter)
+        .arg("-map")
+        .arg("[outv]");
+    if include_audio {
+        command
+            .arg("-map")
+            .arg("[outa]")
+            .arg("-c:a")
+            .arg("aac")
+            .arg("-b:a")
+            .arg("192k");
+    }
+    command
+        .arg("-c:v")
+        .arg("libx264")
+        .arg("-preset")
+        .arg("veryfast")
+        .arg("-crf")
+        .arg("20")
+        .arg("-pix_fmt")
+        .arg("yuv420p");
+}
+
+/// Reads `-progress pipe:1` output until FFmpeg exits, reporting the completed fraction of
+/// `total_duration_seconds` when it is known. The child is handed to `job` meanwhile, so
+/// cancelling the job kills it and leaves the caller to clean up the partial output.
+fn wait_with_progress(
+    mut child: Child,
+    total_duration_seconds: f64,
+    on_progress: &mut impl FnMut(f64),
+    job: &PostProcessJob,
+) -> Result<ExitStatus, String> {
+    let stdout = child.stdout.take();
+    job.attach_child(child)?;
+    if let Some(stdout) = stdout {
+        for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+            if total_duration_seconds <= 0.0 {
+                continue;
+            }
+            if let Some(out_time) = parse_progress_out_time_seconds(&line) {
+                on_progress((out_time / total_duration_seconds).clamp(0.0, 1.0));
+            }
+        }
+    }
+    job.wait_child()
+}
+
+/// Joins user-selected recordings into one file, reusing the segment concat list for
+/// matching inputs and re-encoding through the concat filter otherwise. `decode_hwaccel`
+/// only applies to re-encoding, since stream copy never decodes. `on_progress` receives
+/// the completed fraction of the combined input duration.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn concat_recordings(
+    ffmpeg_binary_path: &Path,
+    input_paths: &[PathBuf],
+    stream_infos: &[RecordingStreamInfo],
+    output_path: &Path,
+    reencode: bool,
+    decode_hwaccel: Option<&str>,
+    job: &PostProcessJob,
+    mut on_progress: impl FnMut(f64),
+) -> Result<(), String> {
+    let total_duration_seconds: f64 = stream_infos
+        .iter()
+        .filter_map(|info| info.duration_seconds)
+        .sum();
+
+    let unique_suffix = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|duration| duration.as_millis())
+        .unwrap_or(0);
+    let concat_workspace = std::env::temp_dir().join(format!("floorpov_concat_{unique_suffix}"));
+
+    let mut command = Command::new(ffmpeg_binary_path);
+    #[cfg(target_os = "windows")]
+    command.creation_flags(CREATE_NO_WINDOW);
+    command
+        .arg("-hide_banner")
+        .arg("-loglevel")
+        .arg("error")
+        .arg("-nostdin")
+        .arg("-y")
+        .arg("-progress")
+        .arg("pipe:1")
+        .arg("-nostats");
+
+    if reencode {
+        for input_path in input_paths {
+            if let Some(hwaccel) = decode_hwaccel {
+                command.arg("-hwaccel").arg(hwaccel);
+            }
+            command.arg("-i").arg(input_path);
+        }
+        append_reencode_concat_args(&mut command, stream_infos);
+    } else {
+        fs::create_dir_all(&concat_workspace)
+            .map_err(|error| format!("Failed to create concat workspace: {error}"))?;
+        let concat_path = write_concat_file(&concat_workspace, input_paths, &[])?;
+        command
+            .arg("-f")
+            .arg("concat")
+            .arg("-safe")
+            .arg("0")
+            .arg("-i")
+            .arg(&concat_path)
+            .arg("-c")
+            .arg("copy");
+    }
+
+    let spawn_result = command
+        .arg("-movflags")
+        .arg("+faststart")
+        .arg(output_path)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn();
+
+    let status = spawn_result
+        .map_err(|error| format!("Failed to start FFmpeg concat process: {error}"))
+        .and_then(|child| {
+            wait_with_progress(child, total_duration_seconds, &mut on_progress, job)
+                .map_err(|error| format!("Failed to wait for FFmpeg concat process: {error}"))
+        });
+
+    if concat_workspace.exists() {
+        let _ = fs::remove_dir_all(&concat_workspace);
+    }
+
+    let status = status?;
+    if !status.success() {
+        if output_path.exists() {
+            let _ = fs::remove_file(output_path);
+        }
+        return Err(format!(
+            "FFmpeg concat process failed with status: {status}"
+        ));
+    }
+
+    on_progress(1.0);
+    Ok(())
+}
+
+/// Spreads `sample_count` stretches of `sample_seconds` evenly over a recording as
+/// `(start, duration)` pairs, or covers the whole recording when it is too short to sample.
+pub(crate) fn compare_sample_windows(
+    duration_seconds: f64,
+    sample_count: usize,
+    sample_seconds: f64,
+) -> Vec<(f64, f64)> {
+    if sample_count == 0 || duration_seconds <= sample_seconds * sample_count as f64 {
+        return vec![(0.0, duration_seconds)];
+    }
+
+    (1..=sample_count)
+        .map(|index| {
+            let center = duration_seconds * index as f64 / (sample_count + 1) as f64;
+            ((center - sample_seconds / 2.0).max(0.0), sample_seconds)
+        })
+        .collect()
+}
+
+/// Runs FFmpeg's `ssim` and `psnr` filters over the same stretch of two recordings and
+/// returns `(ssim, psnr_db)`. The second recording is scaled to the first one's size so
+/// captures at different resolutions still compare.
+pub(crate) fn compare_recording_window(
+    ffmpeg_binary_path: &Path,
+    input_path_a: &Path,
+    input_path_b: &Path,
+    (start_seconds, duration_seconds): (f64, f64),
+    job: &PostProcessJob,
+    on_progress: &mut impl FnMut(f64),
+) -> Result<(f64, f64), String> {
+    let mut command = Command::new(ffmpeg_binary_path);
+    #[cfg(target_os = "windows")]
+    command.creation_flags(CREATE_NO_WINDOW);
+    command
+        .arg("-hide_banner")
+        .arg("-nostdin")
+        .arg("-progress")
+        .arg("pipe:1")
+        .arg("-nostats");
+    for input_path in [input_path_a, input_path_b] {
+        command
+            .arg("-ss")
+            .arg(format!("{start_seconds:.3}"))
+            .arg("-t")
+            .arg(format!("{duration_seconds:.3}"))
+            .arg("-i")
+            .arg(input_path);
+    }
+    let mut child = command
+        .arg("-lavfi")
+        .arg(
+            "[1:v][0:v]scale2ref[b][a];[a]split[a1][a2];[b]split[b1][b2];[a1][b1]ssim;[a2][b2]psnr",
+        )
+        .arg("-f")
+        .arg("null")
+        .arg("-")
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|error| format!("Failed to start FFmpeg compare process: {error}"))?;
+
+    // The scores are logged on stderr once FFmpeg finishes; drain it on its own thread so a
+    // full pipe cannot stall the process while progress is read from stdout.
+    let stderr_reader = child.stderr.take().map(|mut stderr| {
+        thread::spawn(move || {
+            let mut output = String::new();
+            let _ = stderr.read_to_string(&mut output);
+            output
+        })
+    });
+    let status = wait_with_progress(child, duration_seconds, on_progress, job)
+        .map_err(|error| format!("Failed to wait for FFmpeg compare process: {error}"))?;
+    let stderr = stderr_reader
+        .and_then(|reader| reader.join().ok())
+        .unwrap_or_default();
+
+    if !status.success() {
+        return Err(format!(
+            "FFmpeg compare process failed: {}",
+            summarize_ffmpeg_stderr(&stderr)
+        ));
+    }
+    parse_compare_scores(&stderr)
+        .ok_or_else(|| "FFmpeg did not report SSIM and PSNR scores".to_string())
+}
+
+/// Re-encodes a finished recording with `video_filter` burned into the picture, copying
+/// the audio untouched. The filter goes through a script file because one `drawtext` per
+/// marker quickly exceeds the Windows command-line limit.
+pub(crate) fn annotate_recording(
+    ffmpeg_binary_path: &Path,
+    input_path: &Path,
+    output_path: &Path,
+    video_filter: &str,
+    total_duration_seconds: f64,
+    job: &PostProcessJob,
+    mut on_progress: impl FnMut(f64),
+) -> Result<(), String> {
+    let unique_suffix = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|duration| duration.as_millis())
+        .unwrap_or(0);
+    let filter_script_path =
+        std::env::temp_dir().join(format!("floorpov_annotate_{unique_suffix}.txt"));
+    fs::write(&filter_script_path, video_filter)
+        .map_err(|error| format!("Failed to write annotation filter script: {error}"))?;
+
+    let mut command = Command::new(ffmpeg_binary_path);
+    #[cfg(target_os = "windows")]
+    command.creation_flags(CREATE_NO_WINDOW);
+    let spawn_result = command
+        .arg("-hide_banner")
+        .arg("-loglevel")
+        .arg("error")
+        .arg("-nostdin")
+        .arg("-y")
+        .arg("-progress")
+        .arg("pipe:1")
+        .arg("-nostats")
+        .arg("-i")
+        .arg(input_path)
+        .arg("-filter_script:v")
+        .arg(&filter_script_path)
+        .arg("-map")
+        .arg("0:v:0")
+        .arg("-map")
+        .arg("0:a?")
+        .arg("-c:v")
+        .arg("libx264")
+        .arg("-preset")
+        .arg("veryfast")
+        .arg("-crf")
+        .arg("20")
+        .arg("-pix_fmt")
+        .arg("yuv420p")
+        .arg("-c:a")
+        .arg("copy")
+        .arg("-movflags")
+        .arg("+faststart")
+        .arg(output_path)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn();
+
+    let status = spawn_result
+        .map_err(|error| format!("Failed to start FFmpeg annotate process: {error}"))
+        .and_then(|child| {
+            wait_with_progress(child, total_duration_seconds, &mut on_progress, job)
+                .map_err(|error| format!("Failed to wait for FFmpeg annotate process: {error}"))
+        });
+
+    let _ = fs::remove_file(&filter_script_path);
+
+    let status = status?;
+    if !status.success() {
+        if output_path.exists() {
+            let _ = fs::remove_file(output_path);
+        }
+        return Err(format!(
+            "FFmpeg annotate process failed with status: {status}"
+        ));
+    }
+
+    on_progress(1.0);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::{Path, PathBuf};
+    use std::process::{Command, Stdio};
+    use std::time::Duration;
+
+    use super::{
+        build_segment_output_path, compare_sample_windows, count_segments_before_trailing_black,
+        finalize_segmented_recording, list_workspace_segments, mp4_boxes_end,
+        parse_moov_stream_durations, resolve_output_path_override, summarize_ffmpeg_stderr,
+        verify_recording_file,
+    };
+
+    use crate::recording::model::RuntimeCaptureMode;
+
+    #[test]
+    fn trailing_black_segments_are_excluded() {
+        use RuntimeCaptureMode::{Black, Monitor, Window};
+
+        assert_eq!(
+            count_segments_before_trailing_black(&[Window, Black, Window, Black, Black]),
+            3
+        );
+        assert_eq!(count_segments_before_trailing_black(&[Monitor, Window]), 2);
+        assert_eq!(count_segments_before_trailing_black(&[Black, Black]), 2);
+        assert_eq!(count_segments_before_trailing_black(&[]), 0);
+    }
+
+    fn mp4_box(box_type: &[u8; 4], payload_length: usize) -> Vec<u8> {
+        let mut bytes = ((payload_length + 8) as u32).to_be_bytes().to_vec();
+        bytes.extend_from_slice(box_type);
+        bytes.resize(payload_length + 8, 0xAB);
+        bytes
+    }
+
+    #[test]
+    fn finds_end_of_mp4_boxes_before_preallocated_tail() {
+        let mut bytes = mp4_box(b"ftyp", 16);
+        bytes.extend(mp4_box(b"moov", 40));
+        bytes.extend(mp4_box(b"mdat", 100));
+        let written_length = bytes.len() as u64;
+        bytes.resize(bytes.len() + 4096, 0);
+        let file_length = bytes.len() as u64;
+
+        let end = mp4_boxes_end(&mut std::io::Cursor::new(bytes), file_length).unwrap();
+
+        assert_eq!(end, written_length);
+    }
+
+    fn mp4_box_with(box_type: &[u8; 4], payload: &[u8]) -> Vec<u8> {
+        let mut bytes = ((payload.len() + 8) as u32).to_be_bytes().to_vec();
+        bytes.extend_from_slice(box_type);
+        bytes.extend_from_slice(payload);
+        bytes
+    }
+
+    fn mp4_track(handler_type: &[u8; 4], mdhd: Vec<u8>) -> Vec<u8> {
+        let mut hdlr = vec![0u8; 8];
+        hdlr.extend_from_slice(handler_type);
+        hdlr.resize(24, 0);
+        let mut mdia = mp4_box_with(b"mdhd", &mdhd);
+        mdia.extend(mp4_box_with(b"hdlr", &hdlr));
+        mp4_box_with(b"trak", &mp4_box_with(b"mdia", &mdia))
+    }
+
+    #[test]
+    fn reads_stream_durations_from_the_moov_header() {
+        let mut video_mdhd = vec![0u8; 12];
+        video_mdhd.extend_from_slice(&90_000u32.to_be_bytes());
+        video_mdhd.extend_from_slice(&900_000u32.to_be_bytes());
+        video_mdhd.resize(24, 0);
+        let mut audio_mdhd = vec![1u8, 0, 0, 0];
+        audio_mdhd.resize(20, 0);
+        audio_mdhd.extend_from_slice(&48_000u32.to_be_bytes());
+        audio_mdhd.extend_from_slice(&481_200u64.to_be_bytes());
+        audio_mdhd.resize(36, 0);
+        let mut moov = mp4_box(b"mvhd", 100);
+        moov.extend(mp4_track(b"vide", video_mdhd));
+        moov.extend(mp4_track(b"soun", audio_mdhd));
+
+        assert_eq!(
+            parse_moov_stream_durations(&moov),
+            (Some(10.0), Some(10.025))
+        );
+    }
+
+    #[test]
+    fn fragmented_tracks_have_no_header_duration() {
+        let mut empty_mdhd = vec![0u8; 12];
+        empty_mdhd.extend_from_slice(&90_000u32.to_be_bytes());
+        empty_mdhd.resize(24, 0);
+
+        assert_eq!(
+            parse_moov_stream_durations(&mp4_track(b"vide", empty_mdhd)),
+            (None, None)
+        );
+    }
+
+    #[test]
+    fn spreads_compare_samples_over_the_recording() {
+        assert_eq!(
+            compare_sample_windows(100.0, 3, 5.0),
+            vec![(22.5, 5.0), (47.5, 5.0), (72.5, 5.0)]
+        );
+        assert_eq!(compare_sample_windows(12.0, 3, 5.0), vec![(0.0, 12.0)]);
+    }
+
+    #[test]
+    fn keeps_unfinished_or_unwritten_mp4_lengths() {
+        let mut unfinished = mp4_box(b"ftyp", 16);
+        unfinished.extend_from_slice(&[0, 0, 0, 0]);
+        unfinished.extend_from_slice(b"mdat");
+        unfinished.resize(unfinished.len() + 512, 0);
+        let unfinished_length = unfinished.len() as u64;
+        let unwritten = vec![0u8; 1024];
+
+        assert_eq!(
+            mp4_boxes_end(&mut std::io::Cursor::new(unfinished), unfinished_length).unwrap(),
+            unfinished_length
+        );
+        assert_eq!(
+            mp4_boxes_end(&mut std::io::Cursor::new(unwritten), 1024).unwrap(),
+            0
+        );
+    }
+
+    fn bundled_ffmpeg_binary() -> PathBuf {
+        let ffmpeg_binary_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("bin")
+            .join("ffmpeg.exe");
+        assert!(
+            ffmpeg_binary_path.is_file(),
+            "bin/ffmpeg.exe is required by this test"
+        );
+        ffmpeg_binary_path
+    }
+
+    fn write_test_segment(ffmpeg_binary_path: &Path, segment_path: &Path) {
+        let status = Command::new(ffmpeg_binary_path)
+            .args(["-hide_banner", "-loglevel", "error", "-nostdin", "-y"])
+            .args([
+                "-f",
+                "lavfi",
+                "-i",
+                "testsrc=duration=1:size=320x240:rate=30",
+            ])
+            .args(["-c:v", "libx264", "-pix_fmt", "yuv420p"])
+            .arg(segment_path)
+            .stdin(Stdio::null())
+            .status()
+            .expect("FFmpeg should start");
+        assert!(status.success(), "FFmpeg should write the test segment");
+    }
+
+    #[test]
+    fn summarizes_trailing_ffmpeg_stderr_lines() {
+        let stderr = "\
+[concat @ 0000] Line 1
+[concat @ 0000] Line 2
+
+[mov,mp4 @ 0001] moov atom not found
+[concat @ 0000] Impossible to open 'segment_001.mp4'
+[in#0 @ 0002] Error opening input: Invalid data found when processing input
+Error opening input file concat.txt.
+Error opening input files: Invalid data found when processing input
+";
+
+        assert_eq!(
+            summarize_ffmpeg_stderr(stderr),
+            "[mov,mp4 @ 0001] moov atom not found | [concat @ 0000] Impossible to open 'segment_001.mp4' | [in#0 @ 0002] Error opening input: Invalid data found when processing input | Error opening input file concat.txt. | Error opening input files: Invalid data found when processing input"
+        );
+        assert_eq!(summarize_ffmpeg_stderr("\n  \n"), "");
+    }
+
+    #[test]
+    #[ignore = "needs bin/ffmpeg.exe; run with `cargo test -- --ignored`"]
+    fn finalizes_around_a_corrupt_middle_segment() {
+        let ffmpeg_binary_path = bundled_ffmpeg_binary();
+
+        let workspace =
+            std::env::temp_dir().join(format!("floorpov_finalize_test_{}", std::process::id()));
+        std::fs::create_dir_all(&workspace).expect("test workspace should be created");
+        let segment_paths = (0..3)
+            .map(|index| workspace.join(format!("segment_{index:03}.mp4")))
+            .collect::<Vec<PathBuf>>();
+        for segment_path in &segment_paths {
+            write_test_segment(&ffmpeg_binary_path, segment_path);
+        }
+        std::fs::write(&segment_paths[1], b"not an mp4 segment")
+            .expect("middle segment should be overwritten");
+        let segment_durations = vec![Duration::from_secs(1); segment_paths.len()];
+        let output_path = workspace.join("recording.mp4");
+
+        let result = finalize_segmented_recording(
+            &ffmpeg_binary_path,
+            &workspace,
+            &segment_paths,
+            &segment_durations,
+            &output_path.to_string_lossy(),
+        );
+        let verification = verify_recording_file(&ffmpeg_binary_path, &output_path);
+        let _ = std::fs::remove_dir_all(&workspace);
+
+        assert!(result.is_ok(), "finalize should recover: {result:?}");
+        let verification = verification.expect("output should be verifiable");
+        assert!(verification.decodable);
+        let duration_seconds = verification
+            .stream_info
+            .duration_seconds
+            .expect("output should report a duration");
+        assert!(
+            duration_seconds > 1.5,
+            "both intact segments should be kept, got {duration_seconds}s"
+        );
+    }
+
+    #[test]
+    fn output_path_override_stays_directly_in_the_output_folder() {
+        let output_folder = std::env::temp_dir().join("floorpov_output_override_test");
+
+        assert_eq!(
+            resolve_output_path_override(&output_folder, "run_01.mp4"),
+            Ok(output_folder.join("run_01.mp4"))
+        );
+        assert!(resolve_output_path_override(&output_folder, "batch/run_01.mp4").is_err());
+        assert_eq!(
+            resolve_output_path_override(
+                &output_folder,
+                &output_folder.join("run_02.MP4").to_string_lossy()
+            ),
+            Ok(output_folder.join("run_02.MP4"))
+        );
+        assert!(resolve_output_path_override(&output_folder, "../escape.mp4").is_err());
+        assert!(resolve_output_path_override(&output_folder, "run_03.mkv").is_err());
+        assert!(resolve_output_path_override(
+            &output_folder,
+            &std::env::temp_dir().join("elsewhere.mp4").to_string_lossy()
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn lists_workspace_segments_in_order_past_the_padding_width() {
+        let workspace = std::env::temp_dir().join(format!(
+            "floorpov_segment_order_test_{}",
+            std::process::id()
+        ));
+        std::fs::create_dir_all(&workspace).expect("test workspace should be created");
+        let mut expected_paths = [0, 9, 9_999, 10_000, 10_001, 999_999, 1_000_000, 1_234_567]
+            .into_iter()
+            .map(|index| build_segment_output_path(&workspace, index))
+            .collect::<Vec<PathBuf>>();
+        // A four-digit segment left behind by an older version still sorts by its index.
+        expected_paths.insert(2, workspace.join("segment_0042.mp4"));
+        for segment_path in &expected_paths {
+            std::fs::write(segment_path, b"").expect("segment file should be written");
+        }
+        std::fs::write(workspace.join("segments.txt"), b"").expect("concat list should be written");
+
+        let listed_paths = list_workspace_segments(&workspace);
+        let _ = std::fs::remove_dir_all(&workspace);
+
+        assert_eq!(listed_paths, Ok(expected_paths));
+    }
+}
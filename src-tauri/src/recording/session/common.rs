@@ -2,15 +2,19 @@ use std::io::Write;
 use std::sync::mpsc as std_mpsc;
 use std::time::{Duration, Instant};
 
+use tokio::sync::oneshot;
+
 use super::super::model::{
-    CaptureInput, RuntimeCaptureMode, SharedRecordingState, FFMPEG_MODE_SWITCH_TO_BLACK_TIMEOUT,
-    FFMPEG_MODE_SWITCH_TO_WINDOW_TIMEOUT, FFMPEG_STOP_TIMEOUT,
+    CaptureInput, RecordingStopResult, RuntimeCaptureMode, SharedRecordingState,
+    FFMPEG_ENCODER_STALL_STOP_TIMEOUT, FFMPEG_MODE_SWITCH_TO_BLACK_TIMEOUT,
+    FFMPEG_MODE_SWITCH_TO_WINDOW_TIMEOUT, FFMPEG_OUTPUT_LOST_STOP_TIMEOUT, FFMPEG_STOP_TIMEOUT,
 };
 
 pub(super) fn to_runtime_capture_mode(capture_input: &CaptureInput) -> RuntimeCaptureMode {
     match capture_input {
-        CaptureInput::Monitor => RuntimeCaptureMode::Monitor,
+        CaptureInput::Monitor { .. } | CaptureInput::VirtualDesktop => RuntimeCaptureMode::Monitor,
         CaptureInput::Window { .. } => RuntimeCaptureMode::Window,
+        CaptureInput::TestPattern => RuntimeCaptureMode::TestPattern,
     }
 }
 
@@ -19,6 +23,7 @@ pub(super) fn runtime_capture_label(runtime_capture_mode: RuntimeCaptureMode) ->
         RuntimeCaptureMode::Monitor => "monitor",
         RuntimeCaptureMode::Window => "window",
         RuntimeCaptureMode::Black => "black",
+        RuntimeCaptureMode::TestPattern => "test_pattern",
     }
 }
 
@@ -26,14 +31,41 @@ pub(super) fn runtime_capture_label(runtime_capture_mode: RuntimeCaptureMode) ->
 pub(super) enum RequestedTransitionKind {
     ModeSwitchToBlack,
     ModeSwitchToWindow,
+    CaptureRegionChanged,
+    ForegroundWindowChanged,
+    PartSizeLimit,
+    EncoderStall,
+    OutputFolderLost,
 }
 
-pub(super) fn clear_recording_state(state: &SharedRecordingState) {
+/// Resets the shared state and hands back any `stop_recording_and_wait` callers so the
+/// session can answer them once its final events are out.
+pub(super) fn clear_recording_state(
+    state: &SharedRecordingState,
+) -> Vec<oneshot::Sender<RecordingStopResult>> {
     let mut recording_state = state.blocking_write();
     recording_state.is_recording = false;
     recording_state.is_stopping = false;
     recording_state.current_output_path = None;
+    recording_state.started_at = None;
     recording_state.stop_tx = None;
+    recording_state.marker_tone_pending = None;
+    recording_state.folder_lock = None;
+    std::mem::take(&mut recording_state.finalize_waiters)
+}
+
+pub(super) fn notify_finalize_waiters(
+    waiters: Vec<oneshot::Sender<RecordingStopResult>>,
+    output_path: &str,
+    finalized: bool,
+) {
+    for waiter in waiters {
+        // A dropped receiver means the caller gave up waiting; nothing to report.
+        let _ = waiter.send(RecordingStopResult {
+            output_path: output_path.to_string(),
+            finalized,
+        });
+    }
 }
 
 pub(super) fn signal_audio_threads_stop(
@@ -78,10 +110,16 @@ pub(super) fn resolve_stop_timeout(
     if !stop_requested_by_user {
         match requested_transition_kind {
             Some(RequestedTransitionKind::ModeSwitchToBlack) => FFMPEG_MODE_SWITCH_TO_BLACK_TIMEOUT,
-            Some(RequestedTransitionKind::ModeSwitchToWindow) => {
-                FFMPEG_MODE_SWITCH_TO_WINDOW_TIMEOUT
-            }
-            None => FFMPEG_STOP_TIMEOUT,
+            Some(
+                RequestedTransitionKind::ModeSwitchToWindow
+                | RequestedTransitionKind::CaptureRegionChanged
+                | RequestedTransitionKind::ForegroundWindowChanged,
+            ) => FFMPEG_MODE_SWITCH_TO_WINDOW_TIMEOUT,
+            // Closing a full-size part rewrites its index for faststart, which takes as
+            // long as a regular stop.
+            Some(RequestedTransitionKind::PartSizeLimit) | None => FFMPEG_STOP_TIMEOUT,
+            Some(RequestedTransitionKind::EncoderStall) => FFMPEG_ENCODER_STALL_STOP_TIMEOUT,
+            Some(RequestedTransitionKind::OutputFolderLost) => FFMPEG_OUTPUT_LOST_STOP_TIMEOUT,
         }
     } else {
         FFMPEG_STOP_TIMEOUT
@@ -1,25 +1,134 @@
 use tauri::{AppHandle, Emitter};
 
-pub(super) fn emit_recording_stopped(app_handle: &AppHandle) {
-    if let Err(error) = app_handle.emit("recording-stopped", ()) {
+use super::super::ffmpeg::video_encoder_label;
+use super::super::model::{
+    FinalizeHook, PerformanceStatsPayload, RecordingEncoderFallbackPayload,
+    RecordingFinalizedPayload, RecordingHookPayload, RecordingOutputLostPayload,
+    RecordingPartFinalizedPayload, RecordingSessionEventPayload, RecordingWarningPayload,
+};
+
+pub(super) fn emit_recording_stopped(app_handle: &AppHandle, session_id: &str) {
+    let payload = RecordingSessionEventPayload {
+        session_id: session_id.to_string(),
+    };
+    if let Err(error) = app_handle.emit("recording-stopped", payload) {
         tracing::error!("Failed to emit recording-stopped event: {error}");
     }
 }
 
-pub(super) fn emit_recording_finalized(app_handle: &AppHandle, output_path: &str) {
-    if let Err(error) = app_handle.emit("recording-finalized", output_path) {
+pub(super) fn emit_recording_finalized(
+    app_handle: &AppHandle,
+    session_id: &str,
+    output_path: &str,
+) {
+    let payload = RecordingFinalizedPayload {
+        session_id: session_id.to_string(),
+        output_path: output_path.to_string(),
+    };
+    if let Err(error) = app_handle.emit("recording-finalized", payload) {
         tracing::error!("Failed to emit recording-finalized event: {error}");
     }
 }
 
-pub(super) fn emit_recording_warning(app_handle: &AppHandle, warning_message: &str) {
-    if let Err(error) = app_handle.emit("recording-warning", warning_message.to_string()) {
+/// Emits `recording-hook-succeeded`, or `recording-hook-failed` when `error` is set.
+pub(super) fn emit_recording_hook_finished(
+    app_handle: &AppHandle,
+    session_id: &str,
+    output_path: &str,
+    hook: &FinalizeHook,
+    error: Option<String>,
+) {
+    let event_name = if error.is_some() {
+        "recording-hook-failed"
+    } else {
+        "recording-hook-succeeded"
+    };
+    let payload = RecordingHookPayload {
+        session_id: session_id.to_string(),
+        output_path: output_path.to_string(),
+        action: hook.action_label().to_string(),
+        target: hook.target_label(),
+        error,
+    };
+    if let Err(error) = app_handle.emit(event_name, payload) {
+        tracing::error!("Failed to emit {event_name} event: {error}");
+    }
+}
+
+pub(super) fn emit_recording_part_finalized(
+    app_handle: &AppHandle,
+    session_id: &str,
+    output_path: &str,
+    part_number: usize,
+) {
+    let payload = RecordingPartFinalizedPayload {
+        session_id: session_id.to_string(),
+        output_path: output_path.to_string(),
+        part_number,
+    };
+    if let Err(error) = app_handle.emit("recording-part-finalized", payload) {
+        tracing::error!("Failed to emit recording-part-finalized event: {error}");
+    }
+}
+
+pub(super) fn emit_recording_encoder_fallback(
+    app_handle: &AppHandle,
+    session_id: &str,
+    failed_video_encoder: &str,
+    video_encoder: &str,
+) {
+    let payload = RecordingEncoderFallbackPayload {
+        session_id: session_id.to_string(),
+        failed_video_encoder: failed_video_encoder.to_string(),
+        video_encoder: video_encoder.to_string(),
+        video_encoder_label: video_encoder_label(video_encoder).to_string(),
+    };
+    if let Err(error) = app_handle.emit("recording-encoder-fallback", payload) {
+        tracing::error!("Failed to emit recording-encoder-fallback event: {error}");
+    }
+}
+
+pub(super) fn emit_recording_warning(
+    app_handle: &AppHandle,
+    session_id: &str,
+    warning_message: &str,
+) {
+    let payload = RecordingWarningPayload {
+        session_id: session_id.to_string(),
+        message: warning_message.to_string(),
+    };
+    if let Err(error) = app_handle.emit("recording-warning", payload) {
         tracing::error!("Failed to emit recording-warning event: {error}");
     }
 }
 
-pub(super) fn emit_recording_warning_cleared(app_handle: &AppHandle) {
-    if let Err(error) = app_handle.emit("recording-warning-cleared", ()) {
+pub(super) fn emit_recording_output_lost(
+    app_handle: &AppHandle,
+    session_id: &str,
+    output_path: &str,
+    message: &str,
+) {
+    let payload = RecordingOutputLostPayload {
+        session_id: session_id.to_string(),
+        output_path: output_path.to_string(),
+        message: message.to_string(),
+    };
+    if let Err(error) = app_handle.emit("recording-output-lost", payload) {
+        tracing::error!("Failed to emit recording-output-lost event: {error}");
+    }
+}
+
+pub(super) fn emit_recording_warning_cleared(app_handle: &AppHandle, session_id: &str) {
+    let payload = RecordingSessionEventPayload {
+        session_id: session_id.to_string(),
+    };
+    if let Err(error) = app_handle.emit("recording-warning-cleared", payload) {
         tracing::error!("Failed to emit recording-warning-cleared event: {error}");
     }
 }
+
+pub(super) fn emit_performance_stats(app_handle: &AppHandle, payload: PerformanceStatsPayload) {
+    if let Err(error) = app_handle.emit("performance-stats", payload) {
+        tracing::warn!("Failed to emit performance-stats event: {error}");
+    }
+}
@@ -0,0 +1,157 @@
+use std::path::Path;
+use std::thread;
+
+use reqwest::blocking::multipart::Form;
+use reqwest::blocking::Client;
+use tauri::AppHandle;
+
+use super::super::metadata::metadata_sidecar_path;
+use super::super::model::{FinalizeHook, FINALIZE_HOOK_CONNECT_TIMEOUT};
+use super::events::emit_recording_hook_finished;
+
+/// Runs the hook on its own thread so a slow share or endpoint never holds up the session.
+/// Errors only surface as `recording-hook-failed`; the recording is already complete.
+pub(super) fn spawn_finalize_hook(
+    app_handle: AppHandle,
+    hook: FinalizeHook,
+    session_id: String,
+    output_path: String,
+) {
+    let spawn_result = thread::Builder::new()
+        .name("recording-finalize-hook".to_string())
+        .spawn(move || {
+            let result = run_finalize_hook(&hook, &session_id, Path::new(&output_path));
+            match &result {
+                Ok(()) => tracing::info!(
+                    action = hook.action_label(),
+                    target = %hook.target_label(),
+                    output_path = %output_path,
+                    "Recording finalize hook completed"
+                ),
+                Err(error) => tracing::warn!(
+                    action = hook.action_label(),
+                    target = %hook.target_label(),
+                    output_path = %output_path,
+                    "Recording finalize hook failed: {error}"
+                ),
+            }
+            emit_recording_hook_finished(
+                &app_handle,
+                &session_id,
+                &output_path,
+                &hook,
+                result.err(),
+            );
+        });
+
+    if let Err(error) = spawn_result {
+        tracing::error!("Failed to start recording finalize hook thread: {error}");
+    }
+}
+
+fn run_finalize_hook(
+    hook: &FinalizeHook,
+    session_id: &str,
+    recording_path: &Path,
+) -> Result<(), String> {
+    match hook {
+        FinalizeHook::CopyToFolder(destination_folder) => {
+            copy_recording_to_folder(recording_path, destination_folder)
+        }
+        FinalizeHook::HttpPost(url) => post_recording(url, session_id, recording_path),
+    }
+}
+
+fn copy_recording_to_folder(
+    recording_path: &Path,
+    destination_folder: &Path,
+) -> Result<(), String> {
+    std::fs::create_dir_all(destination_folder).map_err(|error| {
+        format!(
+            "Failed to create hook destination folder '{}': {error}",
+            destination_folder.display()
+        )
+    })?;
+
+    let sidecar_path = metadata_sidecar_path(recording_path);
+    let files_to_copy = std::iter::once(recording_path)
+        .chain(sidecar_path.is_file().then_some(sidecar_path.as_path()));
+    for source_path in files_to_copy {
+        let file_name = source_path
+            .file_name()
+            .ok_or_else(|| format!("'{}' has no file name", source_path.display()))?;
+        let destination_path = destination_folder.join(file_name);
+        std::fs::copy(source_path, &destination_path).map_err(|error| {
+            format!(
+                "Failed to copy '{}' to '{}': {error}",
+                source_path.display(),
+                destination_path.display()
+            )
+        })?;
+    }
+
+    Ok(())
+}
+
+/// Sends `session_id`, the metadata sidecar as `metadata` (when there is one) and the video
+/// as `recording` in a single multipart form.
+fn post_recording(url: &str, session_id: &str, recording_path: &Path) -> Result<(), String> {
+    let mut form = Form::new().text("session_id", session_id.to_string());
+    if let Ok(metadata_json) = std::fs::read_to_string(metadata_sidecar_path(recording_path)) {
+        form = form.text("metadata", metadata_json);
+    }
+    let form = form.file("recording", recording_path).map_err(|error| {
+        format!(
+            "Failed to open '{}' for upload: {error}",
+            recording_path.display()
+        )
+    })?;
+
+    let client = Client::builder()
+        .connect_timeout(FINALIZE_HOOK_CONNECT_TIMEOUT)
+        .timeout(None)
+        .build()
+        .map_err(|error| format!("Failed to create upload client: {error}"))?;
+    let response = client
+        .post(url)
+        .multipart(form)
+        .send()
+        .map_err(|error| format!("Failed to upload recording to '{url}': {error}"))?;
+
+    let status = response.status();
+    if !status.is_success() {
+        return Err(format!("Upload endpoint '{url}' responded with {status}"));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::copy_recording_to_folder;
+    use crate::recording::metadata::metadata_sidecar_path;
+
+    #[test]
+    fn copies_recording_with_its_metadata_sidecar() {
+        let test_root = std::env::temp_dir().join(format!(
+            "floorpov_finalize_hook_test_{}",
+            std::process::id()
+        ));
+        let source_folder = test_root.join("recordings");
+        let destination_folder = test_root.join("share");
+        std::fs::create_dir_all(&source_folder).expect("source folder should be created");
+        let recording_path = source_folder.join("screen_recording.mp4");
+        std::fs::write(&recording_path, b"video").expect("recording should be written");
+        std::fs::write(metadata_sidecar_path(&recording_path), b"{}")
+            .expect("sidecar should be written");
+
+        let result = copy_recording_to_folder(&recording_path, &destination_folder);
+        let copied_recording = std::fs::read(destination_folder.join("screen_recording.mp4"));
+        let copied_sidecar = std::fs::read(destination_folder.join("screen_recording.meta.json"));
+        let _ = std::fs::remove_dir_all(&test_root);
+
+        assert_eq!(result, Ok(()));
+        assert_eq!(copied_recording.ok().as_deref(), Some(&b"video"[..]));
+        assert_eq!(copied_sidecar.ok().as_deref(), Some(&b"{}"[..]));
+    }
+}
@@ -1,33 +1,52 @@
 mod common;
 mod events;
+mod finalize_hook;
+mod parts;
 mod segment_runner;
 
 use std::path::{Path, PathBuf};
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use tauri::AppHandle;
 use tokio::sync::mpsc;
 
-use super::ffmpeg::select_video_encoder;
+use super::ffmpeg::{
+    list_available_video_encoders, next_fallback_video_encoder, resolve_pixel_format,
+    resolve_video_encoder_preference, select_audio_encoder, select_video_encoder,
+    video_encoder_initializes, video_encoder_label, video_encoder_preset,
+};
+use super::metadata::{
+    update_recording_metadata, RecordingAudioSyncMetadata, RecordingCaptureQualityMetadata,
+    RecordingSegmentFrameRateMetadata, RecordingSegmentTimelineMetadata,
+};
 use super::model::{
-    RecordingSessionConfig, RuntimeCaptureMode, SegmentConfig, SegmentTransition,
-    SharedRecordingState, WindowCaptureAvailability, WINDOW_CAPTURE_UNAVAILABLE_WARNING,
+    GpuVendor, RecordingSessionConfig, RuntimeCaptureMode, SegmentConfig, SegmentTransition,
+    SharedRecordingState, WindowCaptureAvailability, AUDIO_VIDEO_DURATION_MISMATCH_THRESHOLD_SECS,
+    ENCODER_STALL_RESTART_WARNING, GPU_ADAPTER_MISMATCH_WARNING, LOW_ACHIEVED_FRAME_RATE_RATIO,
+    MAX_ENCODER_STALL_RESTARTS_PER_SESSION, MAX_SEGMENTS_PER_PART,
+    OUTPUT_DRIVE_DISCONNECTED_WARNING, WINDOW_CAPTURE_UNAVAILABLE_WARNING,
 };
 use super::segments::{
-    build_segment_output_path, cleanup_segment_workspace, create_segment_workspace,
-    finalize_segmented_recording,
+    build_segment_output_path, cleanup_segment_workspace, count_segments_before_trailing_black,
+    create_segment_workspace, finalize_segmented_recording, probe_stream_duration_seconds,
+    read_mp4_stream_durations,
 };
 use super::window_capture::{
     evaluate_window_capture_availability, resolve_capture_dimensions,
-    resolve_window_capture_region, warning_message_for_window_capture,
+    resolve_hybrid_capture_gpu_vendor, resolve_window_capture_region,
+    warning_message_for_window_capture,
 };
 
-use self::common::{clear_recording_state, runtime_capture_label, to_runtime_capture_mode};
+use self::common::{
+    clear_recording_state, notify_finalize_waiters, runtime_capture_label, to_runtime_capture_mode,
+};
 use self::events::{
-    emit_recording_finalized, emit_recording_stopped, emit_recording_warning,
-    emit_recording_warning_cleared,
+    emit_recording_encoder_fallback, emit_recording_finalized, emit_recording_output_lost,
+    emit_recording_stopped, emit_recording_warning, emit_recording_warning_cleared,
 };
+use self::finalize_hook::spawn_finalize_hook;
+use self::parts::{PartFinalizer, PartRollover, PartSegments};
 use self::segment_runner::run_ffmpeg_recording_segment;
 
 pub(crate) fn spawn_ffmpeg_recording_task(
@@ -38,14 +57,49 @@ pub(crate) fn spawn_ffmpeg_recording_task(
 ) {
     thread::spawn(move || {
         let mut capture_input = session_config.capture_input;
-        let (video_encoder, encoder_preset) = select_video_encoder(
+        let hybrid_capture_gpu_vendor = resolve_hybrid_capture_gpu_vendor(&capture_input);
+        let (mut video_encoder, mut encoder_preset) = select_video_encoder(
             &session_config.ffmpeg_binary_path,
             &session_config.video_quality,
-            &session_config.video_encoder_preference,
+            resolve_video_encoder_preference(
+                &session_config.video_encoder_preference,
+                &session_config.encoder_gpu,
+                hybrid_capture_gpu_vendor,
+            ),
         );
+        if let (Some(capture_gpu_vendor), Some(encoder_gpu_vendor)) = (
+            hybrid_capture_gpu_vendor,
+            GpuVendor::from_encoder(&video_encoder),
+        ) {
+            if capture_gpu_vendor != encoder_gpu_vendor {
+                tracing::warn!(
+                    capture_gpu_vendor = capture_gpu_vendor.label(),
+                    encoder_gpu_vendor = encoder_gpu_vendor.label(),
+                    video_encoder,
+                    "Capture and encode run on different GPUs"
+                );
+                emit_recording_warning(
+                    &app_handle,
+                    &session_config.session_id,
+                    GPU_ADAPTER_MISMATCH_WARNING,
+                );
+            }
+        }
+        // Fixed for the whole session: every encoder in the fallback chain writes `yuv420p`,
+        // and only libx264, which has no fallback, writes 10-bit.
+        let pixel_format = resolve_pixel_format(&video_encoder, &session_config.pixel_format);
+        let audio_encoder = if session_config.include_system_audio {
+            select_audio_encoder(
+                &session_config.ffmpeg_binary_path,
+                session_config.audio_codec,
+            )
+        } else {
+            "aac"
+        };
         let mut runtime_capture_mode = to_runtime_capture_mode(&capture_input);
         let capture_target = capture_input.target_label();
         let (capture_width, capture_height) = resolve_capture_dimensions(&capture_input);
+        let bitrate = session_config.bitrate;
 
         if matches!(runtime_capture_mode, RuntimeCaptureMode::Window) {
             let initial_availability = evaluate_window_capture_availability(&capture_input);
@@ -63,19 +117,29 @@ pub(crate) fn spawn_ffmpeg_recording_task(
             if matches!(runtime_capture_mode, RuntimeCaptureMode::Black) {
                 emit_recording_warning(
                     &app_handle,
+                    &session_config.session_id,
                     startup_warning.unwrap_or(WINDOW_CAPTURE_UNAVAILABLE_WARNING),
                 );
             }
         }
 
+        // Size-limited recordings need segments even for monitor capture so each part can
+        // be closed and handed off while the session keeps running.
         let segment_workspace =
-            if matches!(capture_input, super::model::CaptureInput::Window { .. }) {
+            if matches!(capture_input, super::model::CaptureInput::Window { .. })
+                || session_config.max_part_bytes.is_some()
+            {
                 match create_segment_workspace(&session_config.output_path) {
                     Ok(workspace) => Some(workspace),
                     Err(error) => {
                         tracing::error!("{error}");
-                        clear_recording_state(&state);
-                        emit_recording_stopped(&app_handle);
+                        let finalize_waiters = clear_recording_state(&state);
+                        emit_recording_stopped(&app_handle, &session_config.session_id);
+                        notify_finalize_waiters(
+                            finalize_waiters,
+                            &session_config.output_path,
+                            false,
+                        );
                         return;
                     }
                 }
@@ -89,19 +153,33 @@ pub(crate) fn spawn_ffmpeg_recording_task(
             video_encoder_preference = %session_config.video_encoder_preference,
             requested_frame_rate = session_config.requested_frame_rate,
             output_frame_rate = session_config.output_frame_rate,
-            bitrate = session_config.bitrate,
+            bitrate,
             capture_source = runtime_capture_label(runtime_capture_mode),
             capture_target = %capture_target,
             include_system_audio = session_config.include_system_audio,
             enable_diagnostics = session_config.enable_diagnostics,
             video_encoder,
+            pixel_format,
             "Starting FFmpeg recording"
         );
 
-        let mut segment_paths: Vec<PathBuf> = Vec::new();
-        let mut segment_durations: Vec<Duration> = Vec::new();
+        let mut part = PartSegments::default();
+        let mut part_rollover = segment_workspace.as_ref().map(|workspace| {
+            PartRollover::new(PartFinalizer {
+                app_handle: app_handle.clone(),
+                ffmpeg_binary_path: session_config.ffmpeg_binary_path.clone(),
+                segment_workspace: workspace.clone(),
+                session_id: session_config.session_id.clone(),
+                session_output_path: session_config.output_path.clone(),
+                requested_frame_rate: session_config.requested_frame_rate,
+                finalize_hook: session_config.finalize_hook.clone(),
+            })
+        });
+        let session_started_at = Instant::now();
         let mut segment_index: usize = 0;
         let mut consecutive_segment_failures = 0u32;
+        let mut encoder_stall_restarts = 0u32;
+        let mut output_folder_lost = false;
 
         loop {
             let segment_output_path = if let Some(workspace) = &segment_workspace {
@@ -110,28 +188,73 @@ pub(crate) fn spawn_ffmpeg_recording_task(
                 PathBuf::from(&session_config.output_path)
             };
 
+            let part_byte_budget = session_config
+                .max_part_bytes
+                .filter(|_| {
+                    !part_rollover
+                        .as_ref()
+                        .is_some_and(PartRollover::is_backing_off)
+                })
+                .map(|max_part_bytes| max_part_bytes.saturating_sub(part.bytes_written));
             let segment_config = SegmentConfig {
+                session_id: &session_config.session_id,
                 ffmpeg_binary_path: &session_config.ffmpeg_binary_path,
                 runtime_capture_mode,
                 output_path: &segment_output_path,
                 video_quality: &session_config.video_quality,
                 requested_frame_rate: session_config.requested_frame_rate,
                 output_frame_rate: session_config.output_frame_rate,
-                bitrate: session_config.bitrate,
+                bitrate,
                 include_system_audio: session_config.include_system_audio,
                 enable_diagnostics: session_config.enable_diagnostics,
                 video_encoder: &video_encoder,
                 encoder_preset: encoder_preset.as_deref(),
+                pixel_format,
                 capture_width,
                 capture_height,
+                capture_cursor: session_config.capture_cursor,
+                frame_sync: session_config.frame_sync,
+                rate_control: session_config.rate_control,
+                vbr_quality: session_config.vbr_quality,
+                bypass_audio_limiter: session_config.bypass_audio_limiter,
+                audio_offset_ms: session_config.audio_offset_ms,
+                downmix_audio_mono: session_config.downmix_audio_mono,
+                wait_for_audio_connection: session_config.wait_for_audio_connection,
+                audio_channel_map_filter: session_config.audio_channel_map_filter.as_deref(),
+                audio_resample_sync: session_config.audio_resample_sync,
+                audio_encoder,
+                audio_bitrate: session_config.audio_bitrate,
+                system_audio_device_id: session_config.system_audio_device_id.as_deref(),
+                system_audio_session_process_id: session_config.system_audio_session_process_id,
+                follow_foreground: session_config.follow_foreground,
+                window_status_poll_interval: session_config.window_status_poll_interval,
+                letterbox_color: session_config.letterbox_color.as_deref(),
+                fragmented_mp4: session_config.fragmented_mp4,
+                emit_performance_stats: session_config.emit_performance_stats,
+                part_byte_budget,
+                preallocate_bytes: session_config.preallocate_bytes.map(|preallocate_bytes| {
+                    part_byte_budget
+                        .map_or(preallocate_bytes, |budget| budget.min(preallocate_bytes))
+                }),
+                marker_tone_pending: session_config.marker_tone_pending.as_ref(),
+                restart_on_encoder_stall: segment_workspace.is_some()
+                    && encoder_stall_restarts < MAX_ENCODER_STALL_RESTARTS_PER_SESSION,
             };
 
+            let segment_started_at_seconds = session_started_at.elapsed().as_secs_f64();
             let run_result = run_ffmpeg_recording_segment(
                 &app_handle,
                 &segment_config,
                 &mut capture_input,
                 &mut stop_rx,
             );
+            part.timeline.push(RecordingSegmentTimelineMetadata {
+                segment_index,
+                capture_mode: runtime_capture_label(runtime_capture_mode).to_string(),
+                started_at_seconds: segment_started_at_seconds,
+                duration_seconds: run_result.wall_clock_duration.as_secs_f64(),
+                force_killed: run_result.force_killed,
+            });
 
             if run_result.output_written {
                 if run_result.force_killed {
@@ -142,11 +265,73 @@ pub(crate) fn spawn_ffmpeg_recording_task(
                          Consider increasing FFMPEG_STOP_TIMEOUT if this happens on normal stops."
                     );
                 } else {
-                    segment_paths.push(segment_output_path);
-                    segment_durations.push(run_result.wall_clock_duration);
+                    part.bytes_written += segment_output_path
+                        .metadata()
+                        .map(|metadata| metadata.len())
+                        .unwrap_or(0);
+                    part.paths.push(segment_output_path);
+                    part.durations.push(run_result.wall_clock_duration);
+                    part.modes.push(runtime_capture_mode);
+
+                    // Black placeholder and test pattern segments come from a synthetic
+                    // source, so their frame rate says nothing about capture performance.
+                    let frame_rate_sample = run_result
+                        .frame_stats
+                        .filter(|_| {
+                            !matches!(
+                                runtime_capture_mode,
+                                RuntimeCaptureMode::Black | RuntimeCaptureMode::TestPattern
+                            )
+                        })
+                        .and_then(|stats| {
+                            Some((stats.captured_frame_rate()?, stats.encoded_seconds))
+                        });
+                    if let Some((achieved_frame_rate, duration_seconds)) = frame_rate_sample {
+                        tracing::info!(
+                            segment_index,
+                            requested_frame_rate = session_config.requested_frame_rate,
+                            achieved_frame_rate,
+                            "Recording segment frame rate"
+                        );
+                        part.frame_rates.push(RecordingSegmentFrameRateMetadata {
+                            segment_index,
+                            achieved_frame_rate,
+                            duration_seconds,
+                        });
+                    }
+                }
+            }
+
+            if let Some(part_rollover) = part_rollover.as_mut() {
+                part_rollover.poll(&mut part);
+                let part_segment_limit_reached = part.paths.len() >= MAX_SEGMENTS_PER_PART;
+                if part_segment_limit_reached && !run_result.part_limit_reached {
+                    tracing::info!(
+                        segment_count = part.paths.len(),
+                        "Recording part reached its segment limit; rolling over to a new part"
+                    );
+                }
+                // The part is finalized on a worker thread while the next segment records.
+                if run_result.part_limit_reached || part_segment_limit_reached {
+                    part_rollover.roll_over(&mut part);
                 }
             }
 
+            output_folder_lost |= run_result.output_folder_lost;
+            if run_result.encoder_stalled {
+                encoder_stall_restarts += 1;
+                tracing::warn!(
+                    encoder_stall_restarts,
+                    max_encoder_stall_restarts = MAX_ENCODER_STALL_RESTARTS_PER_SESSION,
+                    "Restarted recording segment after a video encoder stall"
+                );
+                emit_recording_warning(
+                    &app_handle,
+                    &session_config.session_id,
+                    ENCODER_STALL_RESTART_WARNING,
+                );
+            }
+
             if run_result.ffmpeg_succeeded {
                 consecutive_segment_failures = 0;
             } else if matches!(run_result.transition, SegmentTransition::Switch(_)) {
@@ -166,6 +351,75 @@ pub(crate) fn spawn_ffmpeg_recording_task(
                 break;
             }
 
+            if run_result.encoder_init_failed {
+                let available_encoders =
+                    list_available_video_encoders(&session_config.ffmpeg_binary_path);
+                let mut fallback_video_encoder =
+                    next_fallback_video_encoder(&video_encoder, &available_encoders);
+                while let Some(candidate) = fallback_video_encoder {
+                    if video_encoder_initializes(
+                        &session_config.ffmpeg_binary_path,
+                        candidate,
+                        pixel_format,
+                    ) {
+                        break;
+                    }
+                    tracing::warn!(
+                        video_encoder = candidate,
+                        "Skipping fallback video encoder that failed to initialize"
+                    );
+                    fallback_video_encoder =
+                        next_fallback_video_encoder(candidate, &available_encoders);
+                }
+                let Some(next_video_encoder) = fallback_video_encoder else {
+                    tracing::error!(
+                        video_encoder,
+                        "Stopping recording; no video encoder left to fall back to"
+                    );
+                    break;
+                };
+
+                // Segments of different encoders cannot be joined by the `-c copy` concat,
+                // so the new encoder starts a new part.
+                if !part.paths.is_empty() {
+                    if let Some(part_rollover) = part_rollover.as_mut() {
+                        part_rollover.roll_over(&mut part);
+                    }
+                    if !part.paths.is_empty() {
+                        tracing::error!(
+                            video_encoder,
+                            "Stopping recording; the current part could not be closed before \
+                             switching video encoders"
+                        );
+                        break;
+                    }
+                }
+
+                tracing::warn!(
+                    failed_video_encoder = %video_encoder,
+                    video_encoder = next_video_encoder,
+                    "Falling back to the next video encoder"
+                );
+                emit_recording_encoder_fallback(
+                    &app_handle,
+                    &session_config.session_id,
+                    &video_encoder,
+                    next_video_encoder,
+                );
+                emit_recording_warning(
+                    &app_handle,
+                    &session_config.session_id,
+                    &format!(
+                        "{} failed to start, so recording continues with {}.",
+                        video_encoder_label(&video_encoder),
+                        video_encoder_label(next_video_encoder)
+                    ),
+                );
+                video_encoder = next_video_encoder.to_string();
+                encoder_preset =
+                    video_encoder_preset(&video_encoder, &session_config.video_quality);
+            }
+
             match run_result.transition {
                 SegmentTransition::Stop => {
                     break;
@@ -175,7 +429,10 @@ pub(crate) fn spawn_ffmpeg_recording_task(
                     segment_index = segment_index.saturating_add(1);
                 }
                 SegmentTransition::RestartSameMode => {
-                    if matches!(runtime_capture_mode, RuntimeCaptureMode::Monitor) {
+                    if matches!(
+                        runtime_capture_mode,
+                        RuntimeCaptureMode::Monitor | RuntimeCaptureMode::TestPattern
+                    ) {
                         break;
                     }
                     segment_index = segment_index.saturating_add(1);
@@ -184,28 +441,81 @@ pub(crate) fn spawn_ffmpeg_recording_task(
             }
         }
 
-        let finalized_successfully = if let Some(workspace) = &segment_workspace {
-            let finalize_result = finalize_segmented_recording(
-                &session_config.ffmpeg_binary_path,
-                workspace,
-                &segment_paths,
-                &segment_durations,
-                &session_config.output_path,
-            );
+        let mut final_output_path = session_config.output_path.clone();
+        let finalized_successfully = if let (Some(workspace), Some(part_rollover)) =
+            (&segment_workspace, part_rollover.as_mut())
+        {
+            part_rollover.wait(&mut part);
+            let produced_segments = !part.paths.is_empty();
+            let kept_segment_count = if session_config.trim_trailing_black {
+                count_segments_before_trailing_black(&part.modes)
+            } else {
+                part.paths.len()
+            };
+            if kept_segment_count < part.paths.len() {
+                tracing::info!(
+                    trimmed_segment_count = part.paths.len() - kept_segment_count,
+                    trimmed_seconds = part.durations[kept_segment_count..]
+                        .iter()
+                        .sum::<Duration>()
+                        .as_secs_f64(),
+                    "Trimming trailing black segments from recording"
+                );
+            }
+            part.paths.truncate(kept_segment_count);
+            part.durations.truncate(kept_segment_count);
+            part.modes.truncate(kept_segment_count);
 
-            let was_successful = match finalize_result {
-                Ok(()) => true,
-                Err(error) => {
-                    if !segment_paths.is_empty() {
-                        tracing::error!("Failed to finalize segmented recording: {error}");
-                    } else {
-                        tracing::warn!("No recording segments were produced before stop");
+            let was_successful = if part_rollover.has_finalized_parts() {
+                // Once a session was split, the last part is a `_partN` file like the others
+                // and the recording ends with it.
+                let last_part_finalized = part.paths.is_empty()
+                    || match part_rollover.finalize_last_part(std::mem::take(&mut part)) {
+                        Ok(()) => true,
+                        Err(failed_part) => {
+                            tracing::error!(
+                                segment_count = failed_part.paths.len(),
+                                "Failed to finalize the last recording part"
+                            );
+                            false
+                        }
+                    };
+                if let Some(last_output_path) = part_rollover.last_output_path() {
+                    final_output_path = last_output_path.to_string();
+                }
+                part_rollover.split_session_markers();
+                last_part_finalized
+            } else {
+                match finalize_segmented_recording(
+                    &session_config.ffmpeg_binary_path,
+                    workspace,
+                    &part.paths,
+                    &part.durations,
+                    &session_config.output_path,
+                ) {
+                    Ok(()) => true,
+                    Err(error) => {
+                        if produced_segments {
+                            tracing::error!("Failed to finalize segmented recording: {error}");
+                        } else {
+                            tracing::warn!("No recording segments were produced before stop");
+                        }
+                        false
                     }
-                    false
                 }
             };
 
-            cleanup_segment_workspace(workspace);
+            // Segments that could not be finalized stay in the workspace, where orphaned
+            // recording recovery picks them up, e.g. once a lost output drive is back.
+            if !was_successful && produced_segments {
+                tracing::warn!(
+                    segment_workspace = %workspace.display(),
+                    output_folder_lost,
+                    "Keeping recording segments for orphaned recording recovery"
+                );
+            } else {
+                cleanup_segment_workspace(workspace);
+            }
             was_successful
         } else {
             let output_file = Path::new(&session_config.output_path);
@@ -217,11 +527,158 @@ pub(crate) fn spawn_ffmpeg_recording_task(
         };
 
         if finalized_successfully {
-            emit_recording_finalized(&app_handle, &session_config.output_path);
+            // Parts of a split recording already got their sidecar and hook when each
+            // part was finalized.
+            let recording_split = part_rollover
+                .as_ref()
+                .is_some_and(PartRollover::has_finalized_parts);
+            if !recording_split {
+                persist_part_metadata(
+                    &session_config.ffmpeg_binary_path,
+                    Path::new(&session_config.output_path),
+                    &session_config.session_id,
+                    session_config.requested_frame_rate,
+                    &part,
+                    0.0,
+                );
+                crate::settings::record_recording_written(Path::new(&session_config.output_path));
+                if let Some(hook) = session_config.finalize_hook.clone() {
+                    spawn_finalize_hook(
+                        app_handle.clone(),
+                        hook,
+                        session_config.session_id.clone(),
+                        session_config.output_path.clone(),
+                    );
+                }
+            }
+            emit_recording_finalized(&app_handle, &session_config.session_id, &final_output_path);
         }
 
-        emit_recording_warning_cleared(&app_handle);
-        clear_recording_state(&state);
-        emit_recording_stopped(&app_handle);
+        if output_folder_lost {
+            emit_recording_output_lost(
+                &app_handle,
+                &session_config.session_id,
+                &session_config.output_path,
+                OUTPUT_DRIVE_DISCONNECTED_WARNING,
+            );
+        }
+        emit_recording_warning_cleared(&app_handle, &session_config.session_id);
+        let finalize_waiters = clear_recording_state(&state);
+        emit_recording_stopped(&app_handle, &session_config.session_id);
+        notify_finalize_waiters(finalize_waiters, &final_output_path, finalized_successfully);
     });
 }
+
+/// Records a finished file's capture quality, segment timeline and audio sync in its
+/// sidecar. Timeline entries are timed from `part_started_at_seconds`. The sync check
+/// runs last so a slow fallback probe never delays the other fields.
+fn persist_part_metadata(
+    ffmpeg_binary_path: &Path,
+    output_path: &Path,
+    session_id: &str,
+    requested_frame_rate: u32,
+    part: &PartSegments,
+    part_started_at_seconds: f64,
+) {
+    if let Some(capture_quality) = RecordingCaptureQualityMetadata::from_segments(
+        requested_frame_rate,
+        part.frame_rates.clone(),
+        LOW_ACHIEVED_FRAME_RATE_RATIO,
+    ) {
+        if capture_quality.below_requested_frame_rate {
+            tracing::warn!(
+                requested_frame_rate = capture_quality.requested_frame_rate,
+                achieved_frame_rate = capture_quality.achieved_frame_rate,
+                "Recording captured fewer frames than requested; the system could not keep up"
+            );
+        }
+        if let Err(error) = update_recording_metadata(output_path, |metadata| {
+            metadata.capture_quality = Some(capture_quality);
+            Ok(())
+        }) {
+            tracing::warn!("Failed to persist recording capture quality: {error}");
+        }
+    }
+
+    let segment_timeline: Vec<RecordingSegmentTimelineMetadata> = part
+        .timeline
+        .iter()
+        .cloned()
+        .map(|mut segment| {
+            segment.started_at_seconds -= part_started_at_seconds;
+            segment
+        })
+        .collect();
+    let black_seconds: f64 = segment_timeline
+        .iter()
+        .filter(|segment| segment.capture_mode == "black")
+        .map(|segment| segment.duration_seconds)
+        .sum();
+    tracing::info!(
+        output_path = %output_path.display(),
+        segment_count = segment_timeline.len(),
+        black_seconds,
+        "Recording segment timeline"
+    );
+    if let Err(error) = update_recording_metadata(output_path, |metadata| {
+        metadata.segment_timeline = segment_timeline;
+        metadata.session_id = Some(session_id.to_string());
+        Ok(())
+    }) {
+        tracing::warn!("Failed to persist recording segment timeline: {error}");
+    }
+
+    check_audio_video_sync(ffmpeg_binary_path, output_path);
+}
+
+/// Compares the finalized file's audio and video stream lengths and records the result
+/// in the sidecar, warning when they differ enough to hint at a sync problem. Lengths
+/// come from the MP4 header; only fragmented files, whose header has none, are remuxed
+/// to measure them.
+fn check_audio_video_sync(ffmpeg_binary_path: &Path, output_path: &Path) {
+    let header_durations = read_mp4_stream_durations(output_path)
+        .map_err(|error| {
+            tracing::warn!("Failed to read recording stream durations from header: {error}");
+        })
+        .unwrap_or((None, None));
+    let probe = |stream_specifier| {
+        probe_stream_duration_seconds(ffmpeg_binary_path, output_path, stream_specifier)
+            .map_err(|error| {
+                tracing::warn!("Failed to probe {stream_specifier} stream duration: {error}");
+            })
+            .ok()
+            .flatten()
+    };
+    let (Some(video_duration_seconds), Some(audio_duration_seconds)) = (
+        header_durations.0.or_else(|| probe("v:0")),
+        header_durations.1.or_else(|| probe("a:0")),
+    ) else {
+        return;
+    };
+
+    let audio_sync = RecordingAudioSyncMetadata::from_stream_durations(
+        video_duration_seconds,
+        audio_duration_seconds,
+        AUDIO_VIDEO_DURATION_MISMATCH_THRESHOLD_SECS,
+    );
+    if audio_sync.mismatch_detected {
+        tracing::warn!(
+            video_duration_seconds,
+            audio_duration_seconds,
+            mismatch_seconds = audio_sync.mismatch_seconds,
+            "Recording audio and video stream lengths differ; playback may be out of sync"
+        );
+    } else {
+        tracing::info!(
+            video_duration_seconds,
+            audio_duration_seconds,
+            "Recording audio and video stream lengths match"
+        );
+    }
+    if let Err(error) = update_recording_metadata(output_path, |metadata| {
+        metadata.audio_sync = Some(audio_sync);
+        Ok(())
+    }) {
+        tracing::warn!("Failed to persist recording audio sync check: {error}");
+    }
+}
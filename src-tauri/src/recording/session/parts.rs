@@ -0,0 +1,280 @@
+use std::path::{Path, PathBuf};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use tauri::AppHandle;
+
+use super::super::metadata::{
+    delete_recording_metadata, read_recording_metadata, update_recording_metadata,
+    RecordingSegmentFrameRateMetadata, RecordingSegmentTimelineMetadata,
+};
+use super::super::model::{FinalizeHook, RuntimeCaptureMode, PART_FINALIZE_RETRY_BACKOFF};
+use super::super::segments::{
+    build_part_output_path, finalize_segmented_recording, remove_segment_files,
+};
+use super::events::emit_recording_part_finalized;
+use super::finalize_hook::spawn_finalize_hook;
+use super::persist_part_metadata;
+
+/// Segments recorded into the part that is still open.
+#[derive(Default)]
+pub(super) struct PartSegments {
+    pub(super) paths: Vec<PathBuf>,
+    pub(super) durations: Vec<Duration>,
+    pub(super) modes: Vec<RuntimeCaptureMode>,
+    pub(super) frame_rates: Vec<RecordingSegmentFrameRateMetadata>,
+    /// Every segment of the part, including discarded ones.
+    pub(super) timeline: Vec<RecordingSegmentTimelineMetadata>,
+    pub(super) bytes_written: u64,
+}
+
+impl PartSegments {
+    /// Session time the part's first segment started at.
+    pub(super) fn started_at_seconds(&self) -> f64 {
+        self.timeline
+            .first()
+            .map_or(0.0, |segment| segment.started_at_seconds)
+    }
+
+    fn duration_seconds(&self) -> f64 {
+        self.timeline.last().map_or(0.0, |segment| {
+            segment.started_at_seconds + segment.duration_seconds - self.started_at_seconds()
+        })
+    }
+
+    /// Puts the segments of a part that failed to finalize in front of this part's, so
+    /// they are retried together with it.
+    fn prepend(&mut self, mut earlier: PartSegments) {
+        earlier.paths.append(&mut self.paths);
+        earlier.durations.append(&mut self.durations);
+        earlier.modes.append(&mut self.modes);
+        earlier.frame_rates.append(&mut self.frame_rates);
+        earlier.timeline.append(&mut self.timeline);
+        earlier.bytes_written = earlier.bytes_written.saturating_add(self.bytes_written);
+        *self = earlier;
+    }
+}
+
+pub(super) struct FinalizedPart {
+    pub(super) output_path: String,
+    started_at_seconds: f64,
+    duration_seconds: f64,
+}
+
+/// Everything a worker thread needs to turn a closed part into a `_partN` file.
+#[derive(Clone)]
+pub(super) struct PartFinalizer {
+    pub(super) app_handle: AppHandle,
+    pub(super) ffmpeg_binary_path: PathBuf,
+    pub(super) segment_workspace: PathBuf,
+    pub(super) session_id: String,
+    /// The session's output path; parts are named after it and its sidecar collects the
+    /// session's combat log markers.
+    pub(super) session_output_path: String,
+    pub(super) requested_frame_rate: u32,
+    pub(super) finalize_hook: Option<FinalizeHook>,
+}
+
+impl PartFinalizer {
+    /// Concatenates the part into its `_partN` file and gives it its own sidecar. On failure
+    /// the segments are handed back untouched.
+    fn finalize(
+        &self,
+        part_number: usize,
+        part: PartSegments,
+    ) -> Result<FinalizedPart, Box<PartSegments>> {
+        let output_path = build_part_output_path(&self.session_output_path, part_number)
+            .to_string_lossy()
+            .to_string();
+        if let Err(error) = finalize_segmented_recording(
+            &self.ffmpeg_binary_path,
+            &self.segment_workspace,
+            &part.paths,
+            &part.durations,
+            &output_path,
+        ) {
+            tracing::error!(part_number, "Failed to finalize recording part: {error}");
+            return Err(Box::new(part));
+        }
+
+        tracing::info!(
+            part_number,
+            part_output_path = %output_path,
+            part_bytes_written = part.bytes_written,
+            "Finalized recording part"
+        );
+        let finalized_part = FinalizedPart {
+            output_path,
+            started_at_seconds: part.started_at_seconds(),
+            duration_seconds: part.duration_seconds(),
+        };
+        persist_part_metadata(
+            &self.ffmpeg_binary_path,
+            Path::new(&finalized_part.output_path),
+            &self.session_id,
+            self.requested_frame_rate,
+            &part,
+            finalized_part.started_at_seconds,
+        );
+        // Markers flushed after this point are added once the session ends; the hook
+        // gets the part with what is known now.
+        self.copy_session_markers(&finalized_part, finalized_part.duration_seconds);
+        remove_segment_files(&part.paths);
+
+        crate::settings::record_recording_written(Path::new(&finalized_part.output_path));
+        emit_recording_part_finalized(
+            &self.app_handle,
+            &self.session_id,
+            &finalized_part.output_path,
+            part_number,
+        );
+        if let Some(hook) = self.finalize_hook.clone() {
+            spawn_finalize_hook(
+                self.app_handle.clone(),
+                hook,
+                self.session_id.clone(),
+                finalized_part.output_path.clone(),
+            );
+        }
+        Ok(finalized_part)
+    }
+
+    /// Returns false when the session sidecar exists but the part's could not be updated.
+    fn copy_session_markers(&self, part: &FinalizedPart, part_duration_seconds: f64) -> bool {
+        let session_metadata = match read_recording_metadata(Path::new(&self.session_output_path)) {
+            Ok(Some(session_metadata)) => session_metadata,
+            Ok(None) => return true,
+            Err(error) => {
+                tracing::warn!("Failed to read session markers for recording part: {error}");
+                return false;
+            }
+        };
+
+        match update_recording_metadata(Path::new(&part.output_path), |metadata| {
+            metadata.apply_part_markers(
+                &session_metadata,
+                part.started_at_seconds,
+                part_duration_seconds,
+            );
+            Ok(())
+        }) {
+            Ok(()) => true,
+            Err(error) => {
+                tracing::warn!(
+                    part_output_path = %part.output_path,
+                    "Failed to persist recording part markers: {error}"
+                );
+                false
+            }
+        }
+    }
+}
+
+/// Hands full parts to a worker thread so the next segment starts without waiting for the
+/// concat. At most one part is finalized at a time, which keeps part numbers in order.
+pub(super) struct PartRollover {
+    finalizer: PartFinalizer,
+    pending: Option<JoinHandle<Result<FinalizedPart, Box<PartSegments>>>>,
+    finalized: Vec<FinalizedPart>,
+    retry_at: Option<Instant>,
+}
+
+impl PartRollover {
+    pub(super) fn new(finalizer: PartFinalizer) -> Self {
+        Self {
+            finalizer,
+            pending: None,
+            finalized: Vec::new(),
+            retry_at: None,
+        }
+    }
+
+    /// True while rollover is backing off after a failed finalize; no part limit applies
+    /// meanwhile, so a concat that keeps failing is not retried after every segment.
+    pub(super) fn is_backing_off(&self) -> bool {
+        self.retry_at
+            .is_some_and(|retry_at| Instant::now() < retry_at)
+    }
+
+    pub(super) fn has_finalized_parts(&self) -> bool {
+        !self.finalized.is_empty()
+    }
+
+    pub(super) fn last_output_path(&self) -> Option<&str> {
+        self.finalized.last().map(|part| part.output_path.as_str())
+    }
+
+    /// Collects the worker's result if it is done, without blocking.
+    pub(super) fn poll(&mut self, open_part: &mut PartSegments) {
+        if self.pending.as_ref().is_some_and(JoinHandle::is_finished) {
+            self.wait(open_part);
+        }
+    }
+
+    /// Blocks until the part being finalized is done. A part that failed goes back in
+    /// front of the open one, so its segments are not lost, and rollover backs off.
+    pub(super) fn wait(&mut self, open_part: &mut PartSegments) {
+        let Some(pending) = self.pending.take() else {
+            return;
+        };
+        match pending.join() {
+            Ok(Ok(finalized_part)) => self.finalized.push(finalized_part),
+            Ok(Err(failed_part)) => {
+                open_part.prepend(*failed_part);
+                self.retry_at = Some(Instant::now() + PART_FINALIZE_RETRY_BACKOFF);
+            }
+            Err(error) => {
+                tracing::error!("Recording part finalize thread panicked: {error:?}");
+            }
+        }
+    }
+
+    /// Closes the open part and finalizes it on a worker thread.
+    pub(super) fn roll_over(&mut self, open_part: &mut PartSegments) {
+        self.wait(open_part);
+        if open_part.paths.is_empty() || self.is_backing_off() {
+            return;
+        }
+
+        let part = std::mem::take(open_part);
+        let part_number = self.finalized.len() + 1;
+        let finalizer = self.finalizer.clone();
+        self.pending = Some(thread::spawn(move || finalizer.finalize(part_number, part)));
+    }
+
+    /// Finalizes the part still open when the session stops, on the calling thread.
+    pub(super) fn finalize_last_part(
+        &mut self,
+        part: PartSegments,
+    ) -> Result<(), Box<PartSegments>> {
+        let part_number = self.finalized.len() + 1;
+        let finalized_part = self.finalizer.finalize(part_number, part)?;
+        self.finalized.push(finalized_part);
+        Ok(())
+    }
+
+    /// Moves the session sidecar's combat log markers into the parts now that the watch
+    /// has flushed them all. Markers in the gap between two parts go to the earlier one.
+    pub(super) fn split_session_markers(&self) {
+        let mut all_copied = true;
+        for (index, part) in self.finalized.iter().enumerate() {
+            let part_duration_seconds = self
+                .finalized
+                .get(index + 1)
+                .map_or(part.duration_seconds, |next_part| {
+                    next_part.started_at_seconds - part.started_at_seconds
+                });
+            all_copied &= self
+                .finalizer
+                .copy_session_markers(part, part_duration_seconds);
+        }
+
+        if all_copied {
+            if let Err(error) =
+                delete_recording_metadata(Path::new(&self.finalizer.session_output_path))
+            {
+                tracing::warn!("Failed to remove split recording session markers: {error}");
+            }
+        }
+    }
+}
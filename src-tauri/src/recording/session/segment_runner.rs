@@ -1,9 +1,10 @@
-use std::io::{BufRead, BufReader};
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read, Write};
 use std::net::TcpListener;
 #[cfg(target_os = "windows")]
 use std::os::windows::process::CommandExt;
 use std::process::{Child, Command, ExitStatus, Stdio};
-use std::sync::atomic::Ordering;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::mpsc as std_mpsc;
 use std::sync::{Arc, Mutex};
 use std::thread;
@@ -15,28 +16,43 @@ use tokio::sync::mpsc::error::TryRecvError;
 
 use super::super::audio_pipeline::{
     is_expected_audio_disconnect_error, run_audio_queue_to_writer,
-    run_system_audio_capture_to_queue,
+    run_system_audio_capture_to_queue, MarkerToneMixer,
 };
 use super::super::ffmpeg::{
-    append_runtime_capture_input_args, parse_ffmpeg_speed, resolve_video_filter,
+    append_black_frame_sampler_output_args, append_runtime_capture_input_args,
+    is_black_frame_sample, is_ffmpeg_informational_line, parse_ffmpeg_frame_stats,
+    parse_ffmpeg_speed, recording_ffmpeg_loglevel, resolve_audio_offset_filter,
+    resolve_system_audio_filter, resolve_video_filter, video_rate_control_args,
 };
 #[cfg(target_os = "windows")]
 use super::super::model::CREATE_NO_WINDOW;
 use super::super::model::{
-    AudioPipelineStats, CaptureInput, RuntimeCaptureMode, SegmentConfig, SegmentRunResult,
-    SegmentTransition, WindowCaptureAvailability, AUDIO_TCP_ACCEPT_WAIT,
+    AudioPipelineStats, CaptureInput, FfmpegFrameStats, FrameSync, PerformanceStatsPayload,
+    RuntimeCaptureMode, SegmentConfig, SegmentRunResult, SegmentTransition,
+    WindowCaptureAvailability, WindowCaptureRegion, AUDIO_CONNECTION_PRIMER_FRAMES,
+    AUDIO_TCP_ACCEPT_WAIT, BLACK_FRAME_SAMPLE_HEIGHT, BLACK_FRAME_SAMPLE_WIDTH,
+    CURSOR_CAPTURE_POLL_INTERVAL, CURSOR_MISSING_WARNING_DELAY, CURSOR_OUTSIDE_CAPTURE_WARNING,
+    CURSOR_SUPPRESSED_CAPTURE_WARNING, ENCODER_STALL_TIMEOUT, FASTSTART_MP4_MOVFLAGS,
+    FOREGROUND_WINDOW_POLL_INTERVAL, FRAGMENTED_MP4_MOVFLAGS, GDIGRAB_MONITOR_CAPTURE_WARNING,
+    OUTPUT_DRIVE_DISCONNECTED_WARNING, OUTPUT_FOLDER_CHECK_INTERVAL, PART_SIZE_POLL_INTERVAL,
+    PERFORMANCE_STATS_INTERVAL, PROTECTED_WINDOW_BLACK_SAMPLES, PROTECTED_WINDOW_CAPTURE_WARNING,
     SYSTEM_AUDIO_CHANNEL_COUNT, SYSTEM_AUDIO_QUEUE_CAPACITY, SYSTEM_AUDIO_SAMPLE_RATE_HZ,
-    WINDOW_CAPTURE_STATUS_POLL_INTERVAL, WINDOW_CAPTURE_UNAVAILABLE_WARNING,
+    WINDOW_CAPTURE_REGION_FALLBACK_WARNING, WINDOW_CAPTURE_UNAVAILABLE_WARNING,
+    WINDOW_REGION_RESIZE_SETTLE_DURATION,
 };
+use super::super::segments::{preallocate_output_file, trim_preallocated_output};
 use super::super::window_capture::{
-    evaluate_window_capture_availability, resolve_window_capture_handle,
-    warning_message_for_window_capture,
+    evaluate_window_capture_availability, foreground_capture_window, is_cursor_outside_capture,
+    is_significant_region_resize, is_system_cursor_suppressed, resolve_window_capture_handle,
+    resolve_window_capture_region, warning_message_for_window_capture,
 };
 use super::common::{
     request_ffmpeg_graceful_stop, resolve_stop_timeout, runtime_capture_label,
     signal_audio_threads_stop, RequestedTransitionKind,
 };
-use super::events::{emit_recording_warning, emit_recording_warning_cleared};
+use super::events::{
+    emit_performance_stats, emit_recording_warning, emit_recording_warning_cleared,
+};
 
 fn early_exit_result(
     transition: SegmentTransition,
@@ -48,11 +64,17 @@ fn early_exit_result(
         output_written: false,
         force_killed: false,
         wall_clock_duration: segment_started_at.elapsed(),
+        frame_stats: None,
+        part_limit_reached: false,
+        encoder_init_failed: false,
+        encoder_stalled: false,
+        output_folder_lost: false,
     }
 }
 
 fn segment_result_for_capture_input_error(
     app_handle: &AppHandle,
+    session_id: &str,
     runtime_capture_mode: RuntimeCaptureMode,
     capture_input: &CaptureInput,
     error: &str,
@@ -66,9 +88,9 @@ fn segment_result_for_capture_input_error(
     if matches!(runtime_capture_mode, RuntimeCaptureMode::Window) {
         let availability = evaluate_window_capture_availability(capture_input);
         if let Some(warning_message) = warning_message_for_window_capture(availability) {
-            emit_recording_warning(app_handle, warning_message);
+            emit_recording_warning(app_handle, session_id, warning_message);
         } else {
-            emit_recording_warning(app_handle, WINDOW_CAPTURE_UNAVAILABLE_WARNING);
+            emit_recording_warning(app_handle, session_id, WINDOW_CAPTURE_UNAVAILABLE_WARNING);
         }
 
         return early_exit_result(
@@ -106,6 +128,61 @@ fn should_fallback_window_capture_to_region(
     })
 }
 
+/// `ddagrab` needs DXGI desktop duplication, which older GPUs, some drivers and remote
+/// sessions do not provide; it fails during filter graph setup before any frame is read.
+/// A segment that already encoded frames lost duplication mid-recording (a mode change or
+/// secure desktop), which the next `ddagrab` segment recovers from, so it keeps `ddagrab`.
+fn should_fallback_monitor_capture_to_gdigrab(
+    capture_input: &CaptureInput,
+    encoded_frames: u64,
+    stderr_hints: &[String],
+) -> bool {
+    if !matches!(capture_input, CaptureInput::Monitor { .. })
+        || capture_input.uses_gdigrab_monitor_capture()
+        || encoded_frames > 0
+    {
+        return false;
+    }
+
+    stderr_hints.iter().any(|line| {
+        line.contains("ddagrab")
+            || line.contains("DuplicateOutput")
+            || line.contains("Desktop Duplication")
+            || line.contains("DXGI")
+    })
+}
+
+/// Hardware encoders fail while opening when the GPU or driver lacks support or, for
+/// NVENC, when other apps already hold every encode session the driver allows.
+fn is_video_encoder_init_failure(video_encoder: &str, stderr_hints: &[String]) -> bool {
+    if video_encoder == "libx264" {
+        return false;
+    }
+
+    stderr_hints.iter().any(|line| {
+        line.contains("OpenEncodeSessionEx failed")
+            || line.contains("No capable devices found")
+            || line.contains("No NVENC capable devices found")
+            || line.contains("Cannot load nvcuda.dll")
+            || line.contains("Cannot load nvEncodeAPI")
+            || line.contains("Driver does not support the required nvenc API version")
+            || line.contains("Error creating a MFX session")
+            || line.contains("Error initializing an internal MFX session")
+            || line.contains("amfrt64.dll failed to open")
+            || line.contains("Failed to create AMF")
+            || line.contains("Error while opening encoder")
+    })
+}
+
+/// WGC reports capture blocked by a protected window as an access-denied HRESULT.
+fn is_protected_window_capture_error(stderr_hints: &[String]) -> bool {
+    stderr_hints.iter().any(|line| {
+        line.contains("0x80070005")
+            || line.contains("E_ACCESSDENIED")
+            || line.contains("Access is denied")
+    })
+}
+
 struct AudioListenerSetup {
     listener: TcpListener,
     port: u16,
@@ -135,12 +212,52 @@ fn bind_audio_listener(
     Ok(AudioListenerSetup { listener, port })
 }
 
+struct StderrReaderHandles {
+    hints: Arc<Mutex<Vec<String>>>,
+    frame_stats: Arc<Mutex<Option<FfmpegFrameStats>>>,
+    thread: Option<thread::JoinHandle<()>>,
+}
+
+/// Opens the session's FFmpeg log for appending, so every segment of a recording lands in
+/// one file. Only used with diagnostics on.
+fn open_ffmpeg_session_log(app_handle: &AppHandle, session_id: &str) -> Option<File> {
+    let log_path = match crate::logging::ffmpeg_session_log_path(app_handle, session_id) {
+        Ok(log_path) => log_path,
+        Err(error) => {
+            tracing::warn!("FFmpeg session log is unavailable: {error}");
+            return None;
+        }
+    };
+
+    match std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&log_path)
+    {
+        Ok(log_file) => {
+            tracing::info!(log_path = %log_path.display(), "Writing FFmpeg output to session log");
+            Some(log_file)
+        }
+        Err(error) => {
+            tracing::warn!(
+                log_path = %log_path.display(),
+                "Failed to open FFmpeg session log: {error}"
+            );
+            None
+        }
+    }
+}
+
+/// `session_log` receives every stderr line unfiltered.
 fn spawn_stderr_reader(
     child: &mut Child,
     enable_diagnostics: bool,
-) -> (Arc<Mutex<Vec<String>>>, Option<thread::JoinHandle<()>>) {
+    mut session_log: Option<File>,
+) -> StderrReaderHandles {
     let stderr_hints: Arc<Mutex<Vec<String>>> = Arc::new(Mutex::new(Vec::new()));
     let stderr_hints_for_thread = Arc::clone(&stderr_hints);
+    let frame_stats: Arc<Mutex<Option<FfmpegFrameStats>>> = Arc::new(Mutex::new(None));
+    let frame_stats_for_thread = Arc::clone(&frame_stats);
 
     let stderr_thread = child.stderr.take().map(|stderr| {
         thread::spawn(move || {
@@ -150,6 +267,13 @@ fn spawn_stderr_reader(
             for line in BufReader::new(stderr).lines() {
                 match line {
                     Ok(content) if !content.trim().is_empty() => {
+                        if let Some(log_file) = session_log.as_mut() {
+                            if let Err(error) = writeln!(log_file, "{content}") {
+                                tracing::warn!("Failed to write FFmpeg session log: {error}");
+                                session_log = None;
+                            }
+                        }
+
                         let is_progress_line = content.contains("frame=")
                             || content.contains("fps=")
                             || content.contains("dup=")
@@ -171,11 +295,17 @@ fn spawn_stderr_reader(
                             }
                         }
 
+                        if let Some(stats) = parse_ffmpeg_frame_stats(&content) {
+                            if let Ok(mut latest_stats) = frame_stats_for_thread.lock() {
+                                *latest_stats = Some(stats);
+                            }
+                        }
+
                         if is_progress_line {
                             if enable_diagnostics {
                                 tracing::info!("ffmpeg: {content}");
                             }
-                        } else {
+                        } else if !is_ffmpeg_informational_line(&content) {
                             let trimmed = content.trim();
                             if !trimmed.is_empty() {
                                 if let Ok(mut hints) = stderr_hints_for_thread.lock() {
@@ -200,7 +330,52 @@ fn spawn_stderr_reader(
         })
     });
 
-    (stderr_hints, stderr_thread)
+    StderrReaderHandles {
+        hints: stderr_hints,
+        frame_stats,
+        thread: stderr_thread,
+    }
+}
+
+struct BlackFrameSamplerHandles {
+    persistently_black: Arc<AtomicBool>,
+    thread: Option<thread::JoinHandle<()>>,
+}
+
+/// Reads the sampler frames from FFmpeg's stdout until it closes. The flag is raised after
+/// `PROTECTED_WINDOW_BLACK_SAMPLES` black samples in a row and lowered on the next frame
+/// with content.
+fn spawn_black_frame_sampler(child: &mut Child) -> BlackFrameSamplerHandles {
+    let persistently_black = Arc::new(AtomicBool::new(false));
+    let persistently_black_for_thread = Arc::clone(&persistently_black);
+
+    let sampler_thread = child.stdout.take().map(|mut stdout| {
+        thread::spawn(move || {
+            let mut sample = [0u8; BLACK_FRAME_SAMPLE_WIDTH * BLACK_FRAME_SAMPLE_HEIGHT];
+            let mut black_streak = 0u32;
+
+            while stdout.read_exact(&mut sample).is_ok() {
+                if is_black_frame_sample(&sample) {
+                    black_streak = black_streak.saturating_add(1);
+                    if black_streak == PROTECTED_WINDOW_BLACK_SAMPLES {
+                        tracing::warn!(
+                            black_seconds = black_streak,
+                            "Window capture has only produced black frames; the window may block capture"
+                        );
+                        persistently_black_for_thread.store(true, Ordering::Relaxed);
+                    }
+                } else {
+                    black_streak = 0;
+                    persistently_black_for_thread.store(false, Ordering::Relaxed);
+                }
+            }
+        })
+    });
+
+    BlackFrameSamplerHandles {
+        persistently_black,
+        thread: sampler_thread,
+    }
 }
 
 struct AudioPipelineHandles {
@@ -211,7 +386,13 @@ struct AudioPipelineHandles {
     stats: Arc<AudioPipelineStats>,
 }
 
-fn setup_audio_pipeline(listener: TcpListener) -> AudioPipelineHandles {
+fn setup_audio_pipeline(
+    listener: TcpListener,
+    system_audio_device_id: Option<String>,
+    system_audio_session_process_id: Option<u32>,
+    marker_tone_pending: Option<Arc<AtomicBool>>,
+    wait_for_connection: bool,
+) -> AudioPipelineHandles {
     let (audio_tx, audio_rx) = std_mpsc::sync_channel::<Vec<u8>>(SYSTEM_AUDIO_QUEUE_CAPACITY);
     let (capture_stop_tx, capture_stop_rx) = std_mpsc::channel::<()>();
     let (writer_stop_tx, writer_stop_rx) = std_mpsc::channel::<()>();
@@ -220,7 +401,7 @@ fn setup_audio_pipeline(listener: TcpListener) -> AudioPipelineHandles {
     let writer_stats = Arc::clone(&stats);
     let writer_thread = thread::spawn(move || {
         tracing::info!("Waiting for FFmpeg audio socket connection");
-        let audio_stream = loop {
+        let mut audio_stream = loop {
             match listener.accept() {
                 Ok((stream, _)) => {
                     tracing::info!("FFmpeg audio socket connected");
@@ -243,16 +424,51 @@ fn setup_audio_pipeline(listener: TcpListener) -> AudioPipelineHandles {
         // Non-fatal socket tuning; recording proceeds with defaults if these fail.
         let _ = audio_stream.set_nodelay(true);
         let _ = audio_stream.set_write_timeout(Some(Duration::from_millis(12)));
-        let writer_result =
-            run_audio_queue_to_writer(audio_stream, audio_rx, writer_stop_rx, writer_stats);
+        // Capture runs in parallel with FFmpeg's start-up. Audio queued before the connection
+        // predates the video and is dropped; if capture has not produced anything yet, its
+        // start-up time is filled with silence so the audio still starts with the video.
+        let silence_origin = if wait_for_connection {
+            let primer = vec![0u8; AUDIO_CONNECTION_PRIMER_FRAMES * SYSTEM_AUDIO_CHANNEL_COUNT * 2];
+            if let Err(error) = audio_stream.write_all(&primer) {
+                tracing::warn!("Failed to prime FFmpeg audio input with silence: {error}");
+            }
+            let primed_until = Instant::now()
+                + Duration::from_secs_f64(
+                    AUDIO_CONNECTION_PRIMER_FRAMES as f64 / SYSTEM_AUDIO_SAMPLE_RATE_HZ as f64,
+                );
+            let stale_chunk_count = audio_rx.try_iter().count();
+            if stale_chunk_count > 0 {
+                tracing::debug!(
+                    stale_chunk_count,
+                    "Dropped system audio captured before FFmpeg connected"
+                );
+            }
+            Some(primed_until)
+        } else {
+            None
+        };
+        let writer_result = run_audio_queue_to_writer(
+            audio_stream,
+            audio_rx,
+            writer_stop_rx,
+            writer_stats,
+            marker_tone_pending.map(MarkerToneMixer::new),
+            silence_origin,
+        );
         tracing::info!("System audio writer thread exited");
         writer_result
     });
 
     let capture_stats = Arc::clone(&stats);
     let capture_thread = thread::spawn(move || {
-        let capture_result =
-            run_system_audio_capture_to_queue(audio_tx, capture_stop_rx, capture_stats);
+        let capture_result = run_system_audio_capture_to_queue(
+            audio_tx,
+            capture_stop_rx,
+            capture_stats,
+            system_audio_device_id.as_deref(),
+            system_audio_session_process_id,
+            true,
+        );
         tracing::info!("System audio capture thread exited");
         capture_result
     });
@@ -273,6 +489,11 @@ struct PollLoopState {
     stop_requested_by_user: bool,
     requested_transition: Option<RuntimeCaptureMode>,
     requested_transition_kind: Option<RequestedTransitionKind>,
+    part_limit_reached: bool,
+    encoder_stalled: bool,
+    output_folder_lost: bool,
+    /// Window to capture in the next segment when following the foreground window.
+    followed_window: Option<(usize, String)>,
 }
 
 struct PollLoopOutcome {
@@ -280,15 +501,20 @@ struct PollLoopOutcome {
     state: PollLoopState,
 }
 
+#[allow(clippy::too_many_arguments)]
 fn run_segment_poll_loop(
     app_handle: &AppHandle,
     child: &mut Child,
     capture_input: &CaptureInput,
-    runtime_capture_mode: RuntimeCaptureMode,
-    enable_diagnostics: bool,
+    config: &SegmentConfig,
+    locked_region: Option<WindowCaptureRegion>,
     audio: &Option<AudioPipelineHandles>,
+    frame_stats: &Mutex<Option<FfmpegFrameStats>>,
+    persistently_black: Option<&AtomicBool>,
     stop_rx: &mut mpsc::Receiver<()>,
 ) -> PollLoopOutcome {
+    let runtime_capture_mode = config.runtime_capture_mode;
+    let enable_diagnostics = config.enable_diagnostics;
     let mut state = PollLoopState {
         stop_requested_at: None,
         kill_sent: false,
@@ -296,6 +522,10 @@ fn run_segment_poll_loop(
         stop_requested_by_user: false,
         requested_transition: None,
         requested_transition_kind: None,
+        part_limit_reached: false,
+        encoder_stalled: false,
+        output_folder_lost: false,
+        followed_window: None,
     };
 
     let mut stats_logged_at = Instant::now();
@@ -304,8 +534,34 @@ fn run_segment_poll_loop(
     let mut previous_dropped = 0u64;
     let mut previous_timeouts = 0u64;
     let mut drop_warning_emitted = false;
+    let mut device_in_use_warning_emitted = false;
     let mut window_status_checked_at = Instant::now();
     let mut active_window_warning: Option<&'static str> = None;
+    let mut part_size_checked_at = Instant::now();
+    let mut foreground_checked_at = Instant::now();
+    let mut pending_foreground_hwnd: Option<usize> = None;
+    let mut pending_region_resize: Option<(WindowCaptureRegion, Instant)> = None;
+    let mut performance_stats_emitted_at = Instant::now();
+    let mut previous_encoded_frames = 0u64;
+    // Frame count of the latest report and when it last changed. The clock only starts
+    // with the first report, so a slow encoder start is not taken for a stall. Only CFR
+    // output is checked: without the `fps` filter a static window yields no frames at all.
+    let detect_encoder_stall = config.frame_sync == FrameSync::Cfr;
+    let mut last_frame_advance: Option<(u64, Instant)> = None;
+    let mut encoder_stall_logged = false;
+    let mut output_folder_checked_at = Instant::now();
+    // WGC window capture draws the cursor itself, so only ddagrab segments are checked.
+    let cursor_capture_output_idx = match (runtime_capture_mode, capture_input) {
+        _ if !config.capture_cursor => None,
+        (RuntimeCaptureMode::Monitor, CaptureInput::Monitor { output_idx, .. }) => {
+            Some(*output_idx)
+        }
+        (RuntimeCaptureMode::Window, _) => locked_region.map(|region| region.output_idx),
+        _ => None,
+    };
+    let mut cursor_checked_at = Instant::now();
+    let mut cursor_missing_since: Option<Instant> = None;
+    let mut cursor_warning_emitted = false;
 
     // For request_ffmpeg_graceful_stop.
     let audio_capture_stop_tx = audio.as_ref().map(|a| &a.capture_stop_tx);
@@ -387,11 +643,31 @@ fn run_segment_poll_loop(
                 previous_dropped = dropped_total;
                 previous_timeouts = timeouts_total;
                 stats_logged_at = Instant::now();
+
+                let device_in_use_warning = audio_handles
+                    .stats
+                    .device_in_use_warning
+                    .lock()
+                    .ok()
+                    .and_then(|warning| warning.clone());
+                match device_in_use_warning {
+                    Some(warning_message) if !device_in_use_warning_emitted => {
+                        emit_recording_warning(app_handle, config.session_id, &warning_message);
+                        device_in_use_warning_emitted = true;
+                    }
+                    None if device_in_use_warning_emitted => {
+                        if active_window_warning.is_none() {
+                            emit_recording_warning_cleared(app_handle, config.session_id);
+                        }
+                        device_in_use_warning_emitted = false;
+                    }
+                    _ => {}
+                }
             }
         }
 
         if matches!(capture_input, CaptureInput::Window { .. })
-            && window_status_checked_at.elapsed() >= WINDOW_CAPTURE_STATUS_POLL_INTERVAL
+            && window_status_checked_at.elapsed() >= config.window_status_poll_interval
         {
             window_status_checked_at = Instant::now();
             let capture_availability = evaluate_window_capture_availability(capture_input);
@@ -399,15 +675,19 @@ fn run_segment_poll_loop(
                 && capture_availability == WindowCaptureAvailability::Available
             {
                 Some(WINDOW_CAPTURE_UNAVAILABLE_WARNING)
+            } else if capture_availability == WindowCaptureAvailability::Available
+                && persistently_black.is_some_and(|flag| flag.load(Ordering::Relaxed))
+            {
+                Some(PROTECTED_WINDOW_CAPTURE_WARNING)
             } else {
                 warning_message_for_window_capture(capture_availability)
             };
 
             if next_window_warning != active_window_warning {
                 if let Some(warning_message) = next_window_warning {
-                    emit_recording_warning(app_handle, warning_message);
+                    emit_recording_warning(app_handle, config.session_id, warning_message);
                 } else {
-                    emit_recording_warning_cleared(app_handle);
+                    emit_recording_warning_cleared(app_handle, config.session_id);
                 }
 
                 active_window_warning = next_window_warning;
@@ -457,6 +737,277 @@ fn run_segment_poll_loop(
                     _ => {}
                 }
             }
+
+            if state.requested_transition.is_none()
+                && matches!(runtime_capture_mode, RuntimeCaptureMode::Window)
+                && capture_availability == WindowCaptureAvailability::Available
+            {
+                if let Some(locked_region) = locked_region {
+                    if let Ok(current_region) = resolve_window_capture_region(capture_input) {
+                        let monitor_changed = current_region.output_idx != locked_region.output_idx;
+                        // A resize only restarts the segment once the new size has held for
+                        // the settle duration, so dragging a window edge or a fullscreen toggle
+                        // that passes through intermediate sizes restarts once, not per poll.
+                        let resized = if is_significant_region_resize(locked_region, current_region)
+                        {
+                            match pending_region_resize {
+                                Some((pending_region, pending_since))
+                                    if !is_significant_region_resize(
+                                        pending_region,
+                                        current_region,
+                                    ) =>
+                                {
+                                    pending_since.elapsed() >= WINDOW_REGION_RESIZE_SETTLE_DURATION
+                                }
+                                _ => {
+                                    pending_region_resize = Some((current_region, Instant::now()));
+                                    false
+                                }
+                            }
+                        } else {
+                            pending_region_resize = None;
+                            false
+                        };
+                        if monitor_changed {
+                            tracing::info!(
+                                previous_output_idx = locked_region.output_idx,
+                                current_output_idx = current_region.output_idx,
+                                "Window moved to a different monitor; restarting segment on the new output"
+                            );
+                        } else if resized {
+                            tracing::info!(
+                                locked_width = locked_region.width,
+                                locked_height = locked_region.height,
+                                current_width = current_region.width,
+                                current_height = current_region.height,
+                                "Window capture area changed size; restarting segment with the new region"
+                            );
+                        }
+
+                        if monitor_changed || resized {
+                            state.requested_transition = Some(RuntimeCaptureMode::Window);
+                            state.requested_transition_kind =
+                                Some(RequestedTransitionKind::CaptureRegionChanged);
+                            request_ffmpeg_graceful_stop(
+                                &mut state.stop_requested_at,
+                                child,
+                                &audio_capture_stop_tx,
+                                &audio_writer_stop_tx,
+                            );
+                        }
+                    }
+                }
+            }
+        }
+
+        // A focus change has to hold for two polls before capture follows it, so quickly
+        // tabbing through windows does not restart a segment for each one.
+        if config.follow_foreground
+            && matches!(capture_input, CaptureInput::Window { .. })
+            && state.requested_transition.is_none()
+            && state.stop_requested_at.is_none()
+            && foreground_checked_at.elapsed() >= FOREGROUND_WINDOW_POLL_INTERVAL
+        {
+            foreground_checked_at = Instant::now();
+            let current_window_hwnd = resolve_window_capture_handle(capture_input).ok();
+            match foreground_capture_window() {
+                Some((foreground_hwnd, foreground_title))
+                    if Some(foreground_hwnd) != current_window_hwnd =>
+                {
+                    if pending_foreground_hwnd == Some(foreground_hwnd) {
+                        tracing::info!(
+                            previous_window_hwnd = current_window_hwnd,
+                            window_hwnd = foreground_hwnd,
+                            window_title = %foreground_title,
+                            "Foreground window changed; restarting segment on the focused window"
+                        );
+                        state.followed_window = Some((foreground_hwnd, foreground_title));
+                        state.requested_transition = Some(RuntimeCaptureMode::Window);
+                        state.requested_transition_kind =
+                            Some(RequestedTransitionKind::ForegroundWindowChanged);
+                        request_ffmpeg_graceful_stop(
+                            &mut state.stop_requested_at,
+                            child,
+                            &audio_capture_stop_tx,
+                            &audio_writer_stop_tx,
+                        );
+                    } else {
+                        pending_foreground_hwnd = Some(foreground_hwnd);
+                    }
+                }
+                _ => pending_foreground_hwnd = None,
+            }
+        }
+
+        if let Some(output_idx) = cursor_capture_output_idx {
+            if !cursor_warning_emitted
+                && active_window_warning.is_none()
+                && cursor_checked_at.elapsed() >= CURSOR_CAPTURE_POLL_INTERVAL
+            {
+                cursor_checked_at = Instant::now();
+                let cursor_missing = if is_system_cursor_suppressed() {
+                    Some(("suppressed", CURSOR_SUPPRESSED_CAPTURE_WARNING))
+                } else if is_cursor_outside_capture(output_idx, locked_region) {
+                    Some(("outside_capture", CURSOR_OUTSIDE_CAPTURE_WARNING))
+                } else {
+                    None
+                };
+                if let Some((reason, warning_message)) = cursor_missing {
+                    let missing_since = *cursor_missing_since.get_or_insert_with(Instant::now);
+                    if missing_since.elapsed() >= CURSOR_MISSING_WARNING_DELAY {
+                        tracing::info!(
+                            output_idx,
+                            reason,
+                            "Cursor is not drawn into the recording"
+                        );
+                        emit_recording_warning(app_handle, config.session_id, warning_message);
+                        cursor_warning_emitted = true;
+                    }
+                } else {
+                    cursor_missing_since = None;
+                }
+            }
+        }
+
+        if let Some(part_byte_budget) = config.part_byte_budget {
+            if state.stop_requested_at.is_none()
+                && part_size_checked_at.elapsed() >= PART_SIZE_POLL_INTERVAL
+            {
+                part_size_checked_at = Instant::now();
+                // A preallocated file is full-size from the start, so FFmpeg's own count
+                // is used instead.
+                let written_bytes = if config.preallocate_bytes.is_some() {
+                    frame_stats
+                        .lock()
+                        .ok()
+                        .and_then(|stats| stats.and_then(|stats| stats.output_bytes))
+                        .unwrap_or(0)
+                } else {
+                    config
+                        .output_path
+                        .metadata()
+                        .map(|metadata| metadata.len())
+                        .unwrap_or(0)
+                };
+                if written_bytes >= part_byte_budget {
+                    tracing::info!(
+                        written_bytes,
+                        part_byte_budget,
+                        "Recording part reached its size limit; rolling over to a new part"
+                    );
+                    state.part_limit_reached = true;
+                    state.requested_transition = Some(runtime_capture_mode);
+                    state.requested_transition_kind = Some(RequestedTransitionKind::PartSizeLimit);
+                    request_ffmpeg_graceful_stop(
+                        &mut state.stop_requested_at,
+                        child,
+                        &audio_capture_stop_tx,
+                        &audio_writer_stop_tx,
+                    );
+                }
+            }
+        }
+
+        if state.stop_requested_at.is_none()
+            && output_folder_checked_at.elapsed() >= OUTPUT_FOLDER_CHECK_INTERVAL
+        {
+            output_folder_checked_at = Instant::now();
+            if let Some(output_folder) = config
+                .output_path
+                .parent()
+                .filter(|output_folder| !output_folder.exists())
+            {
+                tracing::error!(
+                    output_folder = %output_folder.display(),
+                    "Recording output folder is no longer available; the drive was likely disconnected"
+                );
+                emit_recording_warning(
+                    app_handle,
+                    config.session_id,
+                    OUTPUT_DRIVE_DISCONNECTED_WARNING,
+                );
+                state.output_folder_lost = true;
+                state.requested_transition_kind = Some(RequestedTransitionKind::OutputFolderLost);
+                request_ffmpeg_graceful_stop(
+                    &mut state.stop_requested_at,
+                    child,
+                    &audio_capture_stop_tx,
+                    &audio_writer_stop_tx,
+                );
+            }
+        }
+
+        if detect_encoder_stall && state.stop_requested_at.is_none() {
+            let encoded_frames = frame_stats
+                .lock()
+                .ok()
+                .and_then(|stats| stats.map(|stats| stats.frames));
+            match (encoded_frames, last_frame_advance) {
+                (Some(frames), Some((previous_frames, _))) if frames != previous_frames => {
+                    last_frame_advance = Some((frames, Instant::now()));
+                    encoder_stall_logged = false;
+                }
+                (Some(frames), None) => last_frame_advance = Some((frames, Instant::now())),
+                (_, Some((frames, advanced_at)))
+                    if advanced_at.elapsed() >= ENCODER_STALL_TIMEOUT && !encoder_stall_logged =>
+                {
+                    encoder_stall_logged = true;
+                    if config.restart_on_encoder_stall {
+                        tracing::warn!(
+                            frames,
+                            stalled_seconds = advanced_at.elapsed().as_secs_f64(),
+                            "Video encoder stopped producing frames; restarting the segment"
+                        );
+                        state.encoder_stalled = true;
+                        state.requested_transition = Some(runtime_capture_mode);
+                        state.requested_transition_kind =
+                            Some(RequestedTransitionKind::EncoderStall);
+                        request_ffmpeg_graceful_stop(
+                            &mut state.stop_requested_at,
+                            child,
+                            &audio_capture_stop_tx,
+                            &audio_writer_stop_tx,
+                        );
+                    } else {
+                        tracing::warn!(
+                            frames,
+                            stalled_seconds = advanced_at.elapsed().as_secs_f64(),
+                            "Video encoder stopped producing frames; not restarting this segment"
+                        );
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        if config.emit_performance_stats
+            && performance_stats_emitted_at.elapsed() >= PERFORMANCE_STATS_INTERVAL
+        {
+            let interval_seconds = performance_stats_emitted_at.elapsed().as_secs_f64();
+            performance_stats_emitted_at = Instant::now();
+            let latest_frame_stats = frame_stats.lock().ok().and_then(|stats| *stats);
+            let encoded_frames =
+                latest_frame_stats.map_or(previous_encoded_frames, |stats| stats.frames);
+            let audio_queue_depth = audio.as_ref().map(|audio_handles| {
+                let queued_total = audio_handles.stats.queued_chunks.load(Ordering::Relaxed);
+                let dequeued_total = audio_handles.stats.dequeued_chunks.load(Ordering::Relaxed);
+                queued_total.saturating_sub(dequeued_total)
+            });
+            emit_performance_stats(
+                app_handle,
+                PerformanceStatsPayload {
+                    session_id: config.session_id.to_string(),
+                    encode_speed: latest_frame_stats.and_then(|stats| stats.speed),
+                    encode_fps: latest_frame_stats.map(|_| {
+                        encoded_frames.saturating_sub(previous_encoded_frames) as f64
+                            / interval_seconds
+                    }),
+                    frames_duplicated: latest_frame_stats.map_or(0, |stats| stats.duplicated),
+                    frames_dropped: latest_frame_stats.map_or(0, |stats| stats.dropped),
+                    audio_queue_depth,
+                },
+            );
+            previous_encoded_frames = encoded_frames;
         }
 
         match child.try_wait() {
@@ -555,7 +1106,7 @@ fn determine_segment_transition(
                 SegmentTransition::RestartSameMode
             }
         }
-        RuntimeCaptureMode::Monitor => SegmentTransition::Stop,
+        RuntimeCaptureMode::Monitor | RuntimeCaptureMode::TestPattern => SegmentTransition::Stop,
     }
 }
 
@@ -592,9 +1143,9 @@ pub(super) fn run_ffmpeg_recording_segment(
     };
 
     let audio_port = audio_setup.as_ref().map(|s| s.port);
+    let samples_black_frames = matches!(config.runtime_capture_mode, RuntimeCaptureMode::Window)
+        && capture_input.uses_wgc_window_capture();
 
-    let bitrate_string = config.bitrate.to_string();
-    let buffer_size_string = config.bitrate.saturating_mul(2).to_string();
     let output_path_string = config.output_path.to_string_lossy().to_string();
 
     let mut command = Command::new(config.ffmpeg_binary_path);
@@ -603,7 +1154,7 @@ pub(super) fn run_ffmpeg_recording_segment(
     command
         .arg("-hide_banner")
         .arg("-loglevel")
-        .arg("warning")
+        .arg(recording_ffmpeg_loglevel(config.enable_diagnostics))
         .arg("-stats")
         .arg("-stats_period")
         .arg("1")
@@ -623,97 +1174,156 @@ pub(super) fn run_ffmpeg_recording_segment(
             .arg(format!("tcp://127.0.0.1:{port}"));
     }
 
-    let capture_input_info = match append_runtime_capture_input_args(
-        &mut command,
-        config.runtime_capture_mode,
-        capture_input,
-        config.requested_frame_rate,
-        config.capture_width,
-        config.capture_height,
-    ) {
-        Ok(info) => info,
-        Err(error) => {
-            return segment_result_for_capture_input_error(
-                app_handle,
-                config.runtime_capture_mode,
-                capture_input,
-                &error,
-                segment_started_at,
-            );
-        }
-    };
+    let capture_input_info =
+        match append_runtime_capture_input_args(&mut command, config, capture_input) {
+            Ok(info) => info,
+            Err(error) => {
+                return segment_result_for_capture_input_error(
+                    app_handle,
+                    config.session_id,
+                    config.runtime_capture_mode,
+                    capture_input,
+                    &error,
+                    segment_started_at,
+                );
+            }
+        };
 
     let video_filter = resolve_video_filter(
         config.runtime_capture_mode,
+        config.frame_sync,
         config.output_frame_rate,
         capture_input_info.width,
         capture_input_info.height,
+        config.pixel_format,
+        config.letterbox_color,
     );
 
     if audio_port.is_some() {
+        let system_audio_filter =
+            resolve_system_audio_filter(config.bypass_audio_limiter, config.audio_resample_sync);
+        let mut audio_filter = match resolve_audio_offset_filter(config.audio_offset_ms) {
+            Some(offset_filter) => format!("{offset_filter},{system_audio_filter}"),
+            None => system_audio_filter,
+        };
+        if let Some(channel_map_filter) = config.audio_channel_map_filter {
+            audio_filter = format!("{channel_map_filter},{audio_filter}");
+        }
+        // Averaging both channels keeps centered voice at its stereo level instead of
+        // summing it twice; it runs after the limiter so the mix cannot clip.
+        let (audio_channels, audio_bitrate) = if config.downmix_audio_mono {
+            audio_filter.push_str(",pan=mono|c0=0.5*c0+0.5*c1");
+            ("1", config.audio_bitrate / 2)
+        } else {
+            ("2", config.audio_bitrate)
+        };
         command
             .arg("-map")
             .arg("1:v:0")
             .arg("-map")
             .arg("0:a:0")
             .arg("-af")
-            .arg("aresample=async=1:min_hard_comp=0.100:first_pts=0,volume=2.2,alimiter=limit=0.98")
+            .arg(audio_filter)
             .arg("-vf")
             .arg(&video_filter)
             .arg("-thread_queue_size")
             .arg("512")
             .arg("-c:a")
-            .arg("aac")
+            .arg(config.audio_encoder)
             .arg("-b:a")
-            .arg("192k")
+            .arg(audio_bitrate.to_string())
             .arg("-ar")
             .arg("48000")
             .arg("-ac")
-            .arg("2");
+            .arg(audio_channels);
     } else {
         command.arg("-vf").arg(&video_filter).arg("-an");
     }
 
-    command.arg("-c:v").arg(config.video_encoder);
+    command
+        .arg("-c:v")
+        .arg(config.video_encoder)
+        .arg("-pix_fmt")
+        .arg(config.pixel_format);
 
     if let Some(preset) = config.encoder_preset {
         command.arg("-preset").arg(preset);
     }
 
     command
-        .arg("-b:v")
-        .arg(&bitrate_string)
-        .arg("-maxrate")
-        .arg(&bitrate_string)
-        .arg("-bufsize")
-        .arg(&buffer_size_string)
+        .args(video_rate_control_args(
+            config.video_encoder,
+            config.rate_control,
+            config.bitrate,
+            config.vbr_quality,
+        ))
         .arg("-fps_mode")
-        .arg("cfr")
+        .arg(config.frame_sync.fps_mode())
         .arg("-max_muxing_queue_size")
         .arg("2048")
         .arg("-movflags")
-        .arg("+faststart")
+        .arg(if config.fragmented_mp4 {
+            FRAGMENTED_MP4_MOVFLAGS
+        } else {
+            FASTSTART_MP4_MOVFLAGS
+        });
+
+    // Without `-truncate 0` FFmpeg would empty the file and give the space back.
+    let preallocated = config.preallocate_bytes.is_some_and(|preallocate_bytes| {
+        match preallocate_output_file(config.output_path, preallocate_bytes) {
+            Ok(()) => true,
+            Err(error) => {
+                tracing::warn!("Recording without preallocation: {error}");
+                false
+            }
+        }
+    });
+    if preallocated {
+        command.arg("-truncate").arg("0");
+    }
+
+    command
         .arg(&output_path_string)
         .stdin(Stdio::piped())
         .stdout(Stdio::null())
         .stderr(Stdio::piped());
 
+    if samples_black_frames {
+        let video_input_index = if audio_port.is_some() { 1 } else { 0 };
+        append_black_frame_sampler_output_args(&mut command, video_input_index);
+        command.stdout(Stdio::piped());
+    }
+
     let mut child = match command.spawn() {
         Ok(process) => process,
         Err(error) => {
             tracing::error!("Failed to spawn FFmpeg recording process: {error}");
+            if preallocated {
+                let _ = std::fs::remove_file(config.output_path);
+            }
             return early_exit_result(SegmentTransition::Stop, segment_started_at);
         }
     };
 
     if matches!(config.runtime_capture_mode, RuntimeCaptureMode::Window) {
-        emit_recording_warning_cleared(app_handle);
+        emit_recording_warning_cleared(app_handle, config.session_id);
     }
 
-    let (stderr_hints, stderr_thread) = spawn_stderr_reader(&mut child, config.enable_diagnostics);
+    let session_log = config
+        .enable_diagnostics
+        .then(|| open_ffmpeg_session_log(app_handle, config.session_id))
+        .flatten();
+    let stderr_reader = spawn_stderr_reader(&mut child, config.enable_diagnostics, session_log);
+    let black_frame_sampler = samples_black_frames.then(|| spawn_black_frame_sampler(&mut child));
 
     let audio_handles = if let Some(setup) = audio_setup {
-        Some(setup_audio_pipeline(setup.listener))
+        Some(setup_audio_pipeline(
+            setup.listener,
+            config.system_audio_device_id.map(str::to_string),
+            config.system_audio_session_process_id,
+            config.marker_tone_pending.cloned(),
+            config.wait_for_audio_connection,
+        ))
     } else {
         None
     };
@@ -723,9 +1333,13 @@ pub(super) fn run_ffmpeg_recording_segment(
         app_handle,
         &mut child,
         capture_input,
-        config.runtime_capture_mode,
-        config.enable_diagnostics,
+        config,
+        capture_input_info.region,
         &audio_handles,
+        &stderr_reader.frame_stats,
+        black_frame_sampler
+            .as_ref()
+            .map(|sampler| sampler.persistently_black.as_ref()),
         stop_rx,
     );
 
@@ -736,15 +1350,33 @@ pub(super) fn run_ffmpeg_recording_segment(
 
     let stderr_hint_lines = join_worker_threads(
         audio_handles,
-        stderr_thread,
-        &stderr_hints,
+        stderr_reader.thread,
+        &stderr_reader.hints,
         outcome.state.stop_requested_by_user,
         outcome.state.requested_transition,
         outcome.state.kill_sent,
     );
 
+    if let Some(handle) = black_frame_sampler.and_then(|sampler| sampler.thread) {
+        if let Err(error) = handle.join() {
+            tracing::warn!("Failed to join black frame sampler thread: {error:?}");
+        }
+    }
+
     let mut force_killed = outcome.state.force_killed;
 
+    if let Some((window_hwnd, window_title)) = &outcome.state.followed_window {
+        capture_input.follow_window(*window_hwnd, window_title.clone());
+    }
+
+    let mut fell_back_to_gdigrab = false;
+    let mut encoder_init_failed = false;
+    let encoded_frames = stderr_reader
+        .frame_stats
+        .lock()
+        .ok()
+        .and_then(|stats| *stats)
+        .map_or(0, |stats| stats.frames);
     let ffmpeg_succeeded = match outcome.exit_status {
         Ok(status) if status.success() => {
             tracing::info!("FFmpeg recording process finished successfully");
@@ -758,10 +1390,49 @@ pub(super) fn run_ffmpeg_recording_segment(
                     "WGC window capture failed. Falling back to region-based window capture"
                 );
                 capture_input.disable_wgc_window_capture();
+                if is_protected_window_capture_error(&stderr_hint_lines) {
+                    tracing::warn!("WGC window capture was denied; the window may block capture");
+                    emit_recording_warning(
+                        app_handle,
+                        config.session_id,
+                        PROTECTED_WINDOW_CAPTURE_WARNING,
+                    );
+                } else {
+                    emit_recording_warning(
+                        app_handle,
+                        config.session_id,
+                        WINDOW_CAPTURE_REGION_FALLBACK_WARNING,
+                    );
+                }
+            } else if !outcome.state.stop_requested_by_user
+                && outcome.state.requested_transition.is_none()
+                && should_fallback_monitor_capture_to_gdigrab(
+                    capture_input,
+                    encoded_frames,
+                    &stderr_hint_lines,
+                )
+            {
+                tracing::warn!(
+                    exit_status = %status,
+                    "Desktop duplication capture failed. Falling back to GDI monitor capture"
+                );
+                capture_input.enable_gdigrab_monitor_capture();
+                fell_back_to_gdigrab = true;
                 emit_recording_warning(
                     app_handle,
-                    "Exclusive window capture is unavailable on this system. Falling back to region-based capture, so overlapping windows may appear.",
+                    config.session_id,
+                    GDIGRAB_MONITOR_CAPTURE_WARNING,
                 );
+            } else if !outcome.state.stop_requested_by_user
+                && outcome.state.requested_transition.is_none()
+                && is_video_encoder_init_failure(config.video_encoder, &stderr_hint_lines)
+            {
+                tracing::warn!(
+                    exit_status = %status,
+                    video_encoder = config.video_encoder,
+                    "Video encoder failed to initialize"
+                );
+                encoder_init_failed = true;
             }
 
             if !stderr_hint_lines.is_empty() {
@@ -794,19 +1465,36 @@ pub(super) fn run_ffmpeg_recording_segment(
         }
     };
 
+    if preallocated {
+        if let Err(error) = trim_preallocated_output(config.output_path) {
+            tracing::warn!("Failed to release unused preallocated space: {error}");
+        }
+    }
+
     let output_written = config.output_path.exists()
         && config
             .output_path
             .metadata()
             .is_ok_and(|metadata| metadata.len() > 0);
 
-    let transition = determine_segment_transition(
-        config.runtime_capture_mode,
-        capture_input,
-        outcome.state.stop_requested_by_user,
-        outcome.state.requested_transition,
-        ffmpeg_succeeded,
-    );
+    // Monitor capture otherwise stops on failure. Switching back into the same mode starts
+    // the next segment with the GDI input or the next encoder and does not count as a
+    // failed segment.
+    let transition = if outcome.state.output_folder_lost {
+        SegmentTransition::Stop
+    } else if fell_back_to_gdigrab {
+        SegmentTransition::Switch(RuntimeCaptureMode::Monitor)
+    } else if encoder_init_failed {
+        SegmentTransition::Switch(config.runtime_capture_mode)
+    } else {
+        determine_segment_transition(
+            config.runtime_capture_mode,
+            capture_input,
+            outcome.state.stop_requested_by_user,
+            outcome.state.requested_transition,
+            ffmpeg_succeeded,
+        )
+    };
 
     SegmentRunResult {
         transition,
@@ -814,5 +1502,15 @@ pub(super) fn run_ffmpeg_recording_segment(
         output_written,
         force_killed,
         wall_clock_duration: segment_started_at.elapsed(),
+        frame_stats: stderr_reader
+            .frame_stats
+            .lock()
+            .ok()
+            .and_then(|stats| *stats),
+        part_limit_reached: outcome.state.part_limit_reached
+            && !outcome.state.stop_requested_by_user,
+        encoder_init_failed,
+        encoder_stalled: outcome.state.encoder_stalled && !outcome.state.stop_requested_by_user,
+        output_folder_lost: outcome.state.output_folder_lost,
     }
 }
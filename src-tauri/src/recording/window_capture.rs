@@ -3,29 +3,43 @@ use crate::settings::RecordingSettings;
 use std::path::Path;
 
 use super::model::{
-    CaptureInput, CaptureWindowInfo, MonitorIndexSearchState, WindowCaptureAvailability,
-    WindowCaptureRegion, DEFAULT_CAPTURE_HEIGHT, DEFAULT_CAPTURE_WIDTH, MIN_CAPTURE_DIMENSION,
-    WINDOW_CAPTURE_CLOSED_WARNING, WINDOW_CAPTURE_MINIMIZED_WARNING,
+    CaptureInput, CaptureMonitorInfo, CaptureWindowDiagnostics, CaptureWindowInfo, GpuVendor,
+    MonitorIndexSearchState, VirtualDesktopLayout, VirtualDesktopMonitor,
+    WindowCaptureAvailability, WindowCaptureRegion, CAPTURE_MONITOR_MISSING_WARNING,
+    DEFAULT_CAPTURE_HEIGHT, DEFAULT_CAPTURE_WIDTH, DEFAULT_SCREEN_DPI, MIN_CAPTURE_DIMENSION,
+    NO_DISPLAY_TEST_PATTERN_WARNING, VIRTUAL_DESKTOP_MAX_DIMENSION, WINDOW_CAPTURE_CLOSED_WARNING,
+    WINDOW_CAPTURE_MINIMIZED_WARNING, WINDOW_REGION_RESIZE_TOLERANCE_PX,
 };
 
+#[cfg(target_os = "windows")]
+use windows::Win32::Graphics::Dxgi::{
+    CreateDXGIFactory1, IDXGIFactory1, DXGI_ADAPTER_FLAG_SOFTWARE,
+};
 #[cfg(target_os = "windows")]
 use windows_sys::core::BOOL;
 #[cfg(target_os = "windows")]
 use windows_sys::Win32::Foundation::{CloseHandle, HWND, LPARAM, POINT, RECT};
 #[cfg(target_os = "windows")]
 use windows_sys::Win32::Graphics::Gdi::{
-    ClientToScreen, EnumDisplayMonitors, GetMonitorInfoW, MonitorFromWindow, HDC, HMONITOR,
-    MONITORINFO, MONITOR_DEFAULTTONEAREST,
+    ClientToScreen, EnumDisplayDevicesW, EnumDisplayMonitors, GetMonitorInfoW, MonitorFromPoint,
+    MonitorFromWindow, DISPLAY_DEVICEW, HDC, HMONITOR, MONITORINFO, MONITORINFOEXW,
+    MONITOR_DEFAULTTONEAREST, MONITOR_DEFAULTTOPRIMARY,
 };
 #[cfg(target_os = "windows")]
 use windows_sys::Win32::System::Threading::{
     OpenProcess, QueryFullProcessImageNameW, PROCESS_QUERY_LIMITED_INFORMATION,
 };
 #[cfg(target_os = "windows")]
+use windows_sys::Win32::UI::HiDpi::{
+    GetDpiForWindow, SetThreadDpiAwarenessContext, DPI_AWARENESS_CONTEXT,
+    DPI_AWARENESS_CONTEXT_PER_MONITOR_AWARE_V2,
+};
+#[cfg(target_os = "windows")]
 use windows_sys::Win32::UI::WindowsAndMessaging::{
-    EnumWindows, GetClientRect, GetWindow, GetWindowLongW, GetWindowTextLengthW, GetWindowTextW,
-    GetWindowThreadProcessId, IsIconic, IsWindow, IsWindowVisible, GWL_EXSTYLE, GW_OWNER,
-    WS_EX_TOOLWINDOW,
+    EnumWindows, GetClassNameW, GetClientRect, GetCursorInfo, GetForegroundWindow, GetWindow,
+    GetWindowLongW, GetWindowTextLengthW, GetWindowTextW, GetWindowThreadProcessId, IsIconic,
+    IsWindow, IsWindowVisible, CURSORINFO, CURSOR_SHOWING, CURSOR_SUPPRESSED, GWL_EXSTYLE,
+    GW_OWNER, MONITORINFOF_PRIMARY, WS_EX_TOOLWINDOW,
 };
 
 fn normalize_optional_setting(value: Option<&String>) -> Option<String> {
@@ -52,7 +66,7 @@ fn normalize_capture_dimension(value: u32) -> u32 {
 }
 
 #[cfg(target_os = "windows")]
-fn resolve_process_name(process_id: u32) -> Option<String> {
+pub(crate) fn resolve_process_name(process_id: u32) -> Option<String> {
     if process_id == 0 {
         return None;
     }
@@ -109,6 +123,16 @@ pub(crate) fn sanitize_capture_dimensions(width: u32, height: u32) -> (u32, u32)
     )
 }
 
+/// Client-rect math can shift the region by a pixel as the window moves, so only
+/// size changes beyond the tolerance justify restarting the segment.
+pub(crate) fn is_significant_region_resize(
+    locked_region: WindowCaptureRegion,
+    current_region: WindowCaptureRegion,
+) -> bool {
+    locked_region.width.abs_diff(current_region.width) > WINDOW_REGION_RESIZE_TOLERANCE_PX
+        || locked_region.height.abs_diff(current_region.height) > WINDOW_REGION_RESIZE_TOLERANCE_PX
+}
+
 #[cfg(target_os = "windows")]
 unsafe extern "system" fn find_monitor_index_callback(
     monitor: HMONITOR,
@@ -150,6 +174,214 @@ fn find_monitor_index(target_monitor: HMONITOR) -> Option<u32> {
     state.found_index
 }
 
+#[cfg(target_os = "windows")]
+unsafe extern "system" fn collect_monitors_callback(
+    monitor: HMONITOR,
+    _hdc: HDC,
+    _rect: *mut RECT,
+    lparam: LPARAM,
+) -> BOOL {
+    let monitors = &mut *(lparam as *mut Vec<HMONITOR>);
+    monitors.push(monitor);
+    1
+}
+
+/// Monitors in enumeration order, which is the order `find_monitor_index` counts in.
+#[cfg(target_os = "windows")]
+fn enumerate_monitors() -> Result<Vec<HMONITOR>, String> {
+    let mut monitors: Vec<HMONITOR> = Vec::new();
+    let callback_result = unsafe {
+        EnumDisplayMonitors(
+            std::ptr::null_mut(),
+            std::ptr::null(),
+            Some(collect_monitors_callback),
+            (&mut monitors as *mut Vec<HMONITOR>) as LPARAM,
+        )
+    };
+
+    if callback_result == 0 {
+        return Err("Windows API returned an error while enumerating monitors".to_string());
+    }
+
+    Ok(monitors)
+}
+
+#[cfg(target_os = "windows")]
+fn monitor_info(monitor: HMONITOR) -> Option<MONITORINFOEXW> {
+    let mut monitor_info = MONITORINFOEXW::default();
+    monitor_info.monitorInfo.cbSize = std::mem::size_of::<MONITORINFOEXW>() as u32;
+    let info_result = unsafe {
+        GetMonitorInfoW(
+            monitor,
+            (&mut monitor_info as *mut MONITORINFOEXW).cast::<MONITORINFO>(),
+        )
+    };
+    (info_result != 0).then_some(monitor_info)
+}
+
+#[cfg(target_os = "windows")]
+fn monitor_device_name(monitor_info: &MONITORINFOEXW) -> String {
+    let name_length = monitor_info
+        .szDevice
+        .iter()
+        .position(|character| *character == 0)
+        .unwrap_or(monitor_info.szDevice.len());
+    String::from_utf16_lossy(&monitor_info.szDevice[..name_length])
+}
+
+#[cfg(target_os = "windows")]
+fn monitor_display_name(monitor: HMONITOR) -> Option<String> {
+    let monitor_info = monitor_info(monitor)?;
+
+    let mut display_device = DISPLAY_DEVICEW {
+        cb: std::mem::size_of::<DISPLAY_DEVICEW>() as u32,
+        ..Default::default()
+    };
+    // Enumerating the adapter device name yields the monitor attached to it.
+    let device_result =
+        unsafe { EnumDisplayDevicesW(monitor_info.szDevice.as_ptr(), 0, &mut display_device, 0) };
+    if device_result == 0 {
+        return None;
+    }
+
+    let name_length = display_device
+        .DeviceString
+        .iter()
+        .position(|character| *character == 0)
+        .unwrap_or(display_device.DeviceString.len());
+    let name = String::from_utf16_lossy(&display_device.DeviceString[..name_length]);
+    let trimmed_name = name.trim();
+    (!trimmed_name.is_empty()).then(|| trimmed_name.to_string())
+}
+
+/// The monitor the capture comes from. Monitor capture records its resolved output,
+/// window capture the monitor the window currently sits on.
+#[cfg(target_os = "windows")]
+fn resolve_capture_monitor(capture_input: &CaptureInput) -> Option<HMONITOR> {
+    let monitor = match capture_input {
+        CaptureInput::Monitor { output_idx, .. } => enumerate_monitors()
+            .ok()
+            .and_then(|monitors| monitors.get(*output_idx as usize).copied())
+            .unwrap_or_else(|| unsafe {
+                MonitorFromPoint(POINT { x: 0, y: 0 }, MONITOR_DEFAULTTOPRIMARY)
+            }),
+        CaptureInput::VirtualDesktop => unsafe {
+            MonitorFromPoint(POINT { x: 0, y: 0 }, MONITOR_DEFAULTTOPRIMARY)
+        },
+        CaptureInput::Window { .. } => {
+            let hwnd = to_window_handle(resolve_window_handle(capture_input)?);
+            unsafe { MonitorFromWindow(hwnd, MONITOR_DEFAULTTONEAREST) }
+        }
+        CaptureInput::TestPattern => return None,
+    };
+    (!monitor.is_null()).then_some(monitor)
+}
+
+/// Names the display the capture comes from.
+#[cfg(target_os = "windows")]
+pub(crate) fn resolve_capture_monitor_name(capture_input: &CaptureInput) -> Option<String> {
+    monitor_display_name(resolve_capture_monitor(capture_input)?)
+}
+
+/// A hardware GPU and the device names of its display outputs, e.g. `\\.\DISPLAY1`.
+#[cfg(target_os = "windows")]
+struct GpuAdapter {
+    vendor_id: u32,
+    output_device_names: Vec<String>,
+}
+
+/// Hardware GPUs as DXGI lists them. Unlike `EnumDisplayDevicesW`, this includes
+/// render-only GPUs without display outputs, such as the NVIDIA GPU of a MUX-less
+/// Optimus laptop.
+#[cfg(target_os = "windows")]
+fn enumerate_gpu_adapters() -> Vec<GpuAdapter> {
+    let utf16_to_string = |buffer: &[u16]| {
+        let length = buffer
+            .iter()
+            .position(|character| *character == 0)
+            .unwrap_or(buffer.len());
+        String::from_utf16_lossy(&buffer[..length])
+    };
+
+    let factory: IDXGIFactory1 = match unsafe { CreateDXGIFactory1() } {
+        Ok(factory) => factory,
+        Err(error) => {
+            tracing::debug!("Failed to create DXGI factory: {error}");
+            return Vec::new();
+        }
+    };
+
+    let mut adapters = Vec::new();
+    for adapter_index in 0.. {
+        let Ok(adapter) = (unsafe { factory.EnumAdapters1(adapter_index) }) else {
+            break;
+        };
+        let Ok(adapter_desc) = (unsafe { adapter.GetDesc1() }) else {
+            continue;
+        };
+        if adapter_desc.Flags & DXGI_ADAPTER_FLAG_SOFTWARE.0 as u32 != 0 {
+            continue;
+        }
+
+        let mut output_device_names = Vec::new();
+        for output_index in 0.. {
+            let Ok(output) = (unsafe { adapter.EnumOutputs(output_index) }) else {
+                break;
+            };
+            if let Ok(output_desc) = unsafe { output.GetDesc() } {
+                output_device_names.push(utf16_to_string(&output_desc.DeviceName));
+            }
+        }
+        adapters.push(GpuAdapter {
+            vendor_id: adapter_desc.VendorId,
+            output_device_names,
+        });
+    }
+    adapters
+}
+
+/// Vendor of the GPU driving the captured display, reported only on hybrid systems with
+/// GPUs from more than one vendor. Single-vendor systems have nothing to bind, so they
+/// keep the regular encoder selection.
+#[cfg(target_os = "windows")]
+pub(crate) fn resolve_hybrid_capture_gpu_vendor(capture_input: &CaptureInput) -> Option<GpuVendor> {
+    let adapters = enumerate_gpu_adapters();
+    let mut vendors: Vec<GpuVendor> = adapters
+        .iter()
+        .filter_map(|adapter| GpuVendor::from_pci_vendor_id(adapter.vendor_id))
+        .collect();
+    vendors.sort_by_key(|vendor| vendor.label());
+    vendors.dedup();
+    if vendors.len() < 2 {
+        return None;
+    }
+
+    let monitor_device_name =
+        monitor_device_name(&monitor_info(resolve_capture_monitor(capture_input)?)?);
+    let capture_vendor = adapters
+        .iter()
+        .find(|adapter| adapter.output_device_names.contains(&monitor_device_name))
+        .and_then(|adapter| GpuVendor::from_pci_vendor_id(adapter.vendor_id));
+    tracing::info!(
+        gpu_vendors = ?vendors,
+        capture_gpu_vendor = ?capture_vendor,
+        "Detected hybrid GPU system"
+    );
+    capture_vendor
+}
+
+#[cfg(not(target_os = "windows"))]
+pub(crate) fn resolve_hybrid_capture_gpu_vendor(
+    _capture_input: &CaptureInput,
+) -> Option<GpuVendor> {
+    None
+}
+
+#[cfg(not(target_os = "windows"))]
+pub(crate) fn resolve_capture_monitor_name(_capture_input: &CaptureInput) -> Option<String> {
+    None
+}
+
 #[cfg(target_os = "windows")]
 fn find_window_handle_by_title(window_title: &str) -> Option<usize> {
     let available_windows = list_capture_windows_internal().ok()?;
@@ -201,6 +433,45 @@ fn to_window_handle(window_hwnd: usize) -> HWND {
     window_hwnd as isize as HWND
 }
 
+/// Handle, title, class and owning process of the window a capture resolves to.
+#[cfg(target_os = "windows")]
+pub(crate) fn describe_capture_window_diagnostics(
+    capture_input: &CaptureInput,
+) -> Option<CaptureWindowDiagnostics> {
+    let window_hwnd = resolve_window_handle(capture_input)?;
+    let hwnd = to_window_handle(window_hwnd);
+
+    let mut process_id: u32 = 0;
+    unsafe {
+        GetWindowThreadProcessId(hwnd, &mut process_id as *mut u32);
+    }
+
+    let mut title_buffer = vec![0u16; 512];
+    let title_length =
+        unsafe { GetWindowTextW(hwnd, title_buffer.as_mut_ptr(), title_buffer.len() as i32) };
+    let mut class_buffer = vec![0u16; 256];
+    let class_length =
+        unsafe { GetClassNameW(hwnd, class_buffer.as_mut_ptr(), class_buffer.len() as i32) };
+    let utf16_text = |buffer: &[u16], length: i32| {
+        (length > 0).then(|| String::from_utf16_lossy(&buffer[..length as usize]))
+    };
+
+    Some(CaptureWindowDiagnostics {
+        hwnd: window_hwnd.to_string(),
+        title: utf16_text(&title_buffer, title_length),
+        class_name: utf16_text(&class_buffer, class_length),
+        process_id,
+        process_name: resolve_process_name(process_id),
+    })
+}
+
+#[cfg(not(target_os = "windows"))]
+pub(crate) fn describe_capture_window_diagnostics(
+    _capture_input: &CaptureInput,
+) -> Option<CaptureWindowDiagnostics> {
+    None
+}
+
 #[cfg(target_os = "windows")]
 fn window_client_rect_in_screen(window_hwnd: HWND) -> Option<RECT> {
     let mut client_rect = RECT {
@@ -242,10 +513,49 @@ fn window_client_rect_in_screen(window_hwnd: HWND) -> Option<RECT> {
     })
 }
 
+/// Switches the calling thread to per-monitor DPI awareness until dropped. Window and
+/// monitor rectangles are then reported in physical pixels, which is what the capture
+/// backends see, instead of being virtualized to the thread's DPI.
+#[cfg(target_os = "windows")]
+struct PerMonitorDpiScope {
+    previous_context: DPI_AWARENESS_CONTEXT,
+}
+
+#[cfg(target_os = "windows")]
+impl PerMonitorDpiScope {
+    fn enter() -> Self {
+        let previous_context =
+            unsafe { SetThreadDpiAwarenessContext(DPI_AWARENESS_CONTEXT_PER_MONITOR_AWARE_V2) };
+        Self { previous_context }
+    }
+
+    fn is_active(&self) -> bool {
+        !self.previous_context.is_null()
+    }
+}
+
+#[cfg(target_os = "windows")]
+impl Drop for PerMonitorDpiScope {
+    fn drop(&mut self) {
+        if self.is_active() {
+            unsafe { SetThreadDpiAwarenessContext(self.previous_context) };
+        }
+    }
+}
+
+/// Scales a coordinate measured at 96 DPI to the pixels of a display running at `dpi`.
+pub(crate) fn logical_to_physical_pixels(value: i32, dpi: u32) -> i32 {
+    if dpi == 0 || dpi == DEFAULT_SCREEN_DPI {
+        return value;
+    }
+    (f64::from(value) * f64::from(dpi) / f64::from(DEFAULT_SCREEN_DPI)).round() as i32
+}
+
 #[cfg(target_os = "windows")]
 pub(crate) fn resolve_window_capture_region(
     capture_input: &CaptureInput,
 ) -> Result<WindowCaptureRegion, String> {
+    let dpi_scope = PerMonitorDpiScope::enter();
     let window_hwnd = resolve_window_handle(capture_input)
         .ok_or_else(|| "Failed to resolve selected window handle".to_string())?;
     let hwnd = to_window_handle(window_hwnd);
@@ -295,12 +605,21 @@ pub(crate) fn resolve_window_capture_region(
         return Err("Selected window has no capturable area".to_string());
     }
 
-    let raw_width = (capture_right - capture_left) as u32;
-    let raw_height = (capture_bottom - capture_top) as u32;
-    let (width, height) = sanitize_capture_dimensions(raw_width, raw_height);
-
-    let offset_x = capture_left - monitor_info.rcMonitor.left;
-    let offset_y = capture_top - monitor_info.rcMonitor.top;
+    let mut raw_width = capture_right - capture_left;
+    let mut raw_height = capture_bottom - capture_top;
+    let mut offset_x = capture_left - monitor_info.rcMonitor.left;
+    let mut offset_y = capture_top - monitor_info.rcMonitor.top;
+
+    // Without per-monitor awareness the rectangles above are in logical units, so scale
+    // them by the window's DPI to line the region up with the rendered pixels.
+    if !dpi_scope.is_active() {
+        let window_dpi = unsafe { GetDpiForWindow(hwnd) };
+        raw_width = logical_to_physical_pixels(raw_width, window_dpi);
+        raw_height = logical_to_physical_pixels(raw_height, window_dpi);
+        offset_x = logical_to_physical_pixels(offset_x, window_dpi);
+        offset_y = logical_to_physical_pixels(offset_y, window_dpi);
+    }
+    let (width, height) = sanitize_capture_dimensions(raw_width as u32, raw_height as u32);
 
     Ok(WindowCaptureRegion {
         output_idx,
@@ -318,7 +637,177 @@ pub(crate) fn resolve_window_capture_region(
     Err("Window capture regions are only supported on Windows".to_string())
 }
 
+/// Whether a visible cursor sits outside what a ddagrab capture of `output_idx` (narrowed
+/// to `region`, if given) records. ddagrab only draws the pointer while it is over the
+/// captured output, so on another monitor it is simply missing from the video. A cursor
+/// the game hides is not reported, since it would not be drawn anyway.
+#[cfg(target_os = "windows")]
+pub(crate) fn is_cursor_outside_capture(
+    output_idx: u32,
+    region: Option<WindowCaptureRegion>,
+) -> bool {
+    // Region offsets are physical pixels, so the cursor has to be read in them too.
+    let dpi_scope = PerMonitorDpiScope::enter();
+    if !dpi_scope.is_active() {
+        return false;
+    }
+
+    let mut cursor_info = CURSORINFO {
+        cbSize: std::mem::size_of::<CURSORINFO>() as u32,
+        ..Default::default()
+    };
+    if unsafe { GetCursorInfo(&mut cursor_info) } == 0 || cursor_info.flags & CURSOR_SHOWING == 0 {
+        return false;
+    }
+
+    let Some(monitor) = enumerate_monitors()
+        .ok()
+        .and_then(|monitors| monitors.get(output_idx as usize).copied())
+    else {
+        return false;
+    };
+    let Some(monitor_info) = monitor_info(monitor) else {
+        return false;
+    };
+
+    let monitor_rect = monitor_info.monitorInfo.rcMonitor;
+    let capture_rect = match region {
+        Some(region) => RECT {
+            left: monitor_rect.left + region.offset_x,
+            top: monitor_rect.top + region.offset_y,
+            right: monitor_rect.left + region.offset_x + region.width as i32,
+            bottom: monitor_rect.top + region.offset_y + region.height as i32,
+        },
+        None => monitor_rect,
+    };
+    let cursor_position = cursor_info.ptScreenPos;
+    !(cursor_position.x >= capture_rect.left
+        && cursor_position.x < capture_rect.right
+        && cursor_position.y >= capture_rect.top
+        && cursor_position.y < capture_rect.bottom)
+}
+
+#[cfg(not(target_os = "windows"))]
+pub(crate) fn is_cursor_outside_capture(
+    _output_idx: u32,
+    _region: Option<WindowCaptureRegion>,
+) -> bool {
+    false
+}
+
+/// Whether Windows has suppressed the system cursor because pen or touch input is in use.
+/// Desktop Duplication then reports no pointer shape, so ddagrab's `draw_mouse` has nothing
+/// to draw. ddagrab and gdigrab only take a yes/no `draw_mouse`, with no way to pick
+/// which pointer to draw, so this can only be reported, not worked around.
+#[cfg(target_os = "windows")]
+pub(crate) fn is_system_cursor_suppressed() -> bool {
+    let mut cursor_info = CURSORINFO {
+        cbSize: std::mem::size_of::<CURSORINFO>() as u32,
+        ..Default::default()
+    };
+    let queried = unsafe { GetCursorInfo(&mut cursor_info) } != 0;
+    queried && cursor_info.flags & CURSOR_SUPPRESSED != 0
+}
+
+#[cfg(not(target_os = "windows"))]
+pub(crate) fn is_system_cursor_suppressed() -> bool {
+    false
+}
+
+/// Places each monitor at its offset from the top-left of the box around all of them.
+/// `monitor_rects` holds `output_idx` and the `[left, top, right, bottom]` of each monitor.
+/// Gaps left by monitors of different sizes are filled black by `xstack`.
+pub(crate) fn build_virtual_desktop_layout(
+    monitor_rects: &[(u32, [i32; 4])],
+) -> Option<VirtualDesktopLayout> {
+    let left = monitor_rects.iter().map(|(_, rect)| rect[0]).min()?;
+    let top = monitor_rects.iter().map(|(_, rect)| rect[1]).min()?;
+    let right = monitor_rects.iter().map(|(_, rect)| rect[2]).max()?;
+    let bottom = monitor_rects.iter().map(|(_, rect)| rect[3]).max()?;
+    let width = u32::try_from(right - left)
+        .ok()
+        .filter(|width| *width > 0)?;
+    let height = u32::try_from(bottom - top)
+        .ok()
+        .filter(|height| *height > 0)?;
+
+    let monitors = monitor_rects
+        .iter()
+        .map(|(output_idx, rect)| VirtualDesktopMonitor {
+            output_idx: *output_idx,
+            x: rect[0].abs_diff(left),
+            y: rect[1].abs_diff(top),
+        })
+        .collect();
+
+    let scale = (VIRTUAL_DESKTOP_MAX_DIMENSION as f64 / width.max(height) as f64).min(1.0);
+    let (output_width, output_height) = sanitize_capture_dimensions(
+        (width as f64 * scale) as u32,
+        (height as f64 * scale) as u32,
+    );
+
+    Some(VirtualDesktopLayout {
+        monitors,
+        width,
+        height,
+        output_width,
+        output_height,
+    })
+}
+
+#[cfg(target_os = "windows")]
+pub(crate) fn resolve_virtual_desktop_layout() -> Result<VirtualDesktopLayout, String> {
+    let _dpi_scope = PerMonitorDpiScope::enter();
+    let monitor_rects: Vec<(u32, [i32; 4])> = enumerate_monitors()?
+        .into_iter()
+        .enumerate()
+        .filter_map(|(output_idx, monitor)| {
+            let rect = monitor_info(monitor)?.monitorInfo.rcMonitor;
+            Some((
+                output_idx as u32,
+                [rect.left, rect.top, rect.right, rect.bottom],
+            ))
+        })
+        .collect();
+
+    build_virtual_desktop_layout(&monitor_rects)
+        .ok_or_else(|| "No monitors found to capture the virtual desktop".to_string())
+}
+
+#[cfg(not(target_os = "windows"))]
+pub(crate) fn resolve_virtual_desktop_layout() -> Result<VirtualDesktopLayout, String> {
+    Err("Virtual desktop capture is only supported on Windows.".to_string())
+}
+
+/// The `[left, top, right, bottom]` of a monitor on the virtual desktop, in physical
+/// pixels, for capture methods that address the desktop rather than an output index.
+#[cfg(target_os = "windows")]
+pub(crate) fn resolve_monitor_rect(output_idx: u32) -> Result<[i32; 4], String> {
+    let _dpi_scope = PerMonitorDpiScope::enter();
+    let monitor = enumerate_monitors()?
+        .get(output_idx as usize)
+        .copied()
+        .ok_or_else(|| format!("Monitor {output_idx} is no longer connected"))?;
+    let rect = monitor_info(monitor)
+        .ok_or_else(|| format!("Failed to read the bounds of monitor {output_idx}"))?
+        .monitorInfo
+        .rcMonitor;
+
+    Ok([rect.left, rect.top, rect.right, rect.bottom])
+}
+
+#[cfg(not(target_os = "windows"))]
+pub(crate) fn resolve_monitor_rect(_output_idx: u32) -> Result<[i32; 4], String> {
+    Err("Monitor capture is only supported on Windows.".to_string())
+}
+
 pub(crate) fn resolve_capture_dimensions(capture_input: &CaptureInput) -> (u32, u32) {
+    if let CaptureInput::VirtualDesktop = capture_input {
+        if let Ok(layout) = resolve_virtual_desktop_layout() {
+            return (layout.output_width, layout.output_height);
+        }
+    }
+
     #[cfg(target_os = "windows")]
     {
         if let CaptureInput::Window { .. } = capture_input {
@@ -351,6 +840,25 @@ fn evaluate_window_capture_by_hwnd(window_hwnd: usize) -> WindowCaptureAvailabil
     WindowCaptureAvailability::Available
 }
 
+/// Parses a window handle from the picker and checks it can be grabbed right now.
+#[cfg(target_os = "windows")]
+pub(crate) fn resolve_thumbnail_window_handle(raw_hwnd: &str) -> Result<usize, String> {
+    let window_hwnd =
+        parse_window_handle(raw_hwnd).ok_or_else(|| "Invalid window handle".to_string())?;
+    match evaluate_window_capture_by_hwnd(window_hwnd) {
+        WindowCaptureAvailability::Closed => Err("Window no longer exists".to_string()),
+        WindowCaptureAvailability::Minimized => {
+            Err("Window is minimized and cannot be previewed".to_string())
+        }
+        WindowCaptureAvailability::Available => Ok(window_hwnd),
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+pub(crate) fn resolve_thumbnail_window_handle(_raw_hwnd: &str) -> Result<usize, String> {
+    Err("Window capture is only supported on Windows.".to_string())
+}
+
 #[cfg(target_os = "windows")]
 fn evaluate_window_capture_by_title(window_title: &str) -> WindowCaptureAvailability {
     let available_windows = match list_capture_windows_internal() {
@@ -414,7 +922,9 @@ pub(crate) fn evaluate_window_capture_availability(
                 ..
             } => evaluate_window_capture_by_title(window_title),
             CaptureInput::Window { .. } => WindowCaptureAvailability::Closed,
-            CaptureInput::Monitor => WindowCaptureAvailability::Available,
+            CaptureInput::Monitor { .. }
+            | CaptureInput::VirtualDesktop
+            | CaptureInput::TestPattern => WindowCaptureAvailability::Available,
         }
     }
 
@@ -437,7 +947,19 @@ pub(crate) fn warning_message_for_window_capture(
 
 pub(crate) fn resolve_capture_input(settings: &RecordingSettings) -> Result<CaptureInput, String> {
     match settings.capture_source.as_str() {
-        "monitor" => Ok(CaptureInput::Monitor),
+        "test_pattern" => Ok(CaptureInput::TestPattern),
+        "virtual_desktop" => match list_capture_monitors_internal() {
+            Ok(monitors) if monitors.is_empty() => {
+                tracing::warn!(
+                    "No display found for virtual desktop capture. Recording a test pattern"
+                );
+                Ok(CaptureInput::TestPattern)
+            }
+            _ => Ok(CaptureInput::VirtualDesktop),
+        },
+        "monitor" => Ok(resolve_monitor_capture_input(
+            normalize_optional_setting(settings.capture_monitor_device.as_ref()).as_deref(),
+        )),
         "window" => {
             let requested_hwnd = normalize_optional_setting(settings.capture_window_hwnd.as_ref());
             let requested_title =
@@ -532,62 +1054,197 @@ pub(crate) fn resolve_capture_input(settings: &RecordingSettings) -> Result<Capt
                 capture_source = %other,
                 "Unknown capture source value. Falling back to primary monitor capture"
             );
-            Ok(CaptureInput::Monitor)
+            Ok(resolve_monitor_capture_input(None))
         }
     }
 }
 
+/// Visible, titled top-level windows that do not belong to FloorPoV itself.
 #[cfg(target_os = "windows")]
-unsafe extern "system" fn collect_capture_windows_callback(hwnd: HWND, lparam: LPARAM) -> BOOL {
+unsafe fn describe_capture_window(hwnd: HWND) -> Option<CaptureWindowInfo> {
     if IsWindowVisible(hwnd) == 0 {
-        return 1;
+        return None;
     }
 
     if !GetWindow(hwnd, GW_OWNER).is_null() {
-        return 1;
+        return None;
     }
 
     let ex_style = GetWindowLongW(hwnd, GWL_EXSTYLE) as u32;
     if ex_style & WS_EX_TOOLWINDOW != 0 {
-        return 1;
+        return None;
     }
 
     let mut process_id: u32 = 0;
     GetWindowThreadProcessId(hwnd, &mut process_id as *mut u32);
     if process_id == std::process::id() {
-        return 1;
+        return None;
     }
 
     let process_name = resolve_process_name(process_id);
 
     let title_length = GetWindowTextLengthW(hwnd);
     if title_length <= 0 {
-        return 1;
+        return None;
     }
 
     let mut title_buffer = vec![0u16; (title_length + 1) as usize];
     let copied_length = GetWindowTextW(hwnd, title_buffer.as_mut_ptr(), title_length + 1);
     if copied_length <= 0 {
-        return 1;
+        return None;
     }
 
     let title = String::from_utf16_lossy(&title_buffer[..copied_length as usize])
         .trim()
         .to_string();
     if title.is_empty() {
-        return 1;
+        return None;
     }
 
-    let capture_windows = &mut *(lparam as *mut Vec<CaptureWindowInfo>);
-    capture_windows.push(CaptureWindowInfo {
+    Some(CaptureWindowInfo {
         hwnd: (hwnd as usize).to_string(),
         title,
         process_name,
-    });
+        thumbnail_jpeg_base64: None,
+    })
+}
+
+#[cfg(target_os = "windows")]
+unsafe extern "system" fn collect_capture_windows_callback(hwnd: HWND, lparam: LPARAM) -> BOOL {
+    if let Some(capture_window) = describe_capture_window(hwnd) {
+        let capture_windows = &mut *(lparam as *mut Vec<CaptureWindowInfo>);
+        capture_windows.push(capture_window);
+    }
 
     1
 }
 
+/// Handle and title of the focused window when it is one that could be selected for
+/// capture; desktop, taskbar and FloorPoV's own windows yield `None`.
+pub(crate) fn foreground_capture_window() -> Option<(usize, String)> {
+    #[cfg(target_os = "windows")]
+    {
+        let hwnd = unsafe { GetForegroundWindow() };
+        if hwnd.is_null() {
+            return None;
+        }
+
+        let capture_window = unsafe { describe_capture_window(hwnd) }?;
+        Some((
+            parse_window_handle(&capture_window.hwnd)?,
+            capture_window.title,
+        ))
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    {
+        None
+    }
+}
+
+pub(crate) fn list_capture_monitors_internal() -> Result<Vec<CaptureMonitorInfo>, String> {
+    #[cfg(target_os = "windows")]
+    {
+        let monitors = enumerate_monitors()?;
+        let capture_monitors = monitors
+            .into_iter()
+            .enumerate()
+            .filter_map(|(output_idx, monitor)| {
+                let info = monitor_info(monitor)?;
+                Some(CaptureMonitorInfo {
+                    device_name: monitor_device_name(&info),
+                    name: monitor_display_name(monitor),
+                    output_idx: output_idx as u32,
+                    is_primary: info.monitorInfo.dwFlags & MONITORINFOF_PRIMARY != 0,
+                })
+            })
+            .collect();
+
+        Ok(capture_monitors)
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    {
+        Err("Monitor capture is only supported on Windows.".to_string())
+    }
+}
+
+/// Maps a saved monitor device name to its current `output_idx`. Display order can change
+/// across reboots and driver updates, so the index is looked up fresh for every recording.
+fn find_capture_monitor_output_idx(
+    capture_monitors: &[CaptureMonitorInfo],
+    device_name: &str,
+) -> Option<u32> {
+    capture_monitors
+        .iter()
+        .find(|monitor| monitor.device_name.eq_ignore_ascii_case(device_name))
+        .map(|monitor| monitor.output_idx)
+}
+
+fn resolve_monitor_capture_input(requested_device_name: Option<&str>) -> CaptureInput {
+    let capture_monitors = match list_capture_monitors_internal() {
+        Ok(monitors) if monitors.is_empty() => {
+            // Headless machines and VMs without a display still get the full pipeline.
+            tracing::warn!("No display found for monitor capture. Recording a test pattern");
+            return CaptureInput::TestPattern;
+        }
+        Ok(monitors) => monitors,
+        Err(error) => {
+            tracing::debug!(error, "Failed to enumerate monitors for monitor capture");
+            Vec::new()
+        }
+    };
+
+    if let Some(device_name) = requested_device_name {
+        if let Some(output_idx) = find_capture_monitor_output_idx(&capture_monitors, device_name) {
+            return CaptureInput::Monitor {
+                output_idx,
+                device_name: Some(device_name.to_string()),
+                use_gdigrab: false,
+            };
+        }
+
+        tracing::warn!(
+            monitor_device = %device_name,
+            "Selected capture monitor not found. Falling back to primary monitor capture"
+        );
+    }
+
+    let primary_output_idx = capture_monitors
+        .iter()
+        .find(|monitor| monitor.is_primary)
+        .map_or(0, |monitor| monitor.output_idx);
+    CaptureInput::Monitor {
+        output_idx: primary_output_idx,
+        device_name: None,
+        use_gdigrab: false,
+    }
+}
+
+/// Warning to show when monitor capture could not record what the settings asked for:
+/// the saved monitor is disconnected, or there is no display at all.
+pub(crate) fn capture_fallback_warning(
+    settings: &RecordingSettings,
+    capture_input: &CaptureInput,
+) -> Option<&'static str> {
+    if !matches!(
+        settings.capture_source.as_str(),
+        "monitor" | "virtual_desktop"
+    ) {
+        return None;
+    }
+
+    match capture_input {
+        CaptureInput::TestPattern => Some(NO_DISPLAY_TEST_PATTERN_WARNING),
+        CaptureInput::Monitor {
+            device_name: None, ..
+        } if normalize_optional_setting(settings.capture_monitor_device.as_ref()).is_some() => {
+            Some(CAPTURE_MONITOR_MISSING_WARNING)
+        }
+        _ => None,
+    }
+}
+
 pub(crate) fn list_capture_windows_internal() -> Result<Vec<CaptureWindowInfo>, String> {
     #[cfg(target_os = "windows")]
     {
@@ -618,3 +1275,89 @@ pub(crate) fn list_capture_windows_internal() -> Result<Vec<CaptureWindowInfo>,
         Err("Window capture is only supported on Windows.".to_string())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        build_virtual_desktop_layout, find_capture_monitor_output_idx, logical_to_physical_pixels,
+    };
+    use crate::recording::model::{CaptureMonitorInfo, VirtualDesktopMonitor};
+
+    #[test]
+    fn lays_out_monitors_left_of_and_above_the_primary() {
+        let layout =
+            build_virtual_desktop_layout(&[(0, [0, 0, 1920, 1080]), (1, [-1280, -300, 0, 724])])
+                .expect("Expected a layout");
+
+        assert_eq!(
+            layout.monitors,
+            vec![
+                VirtualDesktopMonitor {
+                    output_idx: 0,
+                    x: 1280,
+                    y: 300,
+                },
+                VirtualDesktopMonitor {
+                    output_idx: 1,
+                    x: 0,
+                    y: 0,
+                },
+            ]
+        );
+        assert_eq!((layout.width, layout.height), (3200, 1380));
+        assert_eq!((layout.output_width, layout.output_height), (3200, 1380));
+    }
+
+    #[test]
+    fn scales_wide_virtual_desktops_to_encoder_limits() {
+        let layout = build_virtual_desktop_layout(&[
+            (0, [0, 0, 2560, 1440]),
+            (1, [2560, 0, 5120, 1440]),
+            (2, [5120, 0, 7680, 1440]),
+        ])
+        .expect("Expected a layout");
+
+        assert_eq!((layout.width, layout.height), (7680, 1440));
+        assert_eq!((layout.output_width, layout.output_height), (4096, 768));
+        assert_eq!(build_virtual_desktop_layout(&[]), None);
+    }
+
+    #[test]
+    fn scales_logical_coordinates_on_a_150_percent_monitor() {
+        let dpi = 144;
+
+        assert_eq!(logical_to_physical_pixels(1280, dpi), 1920);
+        assert_eq!(logical_to_physical_pixels(720, dpi), 1080);
+        assert_eq!(logical_to_physical_pixels(-1707, dpi), -2561);
+        assert_eq!(logical_to_physical_pixels(1707, dpi), 2561);
+        assert_eq!(logical_to_physical_pixels(1280, 96), 1280);
+        assert_eq!(logical_to_physical_pixels(1280, 0), 1280);
+    }
+
+    #[test]
+    fn resolves_saved_monitor_by_device_name_after_reorder() {
+        let capture_monitors = vec![
+            CaptureMonitorInfo {
+                device_name: r"\\.\DISPLAY2".to_string(),
+                name: Some("DELL U2720Q".to_string()),
+                output_idx: 0,
+                is_primary: false,
+            },
+            CaptureMonitorInfo {
+                device_name: r"\\.\DISPLAY1".to_string(),
+                name: Some("LG 27GP850".to_string()),
+                output_idx: 1,
+                is_primary: true,
+            },
+        ];
+
+        assert_eq!(
+            find_capture_monitor_output_idx(&capture_monitors, r"\\.\display2"),
+            Some(0)
+        );
+        assert_eq!(
+            find_capture_monitor_output_idx(&capture_monitors, r"\\.\DISPLAY3"),
+            None
+        );
+    }
+}
@@ -0,0 +1,69 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::Instant;
+
+use base64::engine::general_purpose::STANDARD as BASE64_STANDARD;
+use base64::Engine;
+
+use super::model::{
+    WINDOW_THUMBNAIL_CACHE_TTL, WINDOW_THUMBNAIL_CAPTURE_TIMEOUT, WINDOW_THUMBNAIL_HEIGHT,
+    WINDOW_THUMBNAIL_WIDTH,
+};
+use super::segments;
+
+/// Recent thumbnails by window handle, so reopening the picker does not grab again.
+static WINDOW_THUMBNAIL_CACHE: Mutex<Option<HashMap<usize, (Instant, String)>>> = Mutex::new(None);
+/// Held for the duration of a grab so concurrent requests queue up instead of starting
+/// several WGC sessions at once.
+static WINDOW_THUMBNAIL_GRAB_LOCK: Mutex<()> = Mutex::new(());
+
+fn cached_thumbnail(window_hwnd: usize) -> Option<String> {
+    let mut cache = WINDOW_THUMBNAIL_CACHE.lock().ok()?;
+    let cache = cache.get_or_insert_with(HashMap::new);
+    cache.retain(|_, (captured_at, _)| captured_at.elapsed() < WINDOW_THUMBNAIL_CACHE_TTL);
+    cache
+        .get(&window_hwnd)
+        .map(|(_, thumbnail)| thumbnail.clone())
+}
+
+fn store_thumbnail(window_hwnd: usize, thumbnail: String) {
+    match WINDOW_THUMBNAIL_CACHE.lock() {
+        Ok(mut cache) => {
+            cache
+                .get_or_insert_with(HashMap::new)
+                .insert(window_hwnd, (Instant::now(), thumbnail));
+        }
+        Err(error) => tracing::warn!("Failed to lock window thumbnail cache: {error}"),
+    }
+}
+
+/// Returns a base64 JPEG of the window, reusing one grabbed within
+/// `WINDOW_THUMBNAIL_CACHE_TTL`.
+pub(crate) fn window_thumbnail_base64(
+    ffmpeg_binary_path: &Path,
+    window_hwnd: usize,
+) -> Result<String, String> {
+    if let Some(thumbnail) = cached_thumbnail(window_hwnd) {
+        return Ok(thumbnail);
+    }
+
+    let _grab_guard = WINDOW_THUMBNAIL_GRAB_LOCK
+        .lock()
+        .map_err(|error| format!("Failed to lock window thumbnail capture: {error}"))?;
+    // Another request may have grabbed this window while we waited for the lock.
+    if let Some(thumbnail) = cached_thumbnail(window_hwnd) {
+        return Ok(thumbnail);
+    }
+
+    let jpeg = segments::capture_window_thumbnail_jpeg(
+        ffmpeg_binary_path,
+        window_hwnd,
+        WINDOW_THUMBNAIL_WIDTH,
+        WINDOW_THUMBNAIL_HEIGHT,
+        WINDOW_THUMBNAIL_CAPTURE_TIMEOUT,
+    )?;
+    let thumbnail = BASE64_STANDARD.encode(jpeg);
+    store_thumbnail(window_hwnd, thumbnail.clone());
+    Ok(thumbnail)
+}
@@ -1,5 +1,10 @@
 use serde::{Deserialize, Serialize};
-use std::path::Path;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime};
+use tauri::{AppHandle, Manager};
+use tauri_plugin_store::StoreExt;
 
 use crate::recording::metadata as recording_metadata;
 
@@ -7,10 +12,70 @@ fn default_capture_source() -> String {
     "monitor".to_string()
 }
 
+fn default_cursor_mode() -> String {
+    "always".to_string()
+}
+
+fn default_system_audio_device() -> String {
+    "auto".to_string()
+}
+
+fn default_frame_sync() -> String {
+    "cfr".to_string()
+}
+
+fn default_rate_control() -> String {
+    "cbr".to_string()
+}
+
+fn default_vbr_quality() -> u32 {
+    23
+}
+
+fn default_wait_for_audio_connection() -> bool {
+    true
+}
+
 fn default_video_encoder_preference() -> String {
     "auto".to_string()
 }
 
+fn default_audio_sync_mode() -> String {
+    "balanced".to_string()
+}
+
+fn default_encoder_gpu() -> String {
+    "auto".to_string()
+}
+
+fn default_pixel_format() -> String {
+    "yuv420p".to_string()
+}
+
+fn default_window_status_poll_ms() -> u32 {
+    150
+}
+
+fn default_letterbox_color() -> String {
+    "black".to_string()
+}
+
+fn default_audio_channel_map() -> String {
+    "stereo".to_string()
+}
+
+fn default_audio_codec() -> String {
+    "aac".to_string()
+}
+
+fn default_audio_bitrate_kbps() -> u32 {
+    192
+}
+
+fn default_finalize_hook() -> String {
+    "off".to_string()
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct RecordingSettings {
     pub video_quality: String,
@@ -18,20 +83,221 @@ pub struct RecordingSettings {
     pub bitrate: u32,
     #[serde(default = "default_video_encoder_preference")]
     pub video_encoder_preference: String,
+    /// GPU that encodes when the encoder preference is `auto`: `auto` follows the GPU
+    /// driving the captured display on hybrid laptops, so frames are not copied between
+    /// adapters; `nvidia`, `intel` or `amd` pin the encoder to that GPU.
+    #[serde(default = "default_encoder_gpu")]
+    pub encoder_gpu: String,
+    /// `yuv420p` or `yuv420p10le`. 10-bit output falls back to 8-bit on encoders that
+    /// cannot produce it.
+    #[serde(default = "default_pixel_format")]
+    pub pixel_format: String,
     #[serde(default = "default_capture_source")]
     pub capture_source: String,
     #[serde(default)]
     pub capture_window_hwnd: Option<String>,
     #[serde(default)]
     pub capture_window_title: Option<String>,
+    /// Device name of the monitor to record (e.g. `\\.\DISPLAY2`). Unset or disconnected
+    /// monitors fall back to the primary one.
+    #[serde(default)]
+    pub capture_monitor_device: Option<String>,
+    /// Window capture switches to whichever window is focused. Every switch restarts the
+    /// FFmpeg segment, so the video briefly stutters at each focus change.
+    #[serde(default)]
+    pub follow_foreground: bool,
+    /// How often window capture checks whether the window was minimized or restored, and
+    /// so how quickly it switches to and from the black placeholder.
+    #[serde(default = "default_window_status_poll_ms")]
+    pub window_status_poll_ms: u32,
+    /// Window capture scales the window into the output resolution without stretching it,
+    /// filling the leftover bars with `letterbox_color`.
+    #[serde(default)]
+    pub preserve_aspect_ratio: bool,
+    /// FFmpeg color name (e.g. `black`) or `#RRGGBB` hex value for the letterbox bars.
+    #[serde(default = "default_letterbox_color")]
+    pub letterbox_color: String,
     pub enable_system_audio: bool,
     pub enable_recording_diagnostics: bool,
+    #[serde(default)]
+    pub auto_bitrate: bool,
+    /// `always`, `never` or `onMovement`. Monitor and region capture only draw the cursor
+    /// while it is over the recorded monitor, so it drops out of the video whenever it
+    /// moves to another one (for example after alt-tabbing to a second screen). They also
+    /// cannot choose which pointer to draw: while pen or touch input suppresses the system
+    /// cursor, no cursor is recorded and a warning is shown instead.
+    #[serde(default = "default_cursor_mode")]
+    pub cursor_mode: String,
+    /// Roll over to a new part file once a recording reaches this size. 0 disables it.
+    #[serde(default)]
+    pub max_file_size_mb: u64,
+    /// `cfr`, `vfr` or `passthrough`. Variable rates give smaller files for games with
+    /// uneven frame pacing but edit less predictably than constant frame rate.
+    #[serde(default = "default_frame_sync")]
+    pub frame_sync: String,
+    /// `cbr` or `vbr`. VBR applies to the hardware encoders only; libx264 stays on CBR.
+    #[serde(default = "default_rate_control")]
+    pub rate_control: String,
+    /// VBR quality target on the H.264 quantizer scale (1-51, lower is better).
+    #[serde(default = "default_vbr_quality")]
+    pub vbr_quality: u32,
+    /// Records system audio without the gain boost and limiter, keeping transients intact.
+    #[serde(default)]
+    pub bypass_audio_limiter: bool,
+    /// `balanced`, `strict`, `smooth` or `custom`; see `AudioResampleSync` for what each
+    /// preset trades off. `custom` uses the two raw `aresample` values below.
+    #[serde(default = "default_audio_sync_mode")]
+    pub audio_sync_mode: String,
+    #[serde(default)]
+    pub audio_resample_async: Option<u32>,
+    #[serde(default)]
+    pub audio_resample_min_hard_comp: Option<f64>,
+    /// Shifts system audio against the video to correct fixed device latency. Positive
+    /// values delay the audio, negative values advance it.
+    #[serde(default)]
+    pub audio_offset_ms: i32,
+    /// Encodes system audio as mono. Capture stays stereo; the downmix happens at encode.
+    #[serde(default)]
+    pub downmix_audio_mono: bool,
+    /// Drops system audio captured before FFmpeg connected to the audio socket, primes the
+    /// input with a short silence and fills any remaining WASAPI start-up time with silence,
+    /// so the first audio sample lines up with the first video frame. Off writes the
+    /// pre-connection audio first, which can put the opening moment out of step.
+    #[serde(default = "default_wait_for_audio_connection")]
+    pub wait_for_audio_connection: bool,
+    /// `stereo`, `swap`, `left`, `right` or `custom` (the `pan` map in
+    /// `audio_channel_map_custom`). Fixes one-sided or silent audio from virtual routing.
+    #[serde(default = "default_audio_channel_map")]
+    pub audio_channel_map: String,
+    #[serde(default)]
+    pub audio_channel_map_custom: Option<String>,
+    /// `aac` or `opus`. Opus falls back to AAC when FFmpeg has no `libopus` encoder.
+    #[serde(default = "default_audio_codec")]
+    pub audio_codec: String,
+    /// Stereo bitrate for system audio; mono recordings use half of it.
+    #[serde(default = "default_audio_bitrate_kbps")]
+    pub audio_bitrate_kbps: u32,
+    /// `off`, `copy` (to the folder in `finalize_hook_target`) or `http` (multipart POST to
+    /// the URL in `finalize_hook_target`). Runs after each recording file is finalized.
+    #[serde(default = "default_finalize_hook")]
+    pub finalize_hook: String,
+    #[serde(default)]
+    pub finalize_hook_target: Option<String>,
+    /// Used instead of the bundled FFmpeg when it passes a version check.
+    #[serde(default)]
+    pub custom_ffmpeg_path: Option<String>,
+    /// `auto` prefers the capture monitor's own audio output, `default` follows the
+    /// default output device, and anything else is a specific endpoint id.
+    #[serde(default = "default_system_audio_device")]
+    pub system_audio_device: String,
+    /// Executable name (e.g. `Wow.exe`) whose audio sessions are recorded on their own
+    /// through process loopback. Empty records everything played on the output device.
+    #[serde(default)]
+    pub system_audio_session: String,
+    /// Records video only, with a warning, when system audio is enabled but the machine
+    /// has no active output device (headless hosts, RDP sessions).
+    #[serde(default)]
+    pub audio_optional: bool,
+    /// Appends the session UUID to the file name so recordings started in the same second
+    /// stay distinct. The UUID is written to the metadata sidecar either way.
+    #[serde(default)]
+    pub session_id_in_filename: bool,
+    /// Writes fragmented MP4 instead of relocating the index with `+faststart` at the
+    /// end, so an unclean stop leaves a playable file at a small size overhead.
+    #[serde(default)]
+    pub fragmented_mp4: bool,
+    /// Drops black placeholder segments at the end of a session on finalize, so the
+    /// recording ends on the last segment with real content.
+    #[serde(default)]
+    pub trim_trailing_black: bool,
+    /// Reserves the estimated size of each segment before FFmpeg writes it, which keeps
+    /// long recordings on hard drives from fragmenting. Unused space is trimmed afterwards.
+    #[serde(default)]
+    pub preallocate_output: bool,
+    /// Mixes a short beep into the recorded system audio when a manual marker is dropped,
+    /// so markers can be found by ear in an editor. Has no effect without system audio.
+    #[serde(default)]
+    pub marker_tone: bool,
+    /// Emits `performance-stats` once a second with encode speed and queue depth.
+    #[serde(default)]
+    pub enable_performance_stats: bool,
+    /// Id from `RECORDING_PRESETS`. The frontend copies the preset into the fields above;
+    /// while it stays selected the preset's fixed bitrate replaces the scaled one and its
+    /// size limit applies when `max_file_size_mb` is 0.
+    #[serde(default)]
+    pub preset: Option<String>,
 }
 
+/// Upload-target settings. Output is always MP4 at the captured resolution, so a preset
+/// only covers what the recorder can change.
+#[derive(Clone, Serialize)]
+pub struct RecordingPreset {
+    pub id: &'static str,
+    pub label: &'static str,
+    pub description: &'static str,
+    pub video_quality: &'static str,
+    pub frame_rate: u32,
+    pub bitrate: u32,
+    pub video_encoder_preference: &'static str,
+    pub pixel_format: &'static str,
+    pub max_file_size_mb: u64,
+}
+
+pub const RECORDING_PRESETS: &[RecordingPreset] = &[
+    RecordingPreset {
+        id: "youtube_1080p60",
+        label: "YouTube 1080p60",
+        description: "12 Mbps at 60 FPS, YouTube's recommended rate for 1080p60 uploads.",
+        video_quality: "high",
+        frame_rate: 60,
+        bitrate: 12_000_000,
+        video_encoder_preference: "auto",
+        pixel_format: "yuv420p",
+        max_file_size_mb: 0,
+    },
+    RecordingPreset {
+        id: "discord_nitro_1080p",
+        label: "Discord Nitro 1080p",
+        description: "6 Mbps at 30 FPS, split into parts under Nitro's 500 MB upload limit.",
+        video_quality: "medium",
+        frame_rate: 30,
+        bitrate: 6_000_000,
+        video_encoder_preference: "auto",
+        pixel_format: "yuv420p",
+        max_file_size_mb: 490,
+    },
+    RecordingPreset {
+        id: "twitter_x",
+        label: "Twitter/X",
+        description: "5 Mbps at 30 FPS, split into parts under the 512 MB video limit.",
+        video_quality: "medium",
+        frame_rate: 30,
+        bitrate: 5_000_000,
+        video_encoder_preference: "auto",
+        pixel_format: "yuv420p",
+        max_file_size_mb: 500,
+    },
+    RecordingPreset {
+        id: "archive",
+        label: "Archive",
+        description: "40 Mbps at 60 FPS for editing later. Uses the CPU encoder.",
+        video_quality: "ultra",
+        frame_rate: 60,
+        bitrate: 40_000_000,
+        video_encoder_preference: "libx264",
+        // 8-bit: 10-bit H.264 (High 10) does not play in the app's own player.
+        pixel_format: "yuv420p",
+        max_file_size_mb: 0,
+    },
+];
+
 impl RecordingSettings {
     const REFERENCE_WIDTH: u32 = 1920;
     const REFERENCE_HEIGHT: u32 = 1080;
     const REFERENCE_FRAME_RATE: u32 = 30;
+    /// Every window status poll enumerates the top-level windows, so it is kept above this.
+    const MIN_WINDOW_STATUS_POLL_MS: u32 = 50;
+    const MAX_WINDOW_STATUS_POLL_MS: u32 = 2_000;
 
     fn bitrate_bounds_bps(quality: &str) -> (u32, u32) {
         match quality {
@@ -43,7 +309,112 @@ impl RecordingSettings {
         }
     }
 
+    fn bits_per_pixel(quality: &str) -> f64 {
+        match quality {
+            "low" => 0.05,
+            "medium" => 0.075,
+            "high" => 0.1,
+            "ultra" => 0.15,
+            _ => 0.09,
+        }
+    }
+
+    /// Derives the bitrate from the captured pixel rate so the quality tier keeps a
+    /// consistent bits-per-pixel budget across resolutions and frame rates.
+    pub fn quality_target_bitrate(&self, width: u32, height: u32) -> u32 {
+        let pixels_per_second = (width as f64) * (height as f64) * (self.frame_rate.max(1) as f64);
+        let target_bitrate =
+            (pixels_per_second * Self::bits_per_pixel(&self.video_quality)).round() as u32;
+        let (minimum_bitrate, maximum_bitrate) = Self::bitrate_bounds_bps(&self.video_quality);
+
+        target_bitrate.clamp(minimum_bitrate, maximum_bitrate)
+    }
+
+    /// FFmpeg's capture sources bake the cursor into each frame, so hiding it only while
+    /// idle would need post-capture compositing. `onMovement` is recorded like `always`.
+    pub fn capture_cursor(&self) -> bool {
+        match self.cursor_mode.as_str() {
+            "always" => true,
+            "never" => false,
+            "onMovement" => {
+                tracing::warn!(
+                    "Hiding an idle cursor is not supported by the capture backend; recording the cursor at all times"
+                );
+                true
+            }
+            other => {
+                tracing::warn!(
+                    cursor_mode = %other,
+                    "Unknown cursor mode value. Falling back to always capturing the cursor"
+                );
+                true
+            }
+        }
+    }
+
+    /// Unknown codecs are recorded as AAC.
+    pub fn audio_codec(&self) -> &'static str {
+        match self.audio_codec.as_str() {
+            "aac" => "aac",
+            "opus" => "opus",
+            other => {
+                tracing::warn!(audio_codec = %other, "Unknown audio codec. Falling back to AAC");
+                "aac"
+            }
+        }
+    }
+
+    /// Opus stays transparent at lower rates than AAC but gains nothing above 256 kbps.
+    pub fn audio_bitrate_bps(&self) -> u32 {
+        let (minimum_kbps, maximum_kbps) = match self.audio_codec() {
+            "opus" => (32, 256),
+            _ => (64, 320),
+        };
+        self.audio_bitrate_kbps.clamp(minimum_kbps, maximum_kbps) * 1000
+    }
+
+    pub fn window_status_poll_interval(&self) -> Duration {
+        let poll_ms = self.window_status_poll_ms.clamp(
+            Self::MIN_WINDOW_STATUS_POLL_MS,
+            Self::MAX_WINDOW_STATUS_POLL_MS,
+        );
+        if poll_ms != self.window_status_poll_ms {
+            tracing::warn!(
+                window_status_poll_ms = self.window_status_poll_ms,
+                clamped_poll_ms = poll_ms,
+                "Window status poll interval is out of range. Clamping it"
+            );
+        }
+        Duration::from_millis(u64::from(poll_ms))
+    }
+
+    pub fn active_preset(&self) -> Option<&'static RecordingPreset> {
+        let id = self.preset.as_deref()?;
+        let preset = RECORDING_PRESETS.iter().find(|preset| preset.id == id);
+        if preset.is_none() {
+            tracing::warn!(preset = %id, "Unknown recording preset. Using the individual settings");
+        }
+        preset
+    }
+
+    pub fn max_part_bytes(&self) -> Option<u64> {
+        let max_file_size_mb = match self.active_preset() {
+            Some(preset) if self.max_file_size_mb == 0 => preset.max_file_size_mb,
+            _ => self.max_file_size_mb,
+        };
+        (max_file_size_mb > 0).then(|| max_file_size_mb.saturating_mul(1024 * 1024))
+    }
+
+    /// A preset's bitrate is the upload target's limit, so it is used as-is rather than
+    /// scaled by the capture size.
     pub fn effective_bitrate(&self, width: u32, height: u32) -> u32 {
+        if let Some(preset) = self.active_preset() {
+            return preset.bitrate;
+        }
+        if self.auto_bitrate {
+            return self.quality_target_bitrate(width, height);
+        }
+
         let reference_workload = (Self::REFERENCE_WIDTH as f64)
             * (Self::REFERENCE_HEIGHT as f64)
             * (Self::REFERENCE_FRAME_RATE as f64);
@@ -61,14 +432,25 @@ impl RecordingSettings {
         target_bitrate.clamp(minimum_bitrate, maximum_bitrate)
     }
 
+    /// Video at the effective bitrate plus the system audio track, with 10% headroom for
+    /// container overhead and bitrate overshoot.
+    pub fn estimate_size_bytes(&self, width: u32, height: u32, duration_secs: u64) -> u64 {
+        let audio_bitrate = if self.enable_system_audio {
+            u64::from(self.audio_bitrate_bps())
+        } else {
+            0
+        };
+        let total_bitrate = self.effective_bitrate(width, height) as u64 + audio_bitrate;
+        let size_bytes = total_bitrate.saturating_mul(duration_secs) / 8;
+        (size_bytes as f64 * 1.1) as u64
+    }
+
     pub fn estimate_size_bytes_for_capture(&self, width: u32, height: u32) -> u64 {
-        let effective_bitrate = self.effective_bitrate(width, height) as u64;
-        let size_per_hour = (effective_bitrate * 3600) / 8;
-        (size_per_hour as f64 * 1.1) as u64
+        self.estimate_size_bytes(width, height, 3600)
     }
 }
 
-#[derive(Serialize)]
+#[derive(Clone, Serialize)]
 pub struct RecordingInfo {
     pub filename: String,
     pub file_path: String,
@@ -82,6 +464,26 @@ pub struct RecordingInfo {
     pub encounter_category: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub key_level: Option<u32>,
+    /// Recorded length from the metadata sidecar's segment timeline.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duration_seconds: Option<f64>,
+}
+
+#[derive(Clone, Copy, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RecordingSortKey {
+    Name,
+    #[default]
+    Date,
+    Size,
+    Duration,
+}
+
+#[derive(Serialize)]
+pub struct RecordingsPage {
+    pub recordings: Vec<RecordingInfo>,
+    /// Number of recordings in the folder, not just on this page.
+    pub total_count: usize,
 }
 
 #[derive(Serialize, Clone)]
@@ -91,6 +493,142 @@ pub struct CleanupResult {
     pub deleted_files: Vec<String>,
 }
 
+#[tauri::command]
+pub fn list_recording_presets() -> Vec<RecordingPreset> {
+    RECORDING_PRESETS.to_vec()
+}
+
+/// Same store file the settings page keeps its form state in.
+const SETTINGS_STORE_FILE: &str = "settings.json";
+const RECORDING_PROFILES_STORE_KEY: &str = "recording-profiles";
+/// Key the settings page saves its whole form under.
+const RECORDING_SETTINGS_STORE_KEY: &str = "recording-settings";
+const MAX_RECORDING_PROFILE_NAME_CHARS: usize = 64;
+
+fn normalize_profile_name(name: &str) -> Result<String, String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("Profile name must not be empty".to_string());
+    }
+    if name.chars().count() > MAX_RECORDING_PROFILE_NAME_CHARS {
+        return Err(format!(
+            "Profile name must be at most {MAX_RECORDING_PROFILE_NAME_CHARS} characters"
+        ));
+    }
+    Ok(name.to_string())
+}
+
+fn read_recording_profiles(
+    store: &tauri_plugin_store::Store<tauri::Wry>,
+) -> BTreeMap<String, serde_json::Value> {
+    store
+        .get(RECORDING_PROFILES_STORE_KEY)
+        .and_then(|value| match serde_json::from_value(value) {
+            Ok(profiles) => Some(profiles),
+            Err(error) => {
+                tracing::warn!("Ignoring unreadable recording profiles: {error}");
+                None
+            }
+        })
+        .unwrap_or_default()
+}
+
+fn settings_store(
+    app_handle: &AppHandle,
+) -> Result<std::sync::Arc<tauri_plugin_store::Store<tauri::Wry>>, String> {
+    app_handle
+        .store(SETTINGS_STORE_FILE)
+        .map_err(|error| format!("Failed to open settings store: {error}"))
+}
+
+fn save_settings_store(store: &tauri_plugin_store::Store<tauri::Wry>) -> Result<(), String> {
+    store
+        .save()
+        .map_err(|error| format!("Failed to save settings store: {error}"))
+}
+
+/// One field of the settings page's saved form, or `None` when the store cannot be read.
+fn stored_recording_setting(app_handle: &AppHandle, field: &str) -> Option<serde_json::Value> {
+    let store = match settings_store(app_handle) {
+        Ok(store) => store,
+        Err(error) => {
+            tracing::warn!("{error}");
+            return None;
+        }
+    };
+    store.get(RECORDING_SETTINGS_STORE_KEY)?.get(field).cloned()
+}
+
+/// The settings page's "Force Software Decoding" toggle; off when the store cannot be read.
+pub(crate) fn stored_force_software_decode(app_handle: &AppHandle) -> bool {
+    stored_recording_setting(app_handle, "forceSoftwareDecode")
+        .and_then(|value| value.as_bool())
+        .unwrap_or(false)
+}
+
+/// The settings page's custom FFmpeg path, if one is set.
+pub(crate) fn stored_custom_ffmpeg_path(app_handle: &AppHandle) -> Option<String> {
+    stored_recording_setting(app_handle, "customFfmpegPath")
+        .and_then(|value| value.as_str().map(str::to_string))
+}
+
+#[tauri::command]
+pub fn list_profiles(app_handle: AppHandle) -> Result<Vec<String>, String> {
+    let store = settings_store(&app_handle)?;
+    Ok(read_recording_profiles(&store).into_keys().collect())
+}
+
+/// Creates or overwrites the named profile. The capture target is not saved with it: a
+/// window handle is only valid while that window is open, and the window or monitor is
+/// picked for each recording.
+#[tauri::command]
+pub fn save_profile(
+    app_handle: AppHandle,
+    name: String,
+    mut settings: RecordingSettings,
+) -> Result<(), String> {
+    let name = normalize_profile_name(&name)?;
+    settings.capture_window_hwnd = None;
+    settings.capture_window_title = None;
+    settings.capture_monitor_device = None;
+    let settings = serde_json::to_value(settings)
+        .map_err(|error| format!("Failed to serialize recording profile: {error}"))?;
+    let store = settings_store(&app_handle)?;
+    let mut profiles = read_recording_profiles(&store);
+    profiles.insert(name.clone(), settings);
+    store.set(RECORDING_PROFILES_STORE_KEY, serde_json::json!(profiles));
+    save_settings_store(&store)?;
+    tracing::info!(profile = %name, "Saved recording profile");
+    Ok(())
+}
+
+/// Returns the named profile's settings for the settings page to apply to its form;
+/// recordings keep using whatever settings they are started with.
+#[tauri::command]
+pub fn load_profile(app_handle: AppHandle, name: String) -> Result<RecordingSettings, String> {
+    let name = normalize_profile_name(&name)?;
+    let store = settings_store(&app_handle)?;
+    let value = read_recording_profiles(&store)
+        .remove(&name)
+        .ok_or_else(|| format!("Recording profile '{name}' does not exist"))?;
+    serde_json::from_value(value)
+        .map_err(|error| format!("Recording profile '{name}' is invalid: {error}"))
+}
+
+#[tauri::command]
+pub fn delete_profile(app_handle: AppHandle, name: String) -> Result<(), String> {
+    let name = normalize_profile_name(&name)?;
+    let store = settings_store(&app_handle)?;
+    let mut profiles = read_recording_profiles(&store);
+    if profiles.remove(&name).is_none() {
+        return Err(format!("Recording profile '{name}' does not exist"));
+    }
+    store.set(RECORDING_PROFILES_STORE_KEY, serde_json::json!(profiles));
+    save_settings_store(&store)?;
+    tracing::info!(profile = %name, "Deleted recording profile");
+    Ok(())
+}
+
 #[tauri::command]
 pub fn get_default_output_folder() -> Result<String, String> {
     let home_dir = std::env::var("USERPROFILE")
@@ -102,34 +640,301 @@ pub fn get_default_output_folder() -> Result<String, String> {
     Ok(videos_dir.to_string_lossy().to_string())
 }
 
+/// Validates a newly chosen output folder and adds it to the asset protocol scope, which
+/// is otherwise only registered for the startup folder, so its recordings play at once.
 #[tauri::command]
-pub fn get_folder_size(path: String) -> Result<u64, String> {
-    let path = Path::new(&path);
-    if !path.exists() {
+pub fn set_output_folder(app_handle: AppHandle, path: String) -> Result<String, String> {
+    let folder = Path::new(path.trim());
+    if folder.as_os_str().is_empty() || !folder.is_absolute() {
+        return Err(format!("Output folder must be an absolute path: '{path}'"));
+    }
+
+    std::fs::create_dir_all(folder).map_err(|error| {
+        format!(
+            "Failed to create output folder '{}': {error}",
+            folder.display()
+        )
+    })?;
+    ensure_folder_writable(folder)?;
+
+    app_handle
+        .asset_protocol_scope()
+        .allow_directory(folder, true)
+        .map_err(|error| {
+            format!(
+                "Failed to allow output folder '{}' in asset scope: {error}",
+                folder.display()
+            )
+        })?;
+    tracing::info!(
+        output_folder = %folder.display(),
+        "Registered asset scope for output folder"
+    );
+
+    Ok(folder.to_string_lossy().to_string())
+}
+
+fn ensure_folder_writable(folder: &Path) -> Result<(), String> {
+    let probe_path = folder.join(".floorpov-write-test");
+    std::fs::write(&probe_path, b"").map_err(|error| {
+        format!(
+            "Output folder '{}' is not writable: {error}",
+            folder.display()
+        )
+    })?;
+    if let Err(error) = std::fs::remove_file(&probe_path) {
+        tracing::warn!(
+            probe_path = %probe_path.display(),
+            "Failed to remove output folder write probe: {error}"
+        );
+    }
+    Ok(())
+}
+
+/// Sizes of the `.mp4` files in a folder as of the last full scan, kept current as
+/// recordings are finalized or deleted so the storage check before each recording does
+/// not have to walk a large archive.
+struct CachedFolderSize {
+    file_sizes: BTreeMap<PathBuf, u64>,
+    scanned_at: Instant,
+}
+
+/// Files added or removed outside FloorPoV are only picked up by a full scan.
+const FOLDER_SIZE_CACHE_MAX_AGE: Duration = Duration::from_secs(5 * 60);
+
+static FOLDER_SIZE_CACHE: Mutex<BTreeMap<PathBuf, CachedFolderSize>> = Mutex::new(BTreeMap::new());
+
+/// Total size of the recordings in `path`. Served from the cache unless `force` is set or
+/// the last full scan is older than `FOLDER_SIZE_CACHE_MAX_AGE`.
+#[tauri::command]
+pub fn get_folder_size(path: String, force: Option<bool>) -> Result<u64, String> {
+    let folder = Path::new(&path);
+    if !folder.exists() {
         return Ok(0);
     }
 
-    let mut total_size: u64 = 0;
-    for entry in std::fs::read_dir(path).map_err(|e| e.to_string())? {
+    let mut cache = FOLDER_SIZE_CACHE
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    if !force.unwrap_or(false) {
+        if let Some(cached) = cache
+            .get(folder)
+            .filter(|cached| cached.scanned_at.elapsed() < FOLDER_SIZE_CACHE_MAX_AGE)
+        {
+            return Ok(cached.file_sizes.values().sum());
+        }
+    }
+
+    let mut file_sizes = BTreeMap::new();
+    for entry in std::fs::read_dir(folder).map_err(|e| e.to_string())? {
         let entry = entry.map_err(|e| e.to_string())?;
         let metadata = entry.metadata().map_err(|e| e.to_string())?;
-        if metadata.is_file() {
-            if let Some(ext) = entry.path().extension() {
-                if ext == "mp4" {
-                    total_size += metadata.len();
-                }
-            }
+        let entry_path = entry.path();
+        if metadata.is_file() && entry_path.extension().is_some_and(|ext| ext == "mp4") {
+            file_sizes.insert(entry_path, metadata.len());
         }
     }
 
+    let total_size = file_sizes.values().sum();
+    cache.insert(
+        folder.to_path_buf(),
+        CachedFolderSize {
+            file_sizes,
+            scanned_at: Instant::now(),
+        },
+    );
     Ok(total_size)
 }
 
+/// Counts a newly written recording towards its folder's cached size. Folders without a
+/// cache entry are left alone; their first size query scans them.
+pub(crate) fn record_recording_written(recording_path: &Path) {
+    let Some(folder) = recording_path.parent() else {
+        return;
+    };
+    let Ok(metadata) = std::fs::metadata(recording_path) else {
+        return;
+    };
+
+    if let Ok(mut cache) = FOLDER_SIZE_CACHE.lock() {
+        if let Some(cached) = cache.get_mut(folder) {
+            cached
+                .file_sizes
+                .insert(recording_path.to_path_buf(), metadata.len());
+        }
+    };
+}
+
+fn record_recording_removed(recording_path: &Path) {
+    let Some(folder) = recording_path.parent() else {
+        return;
+    };
+
+    if let Ok(mut cache) = FOLDER_SIZE_CACHE.lock() {
+        if let Some(cached) = cache.get_mut(folder) {
+            cached.file_sizes.remove(recording_path);
+        }
+    };
+}
+
+/// A listed recording together with the file stamps it was read from. The entry is reused
+/// until the recording or its metadata sidecar changes.
+struct CachedRecordingInfo {
+    size_bytes: u64,
+    modified_at: Option<SystemTime>,
+    sidecar_modified_at: Option<SystemTime>,
+    info: RecordingInfo,
+}
+
+static RECORDINGS_LIST_CACHE: Mutex<BTreeMap<PathBuf, CachedRecordingInfo>> =
+    Mutex::new(BTreeMap::new());
+
+fn file_modified_at(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
 #[tauri::command]
 pub fn get_recordings_list(folder_path: String) -> Result<Vec<RecordingInfo>, String> {
     read_recordings_list(&folder_path)
 }
 
+/// Lists one page of the folder's recordings for incremental loading. Without `offset`
+/// and `page_size` the whole list is returned, oldest first like `get_recordings_list`.
+/// Recordings without a known duration sort as the shortest.
+#[tauri::command]
+pub fn get_recordings_page(
+    folder_path: String,
+    offset: Option<usize>,
+    page_size: Option<usize>,
+    sort_by: Option<RecordingSortKey>,
+    descending: Option<bool>,
+) -> Result<RecordingsPage, String> {
+    let mut recordings = read_recordings_list(&folder_path)?;
+    let total_count = recordings.len();
+
+    match sort_by.unwrap_or_default() {
+        RecordingSortKey::Name => recordings.sort_by(|a, b| a.filename.cmp(&b.filename)),
+        // Already ordered by creation time.
+        RecordingSortKey::Date => {}
+        RecordingSortKey::Size => recordings.sort_by_key(|recording| recording.size_bytes),
+        RecordingSortKey::Duration => recordings.sort_by(|a, b| {
+            a.duration_seconds
+                .unwrap_or(0.0)
+                .total_cmp(&b.duration_seconds.unwrap_or(0.0))
+        }),
+    }
+    if descending.unwrap_or(false) {
+        recordings.reverse();
+    }
+
+    let recordings = recordings
+        .into_iter()
+        .skip(offset.unwrap_or(0))
+        .take(page_size.unwrap_or(usize::MAX))
+        .collect();
+
+    Ok(RecordingsPage {
+        recordings,
+        total_count,
+    })
+}
+
+/// Removes metadata sidecars whose recording was deleted outside FloorPoV and returns how
+/// many were removed. Sidecars of recordings that are still being written or wait for
+/// crash recovery are kept, since those only exist as a segment workspace so far.
+#[tauri::command]
+pub fn prune_orphaned_sidecars(output_folder: String) -> Result<usize, String> {
+    let folder = Path::new(&output_folder);
+    if !folder.exists() {
+        return Ok(0);
+    }
+
+    let mut pruned_count = 0;
+    for entry in std::fs::read_dir(folder).map_err(|e| e.to_string())? {
+        let path = entry.map_err(|e| e.to_string())?.path();
+        let Some(recording_path) = recording_metadata::sidecar_recording_path(&path) else {
+            continue;
+        };
+        if !path.is_file()
+            || recording_path.exists()
+            || crate::recording::has_segment_workspace(&recording_path)
+        {
+            continue;
+        }
+
+        match std::fs::remove_file(&path) {
+            Ok(()) => pruned_count += 1,
+            Err(error) => tracing::warn!(
+                sidecar_path = %path.display(),
+                error = %error,
+                "Failed to remove orphaned recording sidecar"
+            ),
+        }
+    }
+
+    if pruned_count > 0 {
+        tracing::info!(
+            output_folder = %output_folder,
+            pruned_count,
+            "Removed orphaned recording sidecars"
+        );
+    }
+    Ok(pruned_count)
+}
+
+/// Drops every cached entry and the cached size for the folder, writes a basic metadata
+/// sidecar for recordings that have none, and lists the folder from scratch. Use it after
+/// files were changed outside FloorPoV; `prune_sidecars` also removes sidecars whose
+/// recording is gone.
+#[tauri::command]
+pub fn refresh_recordings_cache(
+    output_folder: String,
+    prune_sidecars: Option<bool>,
+) -> Result<Vec<RecordingInfo>, String> {
+    if prune_sidecars.unwrap_or(false) {
+        prune_orphaned_sidecars(output_folder.clone())?;
+    }
+
+    let folder = Path::new(&output_folder);
+    if let Ok(mut cache) = RECORDINGS_LIST_CACHE.lock() {
+        cache.retain(|recording_path, _| !recording_path.starts_with(folder));
+    }
+    if let Ok(mut cache) = FOLDER_SIZE_CACHE.lock() {
+        cache.remove(folder);
+    }
+
+    if folder.exists() {
+        for entry in std::fs::read_dir(folder).map_err(|e| e.to_string())? {
+            let path = entry.map_err(|e| e.to_string())?.path();
+            if path.extension().is_none_or(|ext| ext != "mp4")
+                || recording_metadata::metadata_sidecar_path(&path).exists()
+            {
+                continue;
+            }
+
+            // Going through the update lock keeps a sidecar written meanwhile by a
+            // finishing recording instead of replacing it with an empty one.
+            if let Err(error) = recording_metadata::update_recording_metadata(&path, |_| Ok(())) {
+                tracing::warn!(
+                    recording_path = %path.display(),
+                    metadata_error = %error,
+                    "Failed to generate missing recording metadata sidecar"
+                );
+            }
+        }
+    }
+
+    let recordings = read_recordings_list(&output_folder)?;
+    tracing::info!(
+        output_folder = %output_folder,
+        recording_count = recordings.len(),
+        "Rebuilt recordings list cache"
+    );
+    Ok(recordings)
+}
+
 #[tauri::command]
 pub fn get_recording_metadata(
     file_path: String,
@@ -159,6 +964,7 @@ pub fn delete_recording(file_path: String) -> Result<(), String> {
     }
 
     std::fs::remove_file(path).map_err(|error| format!("Failed to delete recording: {error}"))?;
+    record_recording_removed(path);
 
     if let Err(error) = recording_metadata::delete_recording_metadata(path) {
         tracing::warn!(
@@ -177,14 +983,32 @@ fn read_recordings_list(folder_path: &str) -> Result<Vec<RecordingInfo>, String>
         return Ok(Vec::new());
     }
 
+    let folder = path;
     let mut recordings = Vec::new();
+    let mut cache = RECORDINGS_LIST_CACHE
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    let mut listed_paths = Vec::new();
 
-    for entry in std::fs::read_dir(path).map_err(|e| e.to_string())? {
+    for entry in std::fs::read_dir(folder).map_err(|e| e.to_string())? {
         let entry = entry.map_err(|e| e.to_string())?;
         let path = entry.path();
 
         if path.extension().is_some_and(|ext| ext == "mp4") {
             let metadata = entry.metadata().map_err(|e| e.to_string())?;
+            let modified_at = metadata.modified().ok();
+            let sidecar_modified_at =
+                file_modified_at(&recording_metadata::metadata_sidecar_path(&path));
+            listed_paths.push(path.clone());
+            if let Some(cached) = cache.get(&path).filter(|cached| {
+                cached.size_bytes == metadata.len()
+                    && cached.modified_at == modified_at
+                    && cached.sidecar_modified_at == sidecar_modified_at
+            }) {
+                recordings.push(cached.info.clone());
+                continue;
+            }
+
             let created_at = metadata
                 .created()
                 .map_err(|e| e.to_string())?
@@ -203,19 +1027,28 @@ fn read_recordings_list(folder_path: &str) -> Result<Vec<RecordingInfo>, String>
                     None
                 }
             };
-            let (zone_name, encounter_name, encounter_category, key_level) =
+            let (zone_name, encounter_name, encounter_category, key_level, duration_seconds) =
                 if let Some(metadata) = sidecar_metadata {
+                    let duration_seconds = (!metadata.segment_timeline.is_empty()).then(|| {
+                        metadata
+                            .segment_timeline
+                            .iter()
+                            .filter(|segment| !segment.force_killed)
+                            .map(|segment| segment.duration_seconds)
+                            .sum()
+                    });
                     (
                         metadata.zone_name,
                         metadata.encounter_name,
                         metadata.encounter_category,
                         metadata.key_level,
+                        duration_seconds,
                     )
                 } else {
-                    (None, None, None, None)
+                    (None, None, None, None, None)
                 };
 
-            recordings.push(RecordingInfo {
+            let info = RecordingInfo {
                 filename: path
                     .file_name()
                     .map(|n| n.to_string_lossy().to_string())
@@ -227,10 +1060,26 @@ fn read_recordings_list(folder_path: &str) -> Result<Vec<RecordingInfo>, String>
                 encounter_name,
                 encounter_category,
                 key_level,
-            });
+                duration_seconds,
+            };
+            recordings.push(info.clone());
+            cache.insert(
+                path,
+                CachedRecordingInfo {
+                    size_bytes: metadata.len(),
+                    modified_at,
+                    sidecar_modified_at,
+                    info,
+                },
+            );
         }
     }
 
+    // Forget recordings that were deleted or moved out of this folder.
+    cache.retain(|recording_path, _| {
+        recording_path.parent() != Some(folder) || listed_paths.contains(recording_path)
+    });
+
     recordings.sort_by_key(|r| r.created_at);
 
     Ok(recordings)
@@ -242,7 +1091,7 @@ pub fn cleanup_old_recordings(
     max_bytes: u64,
     required_space: u64,
 ) -> Result<CleanupResult, String> {
-    let current_size = get_folder_size(folder_path.clone())?;
+    let current_size = get_folder_size(folder_path.clone(), None)?;
     let target_size = max_bytes.saturating_sub(required_space);
 
     if current_size <= target_size {
@@ -274,6 +1123,7 @@ pub fn cleanup_old_recordings(
             );
             continue;
         }
+        record_recording_removed(file_path);
 
         if let Err(error) = recording_metadata::delete_recording_metadata(file_path) {
             tracing::warn!(
@@ -15,7 +15,7 @@ export function EventTooltip({ event, x }: EventTooltipProps) {
 
   const description =
     event.type === "death" ? `${event.target} died` :
-    event.type === "manual" ? "User marked this moment" :
+    event.type === "manual" ? event.note ?? "User marked this moment" :
     `${event.source} killed ${event.target}`;
 
   return (
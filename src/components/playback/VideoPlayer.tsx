@@ -37,7 +37,7 @@ export function VideoPlayer() {
     setVideoLoading,
   } = useVideo();
 
-  const { isRecording, recordingWarning } = useRecording();
+  const { isRecording, recordingWarning, performanceStats } = useRecording();
 
   const inlineSurfaceHostRef = useRef<HTMLDivElement>(null);
   const progressRef = useRef<HTMLDivElement>(null);
@@ -397,6 +397,18 @@ export function VideoPlayer() {
         </div>
       )}
 
+      {isRecording && performanceStats && (
+        <div
+          className="absolute bottom-3 left-3 z-20 rounded-sm border border-white/15 bg-neutral-950/70 px-2 py-1 font-mono text-[11px] text-neutral-200"
+          aria-label="Recording performance"
+        >
+          {performanceStats.encode_speed !== null && `${performanceStats.encode_speed.toFixed(2)}x`}
+          {performanceStats.encode_fps !== null && ` · ${performanceStats.encode_fps.toFixed(0)} fps`}
+          {` · ${performanceStats.frames_dropped} dropped`}
+          {performanceStats.audio_queue_depth !== null && ` · audio queue ${performanceStats.audio_queue_depth}`}
+        </div>
+      )}
+
       {!videoSrc && !isRecording && (
         <div className="absolute inset-0 flex flex-col items-center justify-center">
           <>
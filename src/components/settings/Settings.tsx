@@ -21,16 +21,30 @@ import { Input } from "../ui/Input";
 import { FormField } from "../ui/FormField";
 import {
   CaptureSource,
+  FinalizeHook,
+  CombatLogWatchMode,
   FrameRate,
   HOTKEY_OPTIONS,
+  MAX_AUDIO_OFFSET_MS,
   MAX_AUTO_RAID_RECORDING_SECONDS,
+  MAX_MARKER_LEAD_SECS,
   MAX_STORAGE_GB,
+  MAX_VBR_QUALITY,
+  MAX_WINDOW_STATUS_POLL_MS,
   MarkerHotkey,
   MIN_AUTO_RAID_RECORDING_SECONDS,
+  AudioChannelMap,
+  AudioCodec,
+  AudioSyncMode,
   MIN_STORAGE_GB,
+  MIN_VBR_QUALITY,
+  MIN_WINDOW_STATUS_POLL_MS,
+  PixelFormat,
   QUALITY_SETTINGS,
   RecordingSettings,
   VideoEncoderPreference,
+  EncoderGpu,
+  RateControl,
   VideoQuality,
 } from "../../types/settings";
 import { ReadOnlyPathField } from "./ReadOnlyPathField";
@@ -39,12 +53,26 @@ import { SettingsSelect, type SettingsSelectOption } from "./SettingsSelect";
 import { SettingsToggleField } from "./SettingsToggleField";
 import { shallowEqual } from "../../utils/comparison";
 import { formatBytes } from "../../utils/format";
-import { AvailableVideoEncoder, CaptureWindowInfo } from "../../types/recording";
+import {
+  AudioOutputDeviceInfo,
+  AudioSessionInfo,
+  AvailableVideoEncoder,
+  CaptureMonitorInfo,
+  CaptureWindowInfo,
+  RecordingPreset,
+} from "../../types/recording";
 
 const VIDEO_QUALITY_OPTIONS: SettingsSelectOption[] = Object.entries(QUALITY_SETTINGS).map(
   ([key, { label }]) => ({ value: key, label }),
 );
 
+const DEFAULT_AUDIO_DEVICE_OPTIONS: SettingsSelectOption[] = [
+  { value: "auto", label: "Match Capture Monitor (Recommended)" },
+  { value: "default", label: "System Default Output" },
+];
+
+const ALL_SYSTEM_AUDIO_OPTION: SettingsSelectOption = { value: "", label: "All System Audio" };
+
 const FRAME_RATE_OPTIONS: SettingsSelectOption[] = [
   { value: "30", label: "30 FPS" },
   { value: "60", label: "60 FPS" },
@@ -56,8 +84,67 @@ const MARKER_HOTKEY_OPTIONS: SettingsSelectOption[] = HOTKEY_OPTIONS.map(({ valu
 }));
 
 const CAPTURE_SOURCE_OPTIONS: SettingsSelectOption[] = [
-  { value: "monitor", label: "Primary Monitor" },
+  { value: "monitor", label: "Monitor" },
+  { value: "virtual_desktop", label: "All Monitors" },
   { value: "window", label: "Specific Window" },
+  { value: "test_pattern", label: "Test Pattern (No Display)" },
+];
+
+const AUDIO_SYNC_MODE_OPTIONS: SettingsSelectOption[] = [
+  { value: "balanced", label: "Balanced (Recommended)" },
+  { value: "strict", label: "Strict Sync" },
+  { value: "smooth", label: "Smooth" },
+  { value: "custom", label: "Custom" },
+];
+
+const AUDIO_CODEC_OPTIONS: SettingsSelectOption[] = [
+  { value: "aac", label: "AAC (Recommended)" },
+  { value: "opus", label: "Opus" },
+];
+
+const AUDIO_CHANNEL_MAP_OPTIONS: SettingsSelectOption[] = [
+  { value: "stereo", label: "Stereo (Recommended)" },
+  { value: "swap", label: "Swap Left and Right" },
+  { value: "left", label: "Left Channel on Both Sides" },
+  { value: "right", label: "Right Channel on Both Sides" },
+  { value: "custom", label: "Custom Map" },
+];
+
+const AUDIO_BITRATE_OPTIONS: SettingsSelectOption[] = [
+  { value: "96", label: "96 kbps" },
+  { value: "128", label: "128 kbps" },
+  { value: "192", label: "192 kbps" },
+  { value: "256", label: "256 kbps" },
+  { value: "320", label: "320 kbps (AAC only)" },
+];
+
+const FINALIZE_HOOK_OPTIONS: SettingsSelectOption[] = [
+  { value: "off", label: "Off" },
+  { value: "copy", label: "Copy to Folder" },
+  { value: "http", label: "Upload to URL" },
+];
+
+const COMBAT_LOG_WATCH_MODE_OPTIONS: SettingsSelectOption[] = [
+  { value: "auto", label: "Automatic (Recommended)" },
+  { value: "notify", label: "File Notifications" },
+  { value: "poll", label: "Polling" },
+];
+
+const PIXEL_FORMAT_OPTIONS: SettingsSelectOption[] = [
+  { value: "yuv420p", label: "8-bit (Recommended)" },
+  { value: "yuv420p10le", label: "10-bit (No In-App Playback)" },
+];
+
+const ENCODER_GPU_OPTIONS: SettingsSelectOption[] = [
+  { value: "auto", label: "Auto (Match Captured Display)" },
+  { value: "nvidia", label: "NVIDIA" },
+  { value: "intel", label: "Intel" },
+  { value: "amd", label: "AMD" },
+];
+
+const RATE_CONTROL_OPTIONS: SettingsSelectOption[] = [
+  { value: "cbr", label: "Constant Bitrate (CBR)" },
+  { value: "vbr", label: "Variable Bitrate (VBR)" },
 ];
 
 const VIDEO_ENCODER_PREFERENCE_VALUES: VideoEncoderPreference[] = [
@@ -69,17 +156,55 @@ const VIDEO_ENCODER_PREFERENCE_VALUES: VideoEncoderPreference[] = [
 ];
 
 const FIELD_IDS = {
+  recordingPreset: "settings-recording-preset",
   videoQuality: "settings-video-quality",
   videoEncoderPreference: "settings-video-encoder-preference",
+  encoderGpu: "settings-encoder-gpu",
+  rateControl: "settings-rate-control",
+  vbrQuality: "settings-vbr-quality",
+  pixelFormat: "settings-pixel-format",
   frameRate: "settings-frame-rate",
   captureSource: "settings-capture-source",
   captureWindow: "settings-capture-window",
+  captureMonitor: "settings-capture-monitor",
+  followForeground: "settings-follow-foreground",
+  windowStatusPollMs: "settings-window-status-poll-ms",
+  preserveAspectRatio: "settings-preserve-aspect-ratio",
+  letterboxColor: "settings-letterbox-color",
   outputFolder: "settings-output-folder",
   maxStorageGB: "settings-max-storage",
+  maxFileSizeMb: "settings-max-file-size",
+  sessionIdInFilename: "settings-session-id-in-filename",
+  finalizeHook: "settings-finalize-hook",
+  finalizeHookTarget: "settings-finalize-hook-target",
   wowFolder: "settings-wow-folder",
+  combatLogWatchMode: "settings-combat-log-watch-mode",
   markerHotkey: "settings-marker-hotkey",
+  markerLeadSecs: "settings-marker-lead-secs",
+  markerTone: "settings-marker-tone",
+  enableCombatEventStream: "settings-enable-combat-event-stream",
+  fragmentedMp4: "settings-fragmented-mp4",
+  trimTrailingBlack: "settings-trim-trailing-black",
+  preallocateOutput: "settings-preallocate-output",
+  enablePerformanceStats: "settings-enable-performance-stats",
   enableSystemAudio: "settings-enable-system-audio",
+  bypassAudioLimiter: "settings-bypass-audio-limiter",
+  audioSyncMode: "settings-audio-sync-mode",
+  audioCodec: "settings-audio-codec",
+  audioBitrateKbps: "settings-audio-bitrate",
+  audioResampleAsync: "settings-audio-resample-async",
+  audioResampleMinHardComp: "settings-audio-resample-min-hard-comp",
+  audioOffsetMs: "settings-audio-offset-ms",
+  downmixAudioMono: "settings-downmix-audio-mono",
+  waitForAudioConnection: "settings-wait-for-audio-connection",
+  audioChannelMap: "settings-audio-channel-map",
+  audioChannelMapCustom: "settings-audio-channel-map-custom",
+  systemAudioDevice: "settings-system-audio-device",
+  systemAudioSession: "settings-system-audio-session",
+  audioOptional: "settings-audio-optional",
   enableRecordingDiagnostics: "settings-enable-recording-diagnostics",
+  customFfmpegPath: "settings-custom-ffmpeg-path",
+  forceSoftwareDecode: "settings-force-software-decode",
   enableAutoRecording: "settings-enable-auto-recording",
   minAutoRaidRecordingSeconds: "settings-min-auto-raid-recording-seconds",
   enableAutoUpdate: "settings-enable-auto-update",
@@ -107,6 +232,30 @@ function isVideoEncoderPreference(value: string): value is VideoEncoderPreferenc
   return VIDEO_ENCODER_PREFERENCE_VALUES.includes(value as VideoEncoderPreference);
 }
 
+function isRateControl(value: string): value is RateControl {
+  return RATE_CONTROL_OPTIONS.some((option) => option.value === value);
+}
+
+function isEncoderGpu(value: string): value is EncoderGpu {
+  return ENCODER_GPU_OPTIONS.some((option) => option.value === value);
+}
+
+function isAudioCodec(value: string): value is AudioCodec {
+  return AUDIO_CODEC_OPTIONS.some((option) => option.value === value);
+}
+
+function isAudioChannelMap(value: string): value is AudioChannelMap {
+  return AUDIO_CHANNEL_MAP_OPTIONS.some((option) => option.value === value);
+}
+
+function isAudioSyncMode(value: string): value is AudioSyncMode {
+  return AUDIO_SYNC_MODE_OPTIONS.some((option) => option.value === value);
+}
+
+function isPixelFormat(value: string): value is PixelFormat {
+  return PIXEL_FORMAT_OPTIONS.some((option) => option.value === value);
+}
+
 function isMarkerHotkey(value: string): value is MarkerHotkey {
   return HOTKEY_OPTIONS.some((option) => option.value === value);
 }
@@ -117,13 +266,20 @@ export function Settings() {
   const [formData, setFormData] = useState<RecordingSettings>(settings);
   const [folderSize, setFolderSize] = useState<number>(0);
   const [isWowFolderValid, setIsWowFolderValid] = useState<boolean>(false);
+  const [isMarkerHotkeyAvailable, setIsMarkerHotkeyAvailable] = useState(true);
   const [hasChanges, setHasChanges] = useState(false);
   const [captureWindows, setCaptureWindows] = useState<CaptureWindowInfo[]>([]);
   const [isLoadingCaptureWindows, setIsLoadingCaptureWindows] = useState(false);
   const [captureWindowsError, setCaptureWindowsError] = useState<string | null>(null);
+  const [captureWindowThumbnail, setCaptureWindowThumbnail] = useState<string | null>(null);
   const [videoEncoderOptions, setVideoEncoderOptions] = useState<SettingsSelectOption[]>([
     { value: "auto", label: "Auto (Recommended)" },
   ]);
+  const [captureMonitors, setCaptureMonitors] = useState<CaptureMonitorInfo[]>([]);
+  const [audioDeviceOptions, setAudioDeviceOptions] =
+    useState<SettingsSelectOption[]>(DEFAULT_AUDIO_DEVICE_OPTIONS);
+  const [audioSessionApps, setAudioSessionApps] = useState<string[]>([]);
+  const [recordingPresets, setRecordingPresets] = useState<RecordingPreset[]>([]);
   const [isLoadingVideoEncoders, setIsLoadingVideoEncoders] = useState(false);
   const [videoEncodersError, setVideoEncodersError] = useState<string | null>(null);
 
@@ -171,10 +327,66 @@ export function Settings() {
     };
   }, [formData.wowFolder]);
 
+  useEffect(() => {
+    let isMounted = true;
+
+    const checkMarkerHotkey = async () => {
+      try {
+        const isAvailable = await invoke<boolean>('test_hotkey', {
+          shortcut: formData.markerHotkey,
+        });
+
+        if (isMounted) {
+          setIsMarkerHotkeyAvailable(isAvailable);
+        }
+      } catch (error) {
+        if (isMounted) {
+          setIsMarkerHotkeyAvailable(true);
+        }
+        console.error('Failed to test marker hotkey:', error);
+      }
+    };
+
+    checkMarkerHotkey();
+
+    return () => {
+      isMounted = false;
+    };
+  }, [formData.markerHotkey]);
+
   useEffect(() => {
     setHasChanges(!shallowEqual(formData, settings));
   }, [formData, settings]);
 
+  const handleOpenLogs = useCallback(async () => {
+    try {
+      await invoke("open_logs");
+    } catch (error) {
+      console.error("Failed to open log folder:", error);
+    }
+  }, []);
+
+  useEffect(() => {
+    const hwnd = formData.captureWindowHwnd;
+    if (formData.captureSource !== "window" || !hwnd) {
+      setCaptureWindowThumbnail(null);
+      return;
+    }
+
+    let cancelled = false;
+    invoke<string>("get_window_thumbnail", { hwnd })
+      .then((thumbnail) => {
+        if (!cancelled) setCaptureWindowThumbnail(thumbnail);
+      })
+      .catch(() => {
+        // Minimized or closed windows have no preview; the picker still works without it.
+        if (!cancelled) setCaptureWindowThumbnail(null);
+      });
+    return () => {
+      cancelled = true;
+    };
+  }, [formData.captureSource, formData.captureWindowHwnd]);
+
   const loadCaptureWindows = useCallback(async () => {
     setIsLoadingCaptureWindows(true);
     setCaptureWindowsError(null);
@@ -223,7 +435,9 @@ export function Settings() {
     setVideoEncodersError(null);
 
     try {
-      const encoders = await invoke<AvailableVideoEncoder[]>("get_available_video_encoders");
+      const encoders = await invoke<AvailableVideoEncoder[]>("get_available_video_encoders", {
+        customFfmpegPath: settings.customFfmpegPath || null,
+      });
       if (encoders.length === 0) {
         setVideoEncoderOptions([{ value: "auto", label: "Auto (Recommended)" }]);
         return;
@@ -247,12 +461,90 @@ export function Settings() {
     } finally {
       setIsLoadingVideoEncoders(false);
     }
-  }, []);
+  }, [settings.customFfmpegPath]);
 
   useEffect(() => {
     loadAvailableVideoEncoders();
   }, [loadAvailableVideoEncoders]);
 
+  useEffect(() => {
+    if (formData.captureSource !== "monitor") {
+      return;
+    }
+
+    invoke<CaptureMonitorInfo[]>("list_capture_monitors")
+      .then(setCaptureMonitors)
+      .catch((error) => {
+        console.error("Failed to list capture monitors:", error);
+      });
+  }, [formData.captureSource]);
+
+  useEffect(() => {
+    invoke<RecordingPreset[]>("list_recording_presets")
+      .then(setRecordingPresets)
+      .catch((error) => {
+        console.error("Failed to list recording presets:", error);
+      });
+  }, []);
+
+  const recordingPresetOptions = useMemo<SettingsSelectOption[]>(
+    () => [
+      { value: "custom", label: "Custom" },
+      ...recordingPresets.map((preset) => ({ value: preset.id, label: preset.label })),
+    ],
+    [recordingPresets],
+  );
+
+  const selectedRecordingPreset = recordingPresets.find(
+    (preset) => preset.id === formData.recordingPreset,
+  );
+
+  const applyRecordingPreset = (presetId: string) => {
+    const preset = recordingPresets.find((candidate) => candidate.id === presetId);
+    if (!preset) {
+      setFormData({ ...formData, recordingPreset: "custom" });
+      return;
+    }
+
+    setFormData({
+      ...formData,
+      recordingPreset: preset.id,
+      videoQuality: preset.video_quality,
+      frameRate: preset.frame_rate,
+      videoEncoderPreference: preset.video_encoder_preference,
+      pixelFormat: preset.pixel_format,
+    });
+  };
+
+  useEffect(() => {
+    invoke<AudioOutputDeviceInfo[]>("list_audio_output_devices")
+      .then((devices) => {
+        setAudioDeviceOptions([
+          ...DEFAULT_AUDIO_DEVICE_OPTIONS,
+          ...devices.map((device) => ({ value: device.id, label: device.name })),
+        ]);
+      })
+      .catch((error) => {
+        console.error("Failed to list audio output devices:", error);
+      });
+    invoke<AudioSessionInfo[]>("list_audio_sessions")
+      .then((sessions) => {
+        const appNames = sessions.flatMap((session) => (session.app_name ? [session.app_name] : []));
+        setAudioSessionApps([...new Set(appNames)].sort((a, b) => a.localeCompare(b)));
+      })
+      .catch((error) => {
+        console.error("Failed to list audio sessions:", error);
+      });
+  }, []);
+
+  const audioSessionOptions = useMemo(() => {
+    const appNames =
+      formData.systemAudioSession && !audioSessionApps.includes(formData.systemAudioSession)
+        ? [formData.systemAudioSession, ...audioSessionApps]
+        : audioSessionApps;
+    return [ALL_SYSTEM_AUDIO_OPTION, ...appNames.map((appName) => ({ value: appName, label: appName }))];
+  }, [audioSessionApps, formData.systemAudioSession]);
+
   const loadFolderSize = async () => {
     try {
       const size = await invoke<number>("get_folder_size", {
@@ -363,6 +655,31 @@ export function Settings() {
     isSavedCaptureWindowUnavailable,
   ]);
 
+  // Monitors are saved by device name because their index changes when displays are
+  // reconnected or drivers update. A saved monitor that is gone stays listed as unavailable.
+  const captureMonitorOptions: SettingsSelectOption[] = useMemo(() => {
+    const nextCaptureMonitorOptions: SettingsSelectOption[] = [
+      { value: "", label: "Primary Monitor" },
+      ...captureMonitors.map((monitor) => ({
+        value: monitor.device_name,
+        label: `${monitor.name ?? monitor.device_name}${monitor.is_primary ? " (Primary)" : ""}`,
+      })),
+    ];
+
+    if (
+      formData.captureMonitorDevice &&
+      !captureMonitors.some((monitor) => monitor.device_name === formData.captureMonitorDevice)
+    ) {
+      nextCaptureMonitorOptions.push({
+        value: formData.captureMonitorDevice,
+        label: `${formData.captureMonitorDevice} (Unavailable)`,
+        disabled: true,
+      });
+    }
+
+    return nextCaptureMonitorOptions;
+  }, [captureMonitors, formData.captureMonitorDevice]);
+
   const isCaptureWindowSelectDisabled = useMemo(() => {
     return isLoadingCaptureWindows || captureWindowOptions.every((option) => option.disabled);
   }, [captureWindowOptions, isLoadingCaptureWindows]);
@@ -421,7 +738,7 @@ export function Settings() {
                   ariaDescribedBy="settings-capture-source-help"
                 />
                 <p id="settings-capture-source-help" className="mt-1 text-xs text-neutral-400">
-                  Choose your source: primary monitor or one specific window.
+                  Choose your source: a monitor or one specific window.
                 </p>
               </div>
 
@@ -465,6 +782,14 @@ export function Settings() {
                     Pick a visible top-level window. Minimized windows can show black frames.
                   </p>
 
+                  {captureWindowThumbnail && (
+                    <img
+                      src={`data:image/jpeg;base64,${captureWindowThumbnail}`}
+                      alt="Preview of the selected window"
+                      className="w-48 rounded-sm border border-white/15"
+                    />
+                  )}
+
                   {isSavedCaptureWindowUnavailable && (
                     <p className="inline-flex items-center gap-1.5 text-xs text-amber-200">
                       <XCircle className="h-3.5 w-3.5" />
@@ -485,17 +810,132 @@ export function Settings() {
                       {captureWindowsError}
                     </p>
                   )}
+
+                  <SettingsToggleField
+                    id={FIELD_IDS.followForeground}
+                    checked={formData.followForeground}
+                    onChange={(checked) => {
+                      setFormData({
+                        ...formData,
+                        followForeground: checked,
+                      });
+                    }}
+                    label="Follow Focused Window"
+                    description="Starts on the selected window, then switches to whichever window you focus. Each switch briefly stutters the video while capture restarts."
+                  />
+
+                  <SettingsToggleField
+                    id={FIELD_IDS.preserveAspectRatio}
+                    checked={formData.preserveAspectRatio}
+                    onChange={(checked) => {
+                      setFormData({
+                        ...formData,
+                        preserveAspectRatio: checked,
+                      });
+                    }}
+                    label="Keep Window Aspect Ratio"
+                    description="Fits the window into the output resolution with bars instead of stretching it when the window shape does not match."
+                  />
+
+                  {formData.preserveAspectRatio && (
+                    <FormField
+                      id={FIELD_IDS.letterboxColor}
+                      label="Bar Color"
+                      description="A color name such as black or a hex value such as #202020."
+                    >
+                      <Input
+                        id={FIELD_IDS.letterboxColor}
+                        type="text"
+                        value={formData.letterboxColor}
+                        placeholder="black"
+                        onChange={(e) => setFormData({ ...formData, letterboxColor: e.target.value })}
+                      />
+                    </FormField>
+                  )}
+
+                  <FormField
+                    id={FIELD_IDS.windowStatusPollMs}
+                    label="Window Check Interval (ms)"
+                    description="How often FloorPoV checks whether the window was minimized or restored. Lower values switch to and from black frames faster when you alt-tab; higher values use less CPU."
+                  >
+                    <Input
+                      id={FIELD_IDS.windowStatusPollMs}
+                      type="number"
+                      min={MIN_WINDOW_STATUS_POLL_MS}
+                      max={MAX_WINDOW_STATUS_POLL_MS}
+                      step={50}
+                      value={formData.windowStatusPollMs}
+                      onChange={(e) =>
+                        setFormData({
+                          ...formData,
+                          windowStatusPollMs: Math.max(
+                            MIN_WINDOW_STATUS_POLL_MS,
+                            Math.min(MAX_WINDOW_STATUS_POLL_MS, parseInt(e.target.value) || 0),
+                          ),
+                        })
+                      }
+                    />
+                  </FormField>
                 </div>
               )}
 
+              {formData.captureSource === "virtual_desktop" && (
+                <p className="text-sm text-neutral-300">
+                  Records every monitor in one video, arranged as in Windows display settings. Gaps between monitors of different sizes are black, and desktops wider than 4096 pixels are scaled down.
+                </p>
+              )}
+
+              {formData.captureSource === "test_pattern" && (
+                <p className="text-sm text-neutral-300">
+                  Records a generated test pattern instead of the screen, for checking encoding, audio, and saving on machines without a display.
+                </p>
+              )}
+
               {formData.captureSource === "monitor" && (
-                <p className="text-sm text-neutral-300">Records your primary monitor via desktop duplication.</p>
+                <div className="space-y-2 rounded-sm border border-white/15 bg-black/20 p-3">
+                  <label htmlFor={FIELD_IDS.captureMonitor} className="mb-2 block text-sm text-neutral-300">
+                    Monitor
+                  </label>
+                  <SettingsSelect
+                    id={FIELD_IDS.captureMonitor}
+                    value={formData.captureMonitorDevice}
+                    options={captureMonitorOptions}
+                    onChange={(nextValue) => {
+                      setFormData({
+                        ...formData,
+                        captureMonitorDevice: nextValue,
+                      });
+                    }}
+                    ariaDescribedBy="settings-capture-monitor-help"
+                  />
+                  <p id="settings-capture-monitor-help" className="text-xs text-neutral-400">
+                    Records the selected monitor via desktop duplication. If it is disconnected, the primary monitor is recorded instead.
+                  </p>
+                </div>
               )}
             </div>
           </SettingsSection>
 
           <SettingsSection title="Video" icon={<Video className="h-4 w-4" />}>
             <div className="grid gap-4 md:grid-cols-2">
+              <div className="md:col-span-2">
+                <label htmlFor={FIELD_IDS.recordingPreset} className="mb-2 block text-sm text-neutral-300">
+                  Upload Target
+                </label>
+                <SettingsSelect
+                  id={FIELD_IDS.recordingPreset}
+                  value={formData.recordingPreset}
+                  options={recordingPresetOptions}
+                  onChange={applyRecordingPreset}
+                  ariaDescribedBy="settings-recording-preset-help"
+                />
+                <p id="settings-recording-preset-help" className="mt-1 text-xs text-neutral-400">
+                  {selectedRecordingPreset
+                    ? selectedRecordingPreset.description
+                    : "Pick a target to fill in the fields below. Changing any of them switches back to Custom."}
+                </p>
+              </div>
+
               <div>
                 <label htmlFor={FIELD_IDS.videoQuality} className="mb-2 block text-sm text-neutral-300">Quality Preset</label>
                 <SettingsSelect
@@ -504,7 +944,7 @@ export function Settings() {
                   options={VIDEO_QUALITY_OPTIONS}
                   onChange={(nextValue) => {
                     if (isVideoQuality(nextValue)) {
-                      setFormData({ ...formData, videoQuality: nextValue });
+                      setFormData({ ...formData, videoQuality: nextValue, recordingPreset: "custom" });
                     }
                   }}
                   ariaDescribedBy="settings-video-quality-help"
@@ -523,7 +963,7 @@ export function Settings() {
                   onChange={(nextValue) => {
                     const nextFrameRate = Number(nextValue);
                     if (isFrameRate(nextFrameRate)) {
-                      setFormData({ ...formData, frameRate: nextFrameRate });
+                      setFormData({ ...formData, frameRate: nextFrameRate, recordingPreset: "custom" });
                     }
                   }}
                 />
@@ -541,7 +981,7 @@ export function Settings() {
                   disabled={isLoadingVideoEncoders}
                   onChange={(nextValue) => {
                     if (isVideoEncoderPreference(nextValue)) {
-                      setFormData({ ...formData, videoEncoderPreference: nextValue });
+                      setFormData({ ...formData, videoEncoderPreference: nextValue, recordingPreset: "custom" });
                     }
                   }}
                   ariaDescribedBy="settings-video-encoder-help"
@@ -559,6 +999,99 @@ export function Settings() {
                   </p>
                 )}
               </div>
+
+              <div>
+                <label htmlFor={FIELD_IDS.encoderGpu} className="mb-2 block text-sm text-neutral-300">
+                  Encoder GPU
+                </label>
+                <SettingsSelect
+                  id={FIELD_IDS.encoderGpu}
+                  value={formData.encoderGpu}
+                  options={ENCODER_GPU_OPTIONS}
+                  disabled={formData.videoEncoderPreference !== "auto"}
+                  onChange={(nextValue) => {
+                    if (isEncoderGpu(nextValue)) {
+                      setFormData({ ...formData, encoderGpu: nextValue });
+                    }
+                  }}
+                  ariaDescribedBy="settings-encoder-gpu-help"
+                />
+                <p id="settings-encoder-gpu-help" className="mt-1 text-xs text-neutral-400">
+                  On laptops with two GPUs, Auto encodes on the GPU driving the recorded display to avoid copying frames between GPUs. Only applies when the video encoder is Auto.
+                </p>
+              </div>
+
+              <div>
+                <label htmlFor={FIELD_IDS.rateControl} className="mb-2 block text-sm text-neutral-300">
+                  Rate Control
+                </label>
+                <SettingsSelect
+                  id={FIELD_IDS.rateControl}
+                  value={formData.rateControl}
+                  options={RATE_CONTROL_OPTIONS}
+                  onChange={(nextValue) => {
+                    if (isRateControl(nextValue)) {
+                      setFormData({ ...formData, rateControl: nextValue });
+                    }
+                  }}
+                  ariaDescribedBy="settings-rate-control-help"
+                />
+                <p id="settings-rate-control-help" className="mt-1 text-xs text-neutral-400">
+                  VBR saves bits on static scenes and spends up to 1.5x the bitrate on fast motion. Applies to NVIDIA, Intel and AMD encoders; the CPU encoder always uses CBR.
+                </p>
+              </div>
+
+              {formData.rateControl === "vbr" && (
+                <FormField
+                  id={FIELD_IDS.vbrQuality}
+                  label="VBR Quality Target"
+                  description="Lower values look better and use more of the bitrate budget. 23 suits most gameplay. Not used by AMD encoders."
+                >
+                  <Input
+                    id={FIELD_IDS.vbrQuality}
+                    type="number"
+                    min={MIN_VBR_QUALITY}
+                    max={MAX_VBR_QUALITY}
+                    step={1}
+                    value={formData.vbrQuality}
+                    onChange={(e) =>
+                      setFormData({
+                        ...formData,
+                        vbrQuality: Math.max(
+                          MIN_VBR_QUALITY,
+                          Math.min(MAX_VBR_QUALITY, parseInt(e.target.value) || MIN_VBR_QUALITY),
+                        ),
+                      })
+                    }
+                  />
+                </FormField>
+              )}
+
+              <div>
+                <label htmlFor={FIELD_IDS.pixelFormat} className="mb-2 block text-sm text-neutral-300">
+                  Color Depth
+                </label>
+                <SettingsSelect
+                  id={FIELD_IDS.pixelFormat}
+                  value={formData.pixelFormat}
+                  options={PIXEL_FORMAT_OPTIONS}
+                  onChange={(nextValue) => {
+                    if (isPixelFormat(nextValue)) {
+                      setFormData({ ...formData, pixelFormat: nextValue, recordingPreset: "custom" });
+                    }
+                  }}
+                  ariaDescribedBy="settings-pixel-format-help"
+                />
+                <p id="settings-pixel-format-help" className="mt-1 text-xs text-neutral-400">
+                  10-bit needs the CPU (libx264) encoder; hardware encoders record 8-bit instead.
+                </p>
+                {formData.pixelFormat === "yuv420p10le" && (
+                  <p className="mt-1 inline-flex items-center gap-1.5 text-xs text-amber-200">
+                    <AlertTriangle className="h-3.5 w-3.5" />
+                    10-bit H.264 recordings cannot be played in FloorPoV; open them in an external player such as VLC or mpv.
+                  </p>
+                )}
+              </div>
             </div>
           </SettingsSection>
 
@@ -577,6 +1110,277 @@ export function Settings() {
                 }}
                 label="Enable System Audio"
               />
+
+              {formData.enableSystemAudio && (
+                <div>
+                  <label htmlFor={FIELD_IDS.systemAudioDevice} className="mb-2 block text-sm text-neutral-300">
+                    Audio Output Device
+                  </label>
+                  <SettingsSelect
+                    id={FIELD_IDS.systemAudioDevice}
+                    value={formData.systemAudioDevice}
+                    options={audioDeviceOptions}
+                    onChange={(nextValue) => {
+                      setFormData({ ...formData, systemAudioDevice: nextValue });
+                    }}
+                    ariaDescribedBy="settings-system-audio-device-help"
+                  />
+                  <p id="settings-system-audio-device-help" className="mt-1 text-xs text-neutral-400">
+                    Matching uses the audio output of the recorded monitor, such as an HDMI TV, and falls back to the system default.
+                  </p>
+                </div>
+              )}
+
+              {formData.enableSystemAudio && (
+                <div>
+                  <label htmlFor={FIELD_IDS.systemAudioSession} className="mb-2 block text-sm text-neutral-300">
+                    Audio Source
+                  </label>
+                  <SettingsSelect
+                    id={FIELD_IDS.systemAudioSession}
+                    value={formData.systemAudioSession}
+                    options={audioSessionOptions}
+                    onChange={(nextValue) => {
+                      setFormData({ ...formData, systemAudioSession: nextValue });
+                    }}
+                    ariaDescribedBy="settings-system-audio-session-help"
+                  />
+                  <p id="settings-system-audio-session-help" className="mt-1 text-xs text-neutral-400">
+                    Records only the selected application's audio, leaving out voice chat and music. The application must be running when recording starts.
+                  </p>
+                </div>
+              )}
+
+              {formData.enableSystemAudio && (
+                <SettingsToggleField
+                  id={FIELD_IDS.bypassAudioLimiter}
+                  checked={formData.bypassAudioLimiter}
+                  onChange={(checked) => {
+                    setFormData({
+                      ...formData,
+                      bypassAudioLimiter: checked,
+                    });
+                  }}
+                  label="Preserve Audio Dynamics"
+                  description="Skips the volume boost and limiter so sharp sound cues keep their transients. Recordings will be quieter, and loud peaks are no longer caught before they clip."
+                />
+              )}
+
+              {formData.enableSystemAudio && (
+                <SettingsToggleField
+                  id={FIELD_IDS.downmixAudioMono}
+                  checked={formData.downmixAudioMono}
+                  onChange={(checked) => {
+                    setFormData({
+                      ...formData,
+                      downmixAudioMono: checked,
+                    });
+                  }}
+                  label="Mono Audio"
+                  description="Mixes system audio down to a single channel when encoding, halving the audio bitrate. Good for voice-focused recordings; positional sound cues are lost."
+                />
+              )}
+
+              {formData.enableSystemAudio && (
+                <SettingsToggleField
+                  id={FIELD_IDS.waitForAudioConnection}
+                  checked={formData.waitForAudioConnection}
+                  onChange={(checked) => {
+                    setFormData({
+                      ...formData,
+                      waitForAudioConnection: checked,
+                    });
+                  }}
+                  label="Align Audio Start"
+                  description="Discards system audio captured before the encoder is ready and fills the audio device's start-up time with silence, so audio and video begin together. Turning this off can leave the opening moment of a recording out of sync."
+                />
+              )}
+
+              {formData.enableSystemAudio && (
+                <div className="grid gap-4 sm:grid-cols-2">
+                  <div>
+                    <label htmlFor={FIELD_IDS.audioChannelMap} className="mb-2 block text-sm text-neutral-300">
+                      Channel Mapping
+                    </label>
+                    <SettingsSelect
+                      id={FIELD_IDS.audioChannelMap}
+                      value={formData.audioChannelMap}
+                      options={AUDIO_CHANNEL_MAP_OPTIONS}
+                      onChange={(nextValue) => {
+                        if (isAudioChannelMap(nextValue)) {
+                          setFormData({ ...formData, audioChannelMap: nextValue });
+                        }
+                      }}
+                      ariaDescribedBy="settings-audio-channel-map-help"
+                    />
+                    <p id="settings-audio-channel-map-help" className="mt-1 text-xs text-neutral-400">
+                      Fixes recordings that are silent or one-sided with virtual audio routing. Windows mixes surround output down to stereo before capture.
+                    </p>
+                  </div>
+
+                  {formData.audioChannelMap === "custom" && (
+                    <FormField
+                      id={FIELD_IDS.audioChannelMapCustom}
+                      label="Custom Channel Map"
+                      description="FFmpeg pan syntax for the two captured channels, c0 (left) and c1 (right)."
+                    >
+                      <Input
+                        id={FIELD_IDS.audioChannelMapCustom}
+                        type="text"
+                        value={formData.audioChannelMapCustom}
+                        placeholder="c0=c1|c1=0.5*c0+0.5*c1"
+                        onChange={(e) => setFormData({ ...formData, audioChannelMapCustom: e.target.value })}
+                      />
+                    </FormField>
+                  )}
+                </div>
+              )}
+
+              {formData.enableSystemAudio && (
+                <div className="grid gap-4 sm:grid-cols-2">
+                  <div>
+                    <label htmlFor={FIELD_IDS.audioCodec} className="mb-2 block text-sm text-neutral-300">
+                      Audio Codec
+                    </label>
+                    <SettingsSelect
+                      id={FIELD_IDS.audioCodec}
+                      value={formData.audioCodec}
+                      options={AUDIO_CODEC_OPTIONS}
+                      onChange={(nextValue) => {
+                        if (isAudioCodec(nextValue)) {
+                          setFormData({ ...formData, audioCodec: nextValue });
+                        }
+                      }}
+                      ariaDescribedBy="settings-audio-codec-help"
+                    />
+                    <p id="settings-audio-codec-help" className="mt-1 text-xs text-neutral-400">
+                      Opus sounds better at low bitrates, but some editors and older players cannot read Opus in MP4.
+                    </p>
+                  </div>
+
+                  <div>
+                    <label htmlFor={FIELD_IDS.audioBitrateKbps} className="mb-2 block text-sm text-neutral-300">
+                      Audio Bitrate
+                    </label>
+                    <SettingsSelect
+                      id={FIELD_IDS.audioBitrateKbps}
+                      value={String(formData.audioBitrateKbps)}
+                      options={AUDIO_BITRATE_OPTIONS}
+                      onChange={(nextValue) => {
+                        setFormData({ ...formData, audioBitrateKbps: Number(nextValue) });
+                      }}
+                      ariaDescribedBy="settings-audio-bitrate-help"
+                    />
+                    <p id="settings-audio-bitrate-help" className="mt-1 text-xs text-neutral-400">
+                      Higher rates suit music-heavy content. Mono recordings use half of this.
+                    </p>
+                  </div>
+                </div>
+              )}
+
+              {formData.enableSystemAudio && (
+                <div>
+                  <label htmlFor={FIELD_IDS.audioSyncMode} className="mb-2 block text-sm text-neutral-300">
+                    Audio Sync
+                  </label>
+                  <SettingsSelect
+                    id={FIELD_IDS.audioSyncMode}
+                    value={formData.audioSyncMode}
+                    options={AUDIO_SYNC_MODE_OPTIONS}
+                    onChange={(nextValue) => {
+                      if (isAudioSyncMode(nextValue)) {
+                        setFormData({ ...formData, audioSyncMode: nextValue });
+                      }
+                    }}
+                    ariaDescribedBy="settings-audio-sync-mode-help"
+                  />
+                  <p id="settings-audio-sync-mode-help" className="mt-1 text-xs text-neutral-400">
+                    Strict Sync continuously corrects clock drift when audio slowly falls out of sync. Smooth only fixes large gaps, avoiding clicks on systems without drift.
+                  </p>
+                </div>
+              )}
+
+              {formData.enableSystemAudio && formData.audioSyncMode === "custom" && (
+                <>
+                  <FormField
+                    id={FIELD_IDS.audioResampleAsync}
+                    label="Resample Async (samples per second)"
+                    description="How far audio may be stretched each second to follow timestamps. 1 only fills gaps."
+                  >
+                    <Input
+                      id={FIELD_IDS.audioResampleAsync}
+                      type="number"
+                      min={1}
+                      max={48000}
+                      value={formData.audioResampleAsync}
+                      onChange={(e) =>
+                        setFormData({ ...formData, audioResampleAsync: parseInt(e.target.value) || 1 })
+                      }
+                    />
+                  </FormField>
+                  <FormField
+                    id={FIELD_IDS.audioResampleMinHardComp}
+                    label="Hard Compensation Threshold (seconds)"
+                    description="Timestamp error before samples are inserted or dropped outright."
+                  >
+                    <Input
+                      id={FIELD_IDS.audioResampleMinHardComp}
+                      type="number"
+                      min={0}
+                      max={5}
+                      step={0.01}
+                      value={formData.audioResampleMinHardComp}
+                      onChange={(e) =>
+                        setFormData({
+                          ...formData,
+                          audioResampleMinHardComp: Number.parseFloat(e.target.value) || 0,
+                        })
+                      }
+                    />
+                  </FormField>
+                </>
+              )}
+
+              {formData.enableSystemAudio && (
+                <FormField
+                  id={FIELD_IDS.audioOffsetMs}
+                  label="Audio Offset (ms)"
+                  description="Corrects audio that is consistently out of sync with the video. Positive values delay the audio, negative values play it earlier."
+                >
+                  <Input
+                    id={FIELD_IDS.audioOffsetMs}
+                    type="number"
+                    min={-MAX_AUDIO_OFFSET_MS}
+                    max={MAX_AUDIO_OFFSET_MS}
+                    step={10}
+                    value={formData.audioOffsetMs}
+                    onChange={(e) =>
+                      setFormData({
+                        ...formData,
+                        audioOffsetMs: Math.max(
+                          -MAX_AUDIO_OFFSET_MS,
+                          Math.min(MAX_AUDIO_OFFSET_MS, parseInt(e.target.value) || 0),
+                        ),
+                      })
+                    }
+                  />
+                </FormField>
+              )}
+
+              {formData.enableSystemAudio && (
+                <SettingsToggleField
+                  id={FIELD_IDS.audioOptional}
+                  checked={formData.audioOptional}
+                  onChange={(checked) => {
+                    setFormData({
+                      ...formData,
+                      audioOptional: checked,
+                    });
+                  }}
+                  label="Record Without Audio Device"
+                  description="Records video only when no audio output device exists, such as on remote desktop or virtual machines, instead of failing to start."
+                />
+              )}
             </div>
           </SettingsSection>
 
@@ -619,6 +1423,71 @@ export function Settings() {
                   onChange={(e) => setFormData({ ...formData, maxStorageGB: parseInt(e.target.value) || MIN_STORAGE_GB })}
                 />
               </FormField>
+
+              <FormField
+                id={FIELD_IDS.maxFileSizeMb}
+                label="Maximum File Size (MB)"
+                description="Long sessions are split into numbered parts of at most this size. Set to 0 to keep each session in one file."
+              >
+                <Input
+                  id={FIELD_IDS.maxFileSizeMb}
+                  type="number"
+                  min={0}
+                  value={formData.maxFileSizeMb}
+                  onChange={(e) => setFormData({ ...formData, maxFileSizeMb: Math.max(0, parseInt(e.target.value) || 0) })}
+                />
+              </FormField>
+
+              <SettingsToggleField
+                id={FIELD_IDS.sessionIdInFilename}
+                checked={formData.sessionIdInFilename}
+                onChange={(checked) => {
+                  setFormData({
+                    ...formData,
+                    sessionIdInFilename: checked,
+                  });
+                }}
+                label="Add Session ID to File Names"
+                description="Appends the recording's unique session ID to its file name. The ID is always saved in the recording's metadata."
+              />
+
+              <div>
+                <label htmlFor={FIELD_IDS.finalizeHook} className="mb-2 block text-sm text-neutral-300">
+                  After Recording
+                </label>
+                <SettingsSelect
+                  id={FIELD_IDS.finalizeHook}
+                  value={formData.finalizeHook}
+                  options={FINALIZE_HOOK_OPTIONS}
+                  onChange={(nextValue) => {
+                    setFormData({ ...formData, finalizeHook: nextValue as FinalizeHook });
+                  }}
+                  ariaDescribedBy="settings-finalize-hook-help"
+                />
+                <p id="settings-finalize-hook-help" className="mt-1 text-xs text-neutral-400">
+                  Copies or uploads each finished recording and its metadata in the background. A failed copy or upload never affects the recording.
+                </p>
+              </div>
+
+              {formData.finalizeHook !== "off" && (
+                <FormField
+                  id={FIELD_IDS.finalizeHookTarget}
+                  label={formData.finalizeHook === "copy" ? "Destination Folder" : "Upload URL"}
+                  description={
+                    formData.finalizeHook === "copy"
+                      ? "Absolute path, such as a network share (\\\\server\\recordings)."
+                      : "Receives a multipart POST with session_id, metadata, and the recording file."
+                  }
+                >
+                  <Input
+                    id={FIELD_IDS.finalizeHookTarget}
+                    type="text"
+                    value={formData.finalizeHookTarget}
+                    placeholder={formData.finalizeHook === "copy" ? "D:\\Recordings\\Archive" : "https://example.com/upload"}
+                    onChange={(e) => setFormData({ ...formData, finalizeHookTarget: e.target.value })}
+                  />
+                </FormField>
+              )}
             </div>
           </SettingsSection>
 
@@ -689,6 +1558,24 @@ export function Settings() {
                   </p>
                 )}
               </div>
+
+              <div>
+                <label htmlFor={FIELD_IDS.combatLogWatchMode} className="mb-2 block text-sm text-neutral-300">
+                  Combat Log Detection
+                </label>
+                <SettingsSelect
+                  id={FIELD_IDS.combatLogWatchMode}
+                  value={formData.combatLogWatchMode}
+                  options={COMBAT_LOG_WATCH_MODE_OPTIONS}
+                  onChange={(nextValue) => {
+                    setFormData({ ...formData, combatLogWatchMode: nextValue as CombatLogWatchMode });
+                  }}
+                  ariaDescribedBy="settings-combat-log-watch-mode-help"
+                />
+                <p id="settings-combat-log-watch-mode-help" className="mt-1 text-xs text-neutral-400">
+                  Automatic polls network paths such as \\server\share and uses file notifications otherwise. Choose Polling if the WoW folder is on a mapped network drive and combat events stop arriving.
+                </p>
+              </div>
             </div>
           </SettingsSection>
 
@@ -710,7 +1597,48 @@ export function Settings() {
                 <p id="settings-marker-hotkey-help" className="mt-1 text-xs text-neutral-400">
                   Press this key during recording to add a marker. If it conflicts, choose another key.
                 </p>
+                {!isMarkerHotkeyAvailable && (
+                  <p className="mt-2 inline-flex items-center gap-1.5 rounded-sm border border-rose-300/30 bg-rose-500/12 px-2 py-1 text-xs text-rose-200">
+                    <XCircle className="h-3.5 w-3.5 text-rose-300" />
+                    {formData.markerHotkey} is already used by another application.
+                  </p>
+                )}
               </div>
+
+              <FormField
+                id={FIELD_IDS.markerLeadSecs}
+                label="Marker Lead Time (seconds)"
+                description="Places hotkey markers this many seconds earlier to cover your reaction time. Combat log markers are not shifted."
+              >
+                <Input
+                  id={FIELD_IDS.markerLeadSecs}
+                  type="number"
+                  min={0}
+                  max={MAX_MARKER_LEAD_SECS}
+                  step={0.5}
+                  value={formData.markerLeadSecs}
+                  onChange={(e) => {
+                    const parsed = Number.parseFloat(e.target.value);
+                    const normalized = Number.isFinite(parsed)
+                      ? Math.min(MAX_MARKER_LEAD_SECS, Math.max(0, parsed))
+                      : 0;
+                    setFormData({ ...formData, markerLeadSecs: normalized });
+                  }}
+                />
+              </FormField>
+
+              <SettingsToggleField
+                id={FIELD_IDS.markerTone}
+                checked={formData.markerTone}
+                onChange={(checked) => {
+                  setFormData({
+                    ...formData,
+                    markerTone: checked,
+                  });
+                }}
+                label="Marker Beep"
+                description="Mix a short beep into the recorded system audio when you press the marker hotkey, so markers are easy to find by ear while editing. This changes the recorded audio."
+              />
             </div>
           </SettingsSection>
 
@@ -745,8 +1673,122 @@ export function Settings() {
                 });
               }}
               label="Enable Recording Diagnostics"
-              description="Write per-second audio and FFmpeg pacing logs for stutter or crackle debugging."
+              description="Write per-second audio and FFmpeg pacing logs for stutter or crackle debugging, and save verbose FFmpeg output to a per-recording log file."
             />
+
+            <div className="mt-4">
+              <SettingsToggleField
+                id={FIELD_IDS.enablePerformanceStats}
+                checked={formData.enablePerformanceStats}
+                onChange={(checked) => {
+                  setFormData({
+                    ...formData,
+                    enablePerformanceStats: checked,
+                  });
+                }}
+                label="Show Recording Performance"
+                description="Show encode speed, frame rate and dropped frames while recording. Below 1.00x the encoder is falling behind and may be causing stutter."
+              />
+            </div>
+
+            <div className="mt-4">
+              <SettingsToggleField
+                id={FIELD_IDS.forceSoftwareDecode}
+                checked={formData.forceSoftwareDecode}
+                onChange={(checked) => {
+                  setFormData({
+                    ...formData,
+                    forceSoftwareDecode: checked,
+                  });
+                }}
+                label="Force Software Decoding"
+                description="Skip GPU decoding when joining recordings. Slower, but avoids driver-specific decode failures."
+              />
+            </div>
+
+            <div className="mt-4">
+              <SettingsToggleField
+                id={FIELD_IDS.fragmentedMp4}
+                checked={formData.fragmentedMp4}
+                onChange={(checked) => {
+                  setFormData({
+                    ...formData,
+                    fragmentedMp4: checked,
+                  });
+                }}
+                label="Crash-Safe MP4"
+                description="Write fragmented MP4 so recordings stay playable if FloorPoV or your PC crashes. Files are slightly larger and some editors handle them less well."
+              />
+            </div>
+
+            <div className="mt-4">
+              <SettingsToggleField
+                id={FIELD_IDS.trimTrailingBlack}
+                checked={formData.trimTrailingBlack}
+                onChange={(checked) => {
+                  setFormData({
+                    ...formData,
+                    trimTrailingBlack: checked,
+                  });
+                }}
+                label="Trim Trailing Black Screen"
+                description="End the recording on the last captured frame instead of the black placeholder shown while the game window was minimized or closed."
+              />
+            </div>
+
+            <div className="mt-4">
+              <SettingsToggleField
+                id={FIELD_IDS.preallocateOutput}
+                checked={formData.preallocateOutput}
+                onChange={(checked) => {
+                  setFormData({
+                    ...formData,
+                    preallocateOutput: checked,
+                  });
+                }}
+                label="Reserve Disk Space"
+                description="Reserve space for the recording before writing it to avoid fragmentation and write stalls on hard drives. Unused space is released when the recording ends."
+              />
+            </div>
+
+            <div className="mt-4">
+              <SettingsToggleField
+                id={FIELD_IDS.enableCombatEventStream}
+                checked={formData.enableCombatEventStream}
+                onChange={(checked) => {
+                  setFormData({
+                    ...formData,
+                    enableCombatEventStream: checked,
+                  });
+                }}
+                label="Stream Combat Events Locally"
+                description="Serve live combat events as JSON lines on localhost:47810 for overlay tools such as Streamer.bot."
+              />
+            </div>
+
+            <FormField
+              id={FIELD_IDS.customFfmpegPath}
+              label="Custom FFmpeg Binary"
+              description="Optional path to your own ffmpeg.exe. The bundled FFmpeg is used if it is empty or fails the version check."
+              className="mt-4"
+            >
+              <Input
+                id={FIELD_IDS.customFfmpegPath}
+                type="text"
+                placeholder="Use bundled FFmpeg"
+                value={formData.customFfmpegPath}
+                onChange={(e) => setFormData({ ...formData, customFfmpegPath: e.target.value })}
+              />
+            </FormField>
+
+            <div className="mt-4 flex items-center justify-between gap-3">
+              <p className="text-xs text-neutral-400">
+                Application logs rotate daily. Attach the latest file when reporting a bug.
+              </p>
+              <Button variant="secondary" size="sm" onClick={handleOpenLogs}>
+                Open Logs
+              </Button>
+            </div>
           </SettingsSection>
         </div>
       </div>
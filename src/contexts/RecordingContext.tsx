@@ -10,15 +10,17 @@ import {
   convertRecordingMetadataToGameEvents,
   CombatEvent,
   CombatTriggerEvent,
+  CombatWatchHeartbeatEvent,
   CombatWatchStatusEvent,
   RecordingMetadata,
 } from "../types/events";
-import { RecordingStartedPayload, CleanupResult, RecordingCommandSettings, RecordingOrigin, AutoTriggerMode } from "../types/recording";
+import { PerformanceStats, RecordingHookPayload, RecordingOutputLostPayload, RecordingStartedPayload, RecordingWarningPayload, CleanupResult, RecordingCommandSettings, RecordingOrigin, AutoTriggerMode } from "../types/recording";
 
 interface RecordingContextType {
   isRecording: boolean;
   lastError: string | null;
   recordingWarning: string | null;
+  performanceStats: PerformanceStats | null;
   captureWidth: number;
   captureHeight: number;
   recordingPath: string | null;
@@ -31,6 +33,9 @@ interface RecordingContextType {
 }
 
 const AUTO_STOP_GRACE_MS = 5000;
+// The watcher sends a heartbeat every 10s; missing three in a row means it has stalled.
+const COMBAT_WATCH_STALL_TIMEOUT_MS = 30000;
+const COMBAT_WATCH_STALL_CHECK_INTERVAL_MS = 5000;
 
 const RecordingContext = createContext<RecordingContextType | undefined>(undefined);
 
@@ -38,6 +43,7 @@ export function RecordingProvider({ children }: { children: ReactNode }) {
   const [isRecording, setIsRecording] = useState(false);
   const [lastError, setLastError] = useState<string | null>(null);
   const [recordingWarning, setRecordingWarning] = useState<string | null>(null);
+  const [performanceStats, setPerformanceStats] = useState<PerformanceStats | null>(null);
   const [captureWidth, setCaptureWidth] = useState(0);
   const [captureHeight, setCaptureHeight] = useState(0);
   const [recordingPath, setRecordingPath] = useState<string | null>(null);
@@ -59,6 +65,7 @@ export function RecordingProvider({ children }: { children: ReactNode }) {
   const { addEvent, setEvents, clearEvents } = useMarker();
   const operationInFlightRef = useRef(false);
   const isRecordingRef = useRef(false);
+  const systemAudioDeviceRef = useRef(settings.systemAudioDevice);
   const recordingOriginRef = useRef<RecordingOrigin | null>(null);
   const pendingAutoStopTimeoutRef = useRef<number | null>(null);
   const pendingAutoStopModeRef = useRef<AutoTriggerMode | null>(null);
@@ -101,16 +108,28 @@ export function RecordingProvider({ children }: { children: ReactNode }) {
     await invoke("start_combat_watch", {
       wowFolder,
       recordingOutputPath: null,
+      options: {
+        markerLeadSecs: settings.markerLeadSecs,
+        eventStream: settings.enableCombatEventStream,
+        watchMode: settings.combatLogWatchMode,
+      },
     });
     setIsCombatWatchRunning(true);
     setCombatWatchWowFolder(wowFolder);
     return true;
-  }, [settings.enableAutoRecording, settings.wowFolder]);
+  }, [
+    settings.combatLogWatchMode,
+    settings.enableAutoRecording,
+    settings.enableCombatEventStream,
+    settings.markerLeadSecs,
+    settings.wowFolder,
+  ]);
 
   const detachCombatWatchRecordingOutput = useCallback(async () => {
     try {
       await invoke("set_combat_watch_recording_output", {
         recordingOutputPath: null,
+        recordingSessionId: null,
       });
     } catch (error) {
       console.warn("Failed to detach combat watch recording output:", error);
@@ -154,6 +173,25 @@ export function RecordingProvider({ children }: { children: ReactNode }) {
     });
   };
 
+  // Changing the output device in Settings mid-recording moves the running capture over
+  // instead of waiting for the next recording. "auto" is resolved only at start.
+  useEffect(() => {
+    const previousDevice = systemAudioDeviceRef.current;
+    systemAudioDeviceRef.current = settings.systemAudioDevice;
+    if (
+      !isRecording ||
+      !settings.enableSystemAudio ||
+      settings.systemAudioDevice === previousDevice ||
+      settings.systemAudioDevice === "auto"
+    ) {
+      return;
+    }
+
+    invoke("switch_audio_device", { deviceId: settings.systemAudioDevice }).catch((error) => {
+      console.warn("Failed to switch system audio device:", error);
+    });
+  }, [isRecording, settings.enableSystemAudio, settings.systemAudioDevice]);
+
   useEffect(() => {
     let intervalId: number | undefined;
 
@@ -230,29 +268,58 @@ export function RecordingProvider({ children }: { children: ReactNode }) {
   }, [isRecording, settings, updateSettings]);
 
   useEffect(() => {
+    // Starts requested from this context update state when the command returns; this
+    // picks up recordings started from anywhere else.
+    const unlistenRecordingStarted = listen<RecordingStartedPayload>("recording-started", (event) => {
+      if (operationInFlightRef.current || isRecordingRef.current) {
+        return;
+      }
+
+      setIsRecording(true);
+      setRecordingOrigin("manual");
+      setRecordingPath(event.payload.output_path);
+      setCaptureWidth(event.payload.width);
+      setCaptureHeight(event.payload.height);
+      setRecordingStartTime(Date.now());
+    });
+
     const unlistenRecordingStopped = listen("recording-stopped", () => {
       clearPendingAutoStop();
       setIsRecording(false);
       setRecordingStartTime(null);
       setRecordingWarning(null);
+      setPerformanceStats(null);
       setRecordingOrigin(null);
       setActiveAutoTriggerMode(null);
       operationInFlightRef.current = false;
     });
 
-    const unlistenRecordingWarning = listen<string>("recording-warning", (event) => {
-      setRecordingWarning(event.payload);
+    const unlistenRecordingWarning = listen<RecordingWarningPayload>("recording-warning", (event) => {
+      setRecordingWarning(event.payload.message);
     });
 
     const unlistenRecordingWarningCleared = listen("recording-warning-cleared", () => {
       setRecordingWarning(null);
     });
 
+    const unlistenPerformanceStats = listen<PerformanceStats>("performance-stats", (event) => {
+      setPerformanceStats(event.payload);
+    });
+
     const unlistenCleanup = listen<CleanupResult>("storage-cleanup", (event) => {
       const { deleted_count, freed_bytes } = event.payload;
       console.info(`Deleted ${deleted_count} old recording(s) (${(freed_bytes / (1024 ** 3)).toFixed(2)} GB) to stay within storage limit`);
     });
 
+    const unlistenRecordingHookFailed = listen<RecordingHookPayload>("recording-hook-failed", (event) => {
+      const { action, error } = event.payload;
+      setLastError(`Recording ${action === "copy" ? "copy" : "upload"} failed: ${error ?? "unknown error"}`);
+    });
+
+    const unlistenRecordingOutputLost = listen<RecordingOutputLostPayload>("recording-output-lost", (event) => {
+      setLastError(event.payload.message);
+    });
+
     const unlistenCombatEvent = listen<CombatEvent>("combat-event", (event) => {
       if (!isRecordingRef.current) {
         return;
@@ -321,14 +388,26 @@ export function RecordingProvider({ children }: { children: ReactNode }) {
       setCombatWatchDetail(`${statusPayload.message}${logSuffix}`);
     });
 
+    const unlistenAdvancedLoggingDisabled = listen<CombatWatchStatusEvent>(
+      "combat-log-advanced-disabled",
+      (event) => {
+        setCombatWatchDetail(event.payload.message);
+      },
+    );
+
     return () => {
+      unlistenRecordingStarted.then((unsubscribe) => unsubscribe());
       unlistenRecordingStopped.then((unsubscribe) => unsubscribe());
       unlistenRecordingWarning.then((unsubscribe) => unsubscribe());
       unlistenRecordingWarningCleared.then((unsubscribe) => unsubscribe());
+      unlistenPerformanceStats.then((unsubscribe) => unsubscribe());
       unlistenCleanup.then((unsubscribe) => unsubscribe());
+      unlistenRecordingHookFailed.then((unsubscribe) => unsubscribe());
+      unlistenRecordingOutputLost.then((unsubscribe) => unsubscribe());
       unlistenCombatEvent.then((unsubscribe) => unsubscribe());
       unlistenCombatTrigger.then((unsubscribe) => unsubscribe());
       unlistenCombatWatchStatus.then((unsubscribe) => unsubscribe());
+      unlistenAdvancedLoggingDisabled.then((unsubscribe) => unsubscribe());
     };
   }, [activeAutoTriggerMode, addEvent, clearPendingAutoStop, settings.enableAutoRecording]);
 
@@ -375,11 +454,45 @@ export function RecordingProvider({ children }: { children: ReactNode }) {
           frame_rate: settings.frameRate,
           bitrate: bitrateSettings.bitrate,
           video_encoder_preference: settings.videoEncoderPreference,
+          encoder_gpu: settings.encoderGpu,
+          rate_control: settings.rateControl,
+          vbr_quality: settings.vbrQuality,
+          pixel_format: settings.pixelFormat,
           capture_source: settings.captureSource,
           capture_window_hwnd: settings.captureWindowHwnd,
           capture_window_title: settings.captureWindowTitle,
+          capture_monitor_device: settings.captureMonitorDevice,
+          follow_foreground: settings.followForeground,
+          window_status_poll_ms: settings.windowStatusPollMs,
+          preserve_aspect_ratio: settings.preserveAspectRatio,
+          letterbox_color: settings.letterboxColor,
           enable_system_audio: settings.enableSystemAudio,
           enable_recording_diagnostics: settings.enableRecordingDiagnostics,
+          bypass_audio_limiter: settings.bypassAudioLimiter,
+          audio_sync_mode: settings.audioSyncMode,
+          audio_resample_async: settings.audioResampleAsync,
+          audio_resample_min_hard_comp: settings.audioResampleMinHardComp,
+          audio_offset_ms: settings.audioOffsetMs,
+          downmix_audio_mono: settings.downmixAudioMono,
+          wait_for_audio_connection: settings.waitForAudioConnection,
+          audio_channel_map: settings.audioChannelMap,
+          audio_channel_map_custom: settings.audioChannelMapCustom || null,
+          audio_codec: settings.audioCodec,
+          audio_bitrate_kbps: settings.audioBitrateKbps,
+          finalize_hook: settings.finalizeHook,
+          finalize_hook_target: settings.finalizeHookTarget,
+          system_audio_device: settings.systemAudioDevice,
+          system_audio_session: settings.systemAudioSession,
+          audio_optional: settings.audioOptional,
+          session_id_in_filename: settings.sessionIdInFilename,
+          fragmented_mp4: settings.fragmentedMp4,
+          trim_trailing_black: settings.trimTrailingBlack,
+          max_file_size_mb: settings.maxFileSizeMb,
+          preallocate_output: settings.preallocateOutput,
+          marker_tone: settings.markerTone,
+          enable_performance_stats: settings.enablePerformanceStats,
+          preset: settings.recordingPreset === "custom" ? null : settings.recordingPreset,
+          custom_ffmpeg_path: settings.customFfmpegPath || null,
         };
 
         const result = await invoke<RecordingStartedPayload>("start_recording", {
@@ -402,6 +515,7 @@ export function RecordingProvider({ children }: { children: ReactNode }) {
         if (watchStarted) {
           await invoke("set_combat_watch_recording_output", {
             recordingOutputPath: result.output_path,
+            recordingSessionId: result.session_id,
           });
         }
       } catch (error) {
@@ -428,7 +542,41 @@ export function RecordingProvider({ children }: { children: ReactNode }) {
       settings.captureSource,
       settings.captureWindowHwnd,
       settings.captureWindowTitle,
+      settings.captureMonitorDevice,
+      settings.followForeground,
+      settings.windowStatusPollMs,
+      settings.preserveAspectRatio,
+      settings.letterboxColor,
       settings.videoEncoderPreference,
+      settings.encoderGpu,
+      settings.rateControl,
+      settings.vbrQuality,
+      settings.pixelFormat,
+      settings.bypassAudioLimiter,
+      settings.audioSyncMode,
+      settings.audioResampleAsync,
+      settings.audioResampleMinHardComp,
+      settings.audioOffsetMs,
+      settings.downmixAudioMono,
+      settings.waitForAudioConnection,
+      settings.audioChannelMap,
+      settings.audioChannelMapCustom,
+      settings.audioCodec,
+      settings.audioBitrateKbps,
+      settings.finalizeHook,
+      settings.finalizeHookTarget,
+      settings.systemAudioDevice,
+      settings.systemAudioSession,
+      settings.audioOptional,
+      settings.sessionIdInFilename,
+      settings.fragmentedMp4,
+      settings.trimTrailingBlack,
+      settings.maxFileSizeMb,
+      settings.preallocateOutput,
+      settings.markerTone,
+      settings.enablePerformanceStats,
+      settings.recordingPreset,
+      settings.customFfmpegPath,
       settings.enableRecordingDiagnostics,
       settings.enableSystemAudio,
       settings.frameRate,
@@ -592,6 +740,35 @@ export function RecordingProvider({ children }: { children: ReactNode }) {
     settings.wowFolder,
   ]);
 
+  useEffect(() => {
+    if (!isCombatWatchRunning) {
+      return;
+    }
+
+    let lastHeartbeatAt = Date.now();
+    let isStalled = false;
+
+    const unlistenHeartbeat = listen<CombatWatchHeartbeatEvent>("combat-watch-heartbeat", () => {
+      lastHeartbeatAt = Date.now();
+      if (isStalled) {
+        isStalled = false;
+        setCombatWatchDetail("Combatlog watcher active!");
+      }
+    });
+
+    const stallCheckInterval = window.setInterval(() => {
+      if (!isStalled && Date.now() - lastHeartbeatAt > COMBAT_WATCH_STALL_TIMEOUT_MS) {
+        isStalled = true;
+        setCombatWatchDetail("Combat log watch stalled: no new events are being read.");
+      }
+    }, COMBAT_WATCH_STALL_CHECK_INTERVAL_MS);
+
+    return () => {
+      window.clearInterval(stallCheckInterval);
+      unlistenHeartbeat.then((unsubscribe) => unsubscribe());
+    };
+  }, [isCombatWatchRunning]);
+
   useEffect(() => {
     return () => {
       clearPendingAutoStop();
@@ -604,6 +781,7 @@ export function RecordingProvider({ children }: { children: ReactNode }) {
         isRecording,
         lastError,
         recordingWarning,
+        performanceStats,
         captureWidth,
         captureHeight,
         recordingPath,
@@ -73,6 +73,14 @@ export function SettingsProvider({ children }: { children: ReactNode }) {
           shouldPersistMergedSettings = true;
         }
 
+        if (mergedSettings.outputFolder !== defaultFolder) {
+          try {
+            await invoke('set_output_folder', { path: mergedSettings.outputFolder });
+          } catch (error) {
+            console.error('Failed to register output folder:', error);
+          }
+        }
+
         if (shouldPersistMergedSettings) {
           await store.set('recording-settings', mergedSettings);
           await store.save();
@@ -131,6 +139,10 @@ export function SettingsProvider({ children }: { children: ReactNode }) {
         }
       }
       
+      if (newSettings.outputFolder !== settings.outputFolder) {
+        await invoke('set_output_folder', { path: newSettings.outputFolder });
+      }
+
       await store.set('recording-settings', newSettings);
       await store.save();
       setSettings(newSettings);
@@ -5,6 +5,7 @@ export interface GameEvent {
   source?: string;
   target?: string;
   targetKind?: string;
+  note?: string;
 }
 
 export interface RecordingImportantEventMetadata {
@@ -18,6 +19,7 @@ export interface RecordingImportantEventMetadata {
   encounterName?: string;
   encounterCategory?: string;
   keyLevel?: number;
+  note?: string;
 }
 
 export interface RecordingEncounterMetadata {
@@ -35,6 +37,34 @@ export interface RecordingPlayerMetadata {
   specId?: number;
 }
 
+export interface RecordingSegmentFrameRateMetadata {
+  segmentIndex: number;
+  achievedFrameRate: number;
+  durationSeconds: number;
+}
+
+export interface RecordingCaptureQualityMetadata {
+  requestedFrameRate: number;
+  achievedFrameRate: number;
+  belowRequestedFrameRate: boolean;
+  segments?: RecordingSegmentFrameRateMetadata[];
+}
+
+export interface RecordingAudioSyncMetadata {
+  videoDurationSeconds: number;
+  audioDurationSeconds: number;
+  mismatchSeconds: number;
+  mismatchDetected: boolean;
+}
+
+export interface RecordingSegmentTimelineMetadata {
+  segmentIndex: number;
+  captureMode: "monitor" | "window" | "black" | "test_pattern";
+  startedAtSeconds: number;
+  durationSeconds: number;
+  forceKilled: boolean;
+}
+
 export interface RecordingMetadata {
   schemaVersion: number;
   recordingFile: string;
@@ -47,6 +77,10 @@ export interface RecordingMetadata {
   importantEventCounts?: Record<string, number>;
   importantEventsDroppedCount?: number;
   players?: RecordingPlayerMetadata[];
+  captureQuality?: RecordingCaptureQualityMetadata;
+  audioSync?: RecordingAudioSyncMetadata;
+  segmentTimeline?: RecordingSegmentTimelineMetadata[];
+  sessionId?: string;
 }
 
 export interface CombatEvent {
@@ -54,6 +88,25 @@ export interface CombatEvent {
   eventType: string;
   source?: string;
   target?: string;
+  sourceGuid?: string;
+  targetGuid?: string;
+  sessionId?: string;
+  note?: string;
+}
+
+export interface CombatEventOverflow {
+  timestamp: number;
+  eventType: string;
+  suppressedCount: number;
+}
+
+export interface SupportedCombatEvent {
+  eventType: string;
+  rawEventTypes: string[];
+  description: string;
+  fields: string[];
+  contextOnly: boolean;
+  live: boolean;
 }
 
 export interface CombatTriggerEvent {
@@ -70,6 +123,12 @@ export interface CombatWatchStatusEvent {
   watchedLogPath?: string;
 }
 
+export interface CombatWatchHeartbeatEvent {
+  watchedLogPath: string;
+  fileOffset: number;
+  bytesProcessed: number;
+}
+
 export interface ParsedCombatEvent {
   lineNumber: number;
   logTimestamp: string;
@@ -92,6 +151,15 @@ export interface ParseCombatLogDebugResult {
   truncated: boolean;
 }
 
+export interface ParseCombatLogRangeResult {
+  filePath: string;
+  fileSizeBytes: number;
+  startLine: number;
+  linesRead: number;
+  parsedEvents: ParsedCombatEvent[];
+  reachedEndOfFile: boolean;
+}
+
 const SUPPORTED_PLAYBACK_EVENT_TYPES = new Set(["PARTY_KILL", "UNIT_DIED", "MANUAL_MARKER"]);
 
 const NPC_KINDS = new Set(["NPC", "PET", "GUARDIAN", "UNKNOWN"]);
@@ -153,6 +221,7 @@ export function convertRecordingMetadataToGameEvents(
         source: importantEvent.source,
         target: importantEvent.target,
         targetKind: importantEvent.targetKind,
+        note: importantEvent.note,
       }];
     })
     .sort((a, b) => a.timestamp - b.timestamp);
@@ -167,5 +236,6 @@ export function convertCombatEvent(combatEvent: CombatEvent): GameEvent {
     type,
     source: combatEvent.source,
     target: combatEvent.target,
+    note: combatEvent.note,
   };
 }
@@ -1,14 +1,154 @@
 // src/types/recording.ts
+/** Payload of `recording-warning`; other `recording-*` events carry `session_id` too. */
+export interface RecordingWarningPayload {
+  session_id: string;
+  message: string;
+}
+
+/** Payload of `recording-encoder-fallback`; the latest one names the encoder in use. */
+export interface RecordingEncoderFallbackPayload {
+  session_id: string;
+  failed_video_encoder: string;
+  video_encoder: "h264_nvenc" | "h264_qsv" | "h264_amf" | "libx264";
+  video_encoder_label: string;
+}
+
+/** Payload of `recording-output-lost`, sent when the recordings folder's drive disappears. */
+export interface RecordingOutputLostPayload {
+  session_id: string;
+  output_path: string;
+  message: string;
+}
+
+/** Payload of `recording-hook-succeeded` and `recording-hook-failed`. */
+export interface RecordingHookPayload {
+  session_id: string;
+  output_path: string;
+  action: "copy" | "http";
+  target: string;
+  error?: string;
+}
+
+/** Payload of `performance-stats`; frame counters restart with each capture segment. */
+export interface PerformanceStats {
+  session_id: string;
+  encode_speed: number | null;
+  encode_fps: number | null;
+  frames_duplicated: number;
+  frames_dropped: number;
+  audio_queue_depth: number | null;
+}
+
 export interface RecordingStartedPayload {
+  session_id: string;
   output_path: string;
   width: number;
   height: number;
+  bitrate: number;
+  /** Whether the capture method records the window even while it is covered. */
+  occlusion_independent_capture: boolean;
+}
+
+/** Result of the Rust `estimate_recording_size` command. */
+export interface RecordingSizeEstimate {
+  estimated_bytes: number;
+  width: number;
+  height: number;
+  bitrate: number;
+}
+
+export interface RecordingStopResult {
+  output_path: string;
+  finalized: boolean;
+}
+
+/** Result of `stop_all`; idle subsystems report `false`/`null`. */
+export interface StopAllSummary {
+  stopped_recording_path: string | null;
+  audio_monitor_stopped: boolean;
+  combat_watch_stopped: boolean;
+  cancelled_job_count: number;
+  errors: string[];
 }
 
 export interface CaptureWindowInfo {
   hwnd: string;
   title: string;
   process_name: string | null;
+  /** Base64 JPEG, present when `list_capture_windows` was asked for thumbnails. */
+  thumbnail_jpeg_base64?: string;
+}
+
+export interface CaptureMonitorInfo {
+  device_name: string;
+  name: string | null;
+  output_idx: number;
+  is_primary: boolean;
+}
+
+export interface AudioOutputDeviceInfo {
+  id: string;
+  name: string;
+  is_default: boolean;
+}
+
+/** An application's audio session on an output device, from `list_audio_sessions`. */
+export interface AudioSessionInfo {
+  session_id: string;
+  process_id: number;
+  app_name: string | null;
+  device_id: string;
+  device_name: string;
+  is_active: boolean;
+}
+
+/** Result of `get_current_audio_device`: the device the recording loops back right now. */
+export interface CurrentAudioDeviceInfo {
+  id: string;
+  name: string;
+  is_default: boolean;
+  sample_rate_hz: number;
+  channels: number;
+  bits_per_sample: number;
+}
+
+export interface CaptureWindowDiagnostics {
+  hwnd: string;
+  title: string | null;
+  class_name: string | null;
+  process_id: number;
+  process_name: string | null;
+}
+
+/** Result of `get_capture_diagnostics`; each `*_error` explains a part that could not be read. */
+export interface CaptureDiagnostics {
+  capture_source: string;
+  resolved_capture_target: string | null;
+  capture_error: string | null;
+  monitors: CaptureMonitorInfo[];
+  monitors_error: string | null;
+  capture_window: CaptureWindowDiagnostics | null;
+  wgc_supported: boolean;
+  hybrid_capture_gpu_vendor: "nvidia" | "intel" | "amd" | null;
+  ffmpeg_path: string | null;
+  ffmpeg_version: string | null;
+  ffmpeg_error: string | null;
+  available_video_encoders: string[];
+  selected_video_encoder: string | null;
+  system_audio_device: CurrentAudioDeviceInfo | null;
+  system_audio_error: string | null;
+}
+
+export interface RecordingPreset {
+  id: string;
+  label: string;
+  description: string;
+  video_quality: "low" | "medium" | "high" | "ultra";
+  frame_rate: 30 | 60;
+  bitrate: number;
+  video_encoder_preference: "auto" | "h264_nvenc" | "h264_qsv" | "h264_amf" | "libx264";
+  pixel_format: "yuv420p" | "yuv420p10le";
+  max_file_size_mb: number;
 }
 
 export interface AvailableVideoEncoder {
@@ -31,6 +171,59 @@ export interface RecordingInfo {
   encounter_name?: string;
   encounter_category?: string;
   key_level?: number;
+  duration_seconds?: number;
+}
+
+export type RecordingSortKey = "name" | "date" | "size" | "duration";
+
+/** One page of recordings returned by the `get_recordings_page` command. */
+export interface RecordingsPage {
+  recordings: RecordingInfo[];
+  total_count: number;
+}
+
+export interface RecordingComparisonSample {
+  start_seconds: number;
+  duration_seconds: number;
+  ssim: number;
+  /** `null` when the sampled frames were identical (infinite PSNR). */
+  psnr_db: number | null;
+}
+
+/** Result of the `compare_recordings` command, averaged over the sampled stretches. */
+export interface RecordingComparison {
+  ssim: number;
+  psnr_db: number | null;
+  samples: RecordingComparisonSample[];
+}
+
+export interface RecordingCompareProgressPayload {
+  input_path_a: string;
+  input_path_b: string;
+  progress: number;
+}
+
+/** Result of the `reconcile_markers` command. */
+export interface RecordingMarkerReconciliation {
+  durationSeconds: number;
+  droppedEventCount: number;
+  droppedEncounterCount: number;
+  clampedEncounterCount: number;
+}
+
+/** Marker accepted by the `import_markers` command; timestamps are relative to recording start. */
+export interface ImportedRecordingMarker {
+  timestampSeconds: number;
+  eventType?: string;
+  note?: string;
+  encounterName?: string;
+}
+
+/** Result of the `import_markers` command. */
+export interface RecordingMarkerImport {
+  importedCount: number;
+  duplicateCount: number;
+  totalEventCount: number;
 }
 
 /** Settings payload sent to the Rust `start_recording` command. */
@@ -39,11 +232,48 @@ export interface RecordingCommandSettings {
   frame_rate: number;
   bitrate: number;
   video_encoder_preference: string;
+  encoder_gpu?: "auto" | "nvidia" | "intel" | "amd";
+  rate_control?: "cbr" | "vbr";
+  vbr_quality?: number;
+  pixel_format?: "yuv420p" | "yuv420p10le";
   capture_source: string;
   capture_window_hwnd: string;
   capture_window_title: string;
+  capture_monitor_device?: string;
+  follow_foreground?: boolean;
+  window_status_poll_ms?: number;
+  preserve_aspect_ratio?: boolean;
+  letterbox_color?: string;
   enable_system_audio: boolean;
   enable_recording_diagnostics: boolean;
+  auto_bitrate?: boolean;
+  cursor_mode?: string;
+  max_file_size_mb?: number;
+  frame_sync?: "cfr" | "vfr" | "passthrough";
+  bypass_audio_limiter?: boolean;
+  audio_sync_mode?: "balanced" | "strict" | "smooth" | "custom";
+  audio_resample_async?: number;
+  audio_resample_min_hard_comp?: number;
+  audio_offset_ms?: number;
+  downmix_audio_mono?: boolean;
+  wait_for_audio_connection?: boolean;
+  audio_channel_map?: "stereo" | "swap" | "left" | "right" | "custom";
+  audio_channel_map_custom?: string | null;
+  audio_codec?: "aac" | "opus";
+  audio_bitrate_kbps?: number;
+  finalize_hook?: "off" | "copy" | "http";
+  finalize_hook_target?: string;
+  custom_ffmpeg_path?: string | null;
+  system_audio_device?: string;
+  system_audio_session?: string;
+  audio_optional?: boolean;
+  session_id_in_filename?: boolean;
+  fragmented_mp4?: boolean;
+  trim_trailing_black?: boolean;
+  preallocate_output?: boolean;
+  marker_tone?: boolean;
+  enable_performance_stats?: boolean;
+  preset?: string | null;
 }
 
 export type RecordingOrigin = "manual" | "auto";
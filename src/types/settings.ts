@@ -1,43 +1,127 @@
 export type VideoQuality = 'low' | 'medium' | 'high' | 'ultra';
 export type VideoEncoderPreference = 'auto' | 'h264_nvenc' | 'h264_qsv' | 'h264_amf' | 'libx264';
+export type EncoderGpu = 'auto' | 'nvidia' | 'intel' | 'amd';
+export type RateControl = 'cbr' | 'vbr';
+export type AudioSyncMode = 'balanced' | 'strict' | 'smooth' | 'custom';
+export type AudioCodec = 'aac' | 'opus';
+export type AudioChannelMap = 'stereo' | 'swap' | 'left' | 'right' | 'custom';
+export type PixelFormat = 'yuv420p' | 'yuv420p10le';
 export type FrameRate = 30 | 60;
 export type MarkerHotkey = 'F9' | 'F10' | 'F11' | 'F12' | 'none';
-export type CaptureSource = 'monitor' | 'window';
+export type CaptureSource = 'monitor' | 'virtual_desktop' | 'window' | 'test_pattern';
+export type FinalizeHook = 'off' | 'copy' | 'http';
+export type CombatLogWatchMode = 'auto' | 'notify' | 'poll';
 
 export interface RecordingSettings {
+  recordingPreset: string;
   videoQuality: VideoQuality;
   videoEncoderPreference: VideoEncoderPreference;
+  encoderGpu: EncoderGpu;
+  rateControl: RateControl;
+  vbrQuality: number;
+  pixelFormat: PixelFormat;
   frameRate: FrameRate;
   captureSource: CaptureSource;
   captureWindowHwnd: string;
   captureWindowTitle: string;
+  captureMonitorDevice: string;
+  followForeground: boolean;
+  windowStatusPollMs: number;
+  preserveAspectRatio: boolean;
+  letterboxColor: string;
   outputFolder: string;
   wowFolder: string;
+  combatLogWatchMode: CombatLogWatchMode;
   maxStorageGB: number;
+  maxFileSizeMb: number;
   enableSystemAudio: boolean;
+  bypassAudioLimiter: boolean;
+  audioSyncMode: AudioSyncMode;
+  audioResampleAsync: number;
+  audioResampleMinHardComp: number;
+  audioOffsetMs: number;
+  downmixAudioMono: boolean;
+  waitForAudioConnection: boolean;
+  audioChannelMap: AudioChannelMap;
+  audioChannelMapCustom: string;
+  audioCodec: AudioCodec;
+  audioBitrateKbps: number;
+  finalizeHook: FinalizeHook;
+  finalizeHookTarget: string;
+  systemAudioDevice: string;
+  systemAudioSession: string;
+  audioOptional: boolean;
+  sessionIdInFilename: boolean;
+  fragmentedMp4: boolean;
+  trimTrailingBlack: boolean;
+  preallocateOutput: boolean;
+  markerTone: boolean;
+  enablePerformanceStats: boolean;
   enableRecordingDiagnostics: boolean;
+  customFfmpegPath: string;
+  forceSoftwareDecode: boolean;
   enableAutoRecording: boolean;
   minAutoRaidRecordingSeconds: number;
   enableAutoUpdate: boolean;
   markerHotkey: MarkerHotkey;
+  markerLeadSecs: number;
+  enableCombatEventStream: boolean;
 }
 
 export const DEFAULT_SETTINGS: RecordingSettings = {
+  recordingPreset: 'custom',
   videoQuality: 'high',
   videoEncoderPreference: 'auto',
+  encoderGpu: 'auto',
+  rateControl: 'cbr',
+  vbrQuality: 23,
+  pixelFormat: 'yuv420p',
   frameRate: 30,
   captureSource: 'monitor',
   captureWindowHwnd: '',
   captureWindowTitle: '',
+  captureMonitorDevice: '',
+  followForeground: false,
+  windowStatusPollMs: 150,
+  preserveAspectRatio: false,
+  letterboxColor: 'black',
   outputFolder: '',
   wowFolder: '',
+  combatLogWatchMode: 'auto',
   maxStorageGB: 30,
+  maxFileSizeMb: 0,
   enableSystemAudio: false,
+  bypassAudioLimiter: false,
+  audioSyncMode: 'balanced',
+  audioResampleAsync: 1,
+  audioResampleMinHardComp: 0.1,
+  audioOffsetMs: 0,
+  downmixAudioMono: false,
+  waitForAudioConnection: true,
+  audioChannelMap: 'stereo',
+  audioChannelMapCustom: '',
+  audioCodec: 'aac',
+  audioBitrateKbps: 192,
+  finalizeHook: 'off',
+  finalizeHookTarget: '',
+  systemAudioDevice: 'auto',
+  systemAudioSession: '',
+  audioOptional: false,
+  sessionIdInFilename: false,
+  fragmentedMp4: false,
+  trimTrailingBlack: false,
+  preallocateOutput: false,
+  markerTone: false,
+  enablePerformanceStats: false,
   enableRecordingDiagnostics: false,
+  customFfmpegPath: '',
+  forceSoftwareDecode: false,
   enableAutoRecording: false,
   minAutoRaidRecordingSeconds: 25,
   enableAutoUpdate: true,
   markerHotkey: 'F9',
+  markerLeadSecs: 0,
+  enableCombatEventStream: false,
 };
 
 export const QUALITY_SETTINGS = {
@@ -51,6 +135,12 @@ export const MIN_STORAGE_GB = 5;
 export const MAX_STORAGE_GB = 1000;
 export const MIN_AUTO_RAID_RECORDING_SECONDS = 0;
 export const MAX_AUTO_RAID_RECORDING_SECONDS = 300;
+export const MAX_MARKER_LEAD_SECS = 30;
+export const MAX_AUDIO_OFFSET_MS = 5000;
+export const MIN_VBR_QUALITY = 1;
+export const MAX_VBR_QUALITY = 51;
+export const MIN_WINDOW_STATUS_POLL_MS = 50;
+export const MAX_WINDOW_STATUS_POLL_MS = 2000;
 
 export const HOTKEY_OPTIONS = [
   { value: "F9", label: "F9" },